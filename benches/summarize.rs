@@ -0,0 +1,98 @@
+//! Compares [`report::summarize`] with [`report::par_summarize`] over a synthetic year of 50,000
+//! entries. Run with `cargo bench --features parallel`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use nutrition::fdc::FDCMeta;
+use nutrition::log::{FoodLog, LogEntry, MemoryLog};
+use nutrition::quantities::Quantity;
+use nutrition::report::{self, Window};
+
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use std::collections::HashMap;
+use tokio::runtime::Runtime;
+
+const ENTRIES: usize = 50_000;
+const FOODS: i32 = 20;
+
+/// Foods with a few nutrients per 100 g and a cup portion to resolve.
+fn foods() -> HashMap<i32, FDCMeta> {
+    (1..=FOODS)
+        .map(|fdc_id| {
+            let nutrient = |id: i32, unit: &str, amount: f32| {
+                serde_json::json!({
+                    "id": id * 10,
+                    "nutrient": { "id": id, "name": "", "unitName": unit },
+                    "amount": amount * fdc_id as f32,
+                })
+            };
+            let food = serde_json::json!({
+                "dataType": "Other",
+                "fdcId": fdc_id,
+                "description": format!("Food {}", fdc_id),
+                "foodAttributes": [],
+                "foodPortions": [{
+                    "id": fdc_id,
+                    "amount": 1.0,
+                    "gramWeight": 120.0 + fdc_id as f32,
+                    "modifier": "cup, chopped",
+                }],
+                "foodNutrients": [
+                    nutrient(1003, "g", 1.3),
+                    nutrient(1004, "g", 0.7),
+                    nutrient(1005, "g", 2.9),
+                    nutrient(1008, "kcal", 17.0),
+                    nutrient(1093, "mg", 31.0),
+                ],
+            });
+            (fdc_id, serde_json::from_value(food).unwrap())
+        })
+        .collect()
+}
+
+/// A year of entries, alternating between grams and cups.
+async fn log(start: NaiveDate) -> MemoryLog {
+    let log = MemoryLog::new();
+    let midnight = Utc.from_utc_datetime(&start.and_hms_opt(0, 0, 0).unwrap());
+    let step = Duration::days(365).num_seconds() / ENTRIES as i64;
+    for i in 0..ENTRIES {
+        let quantity = match i % 2 {
+            0 => Quantity::from_unit(10.0 + (i % 90) as f32, "g").unwrap(),
+            _ => Quantity::Nominal(0.5 + (i % 4) as f32 * 0.25, "cup".into()),
+        };
+        let eaten_at = midnight + Duration::seconds(step * i as i64);
+        let entry = LogEntry::new(i as i32 % FOODS + 1, quantity, eaten_at);
+        log.add(&entry).await.unwrap();
+    }
+    log
+}
+
+fn bench(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let log = runtime.block_on(log(start));
+    let foods = foods();
+    let window = || Window::trailing(365, start + Duration::days(364), Utc);
+
+    let serial = runtime
+        .block_on(report::summarize(&log, &foods, window()))
+        .unwrap();
+    let parallel = runtime
+        .block_on(report::par_summarize(&log, &foods, window()))
+        .unwrap();
+    assert_eq!(serial, parallel);
+
+    let mut group = c.benchmark_group("summarize a year");
+    group.sample_size(10);
+    group.bench_function("serial", |b| {
+        b.to_async(&runtime)
+            .iter(|| report::summarize(&log, &foods, window()))
+    });
+    group.bench_function("parallel", |b| {
+        b.to_async(&runtime)
+            .iter(|| report::par_summarize(&log, &foods, window()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
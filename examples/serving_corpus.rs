@@ -0,0 +1,30 @@
+//! Regenerates `tests/fixtures/servings.txt` from a bulk CSV bundle of FDC. Run with
+//! `cargo run --example serving_corpus --features test-util -- <bundle dir> [size]`, redirecting
+//! its output to the fixture.
+
+use nutrition::fdc::test_util::serving_corpus;
+
+use std::path::PathBuf;
+
+/// How many serving texts the corpus has unless told otherwise.
+const SIZE: usize = 500;
+
+fn main() -> nutrition::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let dir = match args.next() {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            eprintln!("usage: serving_corpus <bundle dir> [size]");
+            std::process::exit(2);
+        }
+    };
+    let size = args.next().and_then(|s| s.parse().ok()).unwrap_or(SIZE);
+    println!("# Household serving texts of branded foods and portion modifiers and descriptions of other foods,");
+    println!("# as FDC writes them, one per line, for measuring how many the quantity parser reads. Regenerate");
+    println!("# from a bulk CSV bundle with:");
+    println!("#   cargo run --example serving_corpus --features test-util -- <bundle dir> > tests/fixtures/servings.txt");
+    for text in serving_corpus(&dir, size)? {
+        println!("{}", text);
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! The `nutrack` command line, which searches FDC, shows foods, parses serving quantities, and
+//! keeps the food log. Configuration is read through the `env` module, except for `parse` which
+//! needs none.
+
+use nutrition::env;
+use nutrition::fdc::{FDCMeta, FDCService};
+use nutrition::log::{FoodLog, LogEntry};
+use nutrition::nutrition::{
+    self as nutrients, format_amount, screening, Basis, Level, NutrientProfile,
+};
+use nutrition::quantities::{parse, ParseOptions};
+use nutrition::rank;
+use nutrition::source::FoodSource;
+use nutrition::storage::{CachedFoods, SqliteStore};
+
+use anyhow::{anyhow, Result};
+use chrono::{Local, Utc};
+use clap::{Parser, Subcommand};
+use reqwest::Client;
+use std::process::ExitCode;
+
+#[derive(Debug, Parser)]
+#[command(name = "nutrack", about = "Look up foods and log what you eat")]
+struct Cli {
+    /// Print the full error chain and backtrace on failure
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Search FDC and print the ranked results with their FDC ids
+    Search { query: String },
+    /// Print the nutrients and portions of a food
+    Show { fdc_id: i32 },
+    /// Parse serving quantities and print what was understood
+    Parse { text: String },
+    /// Add to or read the food log
+    #[command(subcommand)]
+    Log(LogCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum LogCommand {
+    /// Log a quantity of a food as eaten now
+    Add { fdc_id: i32, quantity: String },
+    /// Print today's entries and their total
+    Today,
+}
+
+/// Connect to the FDC API and the database of the environment.
+async fn foods() -> Result<CachedFoods<SqliteStore>> {
+    let environment = env::get()?;
+    let store = SqliteStore::connect(&environment.database_url).await?;
+    Ok(CachedFoods::new(
+        environment.fdc_service(),
+        Client::new(),
+        store,
+    ))
+}
+
+/// Print each nutrient of the profile on its own line.
+fn print_profile(profile: &NutrientProfile) {
+    for (nutrient, amount) in profile.iter() {
+        println!(
+            "  {:<16}{:>12}",
+            nutrient.name(),
+            format_amount(nutrient, amount)
+        );
+    }
+}
+
+async fn search(query: &str) -> Result<()> {
+    let service = FDCService::from_env()?;
+    let mut results = service.v1_foods_search(&Client::new(), query).await?;
+    rank::rerank(query, &mut results);
+    for food in results {
+        println!(
+            "{:>8}  {:<16}  {}",
+            food.fdc_id, food.data_type, food.description
+        );
+    }
+    Ok(())
+}
+
+async fn show(fdc_id: i32) -> Result<()> {
+    let food = foods()
+        .await?
+        .get(fdc_id)
+        .await?
+        .ok_or_else(|| anyhow!("No food found for id {}", fdc_id))?;
+    println!("{} ({})", food.description(), food.fdc_id());
+    if let Some(brand) = food.brand() {
+        println!("Brand: {}", brand);
+    }
+    let profile = NutrientProfile::per_100g(&food)?;
+    println!("Per 100 g:");
+    print_profile(&profile);
+    println!("Traffic lights:");
+    for (nutrient, level) in screening::classify(&profile, Basis::Food) {
+        let level = match level {
+            Level::Low => "low",
+            Level::Medium => "medium",
+            Level::High => "high",
+            Level::Unknown => "no data",
+        };
+        println!("  {:<16}{}", nutrient.name(), level);
+    }
+    match &food {
+        FDCMeta::Branded(item) => {
+            println!("Serving: {} {}", item.serving_size, item.serving_size_unit);
+            if let Some(text) = &item.household_serving_full_text {
+                println!("  {}", text);
+            }
+        }
+        FDCMeta::Other(item) => {
+            println!("Portions:");
+            for portion in item.sanitized_portions() {
+                let name = [&portion.portion_description, &portion.modifier]
+                    .iter()
+                    .filter_map(|name| name.as_deref())
+                    .find(|name| !name.is_empty())
+                    .unwrap_or("portion");
+                println!(
+                    "  {} {} = {} g",
+                    portion.amount.unwrap_or(1.0),
+                    name,
+                    portion.gram_weight
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse(text: &str) -> Result<()> {
+    let (_, quantities) =
+        parse::quantities(text).map_err(|e| anyhow!("Cannot parse {:?}: {}", text, e))?;
+    for quantity in quantities {
+        println!("{:?}", quantity);
+    }
+    Ok(())
+}
+
+async fn log_add(fdc_id: i32, text: &str) -> Result<()> {
+    // keep the text along with its quantity, so that the entry can be parsed again
+    let entry = LogEntry::parse(fdc_id, text, Utc::now(), &ParseOptions::default())?;
+    let foods = foods().await?;
+    // resolve the quantity before logging it, so that unusable entries are rejected
+    foods.profile(fdc_id, &entry.quantity).await?;
+    foods.store.add(&entry).await?;
+    println!("Logged {}", entry.id);
+    Ok(())
+}
+
+async fn log_today() -> Result<()> {
+    let foods = foods().await?;
+    let entries = foods
+        .store
+        .entries_for_day(Local::now().date_naive(), &Local)
+        .await?;
+    let mut profiles = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let time = entry.eaten_at.with_timezone(&Local).format("%H:%M");
+        println!("{}  {:>8}  {:?}", time, entry.fdc_id, entry.quantity);
+        profiles.push(foods.profile(entry.fdc_id, &entry.quantity).await?);
+    }
+    println!("Total:");
+    print_profile(&nutrients::sum(profiles));
+    Ok(())
+}
+
+async fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Search { query } => search(&query).await,
+        Command::Show { fdc_id } => show(fdc_id).await,
+        Command::Parse { text } => parse(&text),
+        Command::Log(LogCommand::Add { fdc_id, quantity }) => log_add(fdc_id, &quantity).await,
+        Command::Log(LogCommand::Today) => log_today().await,
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if cli.verbose {
+        std::env::set_var("RUST_LIB_BACKTRACE", "1");
+    }
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if cli.verbose => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! This module folds duplicate branded foods out of search results. FDC keeps every submission of
+//! a product, so the same product often appears once for each year it was submitted.
+//!
+//! Branded foods are grouped by their [`Gtin`], normalized to 14 digits. Foods without a valid GTIN
+//! are grouped by their normalized description and brand instead. Each group keeps its most
+//! recently published food.
+
+use crate::fdc::{AbridgedFoodItem, BrandedFoodItem};
+use crate::gtin::Gtin;
+use crate::text;
+use crate::warnings::{warn, Warning, Warnings};
+
+use std::collections::HashMap;
+
+/// A food kept among search results, along with the older duplicates folded into it.
+#[derive(Clone, Debug)]
+pub struct Folded {
+    pub food: AbridgedFoodItem,
+    /// The duplicates of the food, most recently published first.
+    pub older: Vec<AbridgedFoodItem>,
+}
+
+/// What a group of duplicates is keyed by.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Key {
+    Gtin(Gtin),
+    Name(String, String),
+}
+
+/// [Normalize](text::normalize) the text and collapse everything but letters and digits into
+/// single spaces.
+fn normalize(text: &str) -> String {
+    text::normalize(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The group of a branded food.
+fn key(food: &BrandedFoodItem) -> Key {
+    let gtin = food
+        .gtin_upc
+        .as_deref()
+        .and_then(|code| Gtin::parse(code).ok())
+        .filter(|gtin| gtin.to_gtin14().chars().any(|c| c != '0'));
+    if let Some(gtin) = gtin {
+        return Key::Gtin(gtin);
+    }
+    let brand = [&food.brand_name, &food.brand_owner]
+        .iter()
+        .filter_map(|brand| brand.as_deref())
+        .find(|brand| !brand.trim().is_empty())
+        .unwrap_or("");
+    Key::Name(normalize(&food.description), normalize(brand))
+}
+
+/// Deduplicate branded search results, given the details of the branded foods among them. Each
+/// group of duplicates is kept once, at the place of its first result, as its most recently
+/// published food. Ties go to the larger FDC id, which FDC assigns later. Results without details
+/// are kept as they are. Every duplicate folded is pushed to the warnings.
+pub fn branded(
+    results: Vec<AbridgedFoodItem>,
+    details: &[BrandedFoodItem],
+    mut warnings: Option<&mut Warnings>,
+) -> Vec<Folded> {
+    let details = details
+        .iter()
+        .map(|food| (food.fdc_id, food))
+        .collect::<HashMap<_, _>>();
+
+    let mut groups: Vec<Vec<(AbridgedFoodItem, Option<&BrandedFoodItem>)>> = Vec::new();
+    let mut keys: HashMap<Key, usize> = HashMap::new();
+    for result in results {
+        let detail = details.get(&result.fdc_id).copied();
+        match detail.map(key) {
+            Some(key) => match keys.get(&key) {
+                Some(&group) => groups[group].push((result, detail)),
+                None => {
+                    keys.insert(key, groups.len());
+                    groups.push(vec![(result, detail)]);
+                }
+            },
+            None => groups.push(vec![(result, detail)]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|mut group| {
+            group.sort_by_key(|(result, detail)| {
+                std::cmp::Reverse((detail.and_then(|food| food.published()), result.fdc_id))
+            });
+            let mut foods = group.into_iter().map(|(result, _)| result);
+            let food = foods.next().expect("groups are never empty");
+            let older = foods.collect::<Vec<_>>();
+            for duplicate in &older {
+                warn(
+                    &mut warnings,
+                    Warning::Folded {
+                        fdc_id: duplicate.fdc_id,
+                        into: food.fdc_id,
+                    },
+                );
+            }
+            Folded { food, older }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,128 @@
+use super::branded;
+use crate::fdc::{AbridgedFoodItem, BrandedFoodItem};
+
+fn result(fdc_id: i32) -> AbridgedFoodItem {
+    AbridgedFoodItem {
+        fdc_id,
+        data_type: "Branded".into(),
+        description: format!("FOOD {}", fdc_id),
+        food_nutrients: Vec::new(),
+        score: None,
+        publication_date: None,
+    }
+}
+
+fn detail(
+    fdc_id: i32,
+    gtin: Option<&str>,
+    description: &str,
+    brand: &str,
+    published: &str,
+) -> BrandedFoodItem {
+    BrandedFoodItem {
+        fdc_id,
+        description: description.into(),
+        brand_owner: Some(brand.into()),
+        brand_name: None,
+        gtin_upc: gtin.map(String::from),
+        household_serving_full_text: None,
+        ingredients: String::new(),
+        serving_size: 28.0,
+        serving_size_unit: "g".into(),
+        label_nutrients: None,
+        food_nutrients: Vec::new(),
+        publication_date: Some(published.into()),
+    }
+}
+
+fn ids(foods: &[AbridgedFoodItem]) -> Vec<i32> {
+    foods.iter().map(|food| food.fdc_id).collect()
+}
+
+#[test]
+fn groups_by_gtin() {
+    let details = [
+        detail(1, Some("027000612323"), "WESSON OIL", "Conagra", "4/1/2019"),
+        detail(
+            2,
+            Some("00027000612323"),
+            "Wesson Canola",
+            "Conagra",
+            "2021-07-29",
+        ),
+        detail(
+            3,
+            Some("0027000612323"),
+            "WESSON OIL",
+            "Conagra",
+            "10/28/2020",
+        ),
+        detail(4, Some("051000212191"), "WESSON OIL", "Conagra", "4/1/2019"),
+    ];
+    let folded = branded((1..=4).map(result).collect(), &details, None);
+    assert_eq!(folded.len(), 2);
+    assert_eq!(folded[0].food.fdc_id, 2);
+    assert_eq!(ids(&folded[0].older), [3, 1]);
+    assert_eq!(folded[1].food.fdc_id, 4);
+    assert!(folded[1].older.is_empty());
+}
+
+#[test]
+fn groups_by_description_and_brand() {
+    let details = [
+        detail(5, None, "Cheddar Cheese, Sharp", "Tillamook", "4/1/2019"),
+        detail(
+            6,
+            Some("0000"),
+            "CHEDDAR CHEESE SHARP",
+            "TILLAMOOK",
+            "4/1/2019",
+        ),
+        detail(7, None, "Cheddar Cheese, Sharp", "Cabot", "4/1/2021"),
+        detail(
+            8,
+            Some(""),
+            "cheddar cheese  sharp",
+            "Tillamook",
+            "not a date",
+        ),
+        detail(
+            11,
+            Some("027000612324"),
+            "Cheddar Cheese Sharp",
+            "Tillamook",
+            "1/1/2018",
+        ),
+    ];
+    // result 9 has no details and 10 is not branded, so both are kept as they are, while the
+    // GTIN of 11 has a wrong check digit
+    let mut results = (5..=9).map(result).collect::<Vec<_>>();
+    results.push(AbridgedFoodItem {
+        data_type: "Foundation".into(),
+        ..result(10)
+    });
+    results.push(result(11));
+    let folded = branded(results, &details, None);
+    assert_eq!(
+        folded.iter().map(|f| f.food.fdc_id).collect::<Vec<_>>(),
+        [6, 7, 9, 10]
+    );
+    // same date, so the later FDC id wins, and the undated food comes last
+    assert_eq!(ids(&folded[0].older), [5, 11, 8]);
+    assert!(folded[1].older.is_empty());
+}
+
+#[test]
+fn groups_ignoring_diacritics() {
+    let details = [
+        detail(1, None, "Jalapeño Poppers", "Nestlé", "4/1/2019"),
+        detail(2, None, "JALAPENO POPPERS", "NESTLE", "2021-07-29"),
+        detail(3, None, "Jalapeño Poppers", "Nestlé Purina", "4/1/2019"),
+    ];
+    let folded = branded((1..=3).map(result).collect(), &details, None);
+    assert_eq!(
+        folded.iter().map(|f| f.food.fdc_id).collect::<Vec<_>>(),
+        [2, 3]
+    );
+    assert_eq!(ids(&folded[0].older), [1]);
+}
@@ -0,0 +1,349 @@
+//! This module provides the [`Environment`] struct which holds all the information we need from
+//! the environment.
+//!
+//! The variables we read are declared in the [`SPEC`] table. Each [`EnvVar`] entry names the
+//! variable, the aliases it may also be found under, whether it is required, and an optional
+//! validator for its value. [`resolve`] walks any such table against a set of key/value pairs, so
+//! supporting a new variable only requires a new table entry.
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(any(feature = "fdc", feature = "storage"))]
+use crate::error::Error;
+use crate::error::Result;
+#[cfg(feature = "fdc")]
+use crate::fdc::{DataType, FDCError, FDCService, CONCURRENCY};
+#[cfg(feature = "storage")]
+use crate::storage;
+
+#[cfg(feature = "fdc")]
+use reqwest::Client;
+
+/// The failure to build a working configuration from the environment.
+#[derive(Debug)]
+pub enum EnvError {
+    /// A required variable is not set, along with a present name it may be a typo of.
+    Missing {
+        name: &'static str,
+        near_miss: Option<String>,
+    },
+    /// A variable is set to a value its validator rejects.
+    Invalid { name: &'static str, reason: String },
+    /// FDC cannot be reached at the configured root, with why, the key left out.
+    #[cfg(feature = "fdc")]
+    Unreachable { url: String, reason: String },
+    /// FDC rejected the configured key.
+    #[cfg(feature = "fdc")]
+    KeyRejected,
+    /// FDC answered the startup ping with another error status.
+    #[cfg(feature = "fdc")]
+    Unexpected { url: String, status: u16 },
+    /// The configured database cannot be opened.
+    #[cfg(feature = "storage")]
+    Database { url: String, source: Box<Error> },
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnvError::Missing { name, near_miss } => {
+                write!(f, "Environment needs {} value", name)?;
+                match near_miss {
+                    Some(key) => write!(f, " (found {}, did you mean {}?)", key, name),
+                    None => Ok(()),
+                }
+            }
+            EnvError::Invalid { name, reason } => write!(f, "Invalid {} value: {}", name, reason),
+            #[cfg(feature = "fdc")]
+            EnvError::Unreachable { url, reason } => {
+                write!(f, "Cannot reach FDC at {}: {}", url, reason)
+            }
+            #[cfg(feature = "fdc")]
+            EnvError::KeyRejected => write!(f, "Invalid FDC_KEY value: rejected by FDC"),
+            #[cfg(feature = "fdc")]
+            EnvError::Unexpected { url, status } => {
+                write!(f, "FDC at {} answered the ping with status {}", url, status)
+            }
+            #[cfg(feature = "storage")]
+            EnvError::Database { url, source } => {
+                write!(f, "Cannot open DATABASE_URL {}: {}", url, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "storage")]
+            EnvError::Database { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Environment {
+    pub database_url: String,
+    pub database_name: String,
+    pub fdc_key: String,
+    /// Another root for the FDC API than [`FDC_URL`](crate::fdc::FDC_URL), if any.
+    pub fdc_url: Option<String>,
+    /// The most FDC requests in flight at once, [`CONCURRENCY`] unless `FDC_CONCURRENCY` is set.
+    #[cfg(feature = "fdc")]
+    pub fdc_concurrency: usize,
+}
+
+impl Environment {
+    /// The FDC service of the configured key, root, and concurrency.
+    #[cfg(feature = "fdc")]
+    pub fn fdc_service(&self) -> FDCService {
+        let service = FDCService::new(self.fdc_key.clone()).with_concurrency(self.fdc_concurrency);
+        match &self.fdc_url {
+            Some(url) => service.with_base_url(url.clone()),
+            None => service,
+        }
+    }
+
+    /// Check at startup that the configuration works: the FDC API accepts the key and the
+    /// database opens, when the `storage` feature is on.
+    #[cfg(feature = "fdc")]
+    pub async fn validate(&self, client: &Client) -> Result<()> {
+        self.validate_fdc(client).await?;
+        #[cfg(feature = "storage")]
+        self.validate_database().await?;
+        Ok(())
+    }
+
+    /// Ping the FDC API with the configured key, by listing a single food through the
+    /// [`FDCService`] of the environment.
+    #[cfg(feature = "fdc")]
+    pub async fn validate_fdc(&self, client: &Client) -> Result<()> {
+        let service = self.fdc_service();
+        match service
+            .v1_foods_list(client, DataType::Foundation, 1, 1)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(Error::Fdc(FDCError::Status(401 | 403))) => Err(EnvError::KeyRejected.into()),
+            Err(Error::Fdc(FDCError::Status(status))) => Err(EnvError::Unexpected {
+                url: service.base_url,
+                status,
+            }
+            .into()),
+            // the url of the request, which reqwest errors print, holds the key
+            Err(Error::Fdc(FDCError::Http(e))) => Err(EnvError::Unreachable {
+                reason: e.to_string().replace(&service.fdc_key, "<FDC_KEY>"),
+                url: service.base_url,
+            }
+            .into()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Open the database at the configured url.
+    #[cfg(feature = "storage")]
+    pub async fn validate_database(&self) -> Result<()> {
+        storage::open(&self.database_url)
+            .await
+            .map(|_| ())
+            .map_err(|source| {
+                EnvError::Database {
+                    url: self.database_url.clone(),
+                    source: Box::new(source),
+                }
+                .into()
+            })
+    }
+}
+
+/// A validator receives the raw value of a variable and rejects it with a reason.
+pub type Validator = fn(&str) -> std::result::Result<(), String>;
+
+/// Declares a single variable we may read from the environment.
+#[derive(Clone, Copy, Debug)]
+pub struct EnvVar {
+    /// The canonical name, also used as the key in the resolved values.
+    pub name: &'static str,
+    /// Other names the variable is accepted under, checked after `name`.
+    pub aliases: &'static [&'static str],
+    /// Whether [`resolve`] fails when the variable is missing.
+    pub required: bool,
+    /// An optional check run on the value once it is found.
+    pub validator: Option<Validator>,
+}
+
+/// The variables [`get`] reads to build an [`Environment`].
+pub const SPEC: &[EnvVar] = &[
+    EnvVar {
+        name: "DATABASE_URL",
+        aliases: &[],
+        required: true,
+        validator: Some(non_empty),
+    },
+    EnvVar {
+        name: "DATABASE_NAME",
+        aliases: &[],
+        required: true,
+        validator: Some(non_empty),
+    },
+    EnvVar {
+        name: "FDC_KEY",
+        aliases: &[],
+        required: true,
+        validator: Some(non_empty),
+    },
+    EnvVar {
+        name: "FDC_URL",
+        aliases: &[],
+        required: false,
+        validator: Some(non_empty),
+    },
+    EnvVar {
+        name: "FDC_CONCURRENCY",
+        aliases: &[],
+        required: false,
+        // parsed by `get` with the `fdc` feature, which rejects what is not a whole number above
+        // zero
+        validator: None,
+    },
+];
+
+/// Names within this edit distance of a missing variable are suggested in the error message.
+const HINT_DISTANCE: usize = 2;
+
+/// Rejects values which are empty or only whitespace.
+pub fn non_empty(value: &str) -> std::result::Result<(), String> {
+    if value.trim().is_empty() {
+        Err("value is empty".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Load the `.env` file, if any, and build an [`Environment`] from the process environment.
+pub fn get() -> Result<Environment> {
+    dotenv::dotenv().ok();
+    environment(resolve(SPEC, std::env::vars())?)
+}
+
+/// Build an [`Environment`] from the values [`resolve`] found for [`SPEC`].
+fn environment(mut values: HashMap<&'static str, String>) -> Result<Environment> {
+    // without the `fdc` feature, there is no service for the concurrency to apply to
+    #[cfg(feature = "fdc")]
+    let fdc_concurrency = match values.remove("FDC_CONCURRENCY") {
+        Some(value) => concurrency(&value).map_err(|reason| EnvError::Invalid {
+            name: "FDC_CONCURRENCY",
+            reason,
+        })?,
+        None => CONCURRENCY,
+    };
+    let mut take = |name: &'static str| {
+        values.remove(name).ok_or(EnvError::Missing {
+            name,
+            near_miss: None,
+        })
+    };
+    Ok(Environment {
+        database_url: take("DATABASE_URL")?,
+        database_name: take("DATABASE_NAME")?,
+        fdc_key: take("FDC_KEY")?,
+        fdc_url: values.remove("FDC_URL"),
+        #[cfg(feature = "fdc")]
+        fdc_concurrency,
+    })
+}
+
+/// The concurrency of the value, a whole number above zero.
+#[cfg(feature = "fdc")]
+fn concurrency(value: &str) -> std::result::Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(0) => Err("value must be above zero".into()),
+        Ok(concurrency) => Ok(concurrency),
+        Err(_) => Err(format!("{:?} is not a whole number", value)),
+    }
+}
+
+/// Resolve every variable of `spec` against the `vars` key/value pairs, returning the found values
+/// keyed by their canonical name. Fails on the first required variable which is missing or on the
+/// first value rejected by its validator.
+pub fn resolve<I>(spec: &[EnvVar], vars: I) -> Result<HashMap<&'static str, String>>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let present = vars.into_iter().collect::<HashMap<_, _>>();
+    let mut values = HashMap::with_capacity(spec.len());
+    for var in spec {
+        let found = std::iter::once(&var.name)
+            .chain(var.aliases)
+            .find_map(|key| present.get(*key));
+        match found {
+            Some(value) => {
+                if let Some(validate) = var.validator {
+                    validate(value).map_err(|reason| EnvError::Invalid {
+                        name: var.name,
+                        reason,
+                    })?;
+                }
+                values.insert(var.name, value.clone());
+            }
+            None if var.required => {
+                return Err(EnvError::Missing {
+                    name: var.name,
+                    near_miss: near_miss(var, spec, present.keys()).cloned(),
+                }
+                .into());
+            }
+            None => {}
+        }
+    }
+    Ok(values)
+}
+
+/// Find the present key closest to the name or aliases of `var`, ignoring keys that are exact names
+/// of some other variable of `spec`.
+fn near_miss<'a, I>(var: &EnvVar, spec: &[EnvVar], present: I) -> Option<&'a String>
+where
+    I: Iterator<Item = &'a String>,
+{
+    let known = |key: &str| {
+        spec.iter()
+            .any(|v| v.name == key || v.aliases.contains(&key))
+    };
+    present
+        .filter(|key| !known(key))
+        .filter_map(|key| {
+            std::iter::once(&var.name)
+                .chain(var.aliases)
+                .map(|name| edit_distance(key, name))
+                .min()
+                .filter(|d| *d <= HINT_DISTANCE)
+                .map(|d| (d, key))
+        })
+        .min()
+        .map(|(_, key)| key)
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diag
+            } else {
+                1 + diag.min(above).min(row[j])
+            };
+            diag = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,228 @@
+use super::*;
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn edit_distances() {
+    assert_eq!(edit_distance("DATABASE_URL", "DATABASE_URL"), 0);
+    assert_eq!(edit_distance("DATABSE_URL", "DATABASE_URL"), 1);
+    assert_eq!(edit_distance("DATABASE_URI", "DATABASE_URL"), 1);
+    assert_eq!(edit_distance("", "FDC"), 3);
+}
+
+#[test]
+fn resolve_all_present() {
+    let values = resolve(
+        SPEC,
+        vars(&[
+            ("DATABASE_URL", "sqlite://foods.db"),
+            ("DATABASE_NAME", "foods"),
+            ("FDC_KEY", "DEMO_KEY"),
+            ("HOME", "/root"),
+        ]),
+    )
+    .unwrap();
+    assert_eq!(values.len(), 3);
+    assert_eq!(values["DATABASE_URL"], "sqlite://foods.db");
+    assert_eq!(values["FDC_KEY"], "DEMO_KEY");
+}
+
+#[test]
+fn resolve_typo_hint() {
+    let err = resolve(
+        SPEC,
+        vars(&[
+            ("DATABSE_URL", "sqlite://foods.db"),
+            ("DATABASE_NAME", "foods"),
+            ("FDC_KEY", "DEMO_KEY"),
+        ]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Environment needs DATABASE_URL value (found DATABSE_URL, did you mean DATABASE_URL?)"
+    );
+}
+
+#[test]
+fn resolve_no_hint_for_distant_or_known_names() {
+    // `DATABASE_NAME` is within distance 2 of nothing missing, and `DB` is too far away
+    let err = resolve(
+        SPEC,
+        vars(&[("DB", "x"), ("DATABASE_NAME", "foods"), ("FDC_KEY", "k")]),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Environment needs DATABASE_URL value");
+}
+
+#[test]
+fn resolve_validator_rejects() {
+    let err = resolve(
+        SPEC,
+        vars(&[
+            ("DATABASE_URL", "sqlite://foods.db"),
+            ("DATABASE_NAME", "  "),
+            ("FDC_KEY", "DEMO_KEY"),
+        ]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid DATABASE_NAME value: value is empty"
+    );
+}
+
+#[test]
+fn resolve_table_only_variable() {
+    // a new variable only needs an entry in a table
+    const EXTENDED: &[EnvVar] = &[
+        EnvVar {
+            name: "FDC_KEY",
+            aliases: &[],
+            required: true,
+            validator: None,
+        },
+        EnvVar {
+            name: "FDC_TIMEOUT",
+            aliases: &["FDC_TIMEOUT_SECS"],
+            required: false,
+            validator: Some(|value| value.parse::<u64>().map(|_| ()).map_err(|e| e.to_string())),
+        },
+    ];
+    let values = resolve(EXTENDED, vars(&[("FDC_KEY", "k")])).unwrap();
+    assert!(!values.contains_key("FDC_TIMEOUT"));
+    let values = resolve(
+        EXTENDED,
+        vars(&[("FDC_KEY", "k"), ("FDC_TIMEOUT_SECS", "30")]),
+    )
+    .unwrap();
+    assert_eq!(values["FDC_TIMEOUT"], "30");
+    assert!(resolve(EXTENDED, vars(&[("FDC_KEY", "k"), ("FDC_TIMEOUT", "soon")])).is_err());
+}
+
+#[cfg(feature = "fdc")]
+#[test]
+fn resolve_concurrency() {
+    let required = [
+        ("DATABASE_URL", "sqlite://foods.db"),
+        ("DATABASE_NAME", "foods"),
+        ("FDC_KEY", "DEMO_KEY"),
+    ];
+    let env = |pairs: &[(&str, &str)]| {
+        environment(resolve(SPEC, vars(&[&required[..], pairs].concat())).unwrap())
+    };
+    assert_eq!(env(&[]).unwrap().fdc_concurrency, crate::fdc::CONCURRENCY);
+    assert_eq!(
+        env(&[("FDC_CONCURRENCY", " 8 ")]).unwrap().fdc_concurrency,
+        8
+    );
+
+    for (value, reason) in [
+        ("0", "value must be above zero"),
+        ("many", "\"many\" is not a whole number"),
+    ]
+    .iter()
+    {
+        let err = env(&[("FDC_CONCURRENCY", *value)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Invalid FDC_CONCURRENCY value: {}", reason)
+        );
+    }
+}
+
+#[cfg(all(feature = "fdc", feature = "storage"))]
+mod validate {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// A server which only accepts the key "GOOD_KEY", on the requests of the service listing a
+    /// single food.
+    async fn fdc() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/foods/list"))
+            .and(query_param("api_key", "GOOD_KEY"))
+            .and(body_partial_json(serde_json::json!({ "pageSize": 1 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/foods/list"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn environment(fdc_key: &str, fdc_url: String) -> Environment {
+        Environment {
+            database_url: "sqlite::memory:".into(),
+            database_name: "foods".into(),
+            fdc_key: fdc_key.into(),
+            fdc_url: Some(fdc_url),
+            fdc_concurrency: crate::fdc::CONCURRENCY,
+        }
+    }
+
+    #[test]
+    fn applies_concurrency() {
+        let mut env = environment("GOOD_KEY", "http://localhost".into());
+        assert_eq!(env.fdc_service().concurrency, crate::fdc::CONCURRENCY);
+        env.fdc_concurrency = 8;
+        assert_eq!(env.fdc_service().concurrency, 8);
+    }
+
+    #[tokio::test]
+    async fn accepts_working_configuration() {
+        let server = fdc().await;
+        let client = reqwest::Client::new();
+        environment("GOOD_KEY", server.uri())
+            .validate(&client)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_key() {
+        let server = fdc().await;
+        let client = reqwest::Client::new();
+        let err = environment("BAD_KEY", server.uri())
+            .validate(&client)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid FDC_KEY value: rejected by FDC");
+    }
+
+    #[tokio::test]
+    async fn hides_key_when_unreachable() {
+        // nothing listens on the port
+        let env = environment("SECRET_KEY", "http://127.0.0.1:9".into());
+        let client = reqwest::Client::new();
+        let err = env.validate_fdc(&client).await.unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.starts_with("Cannot reach FDC at http://127.0.0.1:9"),
+            "{}",
+            message
+        );
+        assert!(!message.contains("SECRET_KEY"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_database() {
+        let server = fdc().await;
+        let client = reqwest::Client::new();
+        let mut env = environment("GOOD_KEY", server.uri());
+        env.database_url = "postgres://localhost/foods".into();
+        assert!(env.validate_fdc(&client).await.is_ok());
+        let err = env.validate(&client).await.unwrap_err();
+        assert!(err.to_string().starts_with("Cannot open DATABASE_URL"));
+    }
+}
@@ -0,0 +1,252 @@
+//! This module declares the [`Error`] every fallible function of the crate returns. Its variants
+//! tell where a failure comes from, so that applications can handle a rejected key differently
+//! from a typo in a quantity, and retry only what is worth retrying.
+
+#[cfg(feature = "env")]
+use crate::env::EnvError;
+#[cfg(feature = "fdc")]
+use crate::fdc::bulk::ValidationReport;
+#[cfg(feature = "fdc")]
+use crate::fdc::FDCError;
+#[cfg(feature = "fdc")]
+use crate::nutrition::{NeedsDensity, NutritionError};
+use crate::progress::Cancelled;
+use crate::quantities::ParseQuantityError;
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// The result of the fallible functions of the crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The failure of a function of the crate.
+#[derive(Debug)]
+pub enum Error {
+    /// A request to the FDC API failed, or FDC answered with something other than foods.
+    #[cfg(feature = "fdc")]
+    Fdc(FDCError),
+    /// Quantities could not be read or combined, like a mass added to a volume.
+    Parse(ParseQuantityError),
+    /// The environment lacks a variable, has an invalid one, or its FDC key or database does not
+    /// work.
+    #[cfg(feature = "env")]
+    Env(EnvError),
+    /// The database or a file could not be read or written, or holds malformed data.
+    Storage(StorageError),
+    /// Nutrients could not be computed, like for a quantity no portion of the food matches.
+    #[cfg(feature = "fdc")]
+    Nutrition(NutritionError),
+    /// A long-running operation was aborted through its
+    /// [`CancellationToken`](crate::progress::CancellationToken).
+    Cancelled(Cancelled),
+}
+
+/// The failure to read or write the database or a file, like a recipe, a bulk download, or an
+/// export. Only the failures of the database need the `storage` feature.
+#[derive(Debug)]
+pub enum StorageError {
+    /// The database failed, like a locked file or a failed query.
+    #[cfg(feature = "storage")]
+    Database(sqlx::Error),
+    /// The database url has another scheme than `sqlite:`.
+    #[cfg(feature = "storage")]
+    UnsupportedUrl(String),
+    /// A file could not be opened, like a missing file of a bulk download.
+    Read {
+        path: PathBuf,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A row of a CSV file of a bulk download is malformed.
+    Csv { file: String, source: csv::Error },
+    /// A bulk download has more bad rows than allowed, as reported so far.
+    #[cfg(feature = "fdc")]
+    TooManyErrors(Box<ValidationReport>),
+    /// Stored, imported, or exported JSON is malformed, or a value cannot be written as JSON.
+    Json(serde_json::Error),
+    /// An export could not be written to its output.
+    Write(Box<dyn std::error::Error + Send + Sync>),
+    /// A TOML document, like a recipe or targets, is malformed.
+    Toml(toml::de::Error),
+    /// A value cannot be written as TOML.
+    TomlSerialize(toml::ser::Error),
+    /// The data is well-formed but inconsistent, like a bulk download which is not sorted by FDC
+    /// id or an export of an unknown version.
+    Invalid(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "storage")]
+            StorageError::Database(e) => write!(f, "{}", e),
+            #[cfg(feature = "storage")]
+            StorageError::UnsupportedUrl(url) => {
+                write!(
+                    f,
+                    "Unsupported database url {}, expected a sqlite: url",
+                    url
+                )
+            }
+            StorageError::Read { path, source } => {
+                write!(f, "Cannot read {}: {}", path.display(), source)
+            }
+            StorageError::Csv { file, source } => write!(f, "Malformed {}: {}", file, source),
+            #[cfg(feature = "fdc")]
+            StorageError::TooManyErrors(report) => match report.errors.first() {
+                Some(first) => write!(
+                    f,
+                    "Stopped after {} bad rows in the bulk download, the first at {}",
+                    report.error_count, first
+                ),
+                None => write!(f, "Stopped after {} bad rows", report.error_count),
+            },
+            StorageError::Write(e) => write!(f, "Cannot write the export: {}", e),
+            StorageError::Json(e) => write!(f, "{}", e),
+            StorageError::Toml(e) => write!(f, "{}", e),
+            StorageError::TomlSerialize(e) => write!(f, "{}", e),
+            StorageError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "storage")]
+            StorageError::Database(e) => Some(e),
+            StorageError::Read { source, .. } | StorageError::Write(source) => {
+                Some(source.as_ref())
+            }
+            StorageError::Csv { source, .. } => Some(source),
+            StorageError::Json(e) => Some(e),
+            StorageError::Toml(e) => Some(e),
+            StorageError::TomlSerialize(e) => Some(e),
+            #[cfg(feature = "storage")]
+            StorageError::UnsupportedUrl(_) => None,
+            #[cfg(feature = "fdc")]
+            StorageError::TooManyErrors(_) => None,
+            StorageError::Invalid(_) => None,
+        }
+    }
+}
+
+impl Error {
+    /// Whether trying again later may succeed, like after a timeout or a server error of FDC.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "fdc")]
+            Error::Fdc(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "fdc")]
+            Error::Fdc(e) => e.fmt(f),
+            Error::Parse(e) => e.fmt(f),
+            #[cfg(feature = "env")]
+            Error::Env(e) => e.fmt(f),
+            Error::Storage(e) => e.fmt(f),
+            #[cfg(feature = "fdc")]
+            Error::Nutrition(e) => e.fmt(f),
+            Error::Cancelled(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "fdc")]
+            Error::Fdc(e) => e.source(),
+            Error::Parse(e) => e.source(),
+            #[cfg(feature = "env")]
+            Error::Env(e) => e.source(),
+            Error::Storage(e) => e.source(),
+            #[cfg(feature = "fdc")]
+            Error::Nutrition(e) => e.source(),
+            Error::Cancelled(e) => e.source(),
+        }
+    }
+}
+
+#[cfg(feature = "fdc")]
+impl From<FDCError> for Error {
+    fn from(e: FDCError) -> Error {
+        Error::Fdc(e)
+    }
+}
+
+impl From<ParseQuantityError> for Error {
+    fn from(e: ParseQuantityError) -> Error {
+        Error::Parse(e)
+    }
+}
+
+#[cfg(feature = "env")]
+impl From<EnvError> for Error {
+    fn from(e: EnvError) -> Error {
+        Error::Env(e)
+    }
+}
+
+impl From<StorageError> for Error {
+    fn from(e: StorageError) -> Error {
+        Error::Storage(e)
+    }
+}
+
+#[cfg(feature = "fdc")]
+impl From<NutritionError> for Error {
+    fn from(e: NutritionError) -> Error {
+        Error::Nutrition(e)
+    }
+}
+
+impl From<Cancelled> for Error {
+    fn from(e: Cancelled) -> Error {
+        Error::Cancelled(e)
+    }
+}
+
+#[cfg(feature = "fdc")]
+impl From<NeedsDensity> for Error {
+    fn from(e: NeedsDensity) -> Error {
+        Error::Nutrition(NutritionError::NeedsDensity(e))
+    }
+}
+
+#[cfg(feature = "fdc")]
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Error {
+        Error::Fdc(FDCError::Http(e))
+    }
+}
+
+#[cfg(feature = "storage")]
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Error {
+        Error::Storage(StorageError::Database(e))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Storage(StorageError::Json(e))
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Error {
+        Error::Storage(StorageError::Toml(e))
+    }
+}
+
+impl From<toml::ser::Error> for Error {
+    fn from(e: toml::ser::Error) -> Error {
+        Error::Storage(StorageError::TomlSerialize(e))
+    }
+}
@@ -0,0 +1,123 @@
+//! This module dumps the food log and recipes to a single versioned JSON document, like
+//!
+//! ```json
+//! { "version": 1, "entries": [...], "recipes": [...] }
+//! ```
+//!
+//! which [`import_json`] reads back on another machine.
+
+use crate::error::Result;
+use crate::log::{FoodLog, LogEntry};
+use crate::recipe::Recipe;
+use crate::storage::StorageError;
+
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+
+/// The version of the documents [`dump_json`] writes, and the only one [`import_json`] reads.
+pub const DUMP_VERSION: u64 = 1;
+
+/// The document [`dump_json`] writes.
+#[derive(Serialize)]
+struct Dump<'a> {
+    version: u64,
+    entries: &'a [LogEntry],
+    recipes: &'a [Recipe],
+}
+
+/// The document [`import_json`] reads, whose records are deserialized one at a time so that a
+/// malformed record does not fail the others.
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    entries: Vec<serde_json::Value>,
+    #[serde(default)]
+    recipes: Vec<serde_json::Value>,
+}
+
+/// What [`import_json`] did with the records of a document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    /// The number of entries added to the log.
+    pub inserted: usize,
+    /// The number of entries whose id the log already had.
+    pub skipped: usize,
+    /// The number of entries and recipes which were malformed or could not be added.
+    pub failed: usize,
+    /// The recipes of the document, for the caller to keep wherever it keeps recipes.
+    pub recipes: Vec<Recipe>,
+}
+
+/// Write every entry of the log, along with the recipes, as a JSON document.
+pub async fn dump_json<L, W>(log: &L, recipes: &[Recipe], out: W) -> Result<()>
+where
+    L: FoodLog + ?Sized,
+    W: Write,
+{
+    let entries = log
+        .entries_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+        .await?;
+    let dump = Dump {
+        version: DUMP_VERSION,
+        entries: &entries,
+        recipes,
+    };
+    serde_json::to_writer_pretty(out, &dump)?;
+    Ok(())
+}
+
+/// Read a document written by [`dump_json`], adding its entries to the log. Entries whose id the
+/// log already has are skipped, so importing the same document twice adds nothing. Documents of
+/// any version other than [`DUMP_VERSION`] are rejected before anything is added.
+pub async fn import_json<L, R>(log: &L, input: R) -> Result<ImportReport>
+where
+    L: FoodLog + ?Sized,
+    R: Read,
+{
+    let document: serde_json::Value = serde_json::from_reader(input)?;
+    match document.get("version").and_then(serde_json::Value::as_u64) {
+        Some(DUMP_VERSION) => {}
+        Some(version) => {
+            return Err(StorageError::Invalid(format!(
+                "Cannot import version {} of the JSON export, only version {} is supported",
+                version, DUMP_VERSION
+            ))
+            .into())
+        }
+        None => return Err(StorageError::Invalid("The JSON export has no version".into()).into()),
+    }
+    let envelope: Envelope = serde_json::from_value(document)?;
+
+    let mut known = log
+        .entries_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+        .await?
+        .into_iter()
+        .map(|entry| entry.id)
+        .collect::<HashSet<_>>();
+    let mut report = ImportReport::default();
+    for entry in envelope.entries {
+        let entry = match serde_json::from_value::<LogEntry>(entry) {
+            Ok(entry) => entry,
+            Err(_) => {
+                report.failed += 1;
+                continue;
+            }
+        };
+        if known.contains(&entry.id) {
+            report.skipped += 1;
+        } else if log.add(&entry).await.is_ok() {
+            known.insert(entry.id);
+            report.inserted += 1;
+        } else {
+            report.failed += 1;
+        }
+    }
+    for recipe in envelope.recipes {
+        match serde_json::from_value(recipe) {
+            Ok(recipe) => report.recipes.push(recipe),
+            Err(_) => report.failed += 1,
+        }
+    }
+    Ok(report)
+}
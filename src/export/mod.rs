@@ -0,0 +1,110 @@
+//! This module writes foods and nutrient profiles to CSV for spreadsheets, and dumps the food log
+//! and recipes to JSON through the [`json`] module.
+//!
+//! Nutrient columns always follow the declaration order of [`Nutrient`], whatever order they are
+//! requested in, and are headed by the nutrient name and unit like `"Sodium (mg)"`. Amounts which
+//! are unknown are left blank, as are the nutrients of foods which cannot be brought to 100 g.
+
+pub mod json;
+
+pub use json::{dump_json, import_json, ImportReport, DUMP_VERSION};
+
+use crate::error::Result;
+use crate::fdc::FDCMeta;
+use crate::nutrition::{Nutrient, NutrientProfile};
+use crate::storage::StorageError;
+
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Write one row per food with its FDC id, description, data type, brand, and the requested
+/// nutrients per 100 g.
+pub fn write_foods_csv<W: Write>(foods: &[FDCMeta], nutrients: &[Nutrient], out: W) -> Result<()> {
+    let nutrients = columns(nutrients);
+    let mut writer = csv::Writer::from_writer(out);
+
+    let mut header = vec![
+        "fdc_id".to_string(),
+        "description".to_string(),
+        "data_type".to_string(),
+        "brand".to_string(),
+    ];
+    header.extend(
+        nutrients
+            .iter()
+            .map(|n| format!("{} ({}/100 g)", n.name(), n.unit().symbol())),
+    );
+    writer.write_record(&header).map_err(write_error)?;
+
+    for food in foods {
+        let profile = NutrientProfile::per_100g(food).unwrap_or_default();
+        let mut record = vec![
+            food.fdc_id().to_string(),
+            food.description().to_string(),
+            food.data_type().to_string(),
+            food.brand().unwrap_or("").to_string(),
+        ];
+        record.extend(amounts(&profile, &nutrients));
+        writer.write_record(&record).map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)?;
+    Ok(())
+}
+
+/// Write one row per day, in date order, with the requested nutrients of its profile.
+pub fn write_profile_csv<W: Write>(
+    profiles: &BTreeMap<NaiveDate, NutrientProfile>,
+    nutrients: &[Nutrient],
+    out: W,
+) -> Result<()> {
+    let nutrients = columns(nutrients);
+    let mut writer = csv::Writer::from_writer(out);
+
+    let mut header = vec!["date".to_string()];
+    header.extend(nutrients.iter().map(|n| heading(*n)));
+    writer.write_record(&header).map_err(write_error)?;
+
+    for (date, profile) in profiles {
+        let mut record = vec![date.format("%Y-%m-%d").to_string()];
+        record.extend(amounts(profile, &nutrients));
+        writer.write_record(&record).map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)?;
+    Ok(())
+}
+
+/// The failure to write a row of an export.
+fn write_error<E: std::error::Error + Send + Sync + 'static>(e: E) -> StorageError {
+    StorageError::Write(Box::new(e))
+}
+
+/// The requested nutrients, once each and in declaration order.
+fn columns(nutrients: &[Nutrient]) -> Vec<Nutrient> {
+    Nutrient::all()
+        .iter()
+        .copied()
+        .filter(|n| nutrients.contains(n))
+        .collect()
+}
+
+/// The column heading of a nutrient, like `"Sodium (mg)"`.
+fn heading(nutrient: Nutrient) -> String {
+    format!("{} ({})", nutrient.name(), nutrient.unit().symbol())
+}
+
+/// The amounts of the nutrients in the profile, blank when unknown.
+fn amounts<'a>(
+    profile: &'a NutrientProfile,
+    nutrients: &'a [Nutrient],
+) -> impl Iterator<Item = String> + 'a {
+    nutrients.iter().map(move |n| {
+        profile
+            .get(*n)
+            .map(|amount| amount.to_string())
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,248 @@
+use super::*;
+
+/// A branded food whose label reports 120 kcal and 230 mg sodium per 30 g serving.
+fn crackers() -> FDCMeta {
+    let label = [
+        ("fat", 4.5),
+        ("saturatedFat", 1.0),
+        ("transFat", 0.0),
+        ("cholesterol", 0.0),
+        ("sodium", 230.0),
+        ("carbohydrates", 20.0),
+        ("fiber", 1.0),
+        ("sugars", 0.0),
+        ("protein", 3.0),
+        ("calcium", 0.0),
+        ("iron", 1.1),
+        ("potassium", 40.0),
+        ("calories", 120.0),
+    ]
+    .iter()
+    .map(|(name, value)| (name.to_string(), serde_json::json!({ "value": value })))
+    .collect::<serde_json::Map<_, _>>();
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Branded",
+        "fdcId": 5001,
+        "description": "CRACKERS, \"EVERYTHING\" SEASONED, BAKED",
+        "brandOwner": "Snacks, Inc.",
+        "ingredients": "",
+        "servingSize": 30.0,
+        "servingSizeUnit": "g",
+        "labelNutrients": label,
+    }))
+    .unwrap()
+}
+
+/// A non-branded food reporting only protein.
+fn lentils() -> FDCMeta {
+    serde_json::from_value(serde_json::json!({
+        "dataType": "SR Legacy",
+        "fdcId": 5002,
+        "description": "Lentils, raw",
+        "foodAttributes": [],
+        "foodPortions": [],
+        "foodNutrients": [{
+            "id": 1,
+            "nutrient": { "id": 1003, "name": "Protein", "unitName": "g" },
+            "amount": 24.6,
+        }],
+    }))
+    .unwrap()
+}
+
+fn read(bytes: &[u8]) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut reader = csv::Reader::from_reader(bytes);
+    let header = reader.headers().unwrap().iter().map(String::from).collect();
+    let rows = reader
+        .records()
+        .map(|record| record.unwrap().iter().map(String::from).collect())
+        .collect();
+    (header, rows)
+}
+
+#[test]
+fn foods_round_trip() {
+    let mut out = Vec::new();
+    write_foods_csv(
+        &[crackers(), lentils()],
+        &[Nutrient::Sodium, Nutrient::Protein, Nutrient::Sodium],
+        &mut out,
+    )
+    .unwrap();
+    let (header, rows) = read(&out);
+    assert_eq!(
+        header,
+        vec![
+            "fdc_id",
+            "description",
+            "data_type",
+            "brand",
+            "Protein (g/100 g)",
+            "Sodium (mg/100 g)",
+        ]
+    );
+    assert_eq!(rows[0][0], "5001");
+    assert_eq!(rows[0][1], "CRACKERS, \"EVERYTHING\" SEASONED, BAKED");
+    assert_eq!(rows[0][2], "Branded");
+    assert_eq!(rows[0][3], "Snacks, Inc.");
+    assert!((rows[0][4].parse::<f32>().unwrap() - 10.0).abs() < 1e-4);
+    assert!((rows[0][5].parse::<f32>().unwrap() - 766.6667).abs() < 1e-2);
+    assert_eq!(rows[1][2], "SR Legacy");
+    assert_eq!(rows[1][3], "");
+    assert_eq!(rows[1][4].parse::<f32>().unwrap(), 24.6);
+    assert_eq!(rows[1][5], "");
+}
+
+#[test]
+fn profiles_round_trip() {
+    let day = |kcal, sodium| {
+        let mut profile = NutrientProfile::new();
+        profile.insert(Nutrient::Energy, kcal);
+        if let Some(sodium) = sodium {
+            profile.insert(Nutrient::Sodium, sodium);
+        }
+        profile
+    };
+    let mut profiles = BTreeMap::new();
+    profiles.insert(
+        NaiveDate::from_ymd_opt(2024, 3, 2).unwrap(),
+        day(1800.5, None),
+    );
+    profiles.insert(
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+        day(2100.0, Some(2300.0)),
+    );
+
+    let mut out = Vec::new();
+    write_profile_csv(&profiles, &[Nutrient::Sodium, Nutrient::Energy], &mut out).unwrap();
+    let (header, rows) = read(&out);
+    assert_eq!(header, vec!["date", "Energy (kcal)", "Sodium (mg)"]);
+    assert_eq!(rows[0], vec!["2024-03-01", "2100", "2300"]);
+    assert_eq!(rows[1], vec!["2024-03-02", "1800.5", ""]);
+}
+
+mod json {
+    use super::*;
+    use crate::log::{FoodLog, LogEntry, MemoryLog};
+    use crate::quantities::Quantity;
+    use crate::recipe::Recipe;
+    use crate::storage::SqliteStore;
+
+    use chrono::{TimeZone, Utc};
+
+    fn entries() -> Vec<LogEntry> {
+        vec![
+            LogEntry::new(
+                5001,
+                Quantity::Nominal(2.0, "servings".into()),
+                Utc.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap(),
+            )
+            .with_note("breakfast"),
+            LogEntry::new(
+                5002,
+                Quantity::Nominal(1.0, "cup".into()),
+                Utc.with_ymd_and_hms(2024, 3, 1, 19, 30, 0).unwrap(),
+            ),
+        ]
+    }
+
+    fn recipes() -> Vec<Recipe> {
+        vec![Recipe::from_toml(
+            r#"
+            name = "Lentil soup"
+            servings = 4
+
+            [[ingredients]]
+            fdc_id = 5002
+            quantity = "2 cups"
+            "#,
+        )
+        .unwrap()]
+    }
+
+    async fn dump() -> Vec<u8> {
+        let log = MemoryLog::new();
+        for entry in entries() {
+            log.add(&entry).await.unwrap();
+        }
+        let mut out = Vec::new();
+        dump_json(&log, &recipes(), &mut out).await.unwrap();
+        out
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let out = dump().await;
+        let document: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(document["version"], 1);
+        assert_eq!(document["entries"].as_array().unwrap().len(), 2);
+
+        // into another backend, twice
+        let log = SqliteStore::connect("sqlite::memory:").await.unwrap();
+        let report = import_json(&log, &out[..]).await.unwrap();
+        assert_eq!((report.inserted, report.skipped, report.failed), (2, 0, 0));
+        assert_eq!(report.recipes, recipes());
+        let report = import_json(&log, &out[..]).await.unwrap();
+        assert_eq!((report.inserted, report.skipped, report.failed), (0, 2, 0));
+
+        let mut again = Vec::new();
+        dump_json(&log, &recipes(), &mut again).await.unwrap();
+        assert_eq!(again, out);
+    }
+
+    #[tokio::test]
+    async fn counts_failed_records() {
+        let mut document: serde_json::Value = serde_json::from_slice(&dump().await).unwrap();
+        document["entries"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({ "fdc_id": "not an entry" }));
+        document["recipes"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({ "name": "No ingredients" }));
+        let log = MemoryLog::new();
+        let report = import_json(&log, document.to_string().as_bytes())
+            .await
+            .unwrap();
+        assert_eq!((report.inserted, report.skipped, report.failed), (2, 0, 2));
+        assert_eq!(report.recipes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn ignores_unknown_fields() {
+        let mut document: serde_json::Value = serde_json::from_slice(&dump().await).unwrap();
+        document["exported_by"] = serde_json::json!("a newer build");
+        document["entries"][0]["mood"] = serde_json::json!("hungry");
+        let log = MemoryLog::new();
+        let report = import_json(&log, document.to_string().as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(report.inserted, 2);
+        assert_eq!(report.failed, 0);
+    }
+
+    #[tokio::test]
+    async fn rejects_other_versions() {
+        let log = MemoryLog::new();
+        let mut document: serde_json::Value = serde_json::from_slice(&dump().await).unwrap();
+        document["version"] = serde_json::json!(2);
+        let err = import_json(&log, document.to_string().as_bytes())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("version 2"));
+        document.as_object_mut().unwrap().remove("version");
+        assert!(import_json(&log, document.to_string().as_bytes())
+            .await
+            .is_err());
+        // nothing was imported
+        let all = log
+            .entries_between(
+                Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(all.is_empty());
+    }
+}
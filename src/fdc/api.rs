@@ -1,18 +1,155 @@
 //! Contains all of the json payloads we get from the FDC API.
 
+use super::units::serving_unit;
+use super::DataType;
+use crate::error::Result;
+use crate::nutrition::{Nutrient, NutritionError};
+use crate::quantities::Quantity;
+
+use chrono::NaiveDate;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+use uom::si::energy::kilocalorie;
+use uom::si::f32::{Energy, MassDensity, Volume};
+use uom::si::mass::gram;
+use uom::si::volume::milliliter;
+
 /// Corresponds to the base information every food has.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AbridgedFoodItem {
     pub fdc_id: i32,
     pub data_type: String,
     pub description: String,
     pub food_nutrients: Vec<AbridgedFoodNutrient>,
+    /// How relevant the food is to the search, higher being better.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    /// When FDC published the record, which lists call `publicationDate` and searches
+    /// `publishedDate`.
+    #[serde(
+        default,
+        alias = "publishedDate",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub publication_date: Option<String>,
+}
+
+/// The energy and macronutrients of a food, per 100 g, or per 100 ml for branded foods whose
+/// serving is a volume.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Macros {
+    pub calories: Option<f32>,
+    pub protein_g: Option<f32>,
+    pub carbs_g: Option<f32>,
+    pub fat_g: Option<f32>,
+}
+
+/// The macronutrients, along with the nutrients labels list next to them, which can be read off
+/// a food through [`AbridgedFoodItem::macro_value`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Macro {
+    Energy,
+    Protein,
+    Carbs,
+    Fat,
+    Fiber,
+    Sugar,
+    Sodium,
+}
+
+impl Macro {
+    /// The nutrient of the macro, whose FDC ids and unit it is read with.
+    pub fn nutrient(self) -> Nutrient {
+        match self {
+            Macro::Energy => Nutrient::Energy,
+            Macro::Protein => Nutrient::Protein,
+            Macro::Carbs => Nutrient::Carbohydrate,
+            Macro::Fat => Nutrient::TotalFat,
+            Macro::Fiber => Nutrient::Fiber,
+            Macro::Sugar => Nutrient::Sugars,
+            Macro::Sodium => Nutrient::Sodium,
+        }
+    }
+}
+
+impl AbridgedFoodItem {
+    /// The date FDC published the record, whichever format it is written in.
+    pub fn published(&self) -> Option<NaiveDate> {
+        published(self.publication_date.as_deref()?)
+    }
+
+    /// The energy and macronutrients among the food's nutrients, as
+    /// [`AbridgedFoodItem::macro_value`] reads them.
+    pub fn macros(&self) -> Macros {
+        Macros {
+            calories: self.macro_value(Macro::Energy),
+            protein_g: self.macro_value(Macro::Protein),
+            carbs_g: self.macro_value(Macro::Carbs),
+            fat_g: self.macro_value(Macro::Fat),
+        }
+    }
+
+    /// The amount of the macro among the food's nutrients, converted from whichever unit it is
+    /// reported in to the unit of its [`Nutrient`], like milligrams for sodium. When the nutrient
+    /// is reported under several FDC ids, the first in [`Nutrient::fdc_ids`] wins.
+    pub fn macro_value(&self, m: Macro) -> Option<f32> {
+        let nutrient = m.nutrient();
+        nutrient.fdc_ids().iter().find_map(|id| {
+            self.food_nutrients
+                .iter()
+                .filter(|fnut| fnut.nutrient_id == *id)
+                .find_map(|fnut| nutrient.unit().convert(fnut.value, &fnut.unit_name))
+        })
+    }
+
+    /// The energy in a milliliter of the food, given its density, like that of a beverage whose
+    /// label is per volume while FDC reports its nutrients per 100 g. Multiply by a volume in
+    /// milliliters for the energy in it.
+    pub fn energy_density(&self, density: MassDensity) -> Option<Energy> {
+        let kcal_per_gram = self.macro_value(Macro::Energy)? / FoodNutrient::BASIS;
+        let grams_per_ml = (density * Volume::new::<milliliter>(1.0)).get::<gram>();
+        Some(Energy::new::<kilocalorie>(kcal_per_gram * grams_per_ml))
+    }
+
+    /// The food's nutrients scaled from per 100 g to the amount in grams, like the 75 g actually
+    /// eaten.
+    pub fn nutrients_for_grams(&self, grams: f32) -> Vec<AbridgedFoodNutrient> {
+        self.food_nutrients
+            .iter()
+            .map(|fnut| AbridgedFoodNutrient {
+                value: fnut.value * grams / FoodNutrient::BASIS,
+                ..fnut.clone()
+            })
+            .collect()
+    }
+}
+
+/// Add nutrient lists together, like the nutrients of the ingredients of a recipe, summing the
+/// values of matching nutrient ids. Nutrients keep the name and unit of their first occurrence, in
+/// the order they first occur. Values in another unit than the first occurrence, ignoring case,
+/// are skipped rather than summed.
+pub fn merge_nutrients(lists: &[&[AbridgedFoodNutrient]]) -> Vec<AbridgedFoodNutrient> {
+    let mut merged: Vec<AbridgedFoodNutrient> = Vec::new();
+    for fnut in lists.iter().flat_map(|list| list.iter()) {
+        match merged
+            .iter_mut()
+            .find(|total| total.nutrient_id == fnut.nutrient_id)
+        {
+            Some(total) if total.unit_name.eq_ignore_ascii_case(&fnut.unit_name) => {
+                total.value += fnut.value
+            }
+            Some(_) => {}
+            None => merged.push(fnut.clone()),
+        }
+    }
+    merged
 }
 
 /// Corresponds to a nutrient.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AbridgedFoodNutrient {
     pub nutrient_id: i32,
     pub nutrient_name: String,
@@ -21,10 +158,12 @@ pub struct AbridgedFoodNutrient {
 }
 
 /// Corresponds to the metadata that only branded foods have.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BrandedFoodItem {
     pub fdc_id: i32,
+    #[serde(default)]
+    pub description: String,
     pub brand_owner: Option<String>,
     pub brand_name: Option<String>,
     pub gtin_upc: Option<String>,
@@ -33,11 +172,17 @@ pub struct BrandedFoodItem {
     pub serving_size: f32,
     pub serving_size_unit: String,
     pub label_nutrients: Option<LabelNutrients>,
+    #[serde(default)]
+    pub food_nutrients: Vec<FoodNutrient>,
+    /// When FDC published the record, like `"4/1/2019"` from the API or `"2019-04-01"` from bulk
+    /// downloads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publication_date: Option<String>,
 }
 
 /// Corresponds to label nutrients on branded foods.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LabelNutrients {
     pub fat: LabelNutrient,
     pub saturated_fat: LabelNutrient,
@@ -55,24 +200,103 @@ pub struct LabelNutrients {
 }
 
 /// Corresponds to a single nutrient's data in a branded food.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LabelNutrient {
     pub value: f32,
 }
 
 /// Corresponds to the metadata of collections of both `FoodAttribute` and `FoodPortion` structs.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct APFoodItem {
     pub fdc_id: i32,
+    #[serde(default)]
+    pub description: String,
+    /// The data type FDC gives the food, like `"SR Legacy"`, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<String>,
     pub food_attributes: Vec<FoodAttribute>,
     pub food_portions: Vec<FoodPortion>,
+    #[serde(default)]
+    pub food_nutrients: Vec<FoodNutrient>,
+    #[serde(default)]
+    pub nutrient_conversion_factors: Vec<NutrientConversionFactor>,
+    /// The binomial name of the food's species, given for some foundation foods.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scientific_name: Option<String>,
+    /// The colloquial names the food is known by, comma separated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub common_names: Option<String>,
+}
+
+/// Corresponds to a nutrient conversion factor of a food. Calorie conversion factors give the
+/// kilocalories per gram of protein, fat, and carbohydrate; other factors leave them empty.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NutrientConversionFactor {
+    #[serde(rename = "type", default)]
+    pub factor_type: Option<String>,
+    pub protein_value: Option<f32>,
+    pub fat_value: Option<f32>,
+    pub carbohydrate_value: Option<f32>,
+}
+
+/// Corresponds to a nutrient of a food in the "full" format. Amounts are per 100 g, or per 100 ml
+/// for branded foods whose serving is a volume.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodNutrient {
+    pub id: i32,
+    pub nutrient: NutrientMeta,
+    pub amount: Option<f32>,
+    /// How many analyses the amount is based on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_points: Option<i32>,
+    /// How the amount was obtained, like analysis or calculation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub food_nutrient_derivation: Option<FoodNutrientDerivation>,
+}
+
+impl FoodNutrient {
+    /// The amount of food the nutrient amount is given for, in grams, or in milliliters for
+    /// branded foods whose serving is a volume. FDC reports every "full" amount on this basis.
+    pub const BASIS: f32 = 100.0;
+}
+
+/// Corresponds to how the amount of a nutrient was derived, like code `"A"` for "Analytical".
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodNutrientDerivation {
+    pub id: i32,
+    pub code: Option<String>,
+    pub description: Option<String>,
+    pub food_nutrient_source: Option<FoodNutrientSource>,
+}
+
+/// Corresponds to the source of a nutrient derivation, like "Analytical or derived from
+/// analytical".
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodNutrientSource {
+    pub id: i32,
+    pub code: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Corresponds to the metadata of a nutrient.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NutrientMeta {
+    pub id: i32,
+    pub number: Option<String>,
+    pub name: String,
+    pub unit_name: String,
 }
 
 /// Corresponds to the food attributes,
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FoodAttribute {
     pub id: i32,
     pub sequence_number: Option<i32>,
@@ -80,9 +304,35 @@ pub struct FoodAttribute {
     pub food_attribute_type: FoodAttributeType,
 }
 
+/// The attribute types FDC publishes whose meaning is known, matched by name since their ids
+/// change between releases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeKind {
+    /// Other descriptions of a survey food, like "white bread" for "Bread, white".
+    AdditionalDescription,
+    /// A name the food is commonly known by.
+    CommonName,
+    /// The code of the WWEIA category of a survey food.
+    WweiaCategory,
+    /// An adjustment made to the nutrient values, like "Moisture adjusted".
+    Adjustments,
+}
+
+impl AttributeKind {
+    /// The name of the attribute type in API responses.
+    pub fn as_api_str(&self) -> &'static str {
+        match self {
+            AttributeKind::AdditionalDescription => "Additional Description",
+            AttributeKind::CommonName => "Common Name",
+            AttributeKind::WweiaCategory => "WWEIA Category",
+            AttributeKind::Adjustments => "Adjustments",
+        }
+    }
+}
+
 /// Corresponds to metadata of a food attribute.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FoodAttributeType {
     pub id: i32,
     pub name: String,
@@ -90,8 +340,8 @@ pub struct FoodAttributeType {
 }
 
 /// Corresponds to the portions of a given food.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FoodPortion {
     pub id: i32,
     pub amount: Option<f32>,
@@ -102,10 +352,265 @@ pub struct FoodPortion {
     pub sequence_number: Option<i32>,
 }
 
-/// A helper for parsing whether or not a food falls into the branded category.
-#[derive(Debug, Deserialize)]
+impl FoodPortion {
+    /// Whether the portion weighs something, and has a positive amount when it has one. Portions
+    /// failing this would divide by zero in gram math.
+    pub fn is_valid(&self) -> bool {
+        self.gram_weight.is_finite()
+            && self.gram_weight > 0.0
+            && self
+                .amount
+                .is_none_or(|amount| amount.is_finite() && amount > 0.0)
+    }
+}
+
+impl BrandedFoodItem {
+    /// The date FDC published the record, whichever format it is written in.
+    pub fn published(&self) -> Option<NaiveDate> {
+        published(self.publication_date.as_deref()?)
+    }
+
+    /// The serving size as a mass or volume, read through [`serving_unit`]. Units we do not know
+    /// fail with [`NutritionError::UnknownUnit`] rather than being taken for grams.
+    pub fn serving_size_quantity(&self) -> Result<Quantity> {
+        match serving_unit(&self.serving_size_unit) {
+            Some(unit) => Ok(unit.quantity(self.serving_size)),
+            None => Err(NutritionError::UnknownUnit {
+                fdc_id: self.fdc_id,
+                unit: self.serving_size_unit.clone(),
+            }
+            .into()),
+        }
+    }
+}
+
+/// Parse a publication date, written like `"4/1/2019"` or `"2019-04-01"`.
+fn published(date: &str) -> Option<NaiveDate> {
+    let date = date.trim();
+    NaiveDate::parse_from_str(date, "%m/%d/%Y")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .ok()
+}
+
+impl APFoodItem {
+    /// The portions of the food which are valid, in order.
+    pub fn sanitized_portions(&self) -> impl Iterator<Item = &FoodPortion> {
+        self.food_portions
+            .iter()
+            .filter(|portion| portion.is_valid())
+    }
+
+    /// The portion the food is served by when none is asked for: the valid portion first by
+    /// sequence number.
+    pub fn default_portion(&self) -> Option<&FoodPortion> {
+        self.sanitized_portions()
+            .min_by_key(|portion| portion.sequence_number.unwrap_or(i32::MAX))
+    }
+
+    /// The attributes of the food whose type has the name, ignoring case, in order.
+    pub fn attributes_by_type(&self, type_name: &str) -> Vec<&FoodAttribute> {
+        self.food_attributes
+            .iter()
+            .filter(|attribute| {
+                attribute
+                    .food_attribute_type
+                    .name
+                    .eq_ignore_ascii_case(type_name)
+            })
+            .collect()
+    }
+
+    /// The attributes of the food of a known kind, in order.
+    pub fn attributes_of(&self, kind: AttributeKind) -> Vec<&FoodAttribute> {
+        self.attributes_by_type(kind.as_api_str())
+    }
+
+    /// The additional descriptions of the food, in order.
+    pub fn additional_descriptions(&self) -> Vec<&str> {
+        self.attribute_values(AttributeKind::AdditionalDescription)
+    }
+
+    /// The names the food is commonly known by: those of the `commonNames` field, then those of
+    /// its attributes, each once regardless of case.
+    pub fn common_names(&self) -> Vec<&str> {
+        let listed = self
+            .common_names
+            .iter()
+            .flat_map(|names| names.split(','))
+            .map(str::trim);
+        let mut names = Vec::<&str>::new();
+        for name in listed.chain(self.attribute_values(AttributeKind::CommonName)) {
+            if !name.is_empty() && !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The values of the attributes of the kind, in order.
+    fn attribute_values(&self, kind: AttributeKind) -> Vec<&str> {
+        self.attributes_of(kind)
+            .into_iter()
+            .map(|attribute| attribute.value.as_str())
+            .collect()
+    }
+}
+
+/// A helper for parsing whether or not a food falls into the branded category. Foods are read by
+/// their [`DataType`], and those of any other data type than [`DataType::Branded`], like
+/// `"Survey (FNDDS)"` or data types FDC adds later, deserialize as [`FDCMeta::Other`], which keeps
+/// the data type in [`APFoodItem::data_type`] and serializes back with it.
+#[derive(Clone, Debug, Serialize)]
 #[serde(tag = "dataType")]
 pub enum FDCMeta {
     Branded(BrandedFoodItem),
+    #[serde(untagged)]
     Other(APFoodItem),
 }
+
+impl<'de> Deserialize<'de> for FDCMeta {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<FDCMeta, D::Error> {
+        let food = AnyFood::deserialize(deserializer)?;
+        let data_type = food.data_type.as_deref().and_then(|name| name.parse().ok());
+        match data_type {
+            Some(DataType::Branded) => food.branded().map(FDCMeta::Branded),
+            Some(DataType::Foundation | DataType::SrLegacy | DataType::Survey) | None => {
+                food.other().map(FDCMeta::Other)
+            }
+        }
+    }
+}
+
+/// The fields of a food of any data type, read in a single pass since the `dataType` tag may come
+/// after them. The fields all foods share, like the long list of food nutrients, are read as they
+/// come, and only those of one data type are kept as JSON until the tag tells which they are.
+/// Fields added to [`BrandedFoodItem`] or [`APFoodItem`] must be added here too.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnyFood {
+    data_type: Option<String>,
+    fdc_id: i32,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    food_nutrients: Vec<FoodNutrient>,
+    // branded foods
+    brand_owner: Option<Value>,
+    brand_name: Option<Value>,
+    gtin_upc: Option<Value>,
+    household_serving_full_text: Option<Value>,
+    ingredients: Option<Value>,
+    serving_size: Option<Value>,
+    serving_size_unit: Option<Value>,
+    label_nutrients: Option<Value>,
+    publication_date: Option<Value>,
+    // other foods
+    food_attributes: Option<Value>,
+    food_portions: Option<Value>,
+    nutrient_conversion_factors: Option<Value>,
+    scientific_name: Option<Value>,
+    common_names: Option<Value>,
+}
+
+impl AnyFood {
+    /// The food as a branded food.
+    fn branded<E: de::Error>(self) -> Result<BrandedFoodItem, E> {
+        Ok(BrandedFoodItem {
+            fdc_id: self.fdc_id,
+            description: self.description,
+            brand_owner: optional(self.brand_owner)?,
+            brand_name: optional(self.brand_name)?,
+            gtin_upc: optional(self.gtin_upc)?,
+            household_serving_full_text: optional(self.household_serving_full_text)?,
+            ingredients: required("ingredients", self.ingredients)?,
+            serving_size: required("servingSize", self.serving_size)?,
+            serving_size_unit: required("servingSizeUnit", self.serving_size_unit)?,
+            label_nutrients: optional(self.label_nutrients)?,
+            food_nutrients: self.food_nutrients,
+            publication_date: optional(self.publication_date)?,
+        })
+    }
+
+    /// The food as a food of another data type than branded.
+    fn other<E: de::Error>(self) -> Result<APFoodItem, E> {
+        Ok(APFoodItem {
+            fdc_id: self.fdc_id,
+            description: self.description,
+            data_type: self.data_type,
+            food_attributes: required("foodAttributes", self.food_attributes)?,
+            food_portions: required("foodPortions", self.food_portions)?,
+            food_nutrients: self.food_nutrients,
+            nutrient_conversion_factors: optional(self.nutrient_conversion_factors)?
+                .unwrap_or_default(),
+            scientific_name: optional(self.scientific_name)?,
+            common_names: optional(self.common_names)?,
+        })
+    }
+}
+
+/// Read the kept field, which is none when missing or null.
+fn optional<T: de::DeserializeOwned, E: de::Error>(value: Option<Value>) -> Result<Option<T>, E> {
+    match value {
+        Some(value) => Option::<T>::deserialize(value).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Read the kept field, which must be there.
+fn required<T: de::DeserializeOwned, E: de::Error>(
+    name: &'static str,
+    value: Option<Value>,
+) -> Result<T, E> {
+    match value {
+        Some(value) => T::deserialize(value).map_err(de::Error::custom),
+        None => Err(de::Error::missing_field(name)),
+    }
+}
+
+impl FDCMeta {
+    /// The FDC id of the food, whichever variant it is.
+    pub fn fdc_id(&self) -> i32 {
+        match self {
+            FDCMeta::Branded(food) => food.fdc_id,
+            FDCMeta::Other(food) => food.fdc_id,
+        }
+    }
+
+    /// The description of the food, whichever variant it is.
+    pub fn description(&self) -> &str {
+        match self {
+            FDCMeta::Branded(food) => &food.description,
+            FDCMeta::Other(food) => &food.description,
+        }
+    }
+
+    /// The brand name of a branded food, or its brand owner when it has no brand name.
+    pub fn brand(&self) -> Option<&str> {
+        match self {
+            FDCMeta::Branded(food) => food.brand_name.as_deref().or(food.brand_owner.as_deref()),
+            FDCMeta::Other(_) => None,
+        }
+    }
+
+    /// The weight of a serving of the food, in grams: the serving size of branded foods, or the
+    /// [default portion](APFoodItem::default_portion) of other foods. Branded foods served by
+    /// volume or in an unknown unit, and other foods without portions, have none.
+    pub fn serving_grams(&self) -> Option<f32> {
+        match self {
+            FDCMeta::Branded(food) => match food.serving_size_quantity().ok()? {
+                Quantity::Mass(mass) => Some(mass.get::<gram>()),
+                _ => None,
+            },
+            FDCMeta::Other(food) => food.default_portion().map(|portion| portion.gram_weight),
+        }
+    }
+
+    /// The data type of the food as FDC names it, like `"Branded"` or `"SR Legacy"`, or `"Other"`
+    /// when FDC gave none.
+    pub fn data_type(&self) -> &str {
+        match self {
+            FDCMeta::Branded(_) => "Branded",
+            FDCMeta::Other(food) => food.data_type.as_deref().unwrap_or("Other"),
+        }
+    }
+}
@@ -0,0 +1,701 @@
+//! This module reads the CSV bundles FDC publishes for download into the same types the API
+//! produces, so that the whole database can be loaded without making requests.
+//!
+//! A bundle directory holds `food.csv` and `nutrient.csv`, along with any of `branded_food.csv`,
+//! `food_nutrient.csv`, and `food_portion.csv`. The files are gigabytes large, so they are streamed
+//! side by side and joined on `fdc_id` as in a merge join, which requires every file to be sorted
+//! by `fdc_id`, as the published bundles are. Only `nutrient.csv` is read into memory.
+//!
+//! [`read`] fails on the first malformed row, while [`load_into`] can instead report malformed
+//! rows and rows which join no food or nutrient in a [`ValidationReport`], and check a bundle
+//! without writing anything with [`BulkOptions::dry_run`].
+
+use super::{
+    APFoodItem, BrandedFoodItem, DataType, FDCMeta, FoodNutrient, FoodPortion, NutrientMeta,
+};
+use crate::error::{Result, StorageError};
+#[cfg(feature = "storage")]
+use crate::progress::{CancellationToken, ProgressEvent};
+#[cfg(feature = "storage")]
+use crate::storage::FoodStore;
+
+use csv::Position;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::iter::Peekable;
+use std::path::Path;
+
+/// How many foods are loaded between two progress reports.
+pub const PROGRESS_EVERY: usize = 1000;
+
+/// How many of the errors of a bundle a [`ValidationReport`] keeps.
+pub const ERROR_SAMPLE: usize = 100;
+
+/// Options of [`load_into`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BulkOptions {
+    /// Read and check the whole bundle without writing to the store.
+    pub dry_run: bool,
+    /// Stop with [`StorageError::TooManyErrors`] once more rows than this are malformed or cannot
+    /// be joined, so that zero stops on the first.
+    pub max_errors: usize,
+    /// Load only the foods of these data types, or of any when [`None`].
+    pub data_types: Option<Vec<DataType>>,
+}
+
+impl BulkOptions {
+    /// generate a new BulkOptions which loads every food and stops on the first error
+    pub fn new() -> BulkOptions {
+        BulkOptions::default()
+    }
+
+    /// Check the bundle without writing to the store.
+    pub fn with_dry_run(self) -> BulkOptions {
+        BulkOptions {
+            dry_run: true,
+            ..self
+        }
+    }
+
+    /// Skip up to `max_errors` bad rows before stopping.
+    pub fn with_max_errors(self, max_errors: usize) -> BulkOptions {
+        BulkOptions { max_errors, ..self }
+    }
+
+    /// Load only the foods of the data types.
+    pub fn with_data_types<I: IntoIterator<Item = DataType>>(self, data_types: I) -> BulkOptions {
+        BulkOptions {
+            data_types: Some(data_types.into_iter().collect()),
+            ..self
+        }
+    }
+}
+
+/// Why a row of a bundle was left out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowErrorKind {
+    /// The row is not valid CSV or does not have the fields of its file.
+    Malformed,
+    /// The row comes after a row of a larger FDC id.
+    Unsorted,
+    /// The row refers to an FDC id which `food.csv` does not have.
+    MissingFood,
+    /// The row refers to a nutrient which `nutrient.csv` does not have.
+    MissingNutrient,
+    /// The row of `food.csv` is of a branded food without a row in `branded_food.csv`.
+    MissingBranded,
+}
+
+/// A row of a bundle which was left out, with the line it starts on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowError {
+    pub file: String,
+    pub line: u64,
+    pub kind: RowErrorKind,
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+/// What [`load_into`] read of a bundle.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    /// The rows of each file of the bundle, bad rows included.
+    pub rows: BTreeMap<String, usize>,
+    /// The foods loaded, or which would be outside of a dry run.
+    pub foods: usize,
+    /// The foods left out for their data type.
+    pub skipped: usize,
+    /// How many rows were left out as malformed or unjoined.
+    pub error_count: usize,
+    /// The first [`ERROR_SAMPLE`] of the rows left out, in the order they were read.
+    pub errors: Vec<RowError>,
+}
+
+impl ValidationReport {
+    /// Whether every row of the bundle was read.
+    pub fn is_clean(&self) -> bool {
+        self.error_count == 0
+    }
+}
+
+/// A row of `food.csv`.
+#[derive(Debug, Deserialize)]
+struct FoodRow {
+    fdc_id: i32,
+    data_type: String,
+    description: String,
+    publication_date: Option<String>,
+}
+
+/// A row of `branded_food.csv`.
+#[derive(Debug, Deserialize)]
+struct BrandedRow {
+    fdc_id: i32,
+    brand_owner: Option<String>,
+    brand_name: Option<String>,
+    gtin_upc: Option<String>,
+    #[serde(default)]
+    ingredients: String,
+    serving_size: Option<f32>,
+    #[serde(default)]
+    serving_size_unit: String,
+    household_serving_fulltext: Option<String>,
+}
+
+/// A row of `food_nutrient.csv`.
+#[derive(Debug, Deserialize)]
+struct FoodNutrientRow {
+    id: i32,
+    fdc_id: i32,
+    nutrient_id: i32,
+    amount: Option<f32>,
+    data_points: Option<i32>,
+}
+
+/// A row of `food_portion.csv`.
+#[derive(Debug, Deserialize)]
+struct PortionRow {
+    id: i32,
+    fdc_id: i32,
+    seq_num: Option<i32>,
+    amount: Option<f32>,
+    portion_description: Option<String>,
+    modifier: Option<String>,
+    gram_weight: Option<f32>,
+    data_points: Option<i32>,
+}
+
+/// A row of `nutrient.csv`.
+#[derive(Debug, Deserialize)]
+struct NutrientRow {
+    id: i32,
+    name: String,
+    unit_name: String,
+    nutrient_nbr: Option<String>,
+}
+
+/// Rows keyed by FDC id, so that they can be joined.
+trait Keyed {
+    fn fdc_id(&self) -> i32;
+}
+
+impl Keyed for FoodRow {
+    fn fdc_id(&self) -> i32 {
+        self.fdc_id
+    }
+}
+
+impl Keyed for BrandedRow {
+    fn fdc_id(&self) -> i32 {
+        self.fdc_id
+    }
+}
+
+impl Keyed for FoodNutrientRow {
+    fn fdc_id(&self) -> i32 {
+        self.fdc_id
+    }
+}
+
+impl Keyed for PortionRow {
+    fn fdc_id(&self) -> i32 {
+        self.fdc_id
+    }
+}
+
+/// The rows left out of a bundle, when they are reported rather than failing the read.
+#[derive(Debug, Default)]
+struct Tally {
+    count: usize,
+    sample: Vec<RowError>,
+}
+
+impl Tally {
+    /// Leave out the row on the line of the file.
+    fn push(&mut self, file: &str, line: u64, kind: RowErrorKind, message: String) {
+        self.count += 1;
+        if self.sample.len() < ERROR_SAMPLE {
+            self.sample.push(RowError {
+                file: file.into(),
+                line,
+                kind,
+                message,
+            });
+        }
+    }
+}
+
+/// The records of a CSV file with the lines they start on, deserialized one at a time.
+type Records<T> = Box<dyn Iterator<Item = (u64, csv::Result<T>)> + Send>;
+
+/// Open the file of the bundle, if it is there.
+fn records<T: DeserializeOwned + Send + 'static>(
+    dir: &Path,
+    name: &'static str,
+) -> Result<Option<Records<T>>> {
+    let path = dir.join(name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut reader = csv::Reader::from_path(&path).map_err(|e| StorageError::Read {
+        path: path.clone(),
+        source: Box::new(e),
+    })?;
+    let headers = reader
+        .headers()
+        .map_err(|source| StorageError::Csv {
+            file: name.into(),
+            source,
+        })?
+        .clone();
+    let records = reader.into_records().map(move |record| match record {
+        Ok(record) => (
+            record.position().map_or(0, Position::line),
+            record.deserialize(Some(&headers)),
+        ),
+        Err(e) => (e.position().map_or(0, Position::line), Err(e)),
+    });
+    Ok(Some(Box::new(records)))
+}
+
+/// The rows of a CSV file, read one at a time and checked to be sorted by FDC id.
+struct Rows<T: DeserializeOwned> {
+    name: &'static str,
+    rows: Peekable<Records<T>>,
+    last: i32,
+    read: usize,
+}
+
+impl<T: DeserializeOwned + Keyed + Send + 'static> Rows<T> {
+    /// Open the file of the bundle, if it is there.
+    fn open(dir: &Path, name: &'static str) -> Result<Option<Rows<T>>> {
+        Ok(records(dir, name)?.map(|records| Rows {
+            name,
+            rows: records.peekable(),
+            last: i32::MIN,
+            read: 0,
+        }))
+    }
+
+    /// The next record, whatever its FDC id, or [`None`] at the end of the file. A bad record
+    /// fails a strict read, and is left out of a lenient one as `Some(None)`.
+    fn next_record(&mut self, tally: &mut Option<Tally>) -> Result<Option<Option<(u64, T)>>> {
+        let (line, row) = match self.rows.next() {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+        self.read += 1;
+        let row = match (row, tally.as_mut()) {
+            (Ok(row), _) => row,
+            (Err(source), Some(tally)) => {
+                tally.push(self.name, line, RowErrorKind::Malformed, source.to_string());
+                return Ok(Some(None));
+            }
+            (Err(source), None) => {
+                return Err(StorageError::Csv {
+                    file: self.name.into(),
+                    source,
+                }
+                .into())
+            }
+        };
+        if row.fdc_id() < self.last {
+            let message = format!("fdc_id {} comes after {}", row.fdc_id(), self.last);
+            return match tally {
+                Some(tally) => {
+                    tally.push(self.name, line, RowErrorKind::Unsorted, message);
+                    Ok(Some(None))
+                }
+                None => Err(StorageError::Invalid(format!(
+                    "{} is not sorted by fdc_id, {}",
+                    self.name, message
+                ))
+                .into()),
+            };
+        }
+        self.last = row.fdc_id();
+        Ok(Some(Some((line, row))))
+    }
+
+    /// The next row, whatever its FDC id.
+    fn next(&mut self, tally: &mut Option<Tally>) -> Result<Option<(u64, T)>> {
+        loop {
+            match self.next_record(tally)? {
+                Some(Some(row)) => return Ok(Some(row)),
+                Some(None) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Take every row of the FDC id, skipping rows of smaller ids which no food refers to, which a
+    /// lenient read leaves out as errors.
+    fn take(&mut self, fdc_id: i32, tally: &mut Option<Tally>) -> Result<Vec<(u64, T)>> {
+        let mut taken = Vec::new();
+        loop {
+            match self.rows.peek() {
+                Some((_, Ok(row))) if row.fdc_id() > fdc_id => break,
+                None => break,
+                _ => {}
+            }
+            match self.next_record(tally)? {
+                Some(Some((line, row))) if row.fdc_id() == fdc_id => taken.push((line, row)),
+                Some(Some((line, row))) => {
+                    if let Some(tally) = tally {
+                        let message = format!("fdc_id {} is not in food.csv", row.fdc_id());
+                        tally.push(self.name, line, RowErrorKind::MissingFood, message);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(taken)
+    }
+}
+
+/// Take the rows of the FDC id from a file which may be missing from the bundle.
+fn take<T: DeserializeOwned + Keyed + Send + 'static>(
+    rows: &mut Option<Rows<T>>,
+    fdc_id: i32,
+    tally: &mut Option<Tally>,
+) -> Result<Vec<(u64, T)>> {
+    match rows {
+        Some(rows) => rows.take(fdc_id, tally),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The data type of the `data_type` of `food.csv`, like `"sr_legacy_food"`, if it is one the API
+/// has too.
+fn data_type(name: &str) -> Option<DataType> {
+    match name {
+        "branded_food" => Some(DataType::Branded),
+        "foundation_food" => Some(DataType::Foundation),
+        "sr_legacy_food" => Some(DataType::SrLegacy),
+        "survey_fndds_food" => Some(DataType::Survey),
+        _ => None,
+    }
+}
+
+/// The foods of a bundle directory, read one at a time in FDC id order.
+pub struct BulkFoods {
+    foods: Rows<FoodRow>,
+    branded: Option<Rows<BrandedRow>>,
+    food_nutrients: Option<Rows<FoodNutrientRow>>,
+    portions: Option<Rows<PortionRow>>,
+    nutrients: HashMap<i32, NutrientMeta>,
+    // only reported by `load_into`
+    #[cfg_attr(not(feature = "storage"), allow(dead_code))]
+    nutrient_rows: Option<usize>,
+    /// The rows left out so far, when bad rows do not fail the read.
+    tally: Option<Tally>,
+    data_types: Option<Vec<DataType>>,
+    skipped: usize,
+}
+
+/// Open the bundle in the directory.
+pub fn read(dir: &Path) -> Result<BulkFoods> {
+    open(dir, None, None)
+}
+
+/// Open the bundle in the directory, leaving out bad rows rather than failing when given a tally,
+/// and the foods of other data types than those given.
+fn open(
+    dir: &Path,
+    mut tally: Option<Tally>,
+    data_types: Option<Vec<DataType>>,
+) -> Result<BulkFoods> {
+    let foods = Rows::open(dir, "food.csv")?
+        .ok_or_else(|| StorageError::Invalid(format!("No food.csv in {}", dir.display())))?;
+    let food_nutrients = Rows::open(dir, "food_nutrient.csv")?;
+    let (nutrients, nutrient_rows) = match &food_nutrients {
+        Some(_) => {
+            let (nutrients, rows) = read_nutrients(dir, &mut tally)?;
+            (nutrients, Some(rows))
+        }
+        None => (HashMap::new(), None),
+    };
+    Ok(BulkFoods {
+        foods,
+        branded: Rows::open(dir, "branded_food.csv")?,
+        food_nutrients,
+        portions: Rows::open(dir, "food_portion.csv")?,
+        nutrients,
+        nutrient_rows,
+        tally,
+        data_types,
+        skipped: 0,
+    })
+}
+
+/// Read every nutrient of `nutrient.csv`, which is small, keyed by id, along with how many rows it
+/// has.
+fn read_nutrients(
+    dir: &Path,
+    tally: &mut Option<Tally>,
+) -> Result<(HashMap<i32, NutrientMeta>, usize)> {
+    let path = dir.join("nutrient.csv");
+    let records =
+        records::<NutrientRow>(dir, "nutrient.csv")?.ok_or_else(|| StorageError::Read {
+            path: path.clone(),
+            source: "No such file".into(),
+        })?;
+    let mut nutrients = HashMap::new();
+    let mut rows = 0;
+    for (line, row) in records {
+        rows += 1;
+        let row = match (row, tally.as_mut()) {
+            (Ok(row), _) => row,
+            (Err(source), Some(tally)) => {
+                let message = source.to_string();
+                tally.push("nutrient.csv", line, RowErrorKind::Malformed, message);
+                continue;
+            }
+            (Err(source), None) => {
+                return Err(StorageError::Csv {
+                    file: "nutrient.csv".into(),
+                    source,
+                }
+                .into())
+            }
+        };
+        nutrients.insert(
+            row.id,
+            NutrientMeta {
+                id: row.id,
+                number: row.nutrient_nbr,
+                name: row.name,
+                unit_name: row.unit_name,
+            },
+        );
+    }
+    Ok((nutrients, rows))
+}
+
+impl BulkFoods {
+    /// Join the rows of the next food of the data types asked for.
+    fn next_food(&mut self) -> Result<Option<FDCMeta>> {
+        loop {
+            let (line, food) = match self.foods.next(&mut self.tally)? {
+                Some(food) => food,
+                None => {
+                    self.finish()?;
+                    return Ok(None);
+                }
+            };
+            let tally = &mut self.tally;
+            let branded = take(&mut self.branded, food.fdc_id, tally)?.pop();
+            let food_nutrients = take(&mut self.food_nutrients, food.fdc_id, tally)?;
+            let food_portions = take(&mut self.portions, food.fdc_id, tally)?;
+            let wanted = self.data_types.as_ref().is_none_or(|data_types| {
+                data_type(&food.data_type).is_some_and(|d| data_types.contains(&d))
+            });
+            if !wanted {
+                self.skipped += 1;
+                continue;
+            }
+            if let Some(food) = self.join(line, food, branded, food_nutrients, food_portions)? {
+                return Ok(Some(food));
+            }
+        }
+    }
+
+    /// Join the rows of a food, or leave it out of a lenient read when it cannot be joined.
+    fn join(
+        &mut self,
+        line: u64,
+        food: FoodRow,
+        branded: Option<(u64, BrandedRow)>,
+        food_nutrients: Vec<(u64, FoodNutrientRow)>,
+        food_portions: Vec<(u64, PortionRow)>,
+    ) -> Result<Option<FDCMeta>> {
+        let mut nutrients = Vec::new();
+        for (line, row) in food_nutrients {
+            match self.nutrients.get(&row.nutrient_id) {
+                Some(nutrient) => nutrients.push(FoodNutrient {
+                    id: row.id,
+                    nutrient: nutrient.clone(),
+                    amount: row.amount,
+                    data_points: row.data_points,
+                    food_nutrient_derivation: None,
+                }),
+                None => {
+                    if let Some(tally) = &mut self.tally {
+                        let message =
+                            format!("nutrient_id {} is not in nutrient.csv", row.nutrient_id);
+                        tally.push(
+                            "food_nutrient.csv",
+                            line,
+                            RowErrorKind::MissingNutrient,
+                            message,
+                        );
+                    }
+                }
+            }
+        }
+        let food_nutrients = nutrients;
+        let food_portions = food_portions
+            .into_iter()
+            .map(|(_, row)| FoodPortion {
+                id: row.id,
+                amount: row.amount,
+                data_points: row.data_points,
+                gram_weight: row.gram_weight.unwrap_or(0.0),
+                modifier: row.modifier,
+                portion_description: row.portion_description,
+                sequence_number: row.seq_num,
+            })
+            .collect();
+
+        if food.data_type != "branded_food" {
+            return Ok(Some(FDCMeta::Other(APFoodItem {
+                fdc_id: food.fdc_id,
+                description: food.description,
+                data_type: data_type(&food.data_type).map(|d| d.as_api_str().to_string()),
+                food_attributes: Vec::new(),
+                food_portions,
+                food_nutrients,
+                nutrient_conversion_factors: Vec::new(),
+                scientific_name: None,
+                common_names: None,
+            })));
+        }
+        let branded = match (branded, &mut self.tally) {
+            (Some((_, branded)), _) => branded,
+            (None, Some(tally)) => {
+                let message = format!(
+                    "branded food {} has no row in branded_food.csv",
+                    food.fdc_id
+                );
+                tally.push("food.csv", line, RowErrorKind::MissingBranded, message);
+                return Ok(None);
+            }
+            (None, None) => {
+                return Err(StorageError::Invalid(format!(
+                    "Branded food {} has no row in branded_food.csv",
+                    food.fdc_id
+                ))
+                .into())
+            }
+        };
+        Ok(Some(FDCMeta::Branded(BrandedFoodItem {
+            fdc_id: food.fdc_id,
+            description: food.description,
+            brand_owner: branded.brand_owner,
+            brand_name: branded.brand_name,
+            gtin_upc: branded.gtin_upc,
+            household_serving_full_text: branded.household_serving_fulltext,
+            ingredients: branded.ingredients,
+            serving_size: branded.serving_size.unwrap_or(0.0),
+            serving_size_unit: branded.serving_size_unit,
+            label_nutrients: None,
+            food_nutrients,
+            publication_date: food.publication_date,
+        })))
+    }
+
+    /// Leave out the rows after the last food of a lenient read, which no food refers to.
+    fn finish(&mut self) -> Result<()> {
+        if self.tally.is_some() {
+            take(&mut self.branded, i32::MAX, &mut self.tally)?;
+            take(&mut self.food_nutrients, i32::MAX, &mut self.tally)?;
+            take(&mut self.portions, i32::MAX, &mut self.tally)?;
+        }
+        Ok(())
+    }
+
+    /// How many rows were left out so far.
+    #[cfg(feature = "storage")]
+    fn error_count(&self) -> usize {
+        self.tally.as_ref().map_or(0, |tally| tally.count)
+    }
+
+    /// What was read of the bundle so far, of which the foods were loaded.
+    #[cfg(feature = "storage")]
+    fn report(&self, foods: usize) -> ValidationReport {
+        let mut rows = BTreeMap::new();
+        rows.insert(self.foods.name.to_string(), self.foods.read);
+        let others = [
+            self.branded.as_ref().map(|r| (r.name, r.read)),
+            self.food_nutrients.as_ref().map(|r| (r.name, r.read)),
+            self.portions.as_ref().map(|r| (r.name, r.read)),
+            self.nutrient_rows.map(|read| ("nutrient.csv", read)),
+        ];
+        for (name, read) in others.iter().flatten() {
+            rows.insert(name.to_string(), *read);
+        }
+        ValidationReport {
+            rows,
+            foods,
+            skipped: self.skipped,
+            error_count: self.error_count(),
+            errors: self
+                .tally
+                .as_ref()
+                .map(|tally| tally.sample.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Iterator for BulkFoods {
+    type Item = Result<FDCMeta>;
+
+    fn next(&mut self) -> Option<Result<FDCMeta>> {
+        self.next_food().transpose()
+    }
+}
+
+/// Load the foods of the bundle in the directory into the store, reporting progress every
+/// [`PROGRESS_EVERY`] foods and once done. The bundle does not say how many foods it has, so the
+/// events have no total. Stops with [`Cancelled`](crate::progress::Cancelled) before the next food
+/// once the token is cancelled, keeping the foods already loaded.
+///
+/// Malformed rows, and rows which join no food or nutrient, are left out and reported until there
+/// are more than [`BulkOptions::max_errors`] of them, when the load stops with
+/// [`StorageError::TooManyErrors`], keeping the foods already loaded. A dry run reads the whole
+/// bundle the same way without writing to the store. Returns what was read of the bundle.
+#[cfg(feature = "storage")]
+pub async fn load_into<S, F>(
+    store: &S,
+    dir: &Path,
+    options: &BulkOptions,
+    progress: F,
+    cancel: &CancellationToken,
+) -> Result<ValidationReport>
+where
+    S: FoodStore + ?Sized,
+    F: Fn(ProgressEvent),
+{
+    let mut foods = open(dir, Some(Tally::default()), options.data_types.clone())?;
+    let mut current = ProgressEvent::default();
+    loop {
+        let food = foods.next_food()?;
+        if foods.error_count() > options.max_errors {
+            return Err(
+                StorageError::TooManyErrors(Box::new(foods.report(current.completed))).into(),
+            );
+        }
+        let food = match food {
+            Some(food) => food,
+            None => break,
+        };
+        cancel.check(current)?;
+        if !options.dry_run {
+            store.upsert_food(&food).await?;
+        }
+        current.completed += 1;
+        current.current_item = Some(food.fdc_id());
+        if current.completed % PROGRESS_EVERY == 0 {
+            progress(current);
+        }
+    }
+    progress(current);
+    Ok(foods.report(current.completed))
+}
@@ -0,0 +1,56 @@
+//! The [`DataType`]s of FDC foods, which name the `dataType` filter of search requests.
+
+use super::FDCError;
+use std::fmt;
+use std::str::FromStr;
+
+/// A kind of food FDC publishes, as it is written in the `dataType` of the API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum DataType {
+    Branded,
+    Foundation,
+    #[serde(rename = "SR Legacy")]
+    SrLegacy,
+    #[serde(rename = "Survey (FNDDS)")]
+    Survey,
+}
+
+impl DataType {
+    /// Every data type, in the order FDC lists them.
+    pub fn all() -> &'static [DataType] {
+        &[
+            DataType::Branded,
+            DataType::Foundation,
+            DataType::SrLegacy,
+            DataType::Survey,
+        ]
+    }
+
+    /// The value of the data type in API requests and responses.
+    pub fn as_api_str(&self) -> &'static str {
+        match self {
+            DataType::Branded => "Branded",
+            DataType::Foundation => "Foundation",
+            DataType::SrLegacy => "SR Legacy",
+            DataType::Survey => "Survey (FNDDS)",
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_api_str())
+    }
+}
+
+impl FromStr for DataType {
+    type Err = FDCError;
+
+    fn from_str(s: &str) -> Result<DataType, FDCError> {
+        DataType::all()
+            .iter()
+            .find(|data_type| data_type.as_api_str() == s)
+            .copied()
+            .ok_or_else(|| FDCError::UnknownDataType(s.into()))
+    }
+}
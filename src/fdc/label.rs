@@ -0,0 +1,74 @@
+//! Helpers on the [`LabelNutrients`] of branded foods.
+
+use super::LabelNutrients;
+
+use std::collections::HashMap;
+
+/// The FDA reference daily values for adults and children 4 years and older, in the units FDC
+/// reports label nutrients in (grams, milligrams, or kilocalories). Trans fat and total sugars
+/// have no daily value.
+pub const DAILY_VALUES: &[(&str, f32)] = &[
+    ("fat", 78.0),
+    ("saturated_fat", 20.0),
+    ("cholesterol", 300.0),
+    ("sodium", 2300.0),
+    ("carbohydrates", 275.0),
+    ("fiber", 28.0),
+    ("protein", 50.0),
+    ("calcium", 1300.0),
+    ("iron", 18.0),
+    ("potassium", 4700.0),
+    ("calories", 2000.0),
+];
+
+impl LabelNutrients {
+    /// Every nutrient value keyed by its field name, in declaration order.
+    pub fn values(&self) -> [(&'static str, f32); 13] {
+        [
+            ("fat", self.fat.value),
+            ("saturated_fat", self.saturated_fat.value),
+            ("trans_fat", self.trans_fat.value),
+            ("cholesterol", self.cholesterol.value),
+            ("sodium", self.sodium.value),
+            ("carbohydrates", self.carbohydrates.value),
+            ("fiber", self.fiber.value),
+            ("sugars", self.sugars.value),
+            ("protein", self.protein.value),
+            ("calcium", self.calcium.value),
+            ("iron", self.iron.value),
+            ("potassium", self.potassium.value),
+            ("calories", self.calories.value),
+        ]
+    }
+
+    /// Each nutrient as a percentage of its daily value in [`DAILY_VALUES`].
+    pub fn daily_values(&self) -> HashMap<&'static str, f32> {
+        self.daily_values_with(DAILY_VALUES)
+    }
+
+    /// Each nutrient as a percentage of its daily value in the given table. Nutrients missing from
+    /// the table or with a non-positive daily value are left out.
+    pub fn daily_values_with(&self, table: &[(&str, f32)]) -> HashMap<&'static str, f32> {
+        self.values()
+            .iter()
+            .filter_map(|(name, value)| {
+                table
+                    .iter()
+                    .find(|(dv_name, _)| dv_name == name)
+                    .filter(|(_, dv)| *dv > 0.0)
+                    .map(|(_, dv)| (*name, 100.0 * value / dv))
+            })
+            .collect()
+    }
+
+    /// Each nutrient whose value differs from `other` by more than `tolerance`, with the value here
+    /// and the value in `other`, in declaration order.
+    pub fn diff(&self, other: &LabelNutrients, tolerance: f32) -> Vec<(&'static str, f32, f32)> {
+        self.values()
+            .iter()
+            .zip(other.values().iter())
+            .filter(|((_, old), (_, new))| (new - old).abs() > tolerance)
+            .map(|((name, old), (_, new))| (*name, *old, *new))
+            .collect()
+    }
+}
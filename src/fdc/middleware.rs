@@ -0,0 +1,63 @@
+//! This module declares the [`Middleware`] that [`FDCService`](super::FDCService) runs around each
+//! request, for cross-cutting behavior like audit logging, signing, or capturing requests in tests,
+//! without baking each one into the service.
+
+use super::TransportResponse;
+use crate::error::Result;
+
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+/// A request to the FDC API, before the root and key of the service are added to its url.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FDCRequest {
+    /// The endpoint, like `"v1/foods"`.
+    pub endpoint: String,
+    /// The json body which is posted.
+    pub body: Value,
+    /// The headers sent along, those of the service to begin with.
+    pub headers: HeaderMap,
+}
+
+/// `Middleware` sees each request of a service before it is sent, and its outcome after. Both
+/// hooks do nothing by default.
+pub trait Middleware: Send + Sync {
+    /// Inspect the request, or change it, before it is sent.
+    fn on_request(&self, _request: &mut FDCRequest) {}
+
+    /// Observe the response of the request, whatever its status, or the error of the transport.
+    fn on_response(&self, _request: &FDCRequest, _response: &Result<TransportResponse>) {}
+}
+
+/// The middleware of a service, in the order they were registered.
+#[derive(Clone, Default)]
+pub(crate) struct Middlewares(Vec<Arc<dyn Middleware>>);
+
+impl Middlewares {
+    /// Add the middleware after the others.
+    pub(crate) fn push<M: Middleware + 'static>(&mut self, middleware: M) {
+        self.0.push(Arc::new(middleware))
+    }
+
+    /// Let each middleware change the request in turn, so that later ones see earlier changes.
+    pub(crate) fn on_request(&self, request: &mut FDCRequest) {
+        for middleware in &self.0 {
+            middleware.on_request(request);
+        }
+    }
+
+    /// Let each middleware observe the outcome of the request, in turn.
+    pub(crate) fn on_response(&self, request: &FDCRequest, response: &Result<TransportResponse>) {
+        for middleware in &self.0 {
+            middleware.on_response(request, response);
+        }
+    }
+}
+
+impl fmt::Debug for Middlewares {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{} middleware]", self.0.len())
+    }
+}
@@ -2,16 +2,288 @@
 //! [FoodData Central](https://fdc.nal.usda.gov/index.html) API though the [`FDCService`] struct.
 
 pub mod api;
+pub mod bulk;
+pub mod data_type;
+pub mod label;
+pub mod middleware;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod transport;
+pub mod units;
 
 pub use api::*;
+pub use data_type::DataType;
+pub use label::DAILY_VALUES;
+pub use middleware::{FDCRequest, Middleware};
+pub use transport::{HttpTransport, TransportResponse};
+pub use units::ServingUnit;
 
-use anyhow::Result;
+#[cfg(feature = "env")]
+use crate::env;
+use crate::error::{Error, Result};
+use crate::progress::{CancellationToken, Cancelled, ProgressEvent};
+use crate::text;
+use middleware::Middlewares;
+
+use chrono::NaiveDate;
+use futures::future::{self, Either};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
 use reqwest::Client;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// The root of the FDC API which requests are made against by default.
+pub const FDC_URL: &str = "https://api.nal.usda.gov/fdc";
+
+/// The most FDC ids the API accepts in a single "v1/foods" request.
+pub const CHUNK_SIZE: usize = 20;
+
+/// The most "v1/foods" chunks services request at once by default.
+pub const CONCURRENCY: usize = 4;
+
+/// The longest response, in bytes, services accept by default. A "v1/foods" chunk of branded
+/// foods in the "full" format takes a few megabytes.
+pub const MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// The most results the API returns in a single "v1/foods/search" page.
+pub const SEARCH_PAGE_SIZE: usize = 200;
+
+/// A page of "v1/foods/search" results.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchPage {
+    total_pages: u32,
+    foods: Vec<AbridgedFoodItem>,
+}
+
+/// The failure of a request to the FDC API.
+#[derive(Debug)]
+pub enum FDCError {
+    /// The request could not be sent or its response not read, like when FDC is unreachable or
+    /// times out.
+    Http(reqwest::Error),
+    /// A custom [`HttpTransport`] could not send the request or read its response.
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// FDC answered with an error status, like 404 or 500.
+    Status(u16),
+    /// No response came within the timeout of the call.
+    Timeout(Duration),
+    /// The response is longer than the limit of the service, in bytes.
+    TooLarge(usize),
+    /// A request for foods was answered with an error object instead, with the message of the API.
+    NoFoods(String),
+    /// The response does not have the shape of the payloads of [`api`].
+    Decode(serde_json::Error),
+    /// FDC has no food for the FDC id.
+    NotFound(i32),
+    /// The name is not one of a [`DataType`].
+    UnknownDataType(String),
+    /// The "v1/foods" request for a chunk of FDC ids failed.
+    Chunk {
+        fdc_ids: Vec<i32>,
+        source: Box<Error>,
+    },
+}
+
+impl FDCError {
+    /// Whether the request may succeed when made again, as after a timeout, a lost connection, a
+    /// rate limit, or a server error.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            FDCError::Http(e) => e.is_timeout() || e.is_connect(),
+            FDCError::Status(status) => *status >= 500 || *status == 429,
+            FDCError::Timeout(_) => true,
+            FDCError::Chunk { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for FDCError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FDCError::Http(e) => write!(f, "{}", e),
+            FDCError::Transport(e) => write!(f, "{}", e),
+            FDCError::Status(status) => write!(f, "FDC answered with status {}", status),
+            FDCError::Timeout(timeout) => write!(f, "FDC did not answer within {:?}", timeout),
+            FDCError::TooLarge(limit) => write!(f, "FDC response is longer than {} bytes", limit),
+            FDCError::NoFoods(message) => write!(f, "FDC response has no foods: {}", message),
+            FDCError::Decode(e) => write!(f, "Cannot decode the FDC response: {}", e),
+            FDCError::NotFound(fdc_id) => write!(f, "FDC returned no food for id {}", fdc_id),
+            FDCError::UnknownDataType(name) => write!(f, "Unknown FDC data type {:?}", name),
+            FDCError::Chunk { fdc_ids, source } => {
+                write!(f, "Fetching foods {:?} failed: {}", fdc_ids, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FDCError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FDCError::Http(e) => Some(e),
+            FDCError::Transport(e) => Some(e.as_ref()),
+            FDCError::Decode(e) => Some(e),
+            FDCError::Chunk { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Deserialize the "foods" array of a "v1/foods/search" response, or of any endpoint answering
+/// with search results, for requests made without [`FDCService`]. Responses without one, like the
+/// error objects of a rejected key or a malformed request, fail with the message of the API.
+pub fn parse_search_response(mut res: serde_json::Value) -> Result<Vec<AbridgedFoodItem>> {
+    match res["foods"].take() {
+        foods @ serde_json::Value::Array(_) => {
+            Ok(serde_json::from_value(foods).map_err(FDCError::Decode)?)
+        }
+        _ => Err(FDCError::NoFoods(error_message(&res)).into()),
+    }
+}
+
+/// Deserialize a "v1/foods" response, or that of any endpoint answering with an array of full
+/// foods, for requests made without [`FDCService`]. Foods are read as [`FDCMeta`] by their
+/// `dataType`, and error objects fail with the message of the API.
+pub fn parse_foods_response(res: serde_json::Value) -> Result<Vec<FDCMeta>> {
+    match res {
+        foods @ serde_json::Value::Array(_) => {
+            Ok(serde_json::from_value(foods).map_err(FDCError::Decode)?)
+        }
+        _ => Err(FDCError::NoFoods(error_message(&res)).into()),
+    }
+}
+
+/// The message of an error object of the API, or the whole object when it has none.
+fn error_message(res: &serde_json::Value) -> String {
+    // api.data.gov nests the error, while FDC itself puts its message next to it
+    let error = &res["error"];
+    [&error["message"], &res["message"], error, &error["code"]]
+        .iter()
+        .find_map(|value| value.as_str())
+        .map(String::from)
+        .unwrap_or_else(|| res.to_string())
+}
+
+/// Put the foods in the order of the FDC ids they were requested by, as FDC answers in any order.
+/// A food goes to the first position of its id, and foods of ids which were not requested, or
+/// which repeat a food already placed, are kept at the end in the order they came.
+pub fn order_by_ids(fdc_ids: &[i32], foods: Vec<FDCMeta>) -> Vec<FDCMeta> {
+    // the first position of each id wins
+    let positions = fdc_ids
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, id)| (*id, i))
+        .collect::<HashMap<_, _>>();
+    let mut slots = fdc_ids
+        .iter()
+        .map(|_| None)
+        .collect::<Vec<Option<FDCMeta>>>();
+    let mut extra = Vec::new();
+    for food in foods {
+        match positions.get(&food.fdc_id()) {
+            Some(&i) if slots[i].is_none() => slots[i] = Some(food),
+            _ => extra.push(food),
+        }
+    }
+    slots.into_iter().flatten().chain(extra).collect()
+}
+
+/// Bucket search results by their `data_type`, as the API names it, like `"Branded"`, keeping the
+/// order of the results within each bucket. See [`GroupedResults`] for the sections of a picker.
+pub fn group_by_data_type(foods: Vec<AbridgedFoodItem>) -> HashMap<String, Vec<AbridgedFoodItem>> {
+    let mut groups = HashMap::<String, Vec<AbridgedFoodItem>>::new();
+    for food in foods {
+        groups.entry(food.data_type.clone()).or_default().push(food);
+    }
+    groups
+}
+
+/// Options of a single call to the FDC API, over the defaults of the service and its transport.
+#[derive(Clone, Debug, Default)]
+pub struct CallOptions {
+    /// Fail with [`FDCError::Timeout`] when no response came within it.
+    pub timeout: Option<Duration>,
+    /// Fail with [`Cancelled`] as soon as the token is cancelled, abandoning the request.
+    pub cancel: Option<CancellationToken>,
+}
+
+impl CallOptions {
+    /// generate a new CallOptions which overrides nothing
+    pub fn new() -> CallOptions {
+        CallOptions::default()
+    }
+
+    /// Give up on the call after the timeout.
+    pub fn with_timeout(self, timeout: Duration) -> CallOptions {
+        CallOptions {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
 
-/// `FDCService` implements the http requests to the FDC API through an Actix client.
+    /// Abandon the call once the token is cancelled.
+    pub fn with_cancel(self, cancel: CancellationToken) -> CallOptions {
+        CallOptions {
+            cancel: Some(cancel),
+            ..self
+        }
+    }
+}
+
+/// Search results split into the sections of a food picker, by the data type of each result.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GroupedResults {
+    /// Foundation, SR Legacy and Survey (FNDDS) foods.
+    pub common: Vec<AbridgedFoodItem>,
+    /// Branded foods.
+    pub branded: Vec<AbridgedFoodItem>,
+    /// Foods of any other data type, like experimental foods.
+    pub other: Vec<AbridgedFoodItem>,
+}
+
+impl GroupedResults {
+    /// Split the results by data type, keeping their order and the first `per_group` of each.
+    fn partition(results: Vec<AbridgedFoodItem>, per_group: usize) -> GroupedResults {
+        let mut grouped = GroupedResults::default();
+        for food in results {
+            let group = grouped.group(&food.data_type);
+            if group.len() < per_group {
+                group.push(food);
+            }
+        }
+        grouped
+    }
+
+    /// The group foods of the data type belong to.
+    fn group(&mut self, data_type: &str) -> &mut Vec<AbridgedFoodItem> {
+        match data_type.parse() {
+            Ok(DataType::Branded) => &mut self.branded,
+            Ok(DataType::Foundation | DataType::SrLegacy | DataType::Survey) => &mut self.common,
+            Err(_) => &mut self.other,
+        }
+    }
+}
+
+/// `FDCService` implements the http requests to the FDC API through the [`HttpTransport`] `T`
+/// passed to each request, a [`reqwest::Client`] unless another transport is chosen.
 #[derive(Clone, Debug)]
-pub struct FDCService {
+pub struct FDCService<T = Client> {
     pub fdc_key: String,
+    pub base_url: String,
+    /// Responses longer than this many bytes fail with [`FDCError::TooLarge`].
+    pub max_response_bytes: usize,
+    /// Headers sent with every request, like a `User-Agent`.
+    pub headers: HeaderMap,
+    /// The most chunks [`FDCService::v1_foods_chunked`] and [`FDCService::v1_foods_partial`]
+    /// request at once. [`FDCService::search_stream`] takes its own.
+    pub concurrency: usize,
+    middlewares: Middlewares,
+    transport: PhantomData<fn(&T)>,
 }
 
 impl FDCService {
@@ -19,61 +291,542 @@ impl FDCService {
     pub fn new<S: Into<String>>(fdc_key: S) -> FDCService {
         FDCService {
             fdc_key: fdc_key.into(),
+            base_url: FDC_URL.into(),
+            max_response_bytes: MAX_RESPONSE_BYTES,
+            headers: HeaderMap::new(),
+            concurrency: CONCURRENCY,
+            middlewares: Middlewares::default(),
+            transport: PhantomData,
+        }
+    }
+
+    /// generate a new FDCService from the `FDC_KEY`, `FDC_URL`, and `FDC_CONCURRENCY` values of
+    /// [`env::get`]
+    #[cfg(feature = "env")]
+    pub fn from_env() -> Result<FDCService> {
+        Ok(env::get()?.fdc_service())
+    }
+}
+
+impl<T> FDCService<T> {
+    /// The data types FDC can be searched by.
+    pub fn data_types(&self) -> &'static [DataType] {
+        DataType::all()
+    }
+
+    /// Make requests against another root than [`FDC_URL`], like a mirror or a mock server.
+    pub fn with_base_url<S: Into<String>>(self, base_url: S) -> FDCService<T> {
+        FDCService {
+            base_url: base_url.into(),
+            ..self
+        }
+    }
+
+    /// Fail on responses longer than the limit, in bytes, instead of [`MAX_RESPONSE_BYTES`].
+    pub fn with_max_response_bytes(self, max_response_bytes: usize) -> FDCService<T> {
+        FDCService {
+            max_response_bytes,
+            ..self
+        }
+    }
+
+    /// Have [`FDCService::v1_foods_chunked`] and [`FDCService::v1_foods_partial`] request up to
+    /// `concurrency` chunks at once instead of [`CONCURRENCY`], at least one.
+    pub fn with_concurrency(self, concurrency: usize) -> FDCService<T> {
+        FDCService {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    /// Send the headers with every request, in place of any the service already sends by the same
+    /// names.
+    pub fn with_headers(mut self, headers: HeaderMap) -> FDCService<T> {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Send the `User-Agent` with every request, like `"my-app/1.0 (me@example.com)"`, as FDC and
+    /// some proxies ask clients to describe themselves. Text from elsewhere, which may hold line
+    /// breaks, is checked by [`HeaderValue::from_str`].
+    pub fn with_user_agent(mut self, user_agent: HeaderValue) -> FDCService<T> {
+        self.headers.insert(USER_AGENT, user_agent);
+        self
+    }
+
+    /// Run the middleware around every request, after the middleware registered before it.
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> FDCService<T> {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    /// Make requests through another transport than [`reqwest::Client`].
+    pub fn with_transport<U>(self) -> FDCService<U> {
+        FDCService {
+            fdc_key: self.fdc_key,
+            base_url: self.base_url,
+            max_response_bytes: self.max_response_bytes,
+            headers: self.headers,
+            concurrency: self.concurrency,
+            middlewares: self.middlewares,
+            transport: PhantomData,
         }
     }
+}
+
+impl<T: HttpTransport> FDCService<T> {
+    /// Post the body to the endpoint, like `"v1/foods"`, through the middleware of the service.
+    async fn post(
+        &self,
+        client: &T,
+        endpoint: &str,
+        body: serde_json::Value,
+        options: &CallOptions,
+    ) -> Result<TransportResponse> {
+        // a single request, which is either done or not
+        let progress = ProgressEvent {
+            total: Some(1),
+            ..ProgressEvent::default()
+        };
+        if let Some(cancel) = &options.cancel {
+            cancel.check(progress)?;
+        }
+        let mut request = FDCRequest {
+            endpoint: endpoint.into(),
+            body,
+            headers: self.headers.clone(),
+        };
+        self.middlewares.on_request(&mut request);
+
+        let send = async {
+            match options.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, self.send(client, &request))
+                    .await
+                    .unwrap_or_else(|_| Err(FDCError::Timeout(timeout).into())),
+                None => self.send(client, &request).await,
+            }
+        };
+        let res = match &options.cancel {
+            Some(cancel) => {
+                let cancelled = cancel.cancelled();
+                futures::pin_mut!(send, cancelled);
+                match future::select(send, cancelled).await {
+                    Either::Left((res, _)) => res,
+                    Either::Right(_) => Err(Cancelled { progress }.into()),
+                }
+            }
+            None => send.await,
+        };
+        self.middlewares.on_response(&request, &res);
+        res
+    }
+
+    /// Send the request through the transport, enforcing the response limit even on transports
+    /// which do not.
+    async fn send(&self, client: &T, request: &FDCRequest) -> Result<TransportResponse> {
+        let url = format!(
+            "{}/{}?api_key={}",
+            self.base_url, request.endpoint, self.fdc_key
+        );
+        let res = client
+            .post_json_with(
+                &url,
+                &request.body,
+                &request.headers,
+                self.max_response_bytes,
+            )
+            .await?;
+        if res.body.len() > self.max_response_bytes {
+            return Err(FDCError::TooLarge(self.max_response_bytes).into());
+        }
+        Ok(res)
+    }
 
     /// Make a request to "v1/foods/search" and collect the first 10 results to a vector.
     pub async fn v1_foods_search<S: Into<String>>(
         &self,
-        client: &Client,
+        client: &T,
+        query: S,
+    ) -> Result<Vec<AbridgedFoodItem>> {
+        self.v1_foods_search_with(client, query, &CallOptions::default())
+            .await
+    }
+
+    /// Search like [`FDCService::v1_foods_search`], with the timeout and cancellation of the
+    /// options, like a search as the user types that a newer one abandons.
+    pub async fn v1_foods_search_with<S: Into<String>>(
+        &self,
+        client: &T,
         query: S,
+        options: &CallOptions,
     ) -> Result<Vec<AbridgedFoodItem>> {
         // make the request
         let body = serde_json::json!({ "query": query.into(), "pageSize": 10 });
-        let mut res = client
-            .post(format!(
-                "https://api.nal.usda.gov/fdc/v1/foods/search?api_key={}",
-                self.fdc_key
-            ))
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+        let res = self.post(client, "v1/foods/search", body, options).await?;
 
         // extract "foods" json array and deserialize
-        Ok(serde_json::from_value(res["foods"].take())?)
+        parse_search_response(res.json()?)
     }
 
-    /// Make a request to "v1/foods"
-    pub async fn v1_foods(&self, client: &Client, fdc_ids: &[i32]) -> Result<Vec<FDCMeta>> {
-        // make the request
-        let body = serde_json::json!({ "fdcIds": fdc_ids, "format": "full" });
-        let mut res = client
-            .post(format!(
-                "https://api.nal.usda.gov/fdc/v1/foods?api_key={}",
-                self.fdc_key
-            ))
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
+    /// Search "v1/foods/search" for the product of the query among the foods of the brand it starts
+    /// with, as guessed by [`text::split_brand`], like "dark chocolate bar" of the brand owner
+    /// "KIND" for "KIND dark chocolate bar". When no brand is guessed, or the brand has no such
+    /// product, the whole query is searched like [`FDCService::v1_foods_search`].
+    pub async fn search_guessing_brand(
+        &self,
+        client: &T,
+        query: &str,
+    ) -> Result<Vec<AbridgedFoodItem>> {
+        if let Some((brand, product)) = text::split_brand(query) {
+            let body = serde_json::json!({
+                "query": product,
+                "brandOwner": brand,
+                "pageSize": 10,
+            });
+            let res = self
+                .post(client, "v1/foods/search", body, &CallOptions::default())
+                .await?;
+            let foods = parse_search_response(res.json()?)?;
+            if !foods.is_empty() {
+                return Ok(foods);
+            }
+        }
+        self.v1_foods_search(client, query).await
+    }
+
+    /// Search "v1/foods/search" once for a page of [`SEARCH_PAGE_SIZE`] results, and group them by
+    /// data type into at most `per_group` foods each. Groups which come up short are left so.
+    pub async fn v1_foods_search_grouped<S: Into<String>>(
+        &self,
+        client: &T,
+        query: S,
+        per_group: u32,
+    ) -> Result<GroupedResults> {
+        self.v1_foods_search_grouped_with(client, query, per_group, false)
+            .await
+    }
+
+    /// Search like [`FDCService::v1_foods_search_grouped`], and when `fill` is set and FDC has more
+    /// results than the first page, top up the common and branded groups which came up short with
+    /// a search filtered by their data types. The other group cannot be filtered for, so it is
+    /// never filled.
+    pub async fn v1_foods_search_grouped_with<S: Into<String>>(
+        &self,
+        client: &T,
+        query: S,
+        per_group: u32,
+        fill: bool,
+    ) -> Result<GroupedResults> {
+        let query = query.into();
+        let per_group = per_group as usize;
+        let first = self
+            .search_filtered(client, &query, &[], SEARCH_PAGE_SIZE)
             .await?;
+        let more = first.total_pages > 1;
+        let mut grouped = GroupedResults::partition(first.foods, per_group);
+        if !(fill && more) {
+            return Ok(grouped);
+        }
+
+        let common = [DataType::Foundation, DataType::SrLegacy, DataType::Survey];
+        let branded = [DataType::Branded];
+        for data_types in [&common[..], &branded[..]] {
+            let group = grouped.group(data_types[0].as_api_str());
+            if group.len() >= per_group {
+                continue;
+            }
+            let page = self
+                .search_filtered(client, &query, data_types, per_group)
+                .await?;
+            for food in page.foods {
+                if group.len() < per_group && group.iter().all(|f| f.fdc_id != food.fdc_id) {
+                    group.push(food);
+                }
+            }
+        }
+        Ok(grouped)
+    }
+
+    /// Make a request for the first page of "v1/foods/search" among the data types, or among all
+    /// of them when there are none.
+    async fn search_filtered(
+        &self,
+        client: &T,
+        query: &str,
+        data_types: &[DataType],
+        page_size: usize,
+    ) -> Result<SearchPage> {
+        let mut body = serde_json::json!({ "query": query, "pageSize": page_size });
+        if !data_types.is_empty() {
+            body["dataType"] = serde_json::json!(data_types);
+        }
+        self.post(client, "v1/foods/search", body, &CallOptions::default())
+            .await?
+            .error_for_status()?
+            .json()
+    }
 
-        // map the values associated to the `dataType` key so that they can match the enum variants
-        res.as_array_mut().map(|foods| {
-            foods
-                .iter_mut()
-                .for_each(|food| match food["dataType"].as_str() {
-                    Some("Branded") => {}
-                    _ => {
-                        food["dataType"] = serde_json::Value::String("Other".into());
+    /// Search "v1/foods/search" among branded foods only, then fetch the full records of the
+    /// results through [`FDCService::v1_foods_chunked`]. The branded foods are returned in the
+    /// order of the search.
+    pub async fn search_branded_detailed<S: Into<String>>(
+        &self,
+        client: &T,
+        query: S,
+    ) -> Result<Vec<BrandedFoodItem>> {
+        // search with the branded filter
+        let body = serde_json::json!({
+            "query": query.into(),
+            "dataType": [DataType::Branded],
+            "pageSize": 10,
+        });
+        let res = self
+            .post(client, "v1/foods/search", body, &CallOptions::default())
+            .await?
+            .error_for_status()?;
+        let results = parse_search_response(res.json()?)?;
+
+        // fetch the full records and keep the branded ones
+        let fdc_ids = results.iter().map(|food| food.fdc_id).collect::<Vec<_>>();
+        Ok(self
+            .v1_foods_chunked(client, &fdc_ids)
+            .await?
+            .into_iter()
+            .filter_map(|food| match food {
+                FDCMeta::Branded(item) => Some(item),
+                FDCMeta::Other(_) => None,
+            })
+            .collect())
+    }
+
+    /// Make a request for a page of "v1/foods/list", numbered from 1, which lists the foods of a
+    /// data type without a query. The API accepts pages of up to [`SEARCH_PAGE_SIZE`] foods, and
+    /// pages past the last one are empty.
+    pub async fn v1_foods_list(
+        &self,
+        client: &T,
+        data_type: DataType,
+        page_size: usize,
+        page_number: u32,
+    ) -> Result<Vec<AbridgedFoodItem>> {
+        let options = CallOptions::default();
+        self.v1_foods_list_with(client, data_type, page_size, page_number, &options)
+            .await
+    }
+
+    /// List like [`FDCService::v1_foods_list`], with the timeout and cancellation of the options.
+    pub async fn v1_foods_list_with(
+        &self,
+        client: &T,
+        data_type: DataType,
+        page_size: usize,
+        page_number: u32,
+        options: &CallOptions,
+    ) -> Result<Vec<AbridgedFoodItem>> {
+        let body = serde_json::json!({
+            "dataType": [data_type],
+            "pageSize": page_size,
+            "pageNumber": page_number,
+        });
+        self.post(client, "v1/foods/list", body, options)
+            .await?
+            .error_for_status()?
+            .json()
+    }
+
+    /// Stream the foods FDC published after the date, most recent first, for syncing a mirror
+    /// incrementally. Pages of "v1/foods/list" sorted by publication date are fetched one after
+    /// the other until a food is no more recent than the date. Foods without a readable
+    /// publication date are skipped, and the stream ends after the first page which fails.
+    pub fn v1_foods_since<'a>(
+        &'a self,
+        client: &'a T,
+        since: NaiveDate,
+    ) -> impl Stream<Item = Result<AbridgedFoodItem>> + 'a {
+        stream::unfold(Some(1), move |number| async move {
+            let number = number?;
+            let body = serde_json::json!({
+                "pageSize": SEARCH_PAGE_SIZE,
+                "pageNumber": number,
+                "sortBy": "publishedDate",
+                "sortOrder": "desc",
+            });
+            let page = self
+                .post(client, "v1/foods/list", body, &CallOptions::default())
+                .await
+                .and_then(|res| res.error_for_status()?.json::<Vec<AbridgedFoodItem>>());
+            let foods = match page {
+                Ok(foods) => foods,
+                Err(e) => return Some((vec![Err(e)], None)),
+            };
+
+            // a short page is the last one
+            let mut next = Some(number + 1).filter(|_| foods.len() == SEARCH_PAGE_SIZE);
+            let mut newer = Vec::with_capacity(foods.len());
+            for food in foods {
+                match food.published() {
+                    Some(published) if published <= since => {
+                        next = None;
+                        break;
                     }
-                })
+                    Some(_) => newer.push(Ok(food)),
+                    None => {}
+                }
+            }
+            Some((newer, next))
+        })
+        .flat_map(stream::iter)
+    }
+
+    /// Stream every result of "v1/foods/search", page by page. The first page tells how many pages
+    /// there are, after which up to `concurrency` page requests are in flight at once. Results are
+    /// still yielded in page order, and the stream ends after the first page which fails.
+    pub fn search_stream<'a>(
+        &'a self,
+        client: &'a T,
+        query: &'a str,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<AbridgedFoodItem>> + 'a {
+        stream::once(self.search_page(client, query, 1))
+            .map(move |first| match first {
+                Ok(page) => {
+                    let rest = stream::iter(2..=page.total_pages)
+                        .map(move |number| self.search_page(client, query, number))
+                        .buffered(concurrency.max(1));
+                    stream::once(future::ready(Ok(page)))
+                        .chain(rest)
+                        .left_stream()
+                }
+                Err(e) => stream::once(future::ready(Err(e))).right_stream(),
+            })
+            .flatten()
+            .scan(false, |failed, page| {
+                // stop after yielding the first error
+                let item = if *failed { None } else { Some(page) };
+                *failed = matches!(item, Some(Err(_)));
+                future::ready(item)
+            })
+            .map_ok(|page| stream::iter(page.foods.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Make a request for a page of "v1/foods/search", numbered from 1.
+    async fn search_page(&self, client: &T, query: &str, number: u32) -> Result<SearchPage> {
+        let body = serde_json::json!({
+            "query": query,
+            "pageSize": SEARCH_PAGE_SIZE,
+            "pageNumber": number,
         });
+        self.post(client, "v1/foods/search", body, &CallOptions::default())
+            .await?
+            .error_for_status()?
+            .json()
+    }
+
+    /// Make a request to "v1/foods". The foods are in the order of the FDC ids, through
+    /// [`order_by_ids`].
+    pub async fn v1_foods(&self, client: &T, fdc_ids: &[i32]) -> Result<Vec<FDCMeta>> {
+        self.v1_foods_with(client, fdc_ids, &CallOptions::default())
+            .await
+    }
+
+    /// Make a request like [`FDCService::v1_foods`], with the timeout and cancellation of the
+    /// options.
+    pub async fn v1_foods_with(
+        &self,
+        client: &T,
+        fdc_ids: &[i32],
+        options: &CallOptions,
+    ) -> Result<Vec<FDCMeta>> {
+        // make the request
+        let body = serde_json::json!({ "fdcIds": fdc_ids, "format": "full" });
+        let foods = self
+            .post(client, "v1/foods", body, options)
+            .await?
+            .error_for_status()?
+            .json()?;
 
-        // deserialize
-        Ok(serde_json::from_value(res)?)
+        // restore the order of the request
+        Ok(order_by_ids(fdc_ids, foods))
+    }
+
+    /// Make as many requests to "v1/foods" as needed for chunks of at most [`CHUNK_SIZE`] ids, up
+    /// to [`FDCService::concurrency`] at once, stopping at the first chunk which fails. The foods
+    /// are in the order of the FDC ids, except for the unexpected foods [`order_by_ids`] puts at
+    /// the end of each chunk.
+    pub async fn v1_foods_chunked(&self, client: &T, fdc_ids: &[i32]) -> Result<Vec<FDCMeta>> {
+        self.v1_foods_chunked_with(client, fdc_ids, |_| {}, &CancellationToken::new())
+            .await
+    }
+
+    /// Make requests like [`FDCService::v1_foods_chunked`], reporting progress after each chunk
+    /// and stopping with [`Cancelled`] once the token is cancelled. No chunk is requested after
+    /// that, and the chunks already requested are abandoned.
+    pub async fn v1_foods_chunked_with<F>(
+        &self,
+        client: &T,
+        fdc_ids: &[i32],
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<FDCMeta>>
+    where
+        F: Fn(ProgressEvent),
+    {
+        let mut foods = Vec::with_capacity(fdc_ids.len());
+        let mut current = ProgressEvent {
+            total: Some(fdc_ids.len()),
+            ..ProgressEvent::default()
+        };
+        // the stream only requests more chunks when polled, so none is after the cancellation. The
+        // chunks are owned, as the async traits calling this, like `FoodSource`, need futures
+        // which do not borrow for a lifetime of their own
+        let mut fetched = stream::iter(fdc_ids.chunks(CHUNK_SIZE).map(<[i32]>::to_vec))
+            .map(|chunk| async move {
+                let result = self.v1_foods(client, &chunk).await;
+                (chunk, result)
+            })
+            .buffered(self.concurrency.max(1));
+        loop {
+            cancel.check(current)?;
+            let (chunk, result) = match fetched.next().await {
+                Some(next) => next,
+                None => break,
+            };
+            let fetched = result.map_err(|source| FDCError::Chunk {
+                fdc_ids: chunk.clone(),
+                source: Box::new(source),
+            })?;
+            foods.extend(fetched);
+            current.completed += chunk.len();
+            current.current_item = chunk.last().copied();
+            progress(current);
+        }
+        Ok(foods)
+    }
+
+    /// Make requests like [`FDCService::v1_foods_chunked`], but keep going past chunks which fail.
+    /// The foods of the successful chunks are returned along with an error for each failed chunk.
+    /// Up to [`FDCService::concurrency`] chunks are requested at once.
+    pub async fn v1_foods_partial(
+        &self,
+        client: &T,
+        fdc_ids: &[i32],
+    ) -> Result<(Vec<FDCMeta>, Vec<FDCError>)> {
+        let mut foods = Vec::with_capacity(fdc_ids.len());
+        let mut errors = Vec::new();
+        let mut fetched = stream::iter(fdc_ids.chunks(CHUNK_SIZE))
+            .map(|chunk| async move { (chunk, self.v1_foods(client, chunk).await) })
+            .buffered(self.concurrency.max(1));
+        while let Some((chunk, result)) = fetched.next().await {
+            match result {
+                Ok(fetched) => foods.extend(fetched),
+                Err(source) => errors.push(FDCError::Chunk {
+                    fdc_ids: chunk.to_vec(),
+                    source: Box::new(source),
+                }),
+            }
+        }
+        Ok((foods, errors))
     }
 }
 
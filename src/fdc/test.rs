@@ -1,18 +1,18 @@
-use crate::{
-    env,
-    fdc::{FDCMeta, FDCService},
+use crate::fdc::{
+    CallOptions, FDCMeta, FDCRequest, FDCService, HttpTransport, LabelNutrients, Middleware,
+    TransportResponse, FDC_URL,
 };
+use reqwest::header::HeaderValue;
+use std::sync::Mutex;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
 
-fn get_service() -> FDCService {
-    let environment = env::get().unwrap();
-    FDCService::new(environment.fdc_key)
-}
-
+#[cfg(feature = "env")]
 #[tokio::test]
 #[ignore]
 async fn v1_foods_search() {
     // get the service and a client
-    let service = get_service();
+    let service = FDCService::from_env().unwrap();
     let client = reqwest::Client::new();
 
     // first search is a upc:
@@ -35,11 +35,12 @@ async fn v1_foods_search() {
     assert_eq!(cheese.description, "CHEDDAR CHEESE");
 }
 
+#[cfg(feature = "env")]
 #[tokio::test]
 #[ignore]
 async fn v1_foods() {
     // get the service and a client
-    let service = get_service();
+    let service = FDCService::from_env().unwrap();
     let client = reqwest::Client::new();
 
     // search one of each type of food
@@ -97,3 +98,1944 @@ async fn v1_foods() {
         }
     };
 }
+
+/// Label nutrients with the given values, in field declaration order.
+fn label_nutrients(values: [f32; 13]) -> LabelNutrients {
+    let names = [
+        "fat",
+        "saturatedFat",
+        "transFat",
+        "cholesterol",
+        "sodium",
+        "carbohydrates",
+        "fiber",
+        "sugars",
+        "protein",
+        "calcium",
+        "iron",
+        "potassium",
+        "calories",
+    ];
+    let map = names
+        .iter()
+        .zip(values.iter())
+        .map(|(name, value)| (name.to_string(), serde_json::json!({ "value": value })))
+        .collect::<serde_json::Map<_, _>>();
+    serde_json::from_value(serde_json::Value::Object(map)).unwrap()
+}
+
+#[test]
+fn daily_values() {
+    let label = label_nutrients([
+        14.0, 1.0, 0.0, 0.0, 460.0, 0.0, 0.0, 0.0, 25.0, 130.0, 0.0, 0.0, 120.0,
+    ]);
+    let dvs = label.daily_values();
+    assert_eq!(dvs["sodium"], 20.0);
+    assert_eq!(dvs["protein"], 50.0);
+    assert_eq!(dvs["calcium"], 10.0);
+    assert_eq!(dvs["calories"], 6.0);
+    assert!(!dvs.contains_key("trans_fat"));
+    assert!(!dvs.contains_key("sugars"));
+
+    let dvs = label.daily_values_with(&[("sodium", 1500.0), ("fat", 0.0)]);
+    assert_eq!(dvs.len(), 1);
+    assert!((dvs["sodium"] - 30.666_666).abs() < 1e-4);
+}
+
+#[test]
+fn label_diff() {
+    let old = label_nutrients([
+        14.0, 1.0, 0.0, 0.0, 460.0, 20.0, 2.0, 5.0, 25.0, 130.0, 0.0, 0.0, 120.0,
+    ]);
+    let new = label_nutrients([
+        14.0001, 1.0, 0.0, 0.0, 380.0, 20.0, 2.0, 9.0, 25.0, 130.0, 0.0, 0.0, 130.0,
+    ]);
+    assert_eq!(
+        old.diff(&new, 0.01),
+        vec![
+            ("sodium", 460.0, 380.0),
+            ("sugars", 5.0, 9.0),
+            ("calories", 120.0, 130.0)
+        ]
+    );
+    assert_eq!(old.diff(&new, 10.0), vec![("sodium", 460.0, 380.0)]);
+    assert!(old.diff(&old, 0.0).is_empty());
+}
+
+#[test]
+fn sanitized_portions() {
+    let food: super::APFoodItem = serde_json::from_value(serde_json::json!({
+        "fdcId": 1,
+        "foodAttributes": [],
+        "foodPortions": [
+            { "id": 1, "amount": 1.0, "gramWeight": 28.0, "modifier": "slice" },
+            { "id": 2, "amount": 1.0, "gramWeight": 0.0, "modifier": "cup" },
+            { "id": 3, "amount": 0.0, "gramWeight": 30.0, "modifier": "oz" },
+            { "id": 4, "gramWeight": 113.0, "modifier": "cup, shredded" },
+            { "id": 5, "amount": 2.0, "gramWeight": -4.0, "modifier": "tbsp" },
+        ],
+    }))
+    .unwrap();
+    assert!(food.food_portions[0].is_valid());
+    assert!(!food.food_portions[1].is_valid());
+    assert!(!food.food_portions[2].is_valid());
+    let ids = food.sanitized_portions().map(|p| p.id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 4]);
+}
+
+#[test]
+fn attributes_by_type() {
+    let attribute = |id: i32, value: &str, type_id: i32, type_name: &str| {
+        serde_json::json!({
+            "id": id,
+            "sequenceNumber": id,
+            "value": value,
+            "foodAttributeType": { "id": type_id, "name": type_name, "description": "" },
+        })
+    };
+    let food: super::APFoodItem = serde_json::from_value(serde_json::json!({
+        "fdcId": 1,
+        "foodAttributes": [
+            attribute(1, "Moisture adjusted", 1001, "Adjustments"),
+            attribute(2, "cheddar", 1000, "Common Name"),
+            attribute(3, "Fat adjusted", 1001, "Adjustments"),
+        ],
+        "foodPortions": [],
+    }))
+    .unwrap();
+    let values = |type_name| {
+        food.attributes_by_type(type_name)
+            .iter()
+            .map(|attribute| attribute.value.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(values("Adjustments"), ["Moisture adjusted", "Fat adjusted"]);
+    assert_eq!(values("common name"), ["cheddar"]);
+    assert!(values("Additional Description").is_empty());
+    assert!(food.additional_descriptions().is_empty());
+    assert_eq!(
+        food.attributes_of(super::AttributeKind::Adjustments).len(),
+        2
+    );
+}
+
+#[test]
+fn common_names() {
+    let food: super::APFoodItem = serde_json::from_value(serde_json::json!({
+        "fdcId": 1,
+        "commonNames": "garbanzo, Chickpea, ",
+        "foodAttributes": [
+            {
+                "id": 1,
+                "value": "chickpea",
+                "foodAttributeType": { "id": 1000, "name": "Common Name", "description": "" },
+            },
+            {
+                "id": 2,
+                "value": "bengal gram",
+                "foodAttributeType": { "id": 1000, "name": "Common Name", "description": "" },
+            },
+        ],
+        "foodPortions": [],
+    }))
+    .unwrap();
+    assert_eq!(food.common_names(), ["garbanzo", "Chickpea", "bengal gram"]);
+}
+
+/// The paths of the keys of the json, with `[]` for the elements of arrays.
+fn shape(value: &serde_json::Value) -> std::collections::BTreeSet<String> {
+    let mut paths = std::collections::BTreeSet::new();
+    let mut pending = vec![(String::new(), value)];
+    while let Some((path, value)) = pending.pop() {
+        match value {
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    let path = format!("{}.{}", path, key);
+                    paths.insert(path.clone());
+                    pending.push((path, value));
+                }
+            }
+            serde_json::Value::Array(array) => {
+                let path = format!("{}[]", path);
+                pending.extend(array.iter().map(|value| (path.clone(), value)));
+            }
+            _ => {}
+        }
+    }
+    paths
+}
+
+#[test]
+fn builders() {
+    use crate::fdc::test_util::{BrandedFoodBuilder, FoodBuilder};
+    use crate::fdc::{AttributeKind, DataType};
+    use crate::nutrition::Nutrient;
+
+    let reserialize = |value: serde_json::Value| {
+        let food = serde_json::from_value::<FDCMeta>(value).unwrap();
+        serde_json::to_value(food).unwrap()
+    };
+    let fixture = |text: &str| reserialize(serde_json::from_str(text).unwrap());
+
+    // built foods survive a round trip, and have every key the fixtures have but the optional ones
+    // the builders were not given
+    let branded = BrandedFoodBuilder::new(1)
+        .description("DARK CHOCOLATE BAR")
+        .brand_owner("KIND")
+        .gtin("602652171000")
+        .ingredients("ALMONDS, CHOCOLATE")
+        .serving(40.0, "g")
+        .household_serving("1 bar")
+        .label_nutrient(Nutrient::Protein, 6.0)
+        .label_nutrient(Nutrient::Energy, 200.0)
+        .food_nutrient(Nutrient::Protein, 15.0)
+        .published("4/1/2019")
+        .meta();
+    let built = serde_json::to_value(&branded).unwrap();
+    assert_eq!(reserialize(built.clone()), built);
+    let expected = fixture(include_str!("../../tests/fixtures/branded.json"));
+    let optional = [".foodNutrients[].foodNutrientDerivation"];
+    let missing = shape(&expected)
+        .difference(&shape(&built))
+        .filter(|path| !optional.iter().any(|o| path.starts_with(o)))
+        .cloned()
+        .collect::<Vec<_>>();
+    assert!(missing.is_empty(), "{:?}", missing);
+    assert!(shape(&built).is_subset(&shape(&expected)));
+
+    let survey = FoodBuilder::new(2)
+        .data_type(DataType::Survey)
+        .description("Bread, white")
+        .food_nutrient(Nutrient::Protein, 9.43)
+        .portion(28.0, "1 slice")
+        .attribute(AttributeKind::AdditionalDescription, "white bread")
+        .meta();
+    let built = serde_json::to_value(&survey).unwrap();
+    assert_eq!(reserialize(built.clone()), built);
+    let expected = fixture(include_str!("../../tests/fixtures/survey.json"));
+    assert_eq!(shape(&built), shape(&expected));
+
+    // the values land where the rest of the crate reads them
+    match branded {
+        FDCMeta::Branded(food) => {
+            let label = food.label_nutrients.unwrap();
+            assert_eq!((label.protein.value, label.calories.value), (6.0, 200.0));
+            assert_eq!(label.fat.value, 0.0);
+            assert_eq!(food.food_nutrients[0].nutrient.id, 1003);
+        }
+        FDCMeta::Other(_) => panic!("Should have been a branded food!"),
+    }
+    match survey {
+        FDCMeta::Other(food) => {
+            assert_eq!(food.additional_descriptions(), ["white bread"]);
+            assert_eq!(food.sanitized_portions().count(), 1);
+        }
+        FDCMeta::Branded(_) => panic!("Should have been a survey food!"),
+    }
+}
+
+#[test]
+fn serving_units() {
+    use crate::fdc::test_util::BrandedFoodBuilder;
+    use crate::fdc::units::{serving_unit, ServingUnit};
+    use crate::quantities::Quantity;
+    use uom::si::{mass::gram, volume::milliliter};
+
+    let cases = [
+        ("g", ServingUnit::Gram, 1.0),
+        ("gm", ServingUnit::Gram, 1.0),
+        ("gram", ServingUnit::Gram, 1.0),
+        ("grams", ServingUnit::Gram, 1.0),
+        ("GRM", ServingUnit::Gram, 1.0),
+        ("mg", ServingUnit::Milligram, 1e-3),
+        ("MGM", ServingUnit::Milligram, 1e-3),
+        ("mcg", ServingUnit::Microgram, 1e-6),
+        ("ug", ServingUnit::Microgram, 1e-6),
+        ("µg", ServingUnit::Microgram, 1e-6),
+        ("MC", ServingUnit::Microgram, 1e-6),
+        ("kg", ServingUnit::Kilogram, 1e3),
+        ("KGM", ServingUnit::Kilogram, 1e3),
+        ("oz", ServingUnit::Ounce, 28.349524),
+        ("ONZ", ServingUnit::Ounce, 28.349524),
+        ("lb", ServingUnit::Pound, 453.59238),
+        ("LBR", ServingUnit::Pound, 453.59238),
+        ("ml", ServingUnit::Milliliter, 1.0),
+        ("MLT", ServingUnit::Milliliter, 1.0),
+        ("cl", ServingUnit::Centiliter, 10.0),
+        ("CLT", ServingUnit::Centiliter, 10.0),
+        ("dl", ServingUnit::Deciliter, 100.0),
+        ("DLT", ServingUnit::Deciliter, 100.0),
+        ("l", ServingUnit::Liter, 1e3),
+        ("LTR", ServingUnit::Liter, 1e3),
+        ("cc", ServingUnit::CubicCentimeter, 1.0),
+        ("CMQ", ServingUnit::CubicCentimeter, 1.0),
+        ("fl oz", ServingUnit::FluidOunce, 29.57353),
+        ("OZA", ServingUnit::FluidOunce, 29.57353),
+    ];
+    // every code of every unit is covered
+    let codes = ServingUnit::all().iter().map(|unit| unit.codes().len());
+    assert_eq!(codes.sum::<usize>(), cases.len());
+    for (code, unit, size) in cases.iter() {
+        assert_eq!(serving_unit(code), Some(*unit), "{}", code);
+        assert_eq!(
+            serving_unit(&format!(" {} ", code.to_lowercase())),
+            Some(*unit)
+        );
+        let food = BrandedFoodBuilder::new(1).serving(2.0, *code).build();
+        let (amount, volume) = match food.serving_size_quantity().unwrap() {
+            Quantity::Mass(mass) => (mass.get::<gram>(), false),
+            Quantity::Volume(volume) => (volume.get::<milliliter>(), true),
+            Quantity::Nominal(..) => panic!("{} is no unit", code),
+        };
+        assert!((amount / (2.0 * size) - 1.0).abs() < 1e-4, "{}", code);
+        assert_eq!(volume, unit.is_volume(), "{}", code);
+    }
+
+    // unknown codes are reported with the code rather than taken for grams
+    assert_eq!(serving_unit("XYZ"), None);
+    let err = BrandedFoodBuilder::new(7)
+        .serving(30.0, "XYZ")
+        .build()
+        .serving_size_quantity()
+        .unwrap_err();
+    assert!(matches!(
+        &err,
+        crate::Error::Nutrition(crate::nutrition::NutritionError::UnknownUnit { fdc_id: 7, unit })
+            if unit == "XYZ"
+    ));
+}
+
+#[test]
+fn fdc_meta_data_types() {
+    use crate::fdc::DataType;
+
+    let data_type = |value: serde_json::Value| {
+        serde_json::from_value::<FDCMeta>(value)
+            .unwrap()
+            .data_type()
+            .to_string()
+    };
+    let food = |data_type: &str| {
+        serde_json::json!({
+            "fdcId": 1,
+            "foodAttributes": [],
+            "foodPortions": [],
+            "dataType": data_type,
+        })
+    };
+    // every data type of FDC but branded reads as other and keeps its name, even with the tag
+    // after the fields, and so do data types FDC may add and the tag of other foods serialized by
+    // us
+    let others = DataType::all()
+        .iter()
+        .filter(|data_type| **data_type != DataType::Branded)
+        .map(DataType::as_api_str)
+        .chain(["Experimental", "Other"]);
+    for name in others {
+        let food = serde_json::from_value::<FDCMeta>(food(name)).unwrap();
+        assert!(matches!(food, FDCMeta::Other(_)), "{}", name);
+        assert_eq!(food.data_type(), name);
+        let json = serde_json::to_value(&food).unwrap();
+        assert_eq!(json["dataType"], name);
+        assert_eq!(
+            serde_json::from_value::<FDCMeta>(json).unwrap().data_type(),
+            name
+        );
+    }
+    let mut missing = food("Other");
+    missing.as_object_mut().unwrap().remove("dataType");
+    assert_eq!(data_type(missing), "Other");
+    assert_eq!(
+        data_type(serde_json::json!({
+            "fdcId": 2,
+            "description": "CHEDDAR",
+            "ingredients": "MILK, SALT",
+            "servingSize": 28.0,
+            "servingSizeUnit": "g",
+            "dataType": "Branded",
+        })),
+        "Branded"
+    );
+
+    // the fields of the other data types are only read for them, like the attributes of branded
+    // foods, whose shape is not that of other foods
+    assert_eq!(
+        data_type(serde_json::json!({
+            "fdcId": 3,
+            "ingredients": "MILK",
+            "servingSize": 28.0,
+            "servingSizeUnit": "g",
+            "foodAttributes": [{ "id": 1, "name": "Added Package Weight", "value": 2 }],
+            "dataType": "Branded",
+        })),
+        "Branded"
+    );
+
+    // a branded food without its fields fails rather than passing for another kind of food
+    assert!(serde_json::from_value::<FDCMeta>(food("Branded")).is_err());
+}
+
+#[test]
+fn scientific_and_common_names() {
+    let food: super::APFoodItem = serde_json::from_value(serde_json::json!({
+        "fdcId": 1,
+        "description": "Apples, fuji, with skin, raw",
+        "scientificName": "Malus domestica",
+        "commonNames": "fuji apple",
+        "foodAttributes": [],
+        "foodPortions": [],
+    }))
+    .unwrap();
+    assert_eq!(food.scientific_name.as_deref(), Some("Malus domestica"));
+    assert_eq!(food.common_names.as_deref(), Some("fuji apple"));
+
+    // most foods have neither, and serialize without them
+    let food: super::APFoodItem = serde_json::from_value(serde_json::json!({
+        "fdcId": 2,
+        "foodAttributes": [],
+        "foodPortions": [],
+    }))
+    .unwrap();
+    assert!(food.scientific_name.is_none() && food.common_names.is_none());
+    let value = serde_json::to_value(&food).unwrap();
+    assert!(value.get("scientificName").is_none());
+    assert!(value.get("commonNames").is_none());
+}
+
+/// A transport which records the requests sent through it and answers them with a function, in
+/// place of a mock server.
+struct Recorder {
+    respond: fn(&serde_json::Value) -> TransportResponse,
+    requests: Mutex<Vec<(String, serde_json::Value)>>,
+}
+
+impl Recorder {
+    fn new(respond: fn(&serde_json::Value) -> TransportResponse) -> Recorder {
+        Recorder {
+            respond,
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The urls and bodies of the requests so far.
+    fn requests(&self) -> Vec<(String, serde_json::Value)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpTransport for Recorder {
+    async fn post_json(
+        &self,
+        url: &str,
+        body: &serde_json::Value,
+        _limit: usize,
+    ) -> crate::Result<TransportResponse> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((url.to_string(), body.clone()));
+        Ok((self.respond)(body))
+    }
+}
+
+/// Respond to "v1/foods" with a minimal food for each requested id, failing any request that
+/// contains the id `13`.
+fn echo_foods_except_13(body: &serde_json::Value) -> TransportResponse {
+    let ids = body["fdcIds"].as_array().unwrap();
+    if ids.contains(&serde_json::json!(13)) {
+        return TransportResponse::new(500, "Internal Server Error");
+    }
+    let foods = ids
+        .iter()
+        .map(|id| {
+            serde_json::json!({
+                "dataType": "Survey (FNDDS)",
+                "fdcId": id,
+                "foodAttributes": [],
+                "foodPortions": [],
+            })
+        })
+        .collect::<Vec<_>>();
+    TransportResponse::new(200, serde_json::to_vec(&foods).unwrap())
+}
+
+fn recorded_service() -> (Recorder, FDCService<Recorder>) {
+    let service = FDCService::new("DEMO_KEY").with_transport();
+    (Recorder::new(echo_foods_except_13), service)
+}
+
+#[tokio::test]
+async fn v1_foods_chunked_aborts() {
+    let (client, service) = recorded_service();
+    let ids = (1..=50).collect::<Vec<_>>();
+    let err = service.v1_foods_chunked(&client, &ids).await.unwrap_err();
+    assert!(err.is_retryable());
+    match err {
+        crate::Error::Fdc(super::FDCError::Chunk { fdc_ids, .. }) => {
+            assert_eq!(fdc_ids, (1..=20).collect::<Vec<_>>())
+        }
+        e => panic!("Should have been a chunk error: {}", e),
+    }
+    let requests = client.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].0,
+        format!("{}/v1/foods?api_key=DEMO_KEY", FDC_URL)
+    );
+
+    let ids = (21..=45).collect::<Vec<_>>();
+    let foods = service.v1_foods_chunked(&client, &ids).await.unwrap();
+    assert_eq!(foods.len(), 25);
+}
+
+#[tokio::test]
+async fn v1_foods_chunked_cancelled() {
+    let (client, service) = recorded_service();
+    // 100 requests of 20 ids, none of them 13, cancelled after the 10th
+    let ids = (100..2100).collect::<Vec<_>>();
+    let cancel = crate::progress::CancellationToken::new();
+    let events = std::cell::RefCell::new(Vec::new());
+    let err = service
+        .v1_foods_chunked_with(
+            &client,
+            &ids,
+            |event| {
+                events.borrow_mut().push(event);
+                if event.completed == 200 {
+                    cancel.cancel();
+                }
+            },
+            &cancel,
+        )
+        .await
+        .unwrap_err();
+    let cancelled = match err {
+        crate::Error::Cancelled(cancelled) => cancelled,
+        e => panic!("Should have been cancelled: {}", e),
+    };
+    assert_eq!(cancelled.progress.completed, 200);
+    assert_eq!(cancelled.progress.total, Some(2000));
+    assert_eq!(cancelled.progress.current_item, Some(299));
+    assert_eq!(events.borrow().len(), 10);
+    assert_eq!(client.requests().len(), 10);
+}
+
+#[test]
+fn order_by_ids() {
+    let food = |fdc_id: i32| {
+        serde_json::from_value::<FDCMeta>(serde_json::json!({
+            "dataType": "Other",
+            "fdcId": fdc_id,
+            "foodAttributes": [],
+            "foodPortions": [],
+        }))
+        .unwrap()
+    };
+    let ids = |foods: Vec<FDCMeta>| foods.iter().map(|f| f.fdc_id()).collect::<Vec<_>>();
+    let shuffled = vec![food(3), food(9), food(1), food(2)];
+    assert_eq!(ids(super::order_by_ids(&[1, 2, 3], shuffled)), [1, 2, 3, 9]);
+
+    // missing ids are skipped, and repeated ones get a single food at their first position
+    let shuffled = vec![food(2), food(4), food(2)];
+    assert_eq!(ids(super::order_by_ids(&[4, 5, 2, 4], shuffled)), [4, 2, 2]);
+    assert!(super::order_by_ids(&[1], Vec::new()).is_empty());
+}
+
+#[test]
+fn group_by_data_type() {
+    let food = |fdc_id: i32, data_type: &str| crate::fdc::AbridgedFoodItem {
+        fdc_id,
+        data_type: data_type.into(),
+        description: format!("food {}", fdc_id),
+        food_nutrients: Vec::new(),
+        score: None,
+        publication_date: None,
+    };
+    let foods = vec![
+        food(1, "Branded"),
+        food(2, "SR Legacy"),
+        food(3, "Branded"),
+        food(4, "Experimental"),
+    ];
+    let groups = super::group_by_data_type(foods);
+    let ids = |data_type: &str| {
+        groups[data_type]
+            .iter()
+            .map(|f| f.fdc_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(groups.len(), 3);
+    assert_eq!(ids("Branded"), [1, 3]);
+    assert_eq!(ids("SR Legacy"), [2]);
+    assert_eq!(ids("Experimental"), [4]);
+    assert!(super::group_by_data_type(Vec::new()).is_empty());
+}
+
+/// Respond to "v1/foods" with the foods of the requested ids in reverse order, followed by a food
+/// which was not requested.
+fn reversed_foods(body: &serde_json::Value) -> TransportResponse {
+    let mut ids = body["fdcIds"].as_array().unwrap().clone();
+    ids.reverse();
+    ids.push(serde_json::json!(999));
+    let foods = ids
+        .iter()
+        .map(|id| {
+            serde_json::json!({
+                "dataType": "Foundation",
+                "fdcId": id,
+                "foodAttributes": [],
+                "foodPortions": [],
+            })
+        })
+        .collect::<Vec<_>>();
+    TransportResponse::new(200, serde_json::to_vec(&foods).unwrap())
+}
+
+#[tokio::test]
+async fn v1_foods_in_order() {
+    let service = FDCService::new("DEMO_KEY").with_transport();
+    let client = Recorder::new(reversed_foods);
+    let foods = service.v1_foods(&client, &[5, 1, 3]).await.unwrap();
+    let ids = foods.iter().map(|f| f.fdc_id()).collect::<Vec<_>>();
+    assert_eq!(ids, [5, 1, 3, 999]);
+
+    // every chunk is put in order, with its extra food at its end
+    let fdc_ids = (1..=25).collect::<Vec<_>>();
+    let foods = service.v1_foods_chunked(&client, &fdc_ids).await.unwrap();
+    let ids = foods.iter().map(|f| f.fdc_id()).collect::<Vec<_>>();
+    let expected = (1..=20)
+        .chain([999])
+        .chain(21..=25)
+        .chain([999])
+        .collect::<Vec<_>>();
+    assert_eq!(ids, expected);
+}
+
+#[tokio::test]
+async fn v1_foods_partial_collects() {
+    let (client, service) = recorded_service();
+    let ids = (1..=50).collect::<Vec<_>>();
+    let (foods, errors) = service.v1_foods_partial(&client, &ids).await.unwrap();
+    assert_eq!(client.requests().len(), 3);
+    assert_eq!(
+        foods.iter().map(|f| f.fdc_id()).collect::<Vec<_>>(),
+        (21..=50).collect::<Vec<_>>()
+    );
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        super::FDCError::Chunk { fdc_ids, .. } if *fdc_ids == (1..=20).collect::<Vec<_>>()
+    ));
+}
+
+/// A transport which answers "v1/foods" like [`echo_foods_except_13`] after yielding a few times,
+/// keeping track of the most requests it had in flight at once.
+#[derive(Default)]
+struct Gauge {
+    in_flight: Mutex<(usize, usize)>,
+}
+
+#[async_trait::async_trait]
+impl HttpTransport for Gauge {
+    async fn post_json(
+        &self,
+        _url: &str,
+        body: &serde_json::Value,
+        _limit: usize,
+    ) -> crate::Result<TransportResponse> {
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            in_flight.0 += 1;
+            in_flight.1 = in_flight.1.max(in_flight.0);
+        }
+        for _ in 0..3 {
+            tokio::task::yield_now().await;
+        }
+        self.in_flight.lock().unwrap().0 -= 1;
+        Ok(echo_foods_except_13(body))
+    }
+}
+
+#[tokio::test]
+async fn v1_foods_partial_concurrency() {
+    let ids = (1..=200).collect::<Vec<_>>();
+    for concurrency in [1, 3].iter() {
+        let client = Gauge::default();
+        let service = FDCService::new("DEMO_KEY")
+            .with_concurrency(*concurrency)
+            .with_transport();
+        let (foods, errors) = service.v1_foods_partial(&client, &ids).await.unwrap();
+        assert_eq!(client.in_flight.lock().unwrap().1, *concurrency);
+        assert_eq!(
+            foods.iter().map(|f| f.fdc_id()).collect::<Vec<_>>(),
+            (21..=200).collect::<Vec<_>>()
+        );
+        assert_eq!(errors.len(), 1);
+    }
+    assert_eq!(
+        FDCService::new("DEMO_KEY").with_concurrency(0).concurrency,
+        1
+    );
+}
+
+#[tokio::test]
+async fn v1_foods_chunked_concurrency() {
+    let ids = (21..=200).collect::<Vec<_>>();
+    for concurrency in [1, 3].iter() {
+        let client = Gauge::default();
+        let service = FDCService::new("DEMO_KEY")
+            .with_concurrency(*concurrency)
+            .with_transport();
+        let foods = service.v1_foods_chunked(&client, &ids).await.unwrap();
+        assert_eq!(client.in_flight.lock().unwrap().1, *concurrency);
+        assert_eq!(foods.iter().map(|f| f.fdc_id()).collect::<Vec<_>>(), ids);
+    }
+}
+
+/// Respond to "v1/foods/search" with 5 pages of 3 foods each, numbered by page, failing page 3 of
+/// the query "broken".
+/// Earlier pages take longer so that concurrent requests finish out of order.
+fn search_pages(req: &Request) -> ResponseTemplate {
+    let body: serde_json::Value = serde_json::from_slice(&req.body).unwrap();
+    let number = body["pageNumber"].as_u64().unwrap();
+    if number == 3 && body["query"] == "broken" {
+        return ResponseTemplate::new(500);
+    }
+    let foods = (0..3)
+        .map(|i| {
+            serde_json::json!({
+                "fdcId": number * 10 + i,
+                "dataType": "Branded",
+                "description": format!("FOOD {}", i),
+                "foodNutrients": [],
+            })
+        })
+        .collect::<Vec<_>>();
+    ResponseTemplate::new(200)
+        .set_body_json(serde_json::json!({
+            "totalHits": 15,
+            "currentPage": number,
+            "totalPages": 5,
+            "foods": foods,
+        }))
+        .set_delay(std::time::Duration::from_millis(300 - 50 * number.min(5)))
+}
+
+#[tokio::test]
+async fn search_stream_in_order() {
+    use futures::{StreamExt, TryStreamExt};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/foods/search"))
+        .respond_with(search_pages)
+        .mount(&server)
+        .await;
+    let service = FDCService::new("DEMO_KEY").with_base_url(server.uri());
+    let client = reqwest::Client::new();
+
+    // all four remaining pages are requested at once, so this takes about two page delays
+    let start = std::time::Instant::now();
+    let foods: Vec<_> = service
+        .search_stream(&client, "cheddar", 4)
+        .try_collect()
+        .await
+        .unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_millis(800));
+    let ids = foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>();
+    let expected = (1..=5)
+        .flat_map(|page| (0..3).map(move |i| page * 10 + i))
+        .collect::<Vec<_>>();
+    assert_eq!(ids, expected);
+
+    // a failed page ends the stream after the results before it
+    let results: Vec<_> = service.search_stream(&client, "broken", 2).collect().await;
+    assert_eq!(results.len(), 7);
+    assert!(results[..6].iter().all(|result| result.is_ok()));
+    assert!(results[6].is_err());
+}
+
+#[cfg(feature = "storage")]
+mod bulk {
+    use super::*;
+    use crate::fdc::bulk;
+    use crate::progress::{CancellationToken, ProgressEvent};
+    use crate::storage::{FoodStore, SqliteStore, StorageError};
+    use std::cell::RefCell;
+    use std::path::{Path, PathBuf};
+
+    fn sample() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/fdc_bulk")
+    }
+
+    #[test]
+    fn joins_rows() {
+        let foods = bulk::read(&sample())
+            .unwrap()
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        let ids = foods.iter().map(FDCMeta::fdc_id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![167512, 167513, 1105904, 1105905]);
+
+        match &foods[0] {
+            FDCMeta::Other(food) => {
+                assert_eq!(food.food_nutrients.len(), 3);
+                assert_eq!(food.food_nutrients[1].nutrient.name, "Energy");
+                assert_eq!(food.food_nutrients[1].amount, Some(307.0));
+                assert_eq!(food.food_nutrients[0].data_points, Some(3));
+                assert_eq!(food.food_nutrients[1].data_points, None);
+                assert_eq!(food.food_portions[0].gram_weight, 34.0);
+            }
+            _ => panic!("Should have been an sr legacy food!"),
+        }
+        match &foods[1] {
+            // the nutrient missing from nutrient.csv is left out
+            FDCMeta::Other(food) => {
+                assert_eq!(food.food_nutrients.len(), 1);
+                assert_eq!(food.food_portions.len(), 2);
+            }
+            _ => panic!("Should have been an sr legacy food!"),
+        }
+        match &foods[3] {
+            FDCMeta::Branded(food) => {
+                assert_eq!(food.brand_owner.as_deref(), Some("Campbell Soup Company"));
+                assert_eq!(food.brand_name, None);
+                assert!(food.ingredients.contains("\"NATURAL\" FLAVORING"));
+                assert_eq!(food.serving_size, 240.0);
+                assert_eq!(food.household_serving_full_text.as_deref(), Some("1 cup"));
+                assert_eq!(food.food_nutrients.len(), 2);
+            }
+            _ => panic!("Should have been a branded food!"),
+        }
+    }
+
+    #[test]
+    fn requires_sorted_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["food.csv", "nutrient.csv", "food_nutrient.csv"] {
+            std::fs::copy(sample().join(file), dir.path().join(file)).unwrap();
+        }
+        let nutrients = std::fs::read_to_string(dir.path().join("food_nutrient.csv")).unwrap();
+        let mut lines = nutrients.lines().collect::<Vec<_>>();
+        lines.swap(2, 6);
+        std::fs::write(dir.path().join("food_nutrient.csv"), lines.join("\n")).unwrap();
+
+        let err = bulk::read(dir.path())
+            .unwrap()
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap_err();
+        assert!(err.to_string().contains("not sorted by fdc_id"));
+        assert!(bulk::read(&dir.path().join("missing")).is_err());
+    }
+
+    /// Load the bundle in the directory into a new store.
+    async fn load(
+        dir: &Path,
+        options: &bulk::BulkOptions,
+    ) -> (SqliteStore, crate::Result<bulk::ValidationReport>) {
+        let store = SqliteStore::connect("sqlite::memory:").await.unwrap();
+        let cancel = CancellationToken::new();
+        let report = bulk::load_into(&store, dir, options, |_| {}, &cancel).await;
+        (store, report)
+    }
+
+    #[tokio::test]
+    async fn load_into() {
+        let store = SqliteStore::connect("sqlite::memory:").await.unwrap();
+        let reports = RefCell::new(Vec::new());
+        let cancel = CancellationToken::new();
+        let options = bulk::BulkOptions::new().with_max_errors(2);
+        let report = bulk::load_into(
+            &store,
+            &sample(),
+            &options,
+            |p| reports.borrow_mut().push(p),
+            &cancel,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            reports.into_inner(),
+            vec![ProgressEvent {
+                completed: 4,
+                total: None,
+                current_item: Some(1105905)
+            }]
+        );
+        assert_eq!(report.foods, 4);
+        let oil = store.get_food(1105904).await.unwrap().unwrap();
+        assert_eq!(oil.description(), "WESSON Vegetable Oil 1 GAL");
+
+        // the sample has a nutrient row of a food it does not have, and one of an unknown nutrient
+        let kinds = report.errors.iter().map(|e| e.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                bulk::RowErrorKind::MissingFood,
+                bulk::RowErrorKind::MissingNutrient
+            ]
+        );
+        assert_eq!(
+            report.errors[0].to_string(),
+            "food_nutrient.csv:2: fdc_id 167511 is not in food.csv"
+        );
+
+        // which stop a load allowing no error
+        let (store, report) = load(&sample(), &bulk::BulkOptions::new()).await;
+        match report.unwrap_err() {
+            crate::Error::Storage(StorageError::TooManyErrors(report)) => {
+                assert_eq!(report.error_count, 1);
+                assert_eq!(report.foods, 0);
+            }
+            e => panic!("Should have been too many errors: {}", e),
+        }
+        assert!(store.get_food(167512).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn load_into_cancelled() {
+        let store = SqliteStore::connect("sqlite::memory:").await.unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let options = bulk::BulkOptions::new().with_max_errors(2);
+        let err = bulk::load_into(&store, &sample(), &options, |_| {}, &cancel)
+            .await
+            .unwrap_err();
+        let cancelled = match err {
+            crate::Error::Cancelled(cancelled) => cancelled,
+            e => panic!("Should have been cancelled: {}", e),
+        };
+        assert_eq!(cancelled.progress.completed, 0);
+        assert!(store.get_food(1105904).await.unwrap().is_none());
+    }
+
+    /// A copy of the sample with a malformed amount, a nutrient row out of order, a branded food
+    /// without its branded row, and a portion of a food after the last.
+    fn corrupted() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let edit = |file: &str, edit: &dyn Fn(&mut Vec<String>)| {
+            let text = std::fs::read_to_string(sample().join(file)).unwrap();
+            let mut lines = text.lines().map(String::from).collect::<Vec<_>>();
+            edit(&mut lines);
+            std::fs::write(dir.path().join(file), lines.join("\n") + "\n").unwrap();
+        };
+        edit("food.csv", &|_| {});
+        edit("nutrient.csv", &|_| {});
+        edit("food_nutrient.csv", &|lines| {
+            lines[3] = lines[3].replace("\"307\"", "\"abc\"");
+            lines.push(r#""1283702","167513","1003","1","","","","","","","""#.into());
+        });
+        edit("branded_food.csv", &|lines| {
+            lines.pop();
+        });
+        edit("food_portion.csv", &|lines| {
+            lines.push(r#""90000","2000000","1","1.0","9999","","cup","100.0","","","""#.into());
+        });
+        dir
+    }
+
+    #[tokio::test]
+    async fn dry_run() {
+        use bulk::RowErrorKind::*;
+
+        let dir = corrupted();
+        let options = bulk::BulkOptions::new().with_dry_run().with_max_errors(10);
+        let (store, report) = load(dir.path(), &options).await;
+        let report = report.unwrap();
+        let rows = report
+            .rows
+            .iter()
+            .map(|(file, rows)| (file.as_str(), *rows))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                ("branded_food.csv", 1),
+                ("food.csv", 4),
+                ("food_nutrient.csv", 9),
+                ("food_portion.csv", 4),
+                ("nutrient.csv", 3),
+            ]
+        );
+        assert_eq!(report.foods, 3);
+        assert_eq!(report.skipped, 0);
+        assert!(!report.is_clean());
+        assert_eq!(report.error_count, 6);
+        let errors = report
+            .errors
+            .iter()
+            .map(|e| (e.file.as_str(), e.line, e.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                ("food_nutrient.csv", 2, MissingFood),
+                ("food_nutrient.csv", 4, Malformed),
+                ("food_nutrient.csv", 7, MissingNutrient),
+                ("food_nutrient.csv", 10, Unsorted),
+                ("food.csv", 5, MissingBranded),
+                ("food_portion.csv", 5, MissingFood),
+            ]
+        );
+        assert!(report.errors[3]
+            .message
+            .contains("167513 comes after 1105905"));
+
+        // nothing was written
+        assert!(store.get_food(167512).await.unwrap().is_none());
+
+        // a strict read still fails on the first malformed row
+        let err = bulk::read(dir.path())
+            .unwrap()
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Malformed food_nutrient.csv"));
+    }
+
+    #[tokio::test]
+    async fn too_many_errors() {
+        let dir = corrupted();
+        let (store, report) = load(dir.path(), &bulk::BulkOptions::new().with_max_errors(3)).await;
+        let err = report.unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("the first at food_nutrient.csv:2: fdc_id 167511 is not in food.csv"));
+        match err {
+            crate::Error::Storage(StorageError::TooManyErrors(report)) => {
+                assert_eq!(report.error_count, 6);
+                assert_eq!(report.foods, 3);
+            }
+            e => panic!("Should have been too many errors: {}", e),
+        }
+        // the foods before are kept
+        assert!(store.get_food(1105904).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn data_types() {
+        let options = bulk::BulkOptions::new()
+            .with_dry_run()
+            .with_max_errors(1)
+            .with_data_types([crate::fdc::DataType::Branded]);
+        let (_, report) = load(&sample(), &options).await;
+        let report = report.unwrap();
+        assert_eq!(report.foods, 2);
+        assert_eq!(report.skipped, 2);
+        // the unknown nutrient belongs to a skipped food
+        assert_eq!(report.error_count, 1);
+    }
+}
+
+/// Respond to "v1/foods" with a branded food for each requested id, except for id `2` which is
+/// reported as a survey food.
+fn branded_foods(req: &Request) -> ResponseTemplate {
+    let body: serde_json::Value = serde_json::from_slice(&req.body).unwrap();
+    let foods = body["fdcIds"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|id| {
+            if id == 2 {
+                return serde_json::json!({
+                    "dataType": "Survey (FNDDS)",
+                    "fdcId": id,
+                    "foodAttributes": [],
+                    "foodPortions": [],
+                });
+            }
+            serde_json::json!({
+                "dataType": "Branded",
+                "fdcId": id,
+                "description": format!("BRANDED {}", id),
+                "ingredients": "",
+                "servingSize": 28.0,
+                "servingSizeUnit": "g",
+            })
+        })
+        .collect::<Vec<_>>();
+    ResponseTemplate::new(200).set_body_json(foods)
+}
+
+#[tokio::test]
+async fn search_branded_detailed() {
+    use wiremock::matchers::body_partial_json;
+
+    let results = [3, 2, 1]
+        .iter()
+        .map(|id| {
+            serde_json::json!({
+                "fdcId": id,
+                "dataType": "Branded",
+                "description": "CHEDDAR",
+                "foodNutrients": [],
+            })
+        })
+        .collect::<Vec<_>>();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/foods/search"))
+        .and(body_partial_json(
+            serde_json::json!({ "dataType": ["Branded"] }),
+        ))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "foods": results })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/foods"))
+        .respond_with(branded_foods)
+        .expect(1)
+        .mount(&server)
+        .await;
+    let service = FDCService::new("DEMO_KEY").with_base_url(server.uri());
+    let client = reqwest::Client::new();
+
+    let foods = service
+        .search_branded_detailed(&client, "cheddar")
+        .await
+        .unwrap();
+    assert_eq!(
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>(),
+        [3, 1]
+    );
+    assert_eq!(foods[0].description, "BRANDED 3");
+}
+
+#[test]
+fn food_nutrient_derivation() {
+    let nutrient: super::FoodNutrient = serde_json::from_value(serde_json::json!({
+        "type": "FoodNutrient",
+        "id": 2219707,
+        "nutrient": { "id": 1003, "number": "203", "name": "Protein", "unitName": "g" },
+        "amount": 24.9,
+        "dataPoints": 12,
+        "min": 23.5,
+        "max": 26.1,
+        "foodNutrientDerivation": {
+            "id": 1,
+            "code": "A",
+            "description": "Analytical",
+            "foodNutrientSource": {
+                "id": 1,
+                "code": "1",
+                "description": "Analytical or derived from analytical",
+            },
+        },
+    }))
+    .unwrap();
+    assert_eq!(nutrient.data_points, Some(12));
+    let derivation = nutrient.food_nutrient_derivation.unwrap();
+    assert_eq!(derivation.code.as_deref(), Some("A"));
+    assert_eq!(derivation.food_nutrient_source.unwrap().id, 1);
+
+    // abridged records leave them out
+    let nutrient: super::FoodNutrient = serde_json::from_value(serde_json::json!({
+        "id": 1,
+        "nutrient": { "id": 1003, "name": "Protein", "unitName": "g" },
+        "amount": 3.0,
+    }))
+    .unwrap();
+    assert!(nutrient.data_points.is_none());
+    assert!(nutrient.food_nutrient_derivation.is_none());
+}
+
+#[test]
+fn macros() {
+    let food: super::AbridgedFoodItem = serde_json::from_value(serde_json::json!({
+        "fdcId": 1,
+        "dataType": "SR Legacy",
+        "description": "Cheese, cheddar",
+        "foodNutrients": [
+            { "nutrientId": 1062, "nutrientName": "Energy", "unitName": "kJ", "value": 1699.0 },
+            { "nutrientId": 1003, "nutrientName": "Protein", "unitName": "G", "value": 24.9 },
+            { "nutrientId": 1004, "nutrientName": "Total lipid (fat)", "unitName": "G", "value": 33.8 },
+            { "nutrientId": 1008, "nutrientName": "Energy", "unitName": "KCAL", "value": 403.0 },
+        ],
+    }))
+    .unwrap();
+    let macros = food.macros();
+    assert_eq!(macros.calories, Some(403.0));
+    assert_eq!(macros.protein_g, Some(24.9));
+    assert_eq!(macros.fat_g, Some(33.8));
+    assert_eq!(macros.carbs_g, None);
+
+    let eaten = food.nutrients_for_grams(75.0);
+    assert_eq!(eaten.len(), 4);
+    assert_eq!(eaten[1].nutrient_name, "Protein");
+    assert!((eaten[1].value - 18.675).abs() < 1e-4);
+    assert!((eaten[3].value - 302.25).abs() < 1e-3);
+    assert_eq!(eaten[3].unit_name, "KCAL");
+
+    // energy only in kilojoules is converted
+    let food = super::AbridgedFoodItem {
+        food_nutrients: food.food_nutrients[..1].to_vec(),
+        ..food
+    };
+    assert!((food.macros().calories.unwrap() - 406.07).abs() < 0.01);
+}
+
+#[test]
+fn macro_value() {
+    use super::Macro;
+
+    let food: super::AbridgedFoodItem = serde_json::from_value(serde_json::json!({
+        "fdcId": 1,
+        "dataType": "Branded",
+        "description": "WHOLE WHEAT BREAD",
+        "foodNutrients": [
+            { "nutrientId": 1079, "nutrientName": "Fiber, total dietary", "unitName": "G", "value": 6.9 },
+            { "nutrientId": 1063, "nutrientName": "Sugars, Total", "unitName": "G", "value": 4.0 },
+            { "nutrientId": 2000, "nutrientName": "Total Sugars", "unitName": "G", "value": 5.1 },
+            { "nutrientId": 1093, "nutrientName": "Sodium, Na", "unitName": "G", "value": 0.45 },
+        ],
+    }))
+    .unwrap();
+    assert_eq!(food.macro_value(Macro::Fiber), Some(6.9));
+    assert_eq!(food.macro_value(Macro::Sugar), Some(5.1));
+    assert!((food.macro_value(Macro::Sodium).unwrap() - 450.0).abs() < 1e-3);
+    assert_eq!(food.macro_value(Macro::Protein), None);
+    assert_eq!(food.macros().protein_g, food.macro_value(Macro::Protein));
+}
+
+#[test]
+fn serving_grams() {
+    use crate::fdc::test_util::{BrandedFoodBuilder, FoodBuilder};
+
+    let branded = |size, unit| BrandedFoodBuilder::new(1).serving(size, unit).meta();
+    assert_eq!(branded(30.0, "g").serving_grams(), Some(30.0));
+    assert_eq!(branded(30.0, "GRM").serving_grams(), Some(30.0));
+    let ounce = branded(1.0, "oz").serving_grams().unwrap();
+    assert!((ounce - 28.349524).abs() < 1e-4);
+    // volumes need a density, and unknown units are not taken for grams
+    assert_eq!(branded(240.0, "ml").serving_grams(), None);
+    assert_eq!(branded(1.0, "XYZ").serving_grams(), None);
+
+    // the valid portion first by sequence number, whatever the order of the portions
+    let mut food = FoodBuilder::new(2)
+        .portion(30.0, "1 slice")
+        .portion(0.0, "1 crumb")
+        .portion(60.0, "1 cup")
+        .build();
+    food.food_portions[0].sequence_number = Some(3);
+    food.food_portions[1].sequence_number = Some(1);
+    food.food_portions[2].sequence_number = Some(2);
+    assert_eq!(food.default_portion().unwrap().gram_weight, 60.0);
+    assert_eq!(FDCMeta::Other(food).serving_grams(), Some(60.0));
+    assert_eq!(FoodBuilder::new(3).meta().serving_grams(), None);
+}
+
+#[test]
+fn energy_density() {
+    use uom::si::energy::{kilocalorie, kilojoule};
+    use uom::si::f32::{MassDensity, Volume};
+    use uom::si::mass_density::gram_per_cubic_centimeter;
+    use uom::si::volume::{cup, milliliter};
+
+    // whole milk, 61 kcal per 100 g and 1.03 g per ml
+    let milk: super::AbridgedFoodItem = serde_json::from_value(serde_json::json!({
+        "fdcId": 1,
+        "dataType": "SR Legacy",
+        "description": "Milk, whole, 3.25% milkfat",
+        "foodNutrients": [
+            { "nutrientId": 1008, "nutrientName": "Energy", "unitName": "KCAL", "value": 61.0 },
+        ],
+    }))
+    .unwrap();
+    let density = MassDensity::new::<gram_per_cubic_centimeter>(1.03);
+    let per_ml = milk.energy_density(density).unwrap();
+    assert!((per_ml.get::<kilocalorie>() - 0.6283).abs() < 1e-4);
+    let per_cup = per_ml.get::<kilocalorie>() * Volume::new::<cup>(1.0).get::<milliliter>();
+    assert!((per_cup - 148.6).abs() < 0.5);
+    assert!((per_ml.get::<kilojoule>() - 2.629).abs() < 1e-3);
+
+    // energy in kJ converts, and a food without energy has none
+    let kj = super::AbridgedFoodItem {
+        food_nutrients: vec![super::AbridgedFoodNutrient {
+            nutrient_id: 1062,
+            nutrient_name: "Energy".into(),
+            unit_name: "kJ".into(),
+            value: 418.4,
+        }],
+        ..milk.clone()
+    };
+    let per_ml = kj.energy_density(density).unwrap().get::<kilocalorie>();
+    assert!((per_ml - 1.03).abs() < 1e-3);
+    let water = super::AbridgedFoodItem {
+        food_nutrients: Vec::new(),
+        ..milk
+    };
+    assert!(water.energy_density(density).is_none());
+}
+
+#[test]
+fn merge_nutrients() {
+    let nutrient = |id: i32, name: &str, unit: &str, value: f32| super::AbridgedFoodNutrient {
+        nutrient_id: id,
+        nutrient_name: name.into(),
+        unit_name: unit.into(),
+        value,
+    };
+    let flour = [
+        nutrient(1003, "Protein", "G", 10.0),
+        nutrient(1093, "Sodium, Na", "MG", 2.0),
+    ];
+    let butter = [
+        nutrient(1004, "Total lipid (fat)", "G", 81.0),
+        nutrient(1003, "Protein", "g", 0.9),
+        nutrient(1093, "Sodium", "G", 0.6),
+    ];
+    let merged = super::merge_nutrients(&[&flour, &butter, &[]]);
+    assert_eq!(
+        merged.iter().map(|n| n.nutrient_id).collect::<Vec<_>>(),
+        [1003, 1093, 1004]
+    );
+    assert_eq!(merged[0].value, 10.9);
+    assert_eq!(merged[0].unit_name, "G");
+    // grams of sodium are not added to milligrams
+    assert_eq!(merged[1].value, 2.0);
+    assert_eq!(merged[1].nutrient_name, "Sodium, Na");
+    assert!(super::merge_nutrients(&[]).is_empty());
+}
+
+#[test]
+fn data_types() {
+    use crate::fdc::DataType;
+
+    let service = FDCService::new("key");
+    assert_eq!(service.data_types().len(), 4);
+    for data_type in service.data_types() {
+        let api = data_type.as_api_str();
+        assert_eq!(data_type.to_string(), api);
+        assert_eq!(api.parse::<DataType>().unwrap(), *data_type);
+        assert_eq!(serde_json::to_value(data_type).unwrap(), api);
+    }
+    assert_eq!(DataType::SrLegacy.as_api_str(), "SR Legacy");
+    assert_eq!(
+        "Survey (FNDDS)".parse::<DataType>().unwrap(),
+        DataType::Survey
+    );
+    assert!("Experimental".parse::<DataType>().is_err());
+}
+
+/// Respond to "v1/foods/list" with the page of the request out of 5 foundation foods, and with a
+/// bad request for any other data type.
+fn list_pages(body: &serde_json::Value) -> TransportResponse {
+    if body["dataType"] != serde_json::json!(["Foundation"]) {
+        return TransportResponse::new(400, "Bad Request");
+    }
+    let size = body["pageSize"].as_u64().unwrap() as usize;
+    let number = body["pageNumber"].as_u64().unwrap() as usize;
+    let foods = (1..=5)
+        .skip((number - 1) * size)
+        .take(size)
+        .map(|id| {
+            serde_json::json!({
+                "fdcId": id,
+                "dataType": "Foundation",
+                "description": format!("FOOD {}", id),
+                "foodNutrients": [],
+            })
+        })
+        .collect::<Vec<_>>();
+    TransportResponse::new(200, serde_json::to_vec(&foods).unwrap())
+}
+
+#[tokio::test]
+async fn v1_foods_list() {
+    use crate::fdc::DataType;
+
+    let service = FDCService::new("DEMO_KEY").with_transport();
+    let client = Recorder::new(list_pages);
+    let page = |number| service.v1_foods_list(&client, DataType::Foundation, 2, number);
+    let ids = |foods: Vec<crate::fdc::AbridgedFoodItem>| {
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(page(1).await.unwrap()), [1, 2]);
+    assert_eq!(ids(page(3).await.unwrap()), [5]);
+    assert!(page(4).await.unwrap().is_empty());
+    let err = service
+        .v1_foods_list(&client, DataType::Branded, 2, 1)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        crate::Error::Fdc(super::FDCError::Status(400))
+    ));
+    assert!(!err.is_retryable());
+    assert!(client
+        .requests()
+        .iter()
+        .all(|(url, _)| url.ends_with("/v1/foods/list?api_key=DEMO_KEY")));
+}
+
+/// Respond to "v1/foods/list" with 450 foods, the first published on 2024-01-01 and each of the
+/// others a day before, except for the food 5 which has no date.
+fn dated_pages(body: &serde_json::Value) -> TransportResponse {
+    if body["sortBy"] != "publishedDate" || body["sortOrder"] != "desc" {
+        return TransportResponse::new(400, "Bad Request");
+    }
+    let size = body["pageSize"].as_u64().unwrap() as usize;
+    let number = body["pageNumber"].as_u64().unwrap() as usize;
+    let newest = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let foods = (0..450)
+        .skip((number - 1) * size)
+        .take(size)
+        .map(|id| {
+            let published = newest - chrono::Duration::days(id as i64);
+            serde_json::json!({
+                "fdcId": id,
+                "dataType": "Branded",
+                "description": format!("FOOD {}", id),
+                "foodNutrients": [],
+                "publicationDate": if id == 5 { "".to_string() } else { published.to_string() },
+            })
+        })
+        .collect::<Vec<_>>();
+    TransportResponse::new(200, serde_json::to_vec(&foods).unwrap())
+}
+
+#[tokio::test]
+async fn v1_foods_since() {
+    use futures::{StreamExt, TryStreamExt};
+
+    let service = FDCService::new("DEMO_KEY").with_transport();
+    let since = |date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+
+    // the foods after the date, up to the one 250 days before the newest, across two pages
+    let client = Recorder::new(dated_pages);
+    let foods = service
+        .v1_foods_since(&client, since("2023-04-26"))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let ids = foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>();
+    assert_eq!(ids, (0..250).filter(|id| *id != 5).collect::<Vec<_>>());
+    assert_eq!(foods[0].published(), Some(since("2024-01-01")));
+    assert_eq!(client.requests().len(), 2);
+
+    // past the last page, which is short
+    let client = Recorder::new(dated_pages);
+    let foods = service
+        .v1_foods_since(&client, since("2000-01-01"))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(foods.len(), 449);
+    assert_eq!(client.requests().len(), 3);
+
+    // nothing newer
+    let client = Recorder::new(dated_pages);
+    let foods = service.v1_foods_since(&client, since("2024-01-01"));
+    assert_eq!(foods.count().await, 0);
+    assert_eq!(client.requests().len(), 1);
+
+    // a failing page ends the stream with its error
+    let client = Recorder::new(list_pages);
+    let results = service
+        .v1_foods_since(&client, since("2024-01-01"))
+        .collect::<Vec<_>>()
+        .await;
+    assert!(matches!(
+        results[..],
+        [Err(crate::Error::Fdc(super::FDCError::Status(400)))]
+    ));
+}
+
+/// Respond to "v1/foods/search" with the matches of a mix of data types among the foods 1 to 250,
+/// or 1 to 10 for the query "rare", filtered by the `dataType` of the request. Foods are branded,
+/// except for 3 in Foundation, 7 in SR Legacy, 9 which is experimental, and 201 and later which
+/// are in Survey (FNDDS).
+fn mixed_search(body: &serde_json::Value) -> TransportResponse {
+    let data_type = |id| match id {
+        3 => "Foundation",
+        7 => "SR Legacy",
+        9 => "Experimental",
+        201.. => "Survey (FNDDS)",
+        _ => "Branded",
+    };
+    let last = if body["query"] == "rare" { 10 } else { 250 };
+    let size = body["pageSize"].as_u64().unwrap() as usize;
+    let matches = (1..=last)
+        .filter(|id| match body["dataType"].as_array() {
+            Some(filter) => filter.contains(&serde_json::json!(data_type(*id))),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+    let foods = matches
+        .iter()
+        .take(size)
+        .map(|id| {
+            serde_json::json!({
+                "fdcId": id,
+                "dataType": data_type(*id),
+                "description": format!("FOOD {}", id),
+                "foodNutrients": [],
+            })
+        })
+        .collect::<Vec<_>>();
+    let page = serde_json::json!({
+        "totalHits": matches.len(),
+        "totalPages": matches.len().div_ceil(size),
+        "foods": foods,
+    });
+    TransportResponse::new(200, serde_json::to_vec(&page).unwrap())
+}
+
+#[tokio::test]
+async fn v1_foods_search_grouped() {
+    let service = FDCService::new("DEMO_KEY").with_transport();
+    let ids = |foods: &[crate::fdc::AbridgedFoodItem]| {
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>()
+    };
+
+    // a single search, with the common group short
+    let client = Recorder::new(mixed_search);
+    let grouped = service
+        .v1_foods_search_grouped(&client, "cheddar", 5)
+        .await
+        .unwrap();
+    assert_eq!(ids(&grouped.common), [3, 7]);
+    assert_eq!(ids(&grouped.branded), [1, 2, 4, 5, 6]);
+    assert_eq!(ids(&grouped.other), [9]);
+    let requests = client.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].1,
+        serde_json::json!({ "query": "cheddar", "pageSize": 200 })
+    );
+
+    // filling the common group takes a filtered search, while the other group is left short
+    let client = Recorder::new(mixed_search);
+    let grouped = service
+        .v1_foods_search_grouped_with(&client, "cheddar", 5, true)
+        .await
+        .unwrap();
+    assert_eq!(ids(&grouped.common), [3, 7, 201, 202, 203]);
+    assert_eq!(ids(&grouped.branded), [1, 2, 4, 5, 6]);
+    assert_eq!(ids(&grouped.other), [9]);
+    let requests = client.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[1].1["dataType"],
+        serde_json::json!(["Foundation", "SR Legacy", "Survey (FNDDS)"])
+    );
+
+    // nothing to fill from when the first page has every result
+    let client = Recorder::new(mixed_search);
+    let grouped = service
+        .v1_foods_search_grouped_with(&client, "rare", 5, true)
+        .await
+        .unwrap();
+    assert_eq!(ids(&grouped.common), [3, 7]);
+    assert_eq!(ids(&grouped.branded), [1, 2, 4, 5, 6]);
+    assert_eq!(client.requests().len(), 1);
+}
+
+/// Respond to "v1/foods/search" with a food for the query, unless the brand owner is "NOBODY".
+fn brand_search(body: &serde_json::Value) -> TransportResponse {
+    let foods = if body["brandOwner"] == "NOBODY" {
+        Vec::new()
+    } else {
+        vec![serde_json::json!({
+            "fdcId": 1,
+            "dataType": "Branded",
+            "description": body["query"],
+            "foodNutrients": [],
+        })]
+    };
+    let page = serde_json::json!({ "totalPages": 1, "foods": foods });
+    TransportResponse::new(200, serde_json::to_vec(&page).unwrap())
+}
+
+#[tokio::test]
+async fn search_guessing_brand() {
+    let service = FDCService::new("DEMO_KEY").with_transport();
+    let bodies = |client: &Recorder| {
+        client
+            .requests()
+            .into_iter()
+            .map(|(_, body)| body)
+            .collect::<Vec<_>>()
+    };
+
+    // the brand is searched apart from the product
+    let client = Recorder::new(brand_search);
+    let foods = service
+        .search_guessing_brand(&client, "KIND dark chocolate bar")
+        .await
+        .unwrap();
+    assert_eq!(foods[0].description, "dark chocolate bar");
+    assert_eq!(
+        bodies(&client),
+        [serde_json::json!({
+            "query": "dark chocolate bar",
+            "brandOwner": "KIND",
+            "pageSize": 10,
+        })]
+    );
+
+    // without results for the brand, the whole query is searched
+    let client = Recorder::new(brand_search);
+    let foods = service
+        .search_guessing_brand(&client, "NOBODY cheddar")
+        .await
+        .unwrap();
+    assert_eq!(foods[0].description, "NOBODY cheddar");
+    let requests = bodies(&client);
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].get("brandOwner").is_none());
+
+    // and so it is when no brand is guessed
+    let client = Recorder::new(brand_search);
+    service
+        .search_guessing_brand(&client, "dark chocolate")
+        .await
+        .unwrap();
+    assert_eq!(
+        bodies(&client),
+        [serde_json::json!({ "query": "dark chocolate", "pageSize": 10 })]
+    );
+}
+
+#[tokio::test]
+async fn v1_foods_search_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/foods/search"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+            "error": {
+                "code": "API_KEY_INVALID",
+                "message": "An invalid api_key was supplied.",
+            }
+        })))
+        .mount(&server)
+        .await;
+    let service = FDCService::new("BAD_KEY").with_base_url(server.uri());
+    let client = reqwest::Client::new();
+    let err = service
+        .v1_foods_search(&client, "cheddar")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "FDC response has no foods: An invalid api_key was supplied."
+    );
+
+    let err = super::parse_search_response(serde_json::json!({
+        "status": 400,
+        "error": "Bad Request",
+        "message": "pageSize must be at most 200",
+    }))
+    .unwrap_err();
+    assert!(err.to_string().ends_with("pageSize must be at most 200"));
+    assert!(super::parse_search_response(serde_json::json!({ "foods": null })).is_err());
+    assert!(
+        super::parse_search_response(serde_json::json!({ "foods": [] }))
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn parse_foods_response() {
+    use super::parse_foods_response;
+
+    let foods = parse_foods_response(serde_json::json!([
+        {
+            "dataType": "Survey (FNDDS)",
+            "fdcId": 1,
+            "foodAttributes": [],
+            "foodPortions": [],
+        },
+        {
+            "dataType": "Branded",
+            "fdcId": 2,
+            "ingredients": "",
+            "servingSize": 30.0,
+            "servingSizeUnit": "g",
+        },
+    ]))
+    .unwrap();
+    assert!(matches!(
+        foods[..],
+        [FDCMeta::Other(_), FDCMeta::Branded(_)]
+    ));
+    assert_eq!(foods[1].fdc_id(), 2);
+
+    let err = parse_foods_response(serde_json::json!({
+        "error": { "code": "OVER_RATE_LIMIT", "message": "Too many requests." }
+    }))
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "FDC response has no foods: Too many requests."
+    );
+    let err = parse_foods_response(serde_json::json!([{ "dataType": "Branded" }])).unwrap_err();
+    assert!(matches!(err, crate::Error::Fdc(super::FDCError::Decode(_))));
+}
+
+#[tokio::test]
+async fn reqwest_transport() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/foods"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("Retry-After", "30")
+                .insert_header("X-RateLimit-Remaining", "0")
+                .set_body_string("Too Many Requests"),
+        )
+        .mount(&server)
+        .await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/v1/foods", server.uri());
+    let res = client
+        .post_json(&url, &serde_json::json!({ "fdcIds": [1] }), 1024)
+        .await
+        .unwrap();
+    assert_eq!(res.status, 429);
+    assert_eq!(res.retry_after, Some(std::time::Duration::from_secs(30)));
+    assert_eq!(res.rate_limit_remaining, Some(0));
+    assert_eq!(res.body, b"Too Many Requests");
+    assert!(res.error_for_status().unwrap_err().is_retryable());
+
+    // long bodies are abandoned rather than buffered
+    let err = client
+        .post_json(&url, &serde_json::json!({ "fdcIds": [1] }), 8)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        crate::Error::Fdc(super::FDCError::TooLarge(8))
+    ));
+}
+
+#[tokio::test]
+async fn max_response_bytes() {
+    let (client, service) = recorded_service();
+    let foods = service.v1_foods(&client, &[1, 2]).await.unwrap();
+    assert_eq!(foods.len(), 2);
+
+    // the limit holds even for transports which ignore it
+    let service = service.with_max_response_bytes(64);
+    let err = service.v1_foods(&client, &[1, 2]).await.unwrap_err();
+    assert_eq!(err.to_string(), "FDC response is longer than 64 bytes");
+    assert!(!err.is_retryable());
+}
+
+/// A middleware which captures what it sees, and tags the body of requests with its name.
+struct Capture {
+    name: &'static str,
+    seen: std::sync::Arc<Mutex<Vec<String>>>,
+}
+
+impl Middleware for Capture {
+    fn on_request(&self, request: &mut FDCRequest) {
+        let tags = request.body["tags"].as_array().cloned().unwrap_or_default();
+        self.seen
+            .lock()
+            .unwrap()
+            .push(format!("{} {} {:?}", self.name, request.endpoint, tags));
+        request.body["tags"] = tags.into_iter().chain([self.name.into()]).collect();
+    }
+
+    fn on_response(&self, request: &FDCRequest, response: &crate::Result<TransportResponse>) {
+        let outcome = match response {
+            Ok(res) => res.status.to_string(),
+            Err(e) => e.to_string(),
+        };
+        self.seen
+            .lock()
+            .unwrap()
+            .push(format!("{} {} {}", self.name, request.endpoint, outcome));
+    }
+}
+
+/// A transport which cannot reach FDC.
+struct Unreachable;
+
+#[async_trait::async_trait]
+impl HttpTransport for Unreachable {
+    async fn post_json(
+        &self,
+        _url: &str,
+        _body: &serde_json::Value,
+        _limit: usize,
+    ) -> crate::Result<TransportResponse> {
+        Err(super::FDCError::Transport("connection refused".into()).into())
+    }
+}
+
+#[tokio::test]
+async fn middleware() {
+    let seen = std::sync::Arc::new(Mutex::new(Vec::new()));
+    let capture = |name| Capture {
+        name,
+        seen: seen.clone(),
+    };
+    let service = FDCService::new("DEMO_KEY")
+        .with_middleware(capture("first"))
+        .with_middleware(capture("second"))
+        .with_transport();
+
+    // both see the request in registration order, and changes to it reach the transport
+    let client = Recorder::new(echo_foods_except_13);
+    service.v1_foods(&client, &[1, 2]).await.unwrap();
+    let (_, body) = &client.requests()[0];
+    assert_eq!(body["tags"], serde_json::json!(["first", "second"]));
+    assert_eq!(body["fdcIds"], serde_json::json!([1, 2]));
+
+    // error statuses are responses, while failures of the transport are errors
+    assert!(service.v1_foods(&client, &[13]).await.is_err());
+    let failing = service.with_transport();
+    assert!(failing.v1_foods(&Unreachable, &[1]).await.is_err());
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            "first v1/foods []",
+            r#"second v1/foods [String("first")]"#,
+            "first v1/foods 200",
+            "second v1/foods 200",
+            "first v1/foods []",
+            r#"second v1/foods [String("first")]"#,
+            "first v1/foods 500",
+            "second v1/foods 500",
+            "first v1/foods []",
+            r#"second v1/foods [String("first")]"#,
+            "first v1/foods connection refused",
+            "second v1/foods connection refused",
+        ]
+    );
+}
+
+/// A middleware which signs requests with a header.
+struct Sign;
+
+impl Middleware for Sign {
+    fn on_request(&self, request: &mut FDCRequest) {
+        request
+            .headers
+            .insert("x-signature", HeaderValue::from_static("signed"));
+    }
+}
+
+#[tokio::test]
+async fn request_headers() {
+    use wiremock::matchers::header;
+
+    // the server only answers requests with the headers of the service and of the middleware
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/foods/search"))
+        .and(header("user-agent", "nutrack-test/1.0"))
+        .and(header("x-app", "diary"))
+        .and(header("x-signature", "signed"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "totalPages": 0, "foods": [] })),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-app", "diary".parse().unwrap());
+    headers.insert("user-agent", "replaced".parse().unwrap());
+    let service = FDCService::new("DEMO_KEY")
+        .with_base_url(server.uri())
+        .with_headers(headers)
+        .with_user_agent(HeaderValue::from_static("nutrack-test/1.0"))
+        .with_middleware(Sign);
+    let client = reqwest::Client::new();
+    service.v1_foods_search(&client, "cheddar").await.unwrap();
+    service.v1_foods_search(&client, "gouda").await.unwrap();
+}
+
+#[tokio::test]
+async fn headers_need_a_transport_which_sends_them() {
+    // the recorder only implements `post_json`, so it cannot send the user agent
+    let (recorder, service) = recorded_service();
+    let service = service.with_user_agent(HeaderValue::from_static("nutrack-test/1.0"));
+    let err = service.v1_foods(&recorder, &[1]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The transport cannot send the headers user-agent"
+    );
+    assert!(recorder.requests().is_empty());
+}
+
+/// A mock server whose searches answer only after a second.
+async fn slow_search() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/foods/search"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "totalPages": 0, "foods": [] }))
+                .set_delay(std::time::Duration::from_secs(1)),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn call_options_timeout() {
+    let server = slow_search().await;
+    let service = FDCService::new("DEMO_KEY").with_base_url(server.uri());
+    let client = reqwest::Client::new();
+    let timeout = std::time::Duration::from_millis(50);
+    let options = CallOptions::new().with_timeout(timeout);
+    let start = std::time::Instant::now();
+    let err = service
+        .v1_foods_search_with(&client, "cheddar", &options)
+        .await
+        .unwrap_err();
+    assert!(start.elapsed() < std::time::Duration::from_millis(900));
+    assert!(matches!(
+        err,
+        crate::Error::Fdc(super::FDCError::Timeout(t)) if t == timeout
+    ));
+    assert!(err.is_retryable());
+
+    // without the override, the slow response still comes
+    let foods = service.v1_foods_search(&client, "cheddar").await.unwrap();
+    assert!(foods.is_empty());
+}
+
+#[tokio::test]
+async fn call_options_cancel() {
+    let server = slow_search().await;
+    let service = FDCService::new("DEMO_KEY").with_base_url(server.uri());
+    let client = reqwest::Client::new();
+    let cancel = crate::progress::CancellationToken::new();
+    let options = CallOptions::new().with_cancel(cancel.clone());
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        cancel.cancel();
+    });
+    let start = std::time::Instant::now();
+    let err = service
+        .v1_foods_search_with(&client, "cheddar", &options)
+        .await
+        .unwrap_err();
+    assert!(start.elapsed() < std::time::Duration::from_millis(900));
+    match err {
+        crate::Error::Cancelled(cancelled) => {
+            assert_eq!(cancelled.progress.completed, 0);
+            assert_eq!(cancelled.progress.total, Some(1));
+        }
+        e => panic!("expected a cancellation, got {}", e),
+    }
+
+    // a token cancelled beforehand sends nothing
+    let (client, service) = recorded_service();
+    let cancel = crate::progress::CancellationToken::new();
+    cancel.cancel();
+    let options = CallOptions::new().with_cancel(cancel);
+    let err = service.v1_foods_with(&client, &[1], &options).await;
+    assert!(matches!(err, Err(crate::Error::Cancelled(_))));
+    assert!(client.requests().is_empty());
+}
@@ -0,0 +1,294 @@
+//! Builders of FDC foods for tests, here and in downstream crates, behind the `test-util` feature.
+//! They fill in what a test does not care about the way FDC would, so that
+//! `BrandedFoodBuilder::new(1).serving(30.0, "g").label_nutrient(Nutrient::Protein, 5.0).meta()`
+//! stands in for a whole response. [`serving_corpus`] samples the serving texts of a bulk bundle
+//! for the parser corpus of `tests/fixtures/servings.txt`.
+
+use super::bulk;
+use super::{
+    APFoodItem, AttributeKind, BrandedFoodItem, DataType, FDCMeta, FoodAttribute,
+    FoodAttributeType, FoodNutrient, FoodPortion, LabelNutrient, LabelNutrients, NutrientMeta,
+};
+use crate::error::Result;
+use crate::nutrition::Nutrient;
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// A food nutrient of the amount per 100 g, with the record id, FDC id, name, and unit FDC would
+/// give it.
+fn food_nutrient(id: i32, nutrient: Nutrient, amount: f32) -> FoodNutrient {
+    FoodNutrient {
+        id,
+        nutrient: NutrientMeta {
+            id: nutrient.fdc_ids()[0],
+            number: None,
+            name: nutrient.name().into(),
+            unit_name: nutrient.unit().symbol().into(),
+        },
+        amount: Some(amount),
+        data_points: None,
+        food_nutrient_derivation: None,
+    }
+}
+
+/// The label nutrients of a branded food, all zero.
+fn empty_label() -> LabelNutrients {
+    let zero = || LabelNutrient { value: 0.0 };
+    LabelNutrients {
+        fat: zero(),
+        saturated_fat: zero(),
+        trans_fat: zero(),
+        cholesterol: zero(),
+        sodium: zero(),
+        carbohydrates: zero(),
+        fiber: zero(),
+        sugars: zero(),
+        protein: zero(),
+        calcium: zero(),
+        iron: zero(),
+        potassium: zero(),
+        calories: zero(),
+    }
+}
+
+/// `BrandedFoodBuilder` builds a [`BrandedFoodItem`], served by 100 g and without nutrients until
+/// told otherwise.
+#[derive(Clone, Debug)]
+pub struct BrandedFoodBuilder {
+    food: BrandedFoodItem,
+}
+
+impl BrandedFoodBuilder {
+    /// generate a new BrandedFoodBuilder for the FDC id
+    pub fn new(fdc_id: i32) -> BrandedFoodBuilder {
+        BrandedFoodBuilder {
+            food: BrandedFoodItem {
+                fdc_id,
+                description: format!("FOOD {}", fdc_id),
+                brand_owner: None,
+                brand_name: None,
+                gtin_upc: None,
+                household_serving_full_text: None,
+                ingredients: String::new(),
+                serving_size: 100.0,
+                serving_size_unit: "g".into(),
+                label_nutrients: None,
+                food_nutrients: Vec::new(),
+                publication_date: None,
+            },
+        }
+    }
+
+    /// Describe the food.
+    pub fn description<S: Into<String>>(mut self, description: S) -> BrandedFoodBuilder {
+        self.food.description = description.into();
+        self
+    }
+
+    /// Give the food a brand owner.
+    pub fn brand_owner<S: Into<String>>(mut self, brand_owner: S) -> BrandedFoodBuilder {
+        self.food.brand_owner = Some(brand_owner.into());
+        self
+    }
+
+    /// Give the food a GTIN, written as on the package.
+    pub fn gtin<S: Into<String>>(mut self, gtin: S) -> BrandedFoodBuilder {
+        self.food.gtin_upc = Some(gtin.into());
+        self
+    }
+
+    /// List the ingredients of the food, as on the label.
+    pub fn ingredients<S: Into<String>>(mut self, ingredients: S) -> BrandedFoodBuilder {
+        self.food.ingredients = ingredients.into();
+        self
+    }
+
+    /// Serve the food by the size, in the unit, like `(30.0, "g")` or `(240.0, "ml")`.
+    pub fn serving<S: Into<String>>(mut self, size: f32, unit: S) -> BrandedFoodBuilder {
+        self.food.serving_size = size;
+        self.food.serving_size_unit = unit.into();
+        self
+    }
+
+    /// Describe the serving in household terms, like `"1 cup"`.
+    pub fn household_serving<S: Into<String>>(mut self, text: S) -> BrandedFoodBuilder {
+        self.food.household_serving_full_text = Some(text.into());
+        self
+    }
+
+    /// Put the amount of the nutrient per serving on the label, which is all zero until a first
+    /// nutrient is set.
+    ///
+    /// # Panics
+    ///
+    /// When labels have no such nutrient, like vitamin C.
+    pub fn label_nutrient(mut self, nutrient: Nutrient, amount: f32) -> BrandedFoodBuilder {
+        let label = self.food.label_nutrients.get_or_insert_with(empty_label);
+        let field = match nutrient {
+            Nutrient::Energy => &mut label.calories,
+            Nutrient::Protein => &mut label.protein,
+            Nutrient::TotalFat => &mut label.fat,
+            Nutrient::SaturatedFat => &mut label.saturated_fat,
+            Nutrient::TransFat => &mut label.trans_fat,
+            Nutrient::Cholesterol => &mut label.cholesterol,
+            Nutrient::Carbohydrate => &mut label.carbohydrates,
+            Nutrient::Fiber => &mut label.fiber,
+            Nutrient::Sugars => &mut label.sugars,
+            Nutrient::Sodium => &mut label.sodium,
+            Nutrient::Calcium => &mut label.calcium,
+            Nutrient::Iron => &mut label.iron,
+            Nutrient::Potassium => &mut label.potassium,
+            Nutrient::VitaminD | Nutrient::VitaminC => {
+                panic!("labels of branded foods have no {}", nutrient)
+            }
+        };
+        field.value = amount;
+        self
+    }
+
+    /// Add the amount of the nutrient per 100 g, or per 100 ml when served by volume.
+    pub fn food_nutrient(mut self, nutrient: Nutrient, amount: f32) -> BrandedFoodBuilder {
+        let id = self.food.food_nutrients.len() as i32 + 1;
+        self.food
+            .food_nutrients
+            .push(food_nutrient(id, nutrient, amount));
+        self
+    }
+
+    /// Date the record, like `"4/1/2019"`.
+    pub fn published<S: Into<String>>(mut self, date: S) -> BrandedFoodBuilder {
+        self.food.publication_date = Some(date.into());
+        self
+    }
+
+    /// The branded food.
+    pub fn build(self) -> BrandedFoodItem {
+        self.food
+    }
+
+    /// The branded food, as FDC answers it among foods of any data type.
+    pub fn meta(self) -> FDCMeta {
+        FDCMeta::Branded(self.build())
+    }
+}
+
+/// `FoodBuilder` builds an [`APFoodItem`], the food of any other data type than branded, of the SR
+/// Legacy data type and without nutrients, portions, or attributes until told otherwise.
+#[derive(Clone, Debug)]
+pub struct FoodBuilder {
+    food: APFoodItem,
+}
+
+impl FoodBuilder {
+    /// generate a new FoodBuilder for the FDC id
+    pub fn new(fdc_id: i32) -> FoodBuilder {
+        FoodBuilder {
+            food: APFoodItem {
+                fdc_id,
+                description: format!("FOOD {}", fdc_id),
+                data_type: Some(DataType::SrLegacy.as_api_str().into()),
+                food_attributes: Vec::new(),
+                food_portions: Vec::new(),
+                food_nutrients: Vec::new(),
+                nutrient_conversion_factors: Vec::new(),
+                scientific_name: None,
+                common_names: None,
+            },
+        }
+    }
+
+    /// Describe the food.
+    pub fn description<S: Into<String>>(mut self, description: S) -> FoodBuilder {
+        self.food.description = description.into();
+        self
+    }
+
+    /// Give the food another data type than branded.
+    pub fn data_type(mut self, data_type: DataType) -> FoodBuilder {
+        self.food.data_type = Some(data_type.as_api_str().into());
+        self
+    }
+
+    /// Add the amount of the nutrient per 100 g.
+    pub fn food_nutrient(mut self, nutrient: Nutrient, amount: f32) -> FoodBuilder {
+        let id = self.food.food_nutrients.len() as i32 + 1;
+        self.food
+            .food_nutrients
+            .push(food_nutrient(id, nutrient, amount));
+        self
+    }
+
+    /// Add a portion of the food weighing the grams, like `(28.0, "1 slice")`.
+    pub fn portion<S: Into<String>>(mut self, gram_weight: f32, description: S) -> FoodBuilder {
+        let sequence = self.food.food_portions.len() as i32 + 1;
+        self.food.food_portions.push(FoodPortion {
+            id: sequence,
+            amount: Some(1.0),
+            data_points: None,
+            gram_weight,
+            modifier: None,
+            portion_description: Some(description.into()),
+            sequence_number: Some(sequence),
+        });
+        self
+    }
+
+    /// Add an attribute of the kind, like an additional description.
+    pub fn attribute<S: Into<String>>(mut self, kind: AttributeKind, value: S) -> FoodBuilder {
+        let sequence = self.food.food_attributes.len() as i32 + 1;
+        self.food.food_attributes.push(FoodAttribute {
+            id: sequence,
+            sequence_number: Some(sequence),
+            value: value.into(),
+            food_attribute_type: FoodAttributeType {
+                id: 0,
+                name: kind.as_api_str().into(),
+                description: String::new(),
+            },
+        });
+        self
+    }
+
+    /// The food.
+    pub fn build(self) -> APFoodItem {
+        self.food
+    }
+
+    /// The food, as FDC answers it among foods of any data type.
+    pub fn meta(self) -> FDCMeta {
+        FDCMeta::Other(self.build())
+    }
+}
+
+/// Sample up to `size` distinct serving texts of the bulk bundle in the directory: the household
+/// servings of branded foods, and the modifiers and descriptions of the portions of other foods.
+/// The texts are spread evenly over their sorted order, so that the sample is the same for the same
+/// bundle.
+pub fn serving_corpus(dir: &Path, size: usize) -> Result<Vec<String>> {
+    let mut texts = BTreeSet::new();
+    for food in bulk::read(dir)? {
+        match food? {
+            FDCMeta::Branded(food) => texts.extend(food.household_serving_full_text),
+            FDCMeta::Other(food) => {
+                for portion in food.food_portions {
+                    texts.extend(portion.modifier);
+                    texts.extend(portion.portion_description);
+                }
+            }
+        }
+    }
+    let texts = texts
+        .into_iter()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    if texts.len() <= size {
+        return Ok(texts);
+    }
+    Ok((0..size)
+        .map(|i| texts[i * texts.len() / size].clone())
+        .collect())
+}
@@ -0,0 +1,133 @@
+//! This module declares the [`HttpTransport`] that requests to the FDC API are sent through, so
+//! that deployments with their own HTTP stack, like one signing requests, can replace
+//! [`reqwest`]. Statuses are interpreted by [`FDCService`](super::FDCService), above the transport.
+
+use super::FDCError;
+use crate::error::Result;
+
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, Response};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The response of a transport, with the headers FDC and api.data.gov send that matter to clients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransportResponse {
+    pub status: u16,
+    /// How long to wait before trying again, from the seconds of a `Retry-After` header.
+    pub retry_after: Option<Duration>,
+    /// How many requests the key has left this hour, from an `X-RateLimit-Remaining` header.
+    pub rate_limit_remaining: Option<u32>,
+    /// The raw body, which is deserialized straight into the payloads of [`api`](super::api).
+    pub body: Vec<u8>,
+}
+
+impl TransportResponse {
+    /// generate a new TransportResponse without any of the headers
+    pub fn new<B: Into<Vec<u8>>>(status: u16, body: B) -> TransportResponse {
+        TransportResponse {
+            status,
+            retry_after: None,
+            rate_limit_remaining: None,
+            body: body.into(),
+        }
+    }
+
+    /// The response, or [`FDCError::Status`] when its status is not a success.
+    pub fn error_for_status(self) -> Result<TransportResponse> {
+        if (200..300).contains(&self.status) {
+            Ok(self)
+        } else {
+            Err(FDCError::Status(self.status).into())
+        }
+    }
+
+    /// Deserialize the json body, failing with [`FDCError::Decode`].
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body).map_err(FDCError::Decode)?)
+    }
+}
+
+/// `HttpTransport` sends the json requests of [`FDCService`](super::FDCService).
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    /// Post the json body to the url and read the response, whatever its status. Only failures to
+    /// get a response at all are errors, which custom transports report as
+    /// [`FDCError::Transport`]. Bodies longer than `limit` bytes should be abandoned as soon as
+    /// they are known to be, with [`FDCError::TooLarge`].
+    async fn post_json(&self, url: &str, body: &Value, limit: usize) -> Result<TransportResponse>;
+
+    /// Post like [`HttpTransport::post_json`], along with the headers. The default implementation
+    /// is for transports which cannot send headers: it fails with [`FDCError::Transport`] rather
+    /// than leave out any.
+    async fn post_json_with(
+        &self,
+        url: &str,
+        body: &Value,
+        headers: &HeaderMap,
+        limit: usize,
+    ) -> Result<TransportResponse> {
+        if !headers.is_empty() {
+            let names = headers
+                .keys()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let message = format!("The transport cannot send the headers {}", names);
+            return Err(FDCError::Transport(message.into()).into());
+        }
+        self.post_json(url, body, limit).await
+    }
+}
+
+#[async_trait]
+impl HttpTransport for Client {
+    async fn post_json(&self, url: &str, body: &Value, limit: usize) -> Result<TransportResponse> {
+        self.post_json_with(url, body, &HeaderMap::new(), limit)
+            .await
+    }
+
+    async fn post_json_with(
+        &self,
+        url: &str,
+        body: &Value,
+        headers: &HeaderMap,
+        limit: usize,
+    ) -> Result<TransportResponse> {
+        let mut res = self
+            .post(url)
+            .headers(headers.clone())
+            .json(body)
+            .send()
+            .await?;
+        if res.content_length().is_some_and(|len| len > limit as u64) {
+            return Err(FDCError::TooLarge(limit).into());
+        }
+        let status = res.status().as_u16();
+        let retry_after = header(&res, RETRY_AFTER.as_str()).map(Duration::from_secs);
+        let rate_limit_remaining = header(&res, "x-ratelimit-remaining");
+
+        // read chunk by chunk, as the length of compressed or chunked bodies is not known upfront
+        let mut body = Vec::new();
+        while let Some(chunk) = res.chunk().await? {
+            if body.len() + chunk.len() > limit {
+                return Err(FDCError::TooLarge(limit).into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(TransportResponse {
+            status,
+            retry_after,
+            rate_limit_remaining,
+            body,
+        })
+    }
+}
+
+/// The value of the header of the response, if it has one which parses.
+fn header<V: FromStr>(res: &Response, name: &str) -> Option<V> {
+    res.headers().get(name)?.to_str().ok()?.trim().parse().ok()
+}
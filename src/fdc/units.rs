@@ -0,0 +1,106 @@
+//! The units FDC gives the serving sizes of branded foods in, which are either plain symbols like
+//! `"g"` and `"ml"` or the UN/ECE codes of the label data, like `"GRM"`, `"MLT"`, and `"MC"`.
+
+use crate::quantities::Quantity;
+
+use uom::si::f32::{Mass, Volume};
+use uom::si::mass::{gram, kilogram, microgram, milligram, ounce, pound};
+use uom::si::volume::{centiliter, cubic_centimeter, deciliter, fluid_ounce, liter, milliliter};
+
+/// A unit the serving size of a branded food is given in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ServingUnit {
+    Gram,
+    Milligram,
+    Microgram,
+    Kilogram,
+    Ounce,
+    Pound,
+    Milliliter,
+    Centiliter,
+    Deciliter,
+    Liter,
+    CubicCentimeter,
+    FluidOunce,
+}
+
+impl ServingUnit {
+    /// Every serving unit, masses first.
+    pub fn all() -> &'static [ServingUnit] {
+        &[
+            ServingUnit::Gram,
+            ServingUnit::Milligram,
+            ServingUnit::Microgram,
+            ServingUnit::Kilogram,
+            ServingUnit::Ounce,
+            ServingUnit::Pound,
+            ServingUnit::Milliliter,
+            ServingUnit::Centiliter,
+            ServingUnit::Deciliter,
+            ServingUnit::Liter,
+            ServingUnit::CubicCentimeter,
+            ServingUnit::FluidOunce,
+        ]
+    }
+
+    /// The codes FDC writes the unit as, in lowercase: its symbols, then its UN/ECE code.
+    pub fn codes(&self) -> &'static [&'static str] {
+        match self {
+            ServingUnit::Gram => &["g", "gm", "gram", "grams", "grm"],
+            ServingUnit::Milligram => &["mg", "mgm"],
+            ServingUnit::Microgram => &["mcg", "ug", "µg", "mc"],
+            ServingUnit::Kilogram => &["kg", "kgm"],
+            ServingUnit::Ounce => &["oz", "onz"],
+            ServingUnit::Pound => &["lb", "lbr"],
+            ServingUnit::Milliliter => &["ml", "mlt"],
+            ServingUnit::Centiliter => &["cl", "clt"],
+            ServingUnit::Deciliter => &["dl", "dlt"],
+            ServingUnit::Liter => &["l", "ltr"],
+            ServingUnit::CubicCentimeter => &["cc", "cmq"],
+            ServingUnit::FluidOunce => &["fl oz", "oza"],
+        }
+    }
+
+    /// Whether the unit measures volumes rather than masses.
+    pub fn is_volume(&self) -> bool {
+        matches!(
+            self,
+            ServingUnit::Milliliter
+                | ServingUnit::Centiliter
+                | ServingUnit::Deciliter
+                | ServingUnit::Liter
+                | ServingUnit::CubicCentimeter
+                | ServingUnit::FluidOunce
+        )
+    }
+
+    /// The quantity of the amount of the unit.
+    pub fn quantity(&self, amount: f32) -> Quantity {
+        match self {
+            ServingUnit::Gram => Quantity::Mass(Mass::new::<gram>(amount)),
+            ServingUnit::Milligram => Quantity::Mass(Mass::new::<milligram>(amount)),
+            ServingUnit::Microgram => Quantity::Mass(Mass::new::<microgram>(amount)),
+            ServingUnit::Kilogram => Quantity::Mass(Mass::new::<kilogram>(amount)),
+            ServingUnit::Ounce => Quantity::Mass(Mass::new::<ounce>(amount)),
+            ServingUnit::Pound => Quantity::Mass(Mass::new::<pound>(amount)),
+            ServingUnit::Milliliter => Quantity::Volume(Volume::new::<milliliter>(amount)),
+            ServingUnit::Centiliter => Quantity::Volume(Volume::new::<centiliter>(amount)),
+            ServingUnit::Deciliter => Quantity::Volume(Volume::new::<deciliter>(amount)),
+            ServingUnit::Liter => Quantity::Volume(Volume::new::<liter>(amount)),
+            ServingUnit::CubicCentimeter => {
+                Quantity::Volume(Volume::new::<cubic_centimeter>(amount))
+            }
+            ServingUnit::FluidOunce => Quantity::Volume(Volume::new::<fluid_ounce>(amount)),
+        }
+    }
+}
+
+/// The serving unit of a code, ignoring case and surrounding spaces, or [`None`] for a code we do
+/// not know.
+pub fn serving_unit(code: &str) -> Option<ServingUnit> {
+    let code = code.trim().to_lowercase();
+    ServingUnit::all()
+        .iter()
+        .copied()
+        .find(|unit| unit.codes().contains(&&code[..]))
+}
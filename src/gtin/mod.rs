@@ -0,0 +1,121 @@
+//! This module handles the barcodes of branded foods through the [`Gtin`] type. FDC writes them in
+//! `gtin_upc` as GTIN-8, UPC-A, EAN-13, or GTIN-14, sometimes with extra leading zeros, so codes
+//! are normalized to GTIN-14 before they are compared.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A barcode with a valid check digit, kept in its 14 digit GTIN-14 form.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Gtin(String);
+
+/// The failure to parse a [`Gtin`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GtinError {
+    /// The code has a character other than digits, spaces, and dashes.
+    InvalidCharacter(char),
+    /// The code does not have 8, 12, 13, or 14 digits once extra leading zeros are stripped.
+    InvalidLength(usize),
+    /// The last digit of the code is not the check digit of the others.
+    InvalidCheckDigit { expected: u32, found: u32 },
+}
+
+impl fmt::Display for GtinError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GtinError::InvalidCharacter(c) => write!(f, "Invalid character {:?} in GTIN", c),
+            GtinError::InvalidLength(len) => {
+                write!(f, "GTIN has {} digits rather than 8, 12, 13, or 14", len)
+            }
+            GtinError::InvalidCheckDigit { expected, found } => write!(
+                f,
+                "GTIN ends with check digit {} rather than {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GtinError {}
+
+/// The GS1 check digit of the digits before it: weighing digits by 3 and 1 alternately from the
+/// right, the digit which brings their sum to a multiple of 10.
+fn check_digit(digits: &[u32]) -> u32 {
+    let sum = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| if i % 2 == 0 { 3 * digit } else { *digit })
+        .sum::<u32>();
+    (10 - sum % 10) % 10
+}
+
+impl Gtin {
+    /// Parse a GTIN-8, UPC-A, EAN-13, or GTIN-14, ignoring spaces and dashes as well as leading
+    /// zeros past 14 digits, and validate its check digit.
+    pub fn parse(code: &str) -> Result<Gtin, GtinError> {
+        let mut digits = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_digit(10).ok_or(GtinError::InvalidCharacter(c)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let extra = digits
+            .iter()
+            .take(digits.len().saturating_sub(14))
+            .take_while(|digit| **digit == 0)
+            .count();
+        digits.drain(..extra);
+        if ![8, 12, 13, 14].contains(&digits.len()) {
+            return Err(GtinError::InvalidLength(digits.len()));
+        }
+
+        let (found, body) = digits.split_last().expect("codes have digits");
+        let expected = check_digit(body);
+        if *found != expected {
+            return Err(GtinError::InvalidCheckDigit {
+                expected,
+                found: *found,
+            });
+        }
+        let code = digits.iter().map(u32::to_string).collect::<String>();
+        Ok(Gtin(format!("{:0>14}", code)))
+    }
+
+    /// The 14 digit GTIN-14 form of the code.
+    pub fn to_gtin14(&self) -> &str {
+        &self.0
+    }
+
+    /// The 13 digit EAN-13 form of the code, when it has no packaging indicator.
+    pub fn to_ean13(&self) -> Option<&str> {
+        self.0.strip_prefix('0')
+    }
+
+    /// The 12 digit UPC-A form of the code, when it is a UPC-A or a GTIN-8.
+    pub fn to_upc_a(&self) -> Option<&str> {
+        self.0.strip_prefix("00")
+    }
+
+    /// Whether the code is the same as another, written in any form. Codes which do not parse
+    /// match nothing.
+    pub fn matches(&self, other: &str) -> bool {
+        Gtin::parse(other).is_ok_and(|other| other == *self)
+    }
+}
+
+impl FromStr for Gtin {
+    type Err = GtinError;
+
+    fn from_str(code: &str) -> Result<Gtin, GtinError> {
+        Gtin::parse(code)
+    }
+}
+
+impl fmt::Display for Gtin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,106 @@
+use super::*;
+
+fn gtin14(code: &str) -> String {
+    Gtin::parse(code).unwrap().to_gtin14().to_string()
+}
+
+#[test]
+fn valid_gtin8() {
+    for code in ["96385074", "12345670", "9638-5074"] {
+        assert!(Gtin::parse(code).is_ok(), "{}", code);
+    }
+    assert_eq!(gtin14("96385074"), "00000096385074");
+    assert_eq!(
+        Gtin::parse("96385074").unwrap().to_upc_a(),
+        Some("000096385074")
+    );
+}
+
+#[test]
+fn valid_upc_a() {
+    for code in [
+        "036000291452",
+        "012345678905",
+        "027000612323",
+        "051000212191",
+    ] {
+        assert!(Gtin::parse(code).is_ok(), "{}", code);
+    }
+    assert_eq!(gtin14("0 27000 61232 3"), "00027000612323");
+    let gtin = Gtin::parse("036000291452").unwrap();
+    assert_eq!(gtin.to_upc_a(), Some("036000291452"));
+    assert_eq!(gtin.to_ean13(), Some("0036000291452"));
+}
+
+#[test]
+fn valid_ean13() {
+    for code in ["4006381333931", "5901234123457", "0027000612323"] {
+        assert!(Gtin::parse(code).is_ok(), "{}", code);
+    }
+    let gtin = Gtin::parse("4006381333931").unwrap();
+    assert_eq!(gtin.to_gtin14(), "04006381333931");
+    assert_eq!(gtin.to_ean13(), Some("4006381333931"));
+    assert_eq!(gtin.to_upc_a(), None);
+}
+
+#[test]
+fn valid_gtin14() {
+    for code in ["10012345000017", "10614141000415", "00027000690260"] {
+        assert!(Gtin::parse(code).is_ok(), "{}", code);
+    }
+    let gtin = Gtin::parse("10614141000415").unwrap();
+    assert_eq!(gtin.to_ean13(), None);
+    assert_eq!(gtin.to_upc_a(), None);
+    // FDC sometimes pads codes past 14 digits
+    assert_eq!(gtin14("0000027000690260"), "00027000690260");
+}
+
+#[test]
+fn invalid_check_digits() {
+    for (code, expected, found) in [
+        ("96385075", 4, 5),
+        ("036000291453", 2, 3),
+        ("4006381333930", 1, 0),
+        ("10012345000018", 7, 8),
+    ] {
+        assert_eq!(
+            Gtin::parse(code),
+            Err(GtinError::InvalidCheckDigit { expected, found }),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn invalid_lengths() {
+    for code in ["", "1234567", "123456789", "12345678901", "123456789012345"] {
+        assert_eq!(
+            Gtin::parse(code),
+            Err(GtinError::InvalidLength(code.len())),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn invalid_characters() {
+    assert_eq!(
+        Gtin::parse("03600029145X"),
+        Err(GtinError::InvalidCharacter('X'))
+    );
+    assert!("0360002914.52".parse::<Gtin>().is_err());
+}
+
+#[test]
+fn matches() {
+    let gtin: Gtin = "027000612323".parse().unwrap();
+    assert!(gtin.matches("00027000612323"));
+    assert!(gtin.matches("0027000612323"));
+    assert!(gtin.matches("0-27000-61232-3"));
+    assert!(!gtin.matches("027000612324"));
+    assert!(!gtin.matches("051000212191"));
+    assert!(!gtin.matches("not a code"));
+    assert_eq!(gtin.to_string(), "00027000612323");
+}
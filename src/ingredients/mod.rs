@@ -0,0 +1,263 @@
+//! This module breaks the ingredient statement of branded foods into a tree of [`Ingredient`]s
+//! through [`parse`], for allergen and additive checks. Statements look like
+//!
+//! ```text
+//! ENRICHED FLOUR (WHEAT FLOUR, NIACIN, REDUCED IRON), SUGAR, CONTAINS 2% OR LESS OF: SALT,
+//! YEAST. CONTAINS: WHEAT, MILK.
+//! ```
+//!
+//! where brackets list the sub-ingredients of an ingredient, markers like "CONTAINS 2% OR LESS
+//! OF:" introduce the minor ingredients, and a trailing "CONTAINS:" sentence states the allergens.
+
+use crate::fdc::BrandedFoodItem;
+
+/// An ingredient, with the ingredients it is made of.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ingredient {
+    pub name: String,
+    pub sub_ingredients: Vec<Ingredient>,
+    /// Whether the ingredient follows a marker like "CONTAINS 2% OR LESS OF".
+    pub minor: bool,
+}
+
+/// An ingredient statement, split into its ingredients and its allergen statement.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IngredientList {
+    pub ingredients: Vec<Ingredient>,
+    /// The allergens of a "CONTAINS:" statement, as they are written.
+    pub allergens: Vec<String>,
+}
+
+/// Parse the ingredients of a statement, leaving out its allergen statement.
+pub fn parse(statement: &str) -> Vec<Ingredient> {
+    analyze(statement).ingredients
+}
+
+/// Parse the ingredients of a statement along with its allergen statement. Malformed statements,
+/// like those with unbalanced brackets, are parsed as well as they can be rather than rejected.
+pub fn analyze(statement: &str) -> IngredientList {
+    let mut statement = statement.trim();
+    let upper = statement.to_ascii_uppercase();
+    if upper.starts_with("INGREDIENTS") {
+        if let Some(colon) = upper.find(':') {
+            statement = &statement[colon + 1..];
+        }
+    }
+
+    let mut list = IngredientList::default();
+    for sentence in split(statement, |c, next| {
+        c == '.' && next.is_none_or(char::is_whitespace)
+    }) {
+        let items = split(sentence, |c, _| c == ',' || c == ';');
+        // an allergen statement runs to the end of its sentence
+        let at = items
+            .iter()
+            .position(|item| allergen_statement(item).is_some())
+            .unwrap_or(items.len());
+        list.ingredients.extend(ingredients(&items[..at]));
+        if let Some((first, rest)) = items[at..].split_first() {
+            let first = allergen_statement(first).expect("statements start with an allergen");
+            for item in std::iter::once(first).chain(rest.iter().copied()) {
+                list.allergens.extend(allergens(item));
+            }
+        }
+    }
+    list
+}
+
+/// Split the text at the separators outside of brackets. The separator is given each character
+/// along with the next one.
+fn split<F: Fn(char, Option<char>) -> bool>(text: &str, separator: F) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && separator(c, chars.peek().map(|(_, next)| *next)) => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Parse the items of a list, marking those after a minor ingredient marker.
+fn ingredients(items: &[&str]) -> Vec<Ingredient> {
+    let mut minor = false;
+    let mut parsed = Vec::new();
+    for item in items {
+        let mut item = trim(item);
+        if let Some(rest) = minor_marker(item) {
+            minor = true;
+            item = trim(rest);
+        }
+        if !item.is_empty() {
+            parsed.push(ingredient(item, minor));
+        }
+    }
+    parsed
+}
+
+/// Trim spaces, periods, and a leading "AND" from an item.
+fn trim(item: &str) -> &str {
+    let item = item.trim().trim_end_matches('.').trim_end();
+    let upper = item.to_ascii_uppercase();
+    ["AND/OR ", "AND "]
+        .iter()
+        .find(|prefix| upper.starts_with(*prefix))
+        .map_or(item, |prefix| item[prefix.len()..].trim_start())
+}
+
+/// Parse a single item, whose bracketed parts list its sub-ingredients and whose other parts
+/// make its name.
+fn ingredient(item: &str, minor: bool) -> Ingredient {
+    let mut name = String::new();
+    let mut groups = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in item.char_indices() {
+        match c {
+            '(' | '[' | '{' => {
+                if depth == 0 {
+                    start = i + 1;
+                }
+                depth += 1;
+            }
+            ')' | ']' | '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    groups.push(&item[start..i]);
+                    name.push(' ');
+                }
+            }
+            _ if depth == 0 => name.push(c),
+            _ => {}
+        }
+    }
+    // an unclosed bracket runs to the end of the item
+    if depth > 0 {
+        groups.push(&item[start..]);
+    }
+
+    Ingredient {
+        name: name.split_whitespace().collect::<Vec<_>>().join(" "),
+        sub_ingredients: groups
+            .into_iter()
+            .flat_map(|group| ingredients(&split(group, |c, _| c == ',' || c == ';')))
+            .collect(),
+        minor,
+    }
+}
+
+/// The rest of an item starting with a minor ingredient marker, like "CONTAINS 2% OR LESS OF:",
+/// "LESS THAN 2% OF", or "CONTAINS ONE OR MORE OF THE FOLLOWING:".
+fn minor_marker(item: &str) -> Option<&str> {
+    let upper = item.to_ascii_uppercase();
+    let of = upper.match_indices(" OF").map(|(i, _)| i).find(|i| {
+        upper[i + 3..]
+            .chars()
+            .next()
+            .is_none_or(|c| c == ':' || c.is_whitespace())
+    })?;
+    let head = &upper[..of];
+    if head.contains(|c| "([{".contains(c))
+        || !["%", "LESS THAN", "OR LESS", "OR MORE"]
+            .iter()
+            .any(|marker| head.contains(marker))
+    {
+        return None;
+    }
+
+    let mut rest = &item[of + 3..];
+    for filler in ["EACH OF", "THE FOLLOWING"] {
+        let trimmed = rest.trim_start();
+        if trimmed.to_ascii_uppercase().starts_with(filler) {
+            rest = &trimmed[filler.len()..];
+        }
+    }
+    Some(rest.trim_start().trim_start_matches(':'))
+}
+
+/// The rest of an item starting an allergen statement, like "CONTAINS: MILK".
+fn allergen_statement(item: &str) -> Option<&str> {
+    let item = item.trim_start();
+    if !item.to_ascii_uppercase().starts_with("CONTAINS") || minor_marker(item).is_some() {
+        return None;
+    }
+    Some(
+        item["CONTAINS".len()..]
+            .trim_start()
+            .trim_start_matches(':'),
+    )
+}
+
+/// The allergens of an item of an allergen statement, like "MILK AND SOY INGREDIENTS".
+fn allergens(item: &str) -> Vec<String> {
+    let item = item.trim().trim_end_matches('.');
+    let upper = item.to_ascii_uppercase();
+    let mut allergens = Vec::new();
+    let mut start = 0;
+    for (i, _) in upper.match_indices(" AND ") {
+        allergens.push(&item[start..i]);
+        start = i + " AND ".len();
+    }
+    allergens.push(&item[start..]);
+    allergens
+        .into_iter()
+        .map(|allergen| {
+            let allergen = trim(allergen);
+            if allergen.to_ascii_uppercase().ends_with(" INGREDIENTS") {
+                allergen[..allergen.len() - " INGREDIENTS".len()].trim_end()
+            } else {
+                allergen
+            }
+        })
+        .filter(|allergen| !allergen.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+impl Ingredient {
+    /// The ingredient followed by its sub-ingredients, depth first.
+    pub fn walk(&self) -> Vec<&Ingredient> {
+        std::iter::once(self)
+            .chain(self.sub_ingredients.iter().flat_map(Ingredient::walk))
+            .collect()
+    }
+}
+
+impl BrandedFoodItem {
+    /// The names of the ingredients and stated allergens which contain any of the terms,
+    /// ignoring case, without repeats. Terms match within words, so "milk" matches "BUTTERMILK".
+    pub fn contains_any(&self, terms: &[&str]) -> Vec<String> {
+        let terms = terms
+            .iter()
+            .map(|term| term.to_lowercase())
+            .collect::<Vec<_>>();
+        let list = analyze(&self.ingredients);
+        let names = list
+            .ingredients
+            .iter()
+            .flat_map(Ingredient::walk)
+            .map(|ingredient| &ingredient.name)
+            .chain(&list.allergens);
+
+        let mut found: Vec<String> = Vec::new();
+        for name in names {
+            let lower = name.to_lowercase();
+            if terms.iter().any(|term| lower.contains(term.as_str())) && !found.contains(name) {
+                found.push(name.clone());
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,154 @@
+use super::{analyze, parse, Ingredient};
+use crate::fdc::BrandedFoodItem;
+
+fn names(ingredients: &[Ingredient]) -> Vec<&str> {
+    ingredients.iter().map(|i| i.name.as_str()).collect()
+}
+
+fn branded(ingredients: &str) -> BrandedFoodItem {
+    BrandedFoodItem {
+        fdc_id: 1,
+        description: "FOOD".into(),
+        brand_owner: None,
+        brand_name: None,
+        gtin_upc: None,
+        household_serving_full_text: None,
+        ingredients: ingredients.into(),
+        serving_size: 28.0,
+        serving_size_unit: "g".into(),
+        label_nutrients: None,
+        food_nutrients: Vec::new(),
+        publication_date: None,
+    }
+}
+
+const PEANUT_BUTTER: &str = "ROASTED PEANUTS, SUGAR, HYDROGENATED VEGETABLE OILS (COTTONSEED, \
+                             SOYBEAN AND RAPESEED) TO PREVENT SEPARATION, SALT.";
+
+const SANDWICH_BREAD: &str = "Ingredients: ENRICHED FLOUR (WHEAT FLOUR, MALTED BARLEY FLOUR, \
+    NIACIN, REDUCED IRON, THIAMINE MONONITRATE [VITAMIN B1], RIBOFLAVIN [VITAMIN B2], FOLIC \
+    ACID), WATER, SUGAR, CONTAINS 2% OR LESS OF: YEAST, SOYBEAN OIL, SALT, WHEAT GLUTEN, \
+    CALCIUM PROPIONATE (TO RETARD SPOILAGE), MONOGLYCERIDES, SOY LECITHIN. CONTAINS: WHEAT, SOY.";
+
+const COOKIES: &str = "UNBLEACHED ENRICHED FLOUR (WHEAT FLOUR, NIACIN, REDUCED IRON, \
+    THIAMINE MONONITRATE {VITAMIN B1}, RIBOFLAVIN {VITAMIN B2}, FOLIC ACID), SEMISWEET \
+    CHOCOLATE CHIPS (SUGAR, CHOCOLATE, COCOA BUTTER, DEXTROSE, SOY LECITHIN [EMULSIFIER]), \
+    SUGAR, SOYBEAN AND PALM OIL, AND LESS THAN 2% OF EACH OF THE FOLLOWING: BUTTERMILK, \
+    MOLASSES, SALT, NATURAL FLAVOR (CONTAINS MILK, SKIM MILK), LEAVENING (BAKING SODA, \
+    MONOCALCIUM PHOSPHATE, CORNSTARCH (\
+    CONTAINS WHEAT, MILK AND SOY INGREDIENTS.";
+
+#[test]
+fn flat_statement() {
+    let ingredients = parse(PEANUT_BUTTER);
+    assert_eq!(
+        names(&ingredients),
+        [
+            "ROASTED PEANUTS",
+            "SUGAR",
+            "HYDROGENATED VEGETABLE OILS TO PREVENT SEPARATION",
+            "SALT"
+        ]
+    );
+    assert_eq!(
+        names(&ingredients[2].sub_ingredients),
+        ["COTTONSEED", "SOYBEAN AND RAPESEED"]
+    );
+    assert!(ingredients.iter().all(|i| !i.minor));
+    assert!(analyze(PEANUT_BUTTER).allergens.is_empty());
+}
+
+#[test]
+fn minor_ingredients_and_allergens() {
+    let list = analyze(SANDWICH_BREAD);
+    let ingredients = &list.ingredients;
+    assert_eq!(
+        names(ingredients),
+        [
+            "ENRICHED FLOUR",
+            "WATER",
+            "SUGAR",
+            "YEAST",
+            "SOYBEAN OIL",
+            "SALT",
+            "WHEAT GLUTEN",
+            "CALCIUM PROPIONATE",
+            "MONOGLYCERIDES",
+            "SOY LECITHIN"
+        ]
+    );
+    let flour = &ingredients[0].sub_ingredients;
+    assert_eq!(flour.len(), 7);
+    assert_eq!(flour[4].name, "THIAMINE MONONITRATE");
+    assert_eq!(names(&flour[4].sub_ingredients), ["VITAMIN B1"]);
+    assert_eq!(
+        ingredients.iter().map(|i| i.minor).collect::<Vec<_>>(),
+        [false, false, false, true, true, true, true, true, true, true]
+    );
+    assert_eq!(list.allergens, ["WHEAT", "SOY"]);
+}
+
+#[test]
+fn nested_and_unbalanced() {
+    let list = analyze(COOKIES);
+    let ingredients = &list.ingredients;
+    assert_eq!(
+        names(ingredients),
+        [
+            "UNBLEACHED ENRICHED FLOUR",
+            "SEMISWEET CHOCOLATE CHIPS",
+            "SUGAR",
+            "SOYBEAN AND PALM OIL",
+            "BUTTERMILK",
+            "MOLASSES",
+            "SALT",
+            "NATURAL FLAVOR",
+            "LEAVENING"
+        ]
+    );
+    assert_eq!(
+        names(&ingredients[1].sub_ingredients[4].sub_ingredients),
+        ["EMULSIFIER"]
+    );
+    assert!(ingredients[4].minor && !ingredients[3].minor);
+    // "CONTAINS MILK" inside brackets lists a sub-ingredient rather than allergens
+    assert_eq!(
+        names(&ingredients[7].sub_ingredients),
+        ["CONTAINS MILK", "SKIM MILK"]
+    );
+    // the brackets of the leavening are never closed, so they swallow the rest of the statement
+    let leavening = &ingredients[8].sub_ingredients;
+    assert_eq!(
+        names(leavening),
+        ["BAKING SODA", "MONOCALCIUM PHOSPHATE", "CORNSTARCH"]
+    );
+    assert_eq!(
+        names(&leavening[2].sub_ingredients),
+        ["CONTAINS WHEAT", "MILK AND SOY INGREDIENTS"]
+    );
+    assert!(list.allergens.is_empty());
+}
+
+#[test]
+fn contains_any() {
+    let bread = branded(SANDWICH_BREAD);
+    assert_eq!(
+        bread.contains_any(&["soy"]),
+        ["SOYBEAN OIL", "SOY LECITHIN", "SOY"]
+    );
+    assert_eq!(
+        bread.contains_any(&["Propionate", "niacin"]),
+        ["NIACIN", "CALCIUM PROPIONATE"]
+    );
+    assert_eq!(
+        branded(COOKIES).contains_any(&["milk"]),
+        [
+            "BUTTERMILK",
+            "CONTAINS MILK",
+            "SKIM MILK",
+            "MILK AND SOY INGREDIENTS"
+        ]
+    );
+    assert!(branded(PEANUT_BUTTER).contains_any(&["milk"]).is_empty());
+    assert!(branded("").contains_any(&["milk"]).is_empty());
+}
@@ -0,0 +1,44 @@
+#[macro_use]
+extern crate serde;
+
+pub mod error;
+pub mod gtin;
+pub mod progress;
+pub mod quantities;
+pub mod text;
+
+// the food modules are built with the features they use: the FDC payloads and the nutrient math
+// with `fdc`, the configuration with `env`, and whatever reads the store or the food log with
+// `storage`, which brings in `fdc` for the foods it keeps
+#[cfg(feature = "fdc")]
+pub mod dedup;
+#[cfg(feature = "env")]
+pub mod env;
+#[cfg(feature = "storage")]
+pub mod export;
+#[cfg(feature = "fdc")]
+pub mod fdc;
+#[cfg(feature = "fdc")]
+pub mod ingredients;
+#[cfg(feature = "storage")]
+pub mod log;
+#[cfg(feature = "fdc")]
+pub mod nutrition;
+#[cfg(feature = "storage")]
+pub mod rank;
+#[cfg(feature = "storage")]
+pub mod recipe;
+#[cfg(feature = "storage")]
+pub mod report;
+#[cfg(feature = "fdc")]
+pub mod resolve;
+#[cfg(feature = "fdc")]
+pub mod serving;
+#[cfg(feature = "storage")]
+pub mod source;
+#[cfg(feature = "storage")]
+pub mod storage;
+#[cfg(feature = "fdc")]
+pub mod warnings;
+
+pub use error::{Error, Result};
@@ -0,0 +1,186 @@
+//! This module records what was eaten through the [`FoodLog`] trait. Entries can be kept in memory
+//! with [`MemoryLog`] or persisted alongside the stored foods.
+
+use crate::error::Result;
+use crate::quantities::{ParseOptions, Quantity};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// A single record of a quantity of food eaten at some time.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct LogEntry {
+    pub id: Uuid,
+    pub fdc_id: i32,
+    pub quantity: Quantity,
+    pub eaten_at: DateTime<Utc>,
+    pub note: Option<String>,
+    /// The text the quantity was parsed from, like `"1 heaping cup"`, to show and to parse again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_text: Option<String>,
+}
+
+impl LogEntry {
+    /// generate a new LogEntry with a random id and no note
+    pub fn new(fdc_id: i32, quantity: Quantity, eaten_at: DateTime<Utc>) -> LogEntry {
+        LogEntry {
+            id: Uuid::new_v4(),
+            fdc_id,
+            quantity,
+            eaten_at,
+            note: None,
+            source_text: None,
+        }
+    }
+
+    /// generate a new LogEntry with a random id and no note, of the quantity parsed from the text,
+    /// which is kept as its source text
+    pub fn parse(
+        fdc_id: i32,
+        text: &str,
+        eaten_at: DateTime<Utc>,
+        options: &ParseOptions,
+    ) -> Result<LogEntry> {
+        let quantity = options.parse(text)?;
+        Ok(LogEntry::new(fdc_id, quantity, eaten_at).with_source_text(text))
+    }
+
+    /// Attach a note to the entry.
+    pub fn with_note<S: Into<String>>(self, note: S) -> LogEntry {
+        LogEntry {
+            note: Some(note.into()),
+            ..self
+        }
+    }
+
+    /// Record the text the quantity was parsed from.
+    pub fn with_source_text<S: Into<String>>(self, source_text: S) -> LogEntry {
+        LogEntry {
+            source_text: Some(source_text.into()),
+            ..self
+        }
+    }
+
+    /// Parse the source text again, like after a unit alias was added, returning whether the
+    /// quantity changed. Entries without a source text are left as they are.
+    pub fn reparse(&mut self, options: &ParseOptions) -> Result<bool> {
+        let quantity = match &self.source_text {
+            Some(text) => options.parse(text)?,
+            None => return Ok(false),
+        };
+        let changed = quantity != self.quantity;
+        self.quantity = quantity;
+        Ok(changed)
+    }
+}
+
+/// `FoodLog` is implemented by every place entries can be recorded to.
+#[async_trait]
+pub trait FoodLog: Send + Sync {
+    /// Record the entry, replacing any entry with the same id.
+    async fn add(&self, entry: &LogEntry) -> Result<()>;
+
+    /// Remove the entry with the id, returning whether there was one.
+    async fn remove(&self, id: Uuid) -> Result<bool>;
+
+    /// The entries eaten in `[start, end)`, ordered by the time they were eaten.
+    async fn entries_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<LogEntry>>;
+
+    /// The entries eaten on the calendar day `date` of the time zone `tz`.
+    async fn entries_for_day<Tz>(&self, date: NaiveDate, tz: &Tz) -> Result<Vec<LogEntry>>
+    where
+        Tz: TimeZone + Send + Sync,
+        Tz::Offset: Send + Sync,
+    {
+        let (start, end) = day_bounds(date, tz);
+        self.entries_between(start, end).await
+    }
+
+    /// Parse the source text of every entry again with [`LogEntry::reparse`], recording the
+    /// entries whose quantity changed, and return how many did. Fails on the first text which no
+    /// longer parses, keeping the entries recorded before it.
+    async fn reparse(&self, options: &ParseOptions) -> Result<usize> {
+        let entries = self
+            .entries_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+            .await?;
+        let mut changed = 0;
+        for mut entry in entries {
+            if entry.reparse(options)? {
+                self.add(&entry).await?;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+/// The instants at which the day `date` starts and ends in the time zone `tz`. When midnight is
+/// skipped by a daylight saving transition, the day starts at the first whole hour that exists.
+pub fn day_bounds<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start_of = |date: NaiveDate| {
+        (0..24)
+            .find_map(|hour| {
+                tz.from_local_datetime(&date.and_hms_opt(hour, 0, 0)?)
+                    .earliest()
+            })
+            .map(|start| start.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()))
+    };
+    (start_of(date), start_of(date + Duration::days(1)))
+}
+
+/// `MemoryLog` keeps entries in memory, which is handy for tests and short-lived sessions.
+#[derive(Debug, Default)]
+pub struct MemoryLog {
+    entries: Mutex<Vec<LogEntry>>,
+}
+
+impl MemoryLog {
+    /// generate a new, empty MemoryLog
+    pub fn new() -> MemoryLog {
+        MemoryLog::default()
+    }
+}
+
+#[async_trait]
+impl FoodLog for MemoryLog {
+    async fn add(&self, entry: &LogEntry) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|e| e.id != entry.id);
+        entries.push(entry.clone());
+        Ok(())
+    }
+
+    async fn remove(&self, id: Uuid) -> Result<bool> {
+        let mut entries = self.entries.lock().unwrap();
+        let len = entries.len();
+        entries.retain(|e| e.id != id);
+        Ok(entries.len() != len)
+    }
+
+    async fn entries_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<LogEntry>> {
+        let mut found = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| start <= e.eaten_at && e.eaten_at < end)
+            .cloned()
+            .collect::<Vec<_>>();
+        found.sort_by_key(|e| e.eaten_at);
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod test;
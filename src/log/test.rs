@@ -0,0 +1,195 @@
+use super::*;
+use crate::storage::SqliteStore;
+
+use chrono::FixedOffset;
+use uom::si::{f32::Volume, volume::cup};
+
+fn at(rfc3339: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(rfc3339)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn entries() -> Vec<LogEntry> {
+    vec![
+        LogEntry::new(
+            173414,
+            Quantity::Volume(Volume::new::<cup>(1.0 / 3.0)),
+            at("2021-09-01T23:30:00Z"),
+        )
+        .with_note("late snack"),
+        LogEntry::new(
+            1455408,
+            Quantity::Nominal(2.0, "slices".into()),
+            at("2021-09-02T04:30:00Z"),
+        ),
+        LogEntry::new(
+            173414,
+            Quantity::Nominal(1.0, "stick".into()),
+            at("2021-09-02T12:00:00Z"),
+        ),
+    ]
+}
+
+/// Run the same checks against every implementation.
+async fn check<L: FoodLog>(log: L) {
+    for entry in entries() {
+        log.add(&entry).await.unwrap();
+    }
+
+    // in UTC, the first entry is on the 1st and the others on the 2nd
+    let utc = day_bounds_entries(&log, &Utc).await;
+    assert_eq!(utc, (1, 2));
+
+    // five hours behind, the second entry moves to the 1st
+    let behind = FixedOffset::west_opt(5 * 3600).unwrap();
+    assert_eq!(day_bounds_entries(&log, &behind).await, (2, 1));
+
+    // one hour ahead, the first entry moves to the 2nd
+    let ahead = FixedOffset::east_opt(3600).unwrap();
+    assert_eq!(day_bounds_entries(&log, &ahead).await, (0, 3));
+
+    // quantities and notes round-trip exactly
+    let all = log
+        .entries_between(at("2021-01-01T00:00:00Z"), at("2022-01-01T00:00:00Z"))
+        .await
+        .unwrap();
+    assert_eq!(all, entries_sorted(&all));
+    let expected = entries();
+    assert_eq!(all[0].quantity, expected[0].quantity);
+    assert_eq!(all[0].note.as_deref(), Some("late snack"));
+
+    // removal
+    assert!(log.remove(all[1].id).await.unwrap());
+    assert!(!log.remove(all[1].id).await.unwrap());
+    assert!(!log.remove(Uuid::new_v4()).await.unwrap());
+    let date = NaiveDate::from_ymd_opt(2021, 9, 2).unwrap();
+    assert_eq!(log.entries_for_day(date, &Utc).await.unwrap().len(), 1);
+}
+
+async fn day_bounds_entries<L: FoodLog, Tz>(log: &L, tz: &Tz) -> (usize, usize)
+where
+    Tz: TimeZone + Send + Sync,
+    Tz::Offset: Send + Sync,
+{
+    let first = NaiveDate::from_ymd_opt(2021, 9, 1).unwrap();
+    let second = NaiveDate::from_ymd_opt(2021, 9, 2).unwrap();
+    (
+        log.entries_for_day(first, tz).await.unwrap().len(),
+        log.entries_for_day(second, tz).await.unwrap().len(),
+    )
+}
+
+fn entries_sorted(entries: &[LogEntry]) -> Vec<LogEntry> {
+    let mut sorted = entries.to_vec();
+    sorted.sort_by_key(|e| e.eaten_at);
+    sorted
+}
+
+#[test]
+fn day_bounds_offset() {
+    let date = NaiveDate::from_ymd_opt(2021, 9, 1).unwrap();
+    let tz = FixedOffset::east_opt(9 * 3600).unwrap();
+    assert_eq!(
+        day_bounds(date, &tz),
+        (at("2021-08-31T15:00:00Z"), at("2021-09-01T15:00:00Z"))
+    );
+}
+
+#[tokio::test]
+async fn memory_log() {
+    check(MemoryLog::new()).await;
+}
+
+#[tokio::test]
+async fn sqlite_log() {
+    check(SqliteStore::connect("sqlite::memory:").await.unwrap()).await;
+}
+
+/// Reparse entries typed with a unit the parser did not know, once it has an alias.
+async fn check_reparse<L: FoodLog>(log: L) {
+    let plain = ParseOptions::new();
+    let heaping = LogEntry::parse(173414, "1 heaping cup", at("2021-09-01T08:00:00Z"), &plain)
+        .unwrap()
+        .with_note("oats");
+    assert_eq!(
+        heaping.quantity,
+        Quantity::Nominal(1.0, "heaping cup".into())
+    );
+    let sliced = LogEntry::parse(1455408, "2 slices", at("2021-09-01T12:00:00Z"), &plain).unwrap();
+    log.add(&heaping).await.unwrap();
+    log.add(&sliced).await.unwrap();
+    for entry in entries() {
+        log.add(&entry).await.unwrap();
+    }
+
+    // nothing changes with the same options
+    assert_eq!(log.reparse(&plain).await.unwrap(), 0);
+
+    let options = ParseOptions::new().with_alias("heaping cup", "cup");
+    assert_eq!(log.reparse(&options).await.unwrap(), 1);
+    assert_eq!(log.reparse(&options).await.unwrap(), 0);
+    let all = log
+        .entries_between(at("2021-01-01T00:00:00Z"), at("2022-01-01T00:00:00Z"))
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 5);
+    let oats = all.iter().find(|e| e.id == heaping.id).unwrap();
+    assert_eq!(oats.quantity, Quantity::Volume(Volume::new::<cup>(1.0)));
+    assert_eq!(oats.source_text.as_deref(), Some("1 heaping cup"));
+    assert_eq!(oats.note.as_deref(), Some("oats"));
+    assert!(all
+        .iter()
+        .filter(|e| e.id != heaping.id && e.id != sliced.id)
+        .all(|e| e.source_text.is_none()));
+}
+
+#[tokio::test]
+async fn memory_reparse() {
+    check_reparse(MemoryLog::new()).await;
+}
+
+#[tokio::test]
+async fn sqlite_reparse() {
+    check_reparse(SqliteStore::connect("sqlite::memory:").await.unwrap()).await;
+}
+
+#[test]
+fn reparse_entry() {
+    let mut entry = LogEntry::new(
+        1,
+        Quantity::Nominal(1.0, "scoop".into()),
+        at("2021-09-01T08:00:00Z"),
+    );
+    assert!(!entry.reparse(&ParseOptions::new()).unwrap());
+
+    // a source text which no longer parses leaves the entry as it was
+    entry.source_text = Some("a scoop".into());
+    assert!(entry.reparse(&ParseOptions::new()).is_err());
+    assert_eq!(entry.quantity, Quantity::Nominal(1.0, "scoop".into()));
+
+    // entries without a source text serialize as before
+    let json = serde_json::to_value(LogEntry::new(
+        1,
+        Quantity::Nominal(1.0, "scoop".into()),
+        at("2021-09-01T08:00:00Z"),
+    ))
+    .unwrap();
+    assert!(json.get("source_text").is_none());
+    let back: LogEntry = serde_json::from_value(json).unwrap();
+    assert_eq!(back.source_text, None);
+}
+
+#[tokio::test]
+async fn add_replaces_same_id() {
+    let log = MemoryLog::new();
+    let entry = entries().remove(0);
+    log.add(&entry).await.unwrap();
+    log.add(&entry.clone().with_note("edited")).await.unwrap();
+    let all = log
+        .entries_between(at("2021-01-01T00:00:00Z"), at("2022-01-01T00:00:00Z"))
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].note.as_deref(), Some("edited"));
+}
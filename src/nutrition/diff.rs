@@ -0,0 +1,133 @@
+//! This module compares two [`NutrientProfile`]s nutrient by nutrient through [`diff`], like two
+//! products per 100 g. Profiles keep every nutrient in its canonical unit, so amounts line up
+//! whatever units the foods reported them in.
+
+use super::{format_amount, Nutrient, NutrientProfile};
+
+use std::fmt;
+
+/// Whether more or less of a nutrient is better.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Better {
+    More,
+    Less,
+}
+
+/// Which of the two compared profiles a nutrient favors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    A,
+    B,
+    /// Both profiles have the same amount.
+    Neither,
+}
+
+/// The amounts of a nutrient in both profiles, in its canonical unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffLine {
+    pub nutrient: Nutrient,
+    pub a: Option<f32>,
+    pub b: Option<f32>,
+    /// How much more of the nutrient A has than B, when both have it.
+    pub difference: Option<f32>,
+}
+
+/// The comparison of two profiles, one line per nutrient either of them has.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NutrientDiff {
+    pub lines: Vec<DiffLine>,
+}
+
+/// Compare profile `a` against profile `b`. Nutrients are listed in declaration order, and
+/// nutrients only one profile has are listed without a difference.
+pub fn diff(a: &NutrientProfile, b: &NutrientProfile) -> NutrientDiff {
+    let lines = Nutrient::all()
+        .iter()
+        .filter_map(|&nutrient| {
+            let (in_a, in_b) = (a.get(nutrient), b.get(nutrient));
+            if in_a.is_none() && in_b.is_none() {
+                return None;
+            }
+            Some(DiffLine {
+                nutrient,
+                a: in_a,
+                b: in_b,
+                difference: in_a.zip(in_b).map(|(a, b)| a - b),
+            })
+        })
+        .collect();
+    NutrientDiff { lines }
+}
+
+impl NutrientDiff {
+    /// The line of the nutrient, if either profile has it.
+    pub fn line(&self, nutrient: Nutrient) -> Option<&DiffLine> {
+        self.lines.iter().find(|line| line.nutrient == nutrient)
+    }
+
+    /// Which profile is better in the nutrient, when both have it.
+    pub fn better_in(&self, nutrient: Nutrient, better: Better) -> Option<Side> {
+        let difference = self.line(nutrient)?.difference?;
+        let side = if difference == 0.0 {
+            Side::Neither
+        } else if (difference > 0.0) == (better == Better::More) {
+            Side::A
+        } else {
+            Side::B
+        };
+        Some(side)
+    }
+
+    /// Render the diff as an aligned text table, with the profiles named in the header. Amounts
+    /// are written through [`format_amount`], and unknown amounts as `-`.
+    pub fn table(&self, a: &str, b: &str) -> String {
+        let amount = |nutrient, amount: Option<f32>, sign: bool| match amount {
+            Some(amount) => {
+                let text = format_amount(nutrient, amount);
+                if sign && !text.starts_with('-') {
+                    format!("+{}", text)
+                } else {
+                    text
+                }
+            }
+            None => "-".to_string(),
+        };
+        let mut rows = vec![[
+            "Nutrient".to_string(),
+            a.to_string(),
+            b.to_string(),
+            "Difference".to_string(),
+        ]];
+        rows.extend(self.lines.iter().map(|line| {
+            [
+                line.nutrient.name().to_string(),
+                amount(line.nutrient, line.a, false),
+                amount(line.nutrient, line.b, false),
+                amount(line.nutrient, line.difference, true),
+            ]
+        }));
+
+        let mut widths = [0; 4];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let mut out = String::new();
+        for row in &rows {
+            let mut line = format!("{:<1$}", row[0], widths[0]);
+            for (cell, width) in row[1..].iter().zip(&widths[1..]) {
+                line.push_str(&format!("  {:>1$}", cell, width));
+            }
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl fmt::Display for NutrientDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.table("A", "B"))
+    }
+}
@@ -0,0 +1,129 @@
+//! This module checks the reported energy of foods against the energy of their macronutrients
+//! through [`validate_energy`], so that records with obviously wrong data can be set aside.
+
+use super::{Nutrient, NutrientProfile};
+use crate::fdc::FDCMeta;
+
+/// The kilocalories per gram of protein, carbohydrate, and fat of the 4-4-9 rule.
+pub const ATWATER_FACTORS: (f32, f32, f32) = (4.0, 4.0, 9.0);
+
+/// How far the computed energy may stray from the reported energy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnergyThresholds {
+    /// The relative discrepancy past which the food warns.
+    pub warn: f32,
+    /// The relative discrepancy past which the food fails.
+    pub fail: f32,
+    /// Whether to take the calories of fiber out of the carbohydrates.
+    pub subtract_fiber: bool,
+}
+
+impl Default for EnergyThresholds {
+    fn default() -> EnergyThresholds {
+        EnergyThresholds {
+            warn: 0.1,
+            fail: 0.2,
+            subtract_fiber: false,
+        }
+    }
+}
+
+/// The classification of an [`EnergyCheck`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnergyStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// The food lacks its energy or one of its macronutrients.
+    InsufficientData,
+}
+
+/// The reported energy of a food next to the energy of its macronutrients, in kilocalories on the
+/// basis the food reports its nutrients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnergyCheck {
+    pub reported: Option<f32>,
+    pub computed: Option<f32>,
+    pub absolute: Option<f32>,
+    pub relative: Option<f32>,
+    pub status: EnergyStatus,
+}
+
+/// Check the food's energy with the default [`EnergyThresholds`].
+pub fn validate_energy(food: &FDCMeta) -> EnergyCheck {
+    validate_energy_with(food, &EnergyThresholds::default())
+}
+
+/// Check the food's energy against protein, carbohydrate, and fat, weighed by the food's calorie
+/// conversion factors when it has them and by [`ATWATER_FACTORS`] otherwise.
+pub fn validate_energy_with(food: &FDCMeta, thresholds: &EnergyThresholds) -> EnergyCheck {
+    let (profile, factors) = match food {
+        FDCMeta::Branded(item) => match &item.label_nutrients {
+            Some(label) => (NutrientProfile::from_label(label), ATWATER_FACTORS),
+            None => (
+                NutrientProfile::from_food_nutrients(&item.food_nutrients),
+                ATWATER_FACTORS,
+            ),
+        },
+        FDCMeta::Other(item) => {
+            let factors = item
+                .nutrient_conversion_factors
+                .iter()
+                .find_map(|factor| {
+                    Some((
+                        factor.protein_value?,
+                        factor.carbohydrate_value?,
+                        factor.fat_value?,
+                    ))
+                })
+                .unwrap_or(ATWATER_FACTORS);
+            (
+                NutrientProfile::from_food_nutrients(&item.food_nutrients),
+                factors,
+            )
+        }
+    };
+
+    let reported = profile.get(Nutrient::Energy);
+    let computed = (|| {
+        let (protein, carbohydrate, fat) = factors;
+        let mut carbohydrates = profile.get(Nutrient::Carbohydrate)?;
+        if thresholds.subtract_fiber {
+            carbohydrates -= profile.get(Nutrient::Fiber).unwrap_or(0.0);
+        }
+        Some(
+            profile.get(Nutrient::Protein)? * protein
+                + carbohydrates * carbohydrate
+                + profile.get(Nutrient::TotalFat)? * fat,
+        )
+    })();
+
+    let (absolute, relative, status) = match (reported, computed) {
+        (Some(reported), Some(computed)) => {
+            let absolute = (computed - reported).abs();
+            let relative = if reported > 0.0 {
+                absolute / reported
+            } else if absolute > 0.0 {
+                f32::INFINITY
+            } else {
+                0.0
+            };
+            let status = if relative > thresholds.fail {
+                EnergyStatus::Fail
+            } else if relative > thresholds.warn {
+                EnergyStatus::Warn
+            } else {
+                EnergyStatus::Pass
+            };
+            (Some(absolute), Some(relative), status)
+        }
+        _ => (None, None, EnergyStatus::InsufficientData),
+    };
+    EnergyCheck {
+        reported,
+        computed,
+        absolute,
+        relative,
+        status,
+    }
+}
@@ -0,0 +1,75 @@
+//! This module writes nutrient amounts for people to read through [`format_amount`], in the unit
+//! each nutrient is usually labeled in rather than in a single unit for all.
+
+use super::{Nutrient, NutrientUnit};
+
+/// Write an amount of the nutrient, given in its canonical [`Nutrient::unit`]. Energy is written
+/// in whole kilocalories with thousands separators, grams and micrograms with one decimal, and
+/// milligrams as whole numbers. Amounts which round to a thousand or more milligrams or micrograms
+/// are written in the next larger unit, so 999.95 mg of sodium is "1.0 g".
+pub fn format_amount(nutrient: Nutrient, amount: f32) -> String {
+    let mut unit = nutrient.unit();
+    let mut amount = amount;
+    loop {
+        let rounded = round(amount, decimals(unit));
+        match larger(unit) {
+            Some(next) if rounded.abs() >= 1000.0 => {
+                amount /= 1000.0;
+                unit = next;
+            }
+            _ => return write(rounded, unit),
+        }
+    }
+}
+
+/// The decimals amounts in the unit are written with.
+fn decimals(unit: NutrientUnit) -> usize {
+    match unit {
+        NutrientUnit::Gram | NutrientUnit::Microgram => 1,
+        NutrientUnit::Milligram | NutrientUnit::Kilocalorie => 0,
+    }
+}
+
+/// The unit a thousand of the unit make up, if any.
+fn larger(unit: NutrientUnit) -> Option<NutrientUnit> {
+    match unit {
+        NutrientUnit::Microgram => Some(NutrientUnit::Milligram),
+        NutrientUnit::Milligram => Some(NutrientUnit::Gram),
+        NutrientUnit::Gram | NutrientUnit::Kilocalorie => None,
+    }
+}
+
+/// Round half away from zero to the decimals, without leaving a negative zero.
+fn round(amount: f32, decimals: usize) -> f32 {
+    let factor = 10f32.powi(decimals as i32);
+    let rounded = (amount * factor).round() / factor;
+    if rounded == 0.0 {
+        0.0
+    } else {
+        rounded
+    }
+}
+
+/// Write a rounded amount in the unit.
+fn write(rounded: f32, unit: NutrientUnit) -> String {
+    if unit == NutrientUnit::Kilocalorie {
+        return format!("{} {}", thousands(rounded as i64), unit.symbol());
+    }
+    format!("{:.*} {}", decimals(unit), rounded, unit.symbol())
+}
+
+/// Write a whole number with commas between groups of three digits.
+fn thousands(n: i64) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+    if n < 0 {
+        out.push('-');
+    }
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
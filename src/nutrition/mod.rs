@@ -0,0 +1,375 @@
+//! This module scales the nutrients of foods to the quantities consumed through the
+//! [`NutrientProfile`] type, which holds amounts of typed [`Nutrient`]s in their canonical units.
+
+pub mod diff;
+pub mod energy;
+pub mod format;
+pub mod nutrient;
+pub mod reconcile;
+pub mod screening;
+pub mod targets;
+
+pub use diff::{diff, Better, DiffLine, NutrientDiff, Side};
+pub use energy::{
+    validate_energy, validate_energy_with, EnergyCheck, EnergyStatus, EnergyThresholds,
+};
+pub use format::format_amount;
+pub use nutrient::{Nutrient, NutrientUnit, KJ_PER_KCAL};
+pub use reconcile::{reconcile, reconcile_with, Discrepancy, RECONCILE_THRESHOLD};
+pub use screening::{Basis, Level};
+pub use targets::{Direction, NutrientTargets, Target, TargetReport};
+
+use crate::error::{Error, Result};
+use crate::fdc::{BrandedFoodItem, FDCMeta, FoodNutrient, LabelNutrients};
+use crate::quantities::{CustomUnits, Quantity};
+use crate::resolve;
+use crate::warnings::{warn, Warning, Warnings};
+
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use uom::si::mass::gram;
+
+/// The failure to convert between the mass and volume of a food, whose density we do not know.
+#[derive(Debug)]
+pub struct NeedsDensity {
+    pub fdc_id: i32,
+    pub quantity: Quantity,
+}
+
+impl fmt::Display for NeedsDensity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Food {} needs a density to convert {:?} between mass and volume",
+            self.fdc_id, self.quantity
+        )
+    }
+}
+
+impl std::error::Error for NeedsDensity {}
+
+/// The failure to compute the nutrients of foods, recipes, or days.
+#[derive(Debug)]
+pub enum NutritionError {
+    /// A mass and a volume of a food needed converting, without its density.
+    NeedsDensity(NeedsDensity),
+    /// A quantity of a food matches none of its portions or its serving, or matches several which
+    /// weigh differently.
+    Unresolved(String),
+    /// The source has no food for the FDC id.
+    NoFood(i32),
+    /// A food which is not branded has no portion to serve.
+    NoPortion(i32),
+    /// The serving size unit of a branded food is not a known mass or volume unit.
+    UnknownUnit { fdc_id: i32, unit: String },
+    /// An ingredient of the recipe failed.
+    Recipe { name: String, source: Box<Error> },
+    /// The targets file failed to load.
+    Targets { path: PathBuf, source: Box<Error> },
+    /// The input is well-formed but makes no sense, like a recipe of no servings, a window ending
+    /// before it starts, or targets of an unknown nutrient.
+    Invalid(String),
+}
+
+impl fmt::Display for NutritionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NutritionError::NeedsDensity(e) => e.fmt(f),
+            NutritionError::Unresolved(message) | NutritionError::Invalid(message) => {
+                write!(f, "{}", message)
+            }
+            NutritionError::NoFood(fdc_id) => write!(f, "No food found for id {}", fdc_id),
+            NutritionError::NoPortion(fdc_id) => {
+                write!(f, "Food {} has no portion to serve", fdc_id)
+            }
+            NutritionError::UnknownUnit { fdc_id, unit } => write!(
+                f,
+                "Food {} has an unrecognized serving size unit {}",
+                fdc_id, unit
+            ),
+            NutritionError::Recipe { name, source } => {
+                write!(f, "In recipe {}: {}", name, source)
+            }
+            NutritionError::Targets { path, source } => {
+                write!(f, "In targets {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NutritionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NutritionError::NeedsDensity(e) => Some(e),
+            NutritionError::Recipe { source, .. } | NutritionError::Targets { source, .. } => {
+                Some(source.as_ref())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Amounts of nutrients, each in the canonical unit of [`Nutrient::unit`]. Nutrients missing from
+/// the profile are unknown rather than zero.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct NutrientProfile(BTreeMap<Nutrient, f32>);
+
+impl NutrientProfile {
+    /// generate a new, empty NutrientProfile
+    pub fn new() -> NutrientProfile {
+        NutrientProfile::default()
+    }
+
+    /// The amount of the nutrient, if known.
+    pub fn get(&self, nutrient: Nutrient) -> Option<f32> {
+        self.0.get(&nutrient).copied()
+    }
+
+    /// The energy in kcal, if known.
+    pub fn energy_kcal(&self) -> Option<f32> {
+        self.get(Nutrient::Energy)
+    }
+
+    /// The energy in kJ, if known.
+    pub fn energy_kj(&self) -> Option<f32> {
+        self.energy_kcal().map(|kcal| kcal * KJ_PER_KCAL)
+    }
+
+    /// Set the amount of the nutrient.
+    pub fn insert(&mut self, nutrient: Nutrient, amount: f32) {
+        self.0.insert(nutrient, amount);
+    }
+
+    /// Add to the amount of the nutrient, treating an unknown amount as zero.
+    pub fn add(&mut self, nutrient: Nutrient, amount: f32) {
+        *self.0.entry(nutrient).or_insert(0.0) += amount;
+    }
+
+    /// Iterate over the known nutrients in declaration order.
+    pub fn iter(&self) -> impl Iterator<Item = (Nutrient, f32)> + '_ {
+        self.0.iter().map(|(nutrient, amount)| (*nutrient, *amount))
+    }
+
+    /// The number of known nutrients.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no nutrient is known.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Multiply every amount by the factor.
+    pub fn scale(&self, factor: f32) -> NutrientProfile {
+        NutrientProfile(self.0.iter().map(|(n, a)| (*n, a * factor)).collect())
+    }
+
+    /// The profile of the nutrients reported in the "full" format, per 100 g or 100 ml. When a
+    /// nutrient is reported under several FDC ids, the first in [`Nutrient::fdc_ids`] with an
+    /// amount in a known unit wins, so energy in kcal is preferred over energy in kJ. Nutrients
+    /// with no amount or an unknown unit are left out.
+    pub fn from_food_nutrients(food_nutrients: &[FoodNutrient]) -> NutrientProfile {
+        let mut profile = NutrientProfile::new();
+        for nutrient in Nutrient::all() {
+            let amount = nutrient.fdc_ids().iter().find_map(|id| {
+                food_nutrients
+                    .iter()
+                    .filter(|fnut| fnut.nutrient.id == *id)
+                    .find_map(|fnut| {
+                        nutrient
+                            .unit()
+                            .convert(fnut.amount?, &fnut.nutrient.unit_name)
+                    })
+            });
+            if let Some(amount) = amount {
+                profile.insert(*nutrient, amount);
+            }
+        }
+        profile
+    }
+
+    /// The profile of the food per 100 g, for comparing foods. Branded foods rescale their label
+    /// nutrients by their serving size, which fails with [`NeedsDensity`] when the serving is a
+    /// volume. Other foods report their nutrients per 100 g already.
+    pub fn per_100g(food: &FDCMeta) -> Result<NutrientProfile> {
+        match food {
+            FDCMeta::Branded(item) => {
+                match (&item.label_nutrients, item.serving_size_quantity()?) {
+                    (Some(label), Quantity::Mass(mass)) => Ok(NutrientProfile::from_label(label)
+                        .scale(FoodNutrient::BASIS / mass.get::<gram>())),
+                    (None, Quantity::Mass(_)) => {
+                        Ok(NutrientProfile::from_food_nutrients(&item.food_nutrients))
+                    }
+                    (_, quantity) => Err(NeedsDensity {
+                        fdc_id: item.fdc_id,
+                        quantity,
+                    }
+                    .into()),
+                }
+            }
+            FDCMeta::Other(item) => Ok(NutrientProfile::from_food_nutrients(&item.food_nutrients)),
+        }
+    }
+
+    /// The profile of a serving of the food. Branded foods use their serving size, other foods
+    /// their first portion, and fail when they have none.
+    pub fn per_serving(food: &FDCMeta) -> Result<NutrientProfile> {
+        match food {
+            FDCMeta::Branded(item) => {
+                branded_profile(item, &Quantity::Nominal(1.0, "serving".into()), &mut None)
+            }
+            FDCMeta::Other(item) => {
+                let portion = item
+                    .default_portion()
+                    .ok_or(NutritionError::NoPortion(item.fdc_id))?;
+                Ok(NutrientProfile::from_food_nutrients(&item.food_nutrients)
+                    .scale(portion.gram_weight / FoodNutrient::BASIS))
+            }
+        }
+    }
+
+    /// The profile of the label nutrients of a branded food, per serving.
+    pub fn from_label(label: &LabelNutrients) -> NutrientProfile {
+        let mut profile = NutrientProfile::new();
+        for (name, value) in label.values().iter() {
+            let nutrient = match *name {
+                "fat" => Nutrient::TotalFat,
+                "saturated_fat" => Nutrient::SaturatedFat,
+                "trans_fat" => Nutrient::TransFat,
+                "cholesterol" => Nutrient::Cholesterol,
+                "sodium" => Nutrient::Sodium,
+                "carbohydrates" => Nutrient::Carbohydrate,
+                "fiber" => Nutrient::Fiber,
+                "sugars" => Nutrient::Sugars,
+                "protein" => Nutrient::Protein,
+                "calcium" => Nutrient::Calcium,
+                "iron" => Nutrient::Iron,
+                "potassium" => Nutrient::Potassium,
+                "calories" => Nutrient::Energy,
+                _ => continue,
+            };
+            profile.insert(nutrient, *value);
+        }
+        profile
+    }
+}
+
+/// Sum profiles together. A nutrient is in the sum as soon as it is in one of the profiles.
+pub fn sum<I, P>(profiles: I) -> NutrientProfile
+where
+    I: IntoIterator<Item = P>,
+    P: Borrow<NutrientProfile>,
+{
+    profiles
+        .into_iter()
+        .fold(NutrientProfile::new(), |mut total, profile| {
+            for (nutrient, amount) in profile.borrow().iter() {
+                total.add(nutrient, amount);
+            }
+            total
+        })
+}
+
+/// The number of profiles [`par_sum`] sums on one thread before combining the subtotals.
+#[cfg(feature = "parallel")]
+pub const PAR_CHUNK: usize = 1024;
+
+/// Sum profiles together like [`sum`], across threads. The profiles are summed in chunks of
+/// [`PAR_CHUNK`], whose subtotals are then summed in order, so the result is the same whatever
+/// the number of threads. It only matches [`sum`] exactly for up to [`PAR_CHUNK`] profiles.
+#[cfg(feature = "parallel")]
+pub fn par_sum<P>(profiles: &[P]) -> NutrientProfile
+where
+    P: Borrow<NutrientProfile> + Sync,
+{
+    use rayon::prelude::*;
+
+    let subtotals = profiles
+        .par_chunks(PAR_CHUNK)
+        .map(|chunk| sum(chunk.iter().map(Borrow::borrow)))
+        .collect::<Vec<_>>();
+    sum(subtotals)
+}
+
+/// The nutrients in the consumed quantity of the food. Branded foods are scaled from their label
+/// nutrients per serving, or their food nutrients when they have no label. Other foods are scaled
+/// from their food nutrients per 100 g, resolving the quantity to grams with
+/// [`resolve::to_grams`]. Quantities resolved by convention and nutrients left out for their unit
+/// are pushed to the warnings.
+pub fn profile_for(
+    food: &FDCMeta,
+    consumed: &Quantity,
+    mut warnings: Option<&mut Warnings>,
+) -> Result<NutrientProfile> {
+    match food {
+        FDCMeta::Branded(food) => branded_profile(food, consumed, &mut warnings),
+        FDCMeta::Other(item) => {
+            let grams = resolve::to_grams(consumed, food, warnings.as_deref_mut())?.get::<gram>();
+            Ok(
+                food_profile(item.fdc_id, &item.food_nutrients, &mut warnings)
+                    .scale(grams / FoodNutrient::BASIS),
+            )
+        }
+    }
+}
+
+/// The nutrients in the consumed quantity of the food like [`profile_for`], except that nominal
+/// quantities of the custom units resolve to their grams before the food is consulted.
+pub fn profile_for_with(
+    food: &FDCMeta,
+    consumed: &Quantity,
+    units: &CustomUnits,
+    warnings: Option<&mut Warnings>,
+) -> Result<NutrientProfile> {
+    profile_for(food, &units.resolve(consumed), warnings)
+}
+
+/// The profile of the food nutrients of a food like [`NutrientProfile::from_food_nutrients`],
+/// warning of the nutrients left out for their unit.
+fn food_profile(
+    fdc_id: i32,
+    food_nutrients: &[FoodNutrient],
+    warnings: &mut Option<&mut Warnings>,
+) -> NutrientProfile {
+    let profile = NutrientProfile::from_food_nutrients(food_nutrients);
+    for nutrient in Nutrient::all() {
+        if profile.get(*nutrient).is_some() {
+            continue;
+        }
+        let dropped = food_nutrients
+            .iter()
+            .find(|fnut| fnut.amount.is_some() && nutrient.fdc_ids().contains(&fnut.nutrient.id));
+        if let Some(fnut) = dropped {
+            warn(
+                warnings,
+                Warning::UnknownNutrientUnit {
+                    fdc_id,
+                    nutrient: *nutrient,
+                    nutrient_id: fnut.nutrient.id,
+                    unit: fnut.nutrient.unit_name.clone(),
+                },
+            );
+        }
+    }
+    profile
+}
+
+/// Scale a branded food's nutrients to the consumed quantity.
+fn branded_profile(
+    food: &BrandedFoodItem,
+    consumed: &Quantity,
+    warnings: &mut Option<&mut Warnings>,
+) -> Result<NutrientProfile> {
+    let servings = resolve::servings_in(consumed, food, warnings)?;
+    match &food.label_nutrients {
+        Some(label) => Ok(NutrientProfile::from_label(label).scale(servings)),
+        None => Ok(food_profile(food.fdc_id, &food.food_nutrients, warnings)
+            .scale(servings * food.serving_size / FoodNutrient::BASIS)),
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,175 @@
+//! This module declares the typed [`Nutrient`] enum along with the FDC nutrient ids and units each
+//! nutrient maps to.
+
+use std::fmt;
+
+/// The kilojoules in a kilocalorie, the food Calorie of labels.
+pub const KJ_PER_KCAL: f32 = 4.184;
+
+/// The nutrients we keep track of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum Nutrient {
+    Energy,
+    Protein,
+    TotalFat,
+    SaturatedFat,
+    TransFat,
+    Cholesterol,
+    Carbohydrate,
+    Fiber,
+    Sugars,
+    Sodium,
+    Calcium,
+    Iron,
+    Potassium,
+    VitaminD,
+    VitaminC,
+}
+
+/// The canonical unit a nutrient's amounts are kept in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NutrientUnit {
+    Gram,
+    Milligram,
+    Microgram,
+    Kilocalorie,
+}
+
+impl Nutrient {
+    /// Every nutrient, in declaration order.
+    pub fn all() -> &'static [Nutrient] {
+        &[
+            Nutrient::Energy,
+            Nutrient::Protein,
+            Nutrient::TotalFat,
+            Nutrient::SaturatedFat,
+            Nutrient::TransFat,
+            Nutrient::Cholesterol,
+            Nutrient::Carbohydrate,
+            Nutrient::Fiber,
+            Nutrient::Sugars,
+            Nutrient::Sodium,
+            Nutrient::Calcium,
+            Nutrient::Iron,
+            Nutrient::Potassium,
+            Nutrient::VitaminD,
+            Nutrient::VitaminC,
+        ]
+    }
+
+    /// A human readable name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Nutrient::Energy => "Energy",
+            Nutrient::Protein => "Protein",
+            Nutrient::TotalFat => "Total fat",
+            Nutrient::SaturatedFat => "Saturated fat",
+            Nutrient::TransFat => "Trans fat",
+            Nutrient::Cholesterol => "Cholesterol",
+            Nutrient::Carbohydrate => "Carbohydrate",
+            Nutrient::Fiber => "Fiber",
+            Nutrient::Sugars => "Sugars",
+            Nutrient::Sodium => "Sodium",
+            Nutrient::Calcium => "Calcium",
+            Nutrient::Iron => "Iron",
+            Nutrient::Potassium => "Potassium",
+            Nutrient::VitaminD => "Vitamin D",
+            Nutrient::VitaminC => "Vitamin C",
+        }
+    }
+
+    /// The canonical unit amounts of this nutrient are kept in.
+    pub fn unit(&self) -> NutrientUnit {
+        match self {
+            Nutrient::Energy => NutrientUnit::Kilocalorie,
+            Nutrient::Protein
+            | Nutrient::TotalFat
+            | Nutrient::SaturatedFat
+            | Nutrient::TransFat
+            | Nutrient::Carbohydrate
+            | Nutrient::Fiber
+            | Nutrient::Sugars => NutrientUnit::Gram,
+            Nutrient::Cholesterol
+            | Nutrient::Sodium
+            | Nutrient::Calcium
+            | Nutrient::Iron
+            | Nutrient::Potassium
+            | Nutrient::VitaminC => NutrientUnit::Milligram,
+            Nutrient::VitaminD => NutrientUnit::Microgram,
+        }
+    }
+
+    /// The FDC nutrient ids reporting this nutrient, in order of preference. Energy prefers 1008,
+    /// the energy in kcal of most foods, then the Atwater general (2047) and specific (2048)
+    /// energies of foundation foods, and last 1062, the energy in kJ.
+    pub fn fdc_ids(&self) -> &'static [i32] {
+        match self {
+            Nutrient::Energy => &[1008, 2047, 2048, 1062],
+            Nutrient::Protein => &[1003],
+            Nutrient::TotalFat => &[1004, 1085],
+            Nutrient::SaturatedFat => &[1258],
+            Nutrient::TransFat => &[1257],
+            Nutrient::Cholesterol => &[1253],
+            Nutrient::Carbohydrate => &[1005, 1050],
+            Nutrient::Fiber => &[1079],
+            Nutrient::Sugars => &[2000, 1063],
+            Nutrient::Sodium => &[1093],
+            Nutrient::Calcium => &[1087],
+            Nutrient::Iron => &[1089],
+            Nutrient::Potassium => &[1092],
+            Nutrient::VitaminD => &[1114],
+            Nutrient::VitaminC => &[1162],
+        }
+    }
+
+    /// The nutrient an FDC nutrient id reports, if we keep track of it.
+    pub fn from_fdc_id(id: i32) -> Option<Nutrient> {
+        Nutrient::all()
+            .iter()
+            .copied()
+            .find(|nutrient| nutrient.fdc_ids().contains(&id))
+    }
+}
+
+impl fmt::Display for Nutrient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl NutrientUnit {
+    /// The symbol of the unit.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            NutrientUnit::Gram => "g",
+            NutrientUnit::Milligram => "mg",
+            NutrientUnit::Microgram => "µg",
+            NutrientUnit::Kilocalorie => "kcal",
+        }
+    }
+
+    /// Convert an amount given in the FDC unit name (like `"MG"`, `"µg"`, or `"kJ"`) to this unit.
+    /// Returns [`None`] when the unit name is unknown or of another dimension.
+    pub fn convert(&self, amount: f32, unit_name: &str) -> Option<f32> {
+        // factors to grams or kilocalories
+        let from = match &unit_name.to_lowercase()[..] {
+            "g" => (1.0, false),
+            "mg" => (1e-3, false),
+            "ug" | "µg" | "μg" | "mcg" => (1e-6, false),
+            "kcal" => (1.0, true),
+            "kj" => (1.0 / KJ_PER_KCAL as f64, true),
+            _ => return None,
+        };
+        let to = match self {
+            NutrientUnit::Gram => (1.0, false),
+            NutrientUnit::Milligram => (1e-3, false),
+            NutrientUnit::Microgram => (1e-6, false),
+            NutrientUnit::Kilocalorie => (1.0, true),
+        };
+        if from.1 == to.1 {
+            Some((amount as f64 * from.0 / to.0) as f32)
+        } else {
+            None
+        }
+    }
+}
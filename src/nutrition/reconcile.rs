@@ -0,0 +1,82 @@
+//! This module checks the label nutrients of branded foods against their food nutrients through
+//! [`reconcile`]. Labels give amounts per serving while food nutrients give amounts per 100 g, so
+//! disagreeing amounts after scaling point to data-entry errors.
+
+use super::{Nutrient, NutrientProfile};
+use crate::fdc::{BrandedFoodItem, FoodNutrient};
+use crate::quantities::Quantity;
+
+use uom::si::mass::gram;
+
+/// The relative difference past which [`reconcile`] reports a nutrient.
+pub const RECONCILE_THRESHOLD: f32 = 0.1;
+
+/// A disagreement between the label and the food nutrients of a branded food.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discrepancy {
+    /// The label amount of the nutrient and its food nutrient amount scaled to the serving differ
+    /// by more than the threshold, relative to the label amount.
+    Differs {
+        nutrient: Nutrient,
+        label: f32,
+        scaled: f32,
+        relative: f32,
+    },
+    /// The serving size cannot be converted to grams, like a volume without a density, so no
+    /// nutrient can be compared.
+    NotComparable { reason: String },
+}
+
+/// Reconcile the food with the default [`RECONCILE_THRESHOLD`].
+pub fn reconcile(food: &BrandedFoodItem) -> Vec<Discrepancy> {
+    reconcile_with(food, RECONCILE_THRESHOLD)
+}
+
+/// Compare each nutrient both on the label and among the food nutrients, reporting those whose
+/// amounts differ by more than the relative threshold. Foods without label nutrients have nothing
+/// to reconcile.
+pub fn reconcile_with(food: &BrandedFoodItem, threshold: f32) -> Vec<Discrepancy> {
+    let label = match &food.label_nutrients {
+        Some(label) => NutrientProfile::from_label(label),
+        None => return Vec::new(),
+    };
+    let grams = match food.serving_size_quantity() {
+        Ok(Quantity::Mass(mass)) => mass.get::<gram>(),
+        Ok(serving) => {
+            return vec![Discrepancy::NotComparable {
+                reason: format!(
+                    "Food {} has a serving of {:?} and no density to weigh it",
+                    food.fdc_id, serving
+                ),
+            }]
+        }
+        Err(e) => {
+            return vec![Discrepancy::NotComparable {
+                reason: e.to_string(),
+            }]
+        }
+    };
+    let scaled = NutrientProfile::from_food_nutrients(&food.food_nutrients)
+        .scale(grams / FoodNutrient::BASIS);
+
+    label
+        .iter()
+        .filter_map(|(nutrient, label)| {
+            let scaled = scaled.get(nutrient)?;
+            let absolute = (scaled - label).abs();
+            let relative = if label > 0.0 {
+                absolute / label
+            } else if absolute > 0.0 {
+                f32::INFINITY
+            } else {
+                0.0
+            };
+            (relative > threshold).then_some(Discrepancy::Differs {
+                nutrient,
+                label,
+                scaled,
+                relative,
+            })
+        })
+        .collect()
+}
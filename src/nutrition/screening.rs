@@ -0,0 +1,135 @@
+//! This module screens foods for nutrients of concern through [`classify`], using the front of
+//! pack traffic light cutoffs of the UK Department of Health (2016). Amounts are per 100 g of
+//! food or per 100 ml of drink, and the cutoffs are
+//!
+//! | Nutrient      | Low, foods | High, foods | Low, drinks | High, drinks |
+//! |---------------|-----------:|------------:|------------:|-------------:|
+//! | Total fat     | ≤ 3 g      | > 17.5 g    | ≤ 1.5 g     | > 8.75 g     |
+//! | Saturated fat | ≤ 1.5 g    | > 5 g       | ≤ 0.75 g    | > 2.5 g      |
+//! | Sugars        | ≤ 5 g      | > 22.5 g    | ≤ 2.5 g     | > 11.25 g    |
+//! | Sodium        | ≤ 120 mg   | > 600 mg    | ≤ 120 mg    | > 300 mg     |
+//!
+//! where the sodium cutoffs are the salt cutoffs divided by 2.5.
+
+use super::{Nutrient, NutrientProfile};
+
+/// What the amounts of a profile are per.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Basis {
+    /// Per 100 g of a food.
+    Food,
+    /// Per 100 ml of a drink.
+    Drink,
+}
+
+/// How much of a nutrient a food has, against its [`Threshold`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Low,
+    Medium,
+    High,
+    /// The profile has no amount for the nutrient, which is not the same as having none of it.
+    Unknown,
+}
+
+/// The cutoffs of a nutrient, in its canonical unit: amounts up to `low` are low, and amounts over
+/// `high` are high.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Threshold {
+    pub nutrient: Nutrient,
+    pub low: f32,
+    pub high: f32,
+}
+
+/// The traffic light cutoffs per 100 g of food.
+pub const FOOD_THRESHOLDS: &[Threshold] = &[
+    Threshold {
+        nutrient: Nutrient::TotalFat,
+        low: 3.0,
+        high: 17.5,
+    },
+    Threshold {
+        nutrient: Nutrient::SaturatedFat,
+        low: 1.5,
+        high: 5.0,
+    },
+    Threshold {
+        nutrient: Nutrient::Sugars,
+        low: 5.0,
+        high: 22.5,
+    },
+    Threshold {
+        nutrient: Nutrient::Sodium,
+        low: 120.0,
+        high: 600.0,
+    },
+];
+
+/// The traffic light cutoffs per 100 ml of drink.
+pub const DRINK_THRESHOLDS: &[Threshold] = &[
+    Threshold {
+        nutrient: Nutrient::TotalFat,
+        low: 1.5,
+        high: 8.75,
+    },
+    Threshold {
+        nutrient: Nutrient::SaturatedFat,
+        low: 0.75,
+        high: 2.5,
+    },
+    Threshold {
+        nutrient: Nutrient::Sugars,
+        low: 2.5,
+        high: 11.25,
+    },
+    Threshold {
+        nutrient: Nutrient::Sodium,
+        low: 120.0,
+        high: 300.0,
+    },
+];
+
+impl Basis {
+    /// The traffic light cutoffs of the basis.
+    pub fn thresholds(&self) -> &'static [Threshold] {
+        match self {
+            Basis::Food => FOOD_THRESHOLDS,
+            Basis::Drink => DRINK_THRESHOLDS,
+        }
+    }
+}
+
+impl Threshold {
+    /// The level of an amount of the nutrient.
+    pub fn level(&self, amount: f32) -> Level {
+        if amount <= self.low {
+            Level::Low
+        } else if amount > self.high {
+            Level::High
+        } else {
+            Level::Medium
+        }
+    }
+}
+
+/// Classify the profile against the traffic light cutoffs of the basis.
+pub fn classify(profile: &NutrientProfile, basis: Basis) -> Vec<(Nutrient, Level)> {
+    classify_with(profile, basis.thresholds())
+}
+
+/// Classify each nutrient of the thresholds, in their order. Nutrients the profile has no amount
+/// for are [`Level::Unknown`].
+pub fn classify_with(
+    profile: &NutrientProfile,
+    thresholds: &[Threshold],
+) -> Vec<(Nutrient, Level)> {
+    thresholds
+        .iter()
+        .map(|threshold| {
+            let level = profile
+                .get(threshold.nutrient)
+                .map_or(Level::Unknown, |amount| threshold.level(amount));
+            (threshold.nutrient, level)
+        })
+        .collect()
+}
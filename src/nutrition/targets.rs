@@ -0,0 +1,367 @@
+//! This module compares a [`NutrientProfile`] against daily goals through [`NutrientTargets`].
+//!
+//! Targets are written in TOML, one nutrient per key, like
+//!
+//! ```toml
+//! Energy = { at_most = 1800 }
+//! Protein = { at_least = 120 }
+//! Sodium = { range = [1500, 2300] }
+//! ```
+//!
+//! A household keeps the targets of each person in one file through [`load`], where every
+//! profile starts from the shared defaults and replaces the targets of the nutrients it names:
+//!
+//! ```toml
+//! [defaults]
+//! Sodium = { at_most = 2300 }
+//!
+//! [profiles.alice]
+//! Energy = { at_most = 1800 }
+//!
+//! [profiles.bob]
+//! Protein = { at_least = 150, at_most = 220 }
+//! ```
+
+use super::{Nutrient, NutrientProfile, NutritionError};
+#[cfg(feature = "env")]
+use crate::env::{self, EnvVar};
+use crate::error::{Result, StorageError};
+
+use serde::de::{value, IntoDeserializer};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use toml::Spanned;
+
+/// A goal for a single nutrient, in its canonical unit.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    AtLeast(f32),
+    AtMost(f32),
+    Range(f32, f32),
+}
+
+/// Which way a [`Target`] goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    AtLeast,
+    AtMost,
+    Range,
+}
+
+impl Target {
+    /// Which way the target goes.
+    pub fn direction(&self) -> Direction {
+        match self {
+            Target::AtLeast(_) => Direction::AtLeast,
+            Target::AtMost(_) => Direction::AtMost,
+            Target::Range(_, _) => Direction::Range,
+        }
+    }
+
+    /// Whether the consumed amount meets the target.
+    pub fn is_met(&self, consumed: f32) -> bool {
+        match *self {
+            Target::AtLeast(min) => consumed >= min,
+            Target::AtMost(max) => consumed <= max,
+            Target::Range(min, max) => min <= consumed && consumed <= max,
+        }
+    }
+
+    /// The amount consumed amounts are measured against: the bound of one-sided targets and the
+    /// upper bound of ranges.
+    fn reference(&self) -> f32 {
+        match *self {
+            Target::AtLeast(amount) | Target::AtMost(amount) | Target::Range(_, amount) => amount,
+        }
+    }
+}
+
+/// Goals for any number of nutrients.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct NutrientTargets(pub BTreeMap<Nutrient, Target>);
+
+impl NutrientTargets {
+    /// Parse targets written in TOML.
+    pub fn from_toml(input: &str) -> Result<NutrientTargets> {
+        Ok(toml::from_str(input)?)
+    }
+
+    /// Read targets from a TOML file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<NutrientTargets> {
+        NutrientTargets::from_toml(&read(path.as_ref())?)
+    }
+
+    /// Read targets from the TOML file named by the `NUTRIENT_TARGETS` variable of the
+    /// environment, if it is set.
+    #[cfg(feature = "env")]
+    pub fn from_env() -> Result<Option<NutrientTargets>> {
+        const SPEC: &[EnvVar] = &[EnvVar {
+            name: "NUTRIENT_TARGETS",
+            aliases: &[],
+            required: false,
+            validator: Some(env::non_empty),
+        }];
+        dotenv::dotenv().ok();
+        env::resolve(SPEC, std::env::vars())?
+            .remove("NUTRIENT_TARGETS")
+            .map(NutrientTargets::load)
+            .transpose()
+    }
+}
+
+/// The bounds of a nutrient in a profile file, which unlike [`Target`] may give both bounds.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Bounds {
+    at_least: Option<f32>,
+    at_most: Option<f32>,
+    range: Option<(f32, f32)>,
+}
+
+type RawTargets = BTreeMap<Spanned<String>, Spanned<Bounds>>;
+
+/// A profile file, as read by [`load`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileFile {
+    #[serde(default)]
+    defaults: RawTargets,
+    #[serde(default)]
+    profiles: BTreeMap<String, RawTargets>,
+}
+
+/// The line of the input at a byte offset, counting from 1.
+fn line_of(input: &str, offset: usize) -> usize {
+    input[..offset.min(input.len())].matches('\n').count() + 1
+}
+
+/// Check the nutrients and bounds of a table of the profile file.
+fn validate(input: &str, raw: RawTargets) -> Result<NutrientTargets> {
+    let mut targets = BTreeMap::new();
+    for (name, bounds) in raw {
+        let line = line_of(input, name.span().start);
+        let nutrient = Nutrient::deserialize(IntoDeserializer::<value::Error>::into_deserializer(
+            name.get_ref().as_str(),
+        ))
+        .map_err(|_| {
+            NutritionError::Invalid(format!(
+                "Unknown nutrient {:?} on line {}",
+                name.get_ref(),
+                line
+            ))
+        })?;
+        let target = match bounds.into_inner() {
+            Bounds {
+                at_least: Some(min),
+                at_most: Some(max),
+                range: None,
+            }
+            | Bounds {
+                at_least: None,
+                at_most: None,
+                range: Some((min, max)),
+            } => {
+                if max < min {
+                    return Err(NutritionError::Invalid(format!(
+                        "The target of {} on line {} is at most {} but at least {}",
+                        name.get_ref(),
+                        line,
+                        max,
+                        min
+                    ))
+                    .into());
+                }
+                Target::Range(min, max)
+            }
+            Bounds {
+                at_least: Some(min),
+                at_most: None,
+                range: None,
+            } => Target::AtLeast(min),
+            Bounds {
+                at_least: None,
+                at_most: Some(max),
+                range: None,
+            } => Target::AtMost(max),
+            _ => {
+                return Err(NutritionError::Invalid(format!(
+                    "The target of {} on line {} needs either a range or at_least and at_most",
+                    name.get_ref(),
+                    line
+                ))
+                .into())
+            }
+        };
+        targets.insert(nutrient, target);
+    }
+    Ok(NutrientTargets(targets))
+}
+
+/// Parse a profile file written in TOML into the targets of each profile, with the defaults
+/// filled in.
+pub fn profiles_from_toml(input: &str) -> Result<HashMap<String, NutrientTargets>> {
+    let file: ProfileFile = toml::from_str(input)?;
+    let defaults = validate(input, file.defaults)?;
+    file.profiles
+        .into_iter()
+        .map(|(name, raw)| {
+            let mut targets = defaults.clone();
+            targets.0.extend(validate(input, raw)?.0);
+            Ok((name, targets))
+        })
+        .collect()
+}
+
+/// Read the targets of each profile from a TOML file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<HashMap<String, NutrientTargets>> {
+    let path = path.as_ref();
+    profiles_from_toml(&read(path)?).map_err(|source| {
+        NutritionError::Targets {
+            path: path.into(),
+            source: Box::new(source),
+        }
+        .into()
+    })
+}
+
+/// The profile named by the `NUTRIENT_PROFILE` variable of the environment, if it is set.
+#[cfg(feature = "env")]
+fn profile_from_env() -> Result<Option<String>> {
+    const SPEC: &[EnvVar] = &[EnvVar {
+        name: "NUTRIENT_PROFILE",
+        aliases: &[],
+        required: false,
+        validator: Some(env::non_empty),
+    }];
+    dotenv::dotenv().ok();
+    Ok(env::resolve(SPEC, std::env::vars())?.remove("NUTRIENT_PROFILE"))
+}
+
+/// Without the `env` feature, no profile is named by the environment.
+#[cfg(not(feature = "env"))]
+fn profile_from_env() -> Result<Option<String>> {
+    Ok(None)
+}
+
+/// Read a targets file.
+fn read(path: &Path) -> Result<String> {
+    Ok(
+        std::fs::read_to_string(path).map_err(|e| StorageError::Read {
+            path: path.into(),
+            source: Box::new(e),
+        })?,
+    )
+}
+
+/// Pick the active profile: the one named, else the one named by the `NUTRIENT_PROFILE`
+/// variable of the environment, else the only profile there is. The environment is only read with
+/// the `env` feature.
+pub fn select(
+    mut profiles: HashMap<String, NutrientTargets>,
+    name: Option<&str>,
+) -> Result<NutrientTargets> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => match profile_from_env()? {
+            Some(name) => name,
+            None if profiles.len() == 1 => return Ok(profiles.drain().next().unwrap().1),
+            None => {
+                return Err(NutritionError::Invalid(format!(
+                    "Choose a profile of the targets with NUTRIENT_PROFILE, out of {}",
+                    profile_names(&profiles)
+                ))
+                .into())
+            }
+        },
+    };
+    profiles.remove(&name).ok_or_else(|| {
+        NutritionError::Invalid(format!(
+            "No profile {:?} in the targets, out of {}",
+            name,
+            profile_names(&profiles)
+        ))
+        .into()
+    })
+}
+
+/// The names of the profiles, sorted and separated by commas.
+fn profile_names(profiles: &HashMap<String, NutrientTargets>) -> String {
+    let mut names = profiles.keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort_unstable();
+    names.join(", ")
+}
+
+/// How the consumed amount of a nutrient compares to its target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetLine {
+    pub nutrient: Nutrient,
+    pub target: Target,
+    /// The amount consumed, zero when the nutrient is absent from the profile.
+    pub consumed: f32,
+    /// Whether the nutrient is absent from the profile.
+    pub missing: bool,
+    /// How much is left before reaching the target, or its upper bound for ranges.
+    pub remaining: f32,
+    /// How much the consumed amount exceeds the target, or its upper bound for ranges.
+    pub over: f32,
+    /// The consumed amount as a percentage of the target, or its upper bound for ranges. Zero
+    /// targets have no percentage.
+    pub percent: Option<f32>,
+    pub met: bool,
+}
+
+/// The comparison of a profile against every target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetReport {
+    pub lines: Vec<TargetLine>,
+}
+
+impl TargetReport {
+    /// Whether every target going in the direction is met.
+    pub fn passed(&self, direction: Direction) -> bool {
+        self.lines
+            .iter()
+            .filter(|line| line.target.direction() == direction)
+            .all(|line| line.met)
+    }
+
+    /// Whether every target is met.
+    pub fn passed_all(&self) -> bool {
+        self.lines.iter().all(|line| line.met)
+    }
+
+    /// The line of the nutrient, if it has a target.
+    pub fn line(&self, nutrient: Nutrient) -> Option<&TargetLine> {
+        self.lines.iter().find(|line| line.nutrient == nutrient)
+    }
+}
+
+impl NutrientProfile {
+    /// Compare the profile against the targets, one line per target.
+    pub fn against(&self, targets: &NutrientTargets) -> TargetReport {
+        let lines = targets
+            .0
+            .iter()
+            .map(|(nutrient, target)| {
+                let amount = self.get(*nutrient);
+                let consumed = amount.unwrap_or(0.0);
+                let reference = target.reference();
+                TargetLine {
+                    nutrient: *nutrient,
+                    target: *target,
+                    consumed,
+                    missing: amount.is_none(),
+                    remaining: (reference - consumed).max(0.0),
+                    over: (consumed - reference).max(0.0),
+                    percent: Some(reference)
+                        .filter(|r| *r != 0.0)
+                        .map(|r| 100.0 * consumed / r),
+                    met: target.is_met(consumed),
+                }
+            })
+            .collect();
+        TargetReport { lines }
+    }
+}
@@ -0,0 +1,883 @@
+use super::*;
+use uom::si::{f32::Mass, mass::ounce};
+
+/// A full-format food nutrient.
+fn food_nutrient(id: i32, name: &str, unit_name: &str, amount: f32) -> serde_json::Value {
+    serde_json::json!({
+        "type": "FoodNutrient",
+        "id": id * 10,
+        "nutrient": { "id": id, "number": "", "name": name, "unitName": unit_name },
+        "amount": amount,
+    })
+}
+
+/// A branded granola bar whose label reports 190 kcal, 4 g protein, and 90 mg sodium per 42 g
+/// serving of 1 bar.
+fn granola_bar() -> FDCMeta {
+    let label = [
+        ("fat", 7.0),
+        ("saturatedFat", 1.0),
+        ("transFat", 0.0),
+        ("cholesterol", 0.0),
+        ("sodium", 90.0),
+        ("carbohydrates", 29.0),
+        ("fiber", 2.0),
+        ("sugars", 12.0),
+        ("protein", 4.0),
+        ("calcium", 20.0),
+        ("iron", 1.1),
+        ("potassium", 115.0),
+        ("calories", 190.0),
+    ]
+    .iter()
+    .map(|(name, value)| (name.to_string(), serde_json::json!({ "value": value })))
+    .collect::<serde_json::Map<_, _>>();
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Branded",
+        "fdcId": 2001,
+        "description": "OATS & HONEY GRANOLA BAR",
+        "ingredients": "WHOLE GRAIN OATS, SUGAR, HONEY.",
+        "servingSize": 42.0,
+        "servingSizeUnit": "g",
+        "householdServingFullText": "1 bar",
+        "labelNutrients": label,
+    }))
+    .unwrap()
+}
+
+/// A foundation cheddar reporting 24.9 g protein, 654 mg sodium, and 1699 kJ per 100 g, with a
+/// 28 g slice portion.
+fn cheddar() -> FDCMeta {
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Other",
+        "fdcId": 2002,
+        "description": "Cheese, cheddar",
+        "foodAttributes": [],
+        "foodPortions": [
+            { "id": 1, "amount": 1.0, "gramWeight": 28.0, "modifier": "slice", "sequenceNumber": 1 },
+            { "id": 2, "amount": 1.0, "gramWeight": 132.0, "modifier": "cup, diced", "sequenceNumber": 2 },
+        ],
+        "foodNutrients": [
+            food_nutrient(1003, "Protein", "g", 24.9),
+            food_nutrient(1093, "Sodium, Na", "mg", 654.0),
+            food_nutrient(1062, "Energy", "kJ", 1699.0),
+            food_nutrient(1004, "Total lipid (fat)", "g", 33.8),
+            food_nutrient(9999, "Unknown", "g", 1.0),
+        ],
+    }))
+    .unwrap()
+}
+
+fn close(a: Option<f32>, b: f32) -> bool {
+    a.is_some_and(|a| (a - b).abs() < 1e-3 * b.abs().max(1.0))
+}
+
+#[test]
+fn unit_conversion() {
+    assert_eq!(NutrientUnit::Milligram.convert(1.5, "G"), Some(1500.0));
+    assert_eq!(NutrientUnit::Gram.convert(250.0, "mg"), Some(0.25));
+    assert_eq!(NutrientUnit::Microgram.convert(0.01, "MG"), Some(10.0));
+    assert!(close(
+        NutrientUnit::Kilocalorie.convert(4184.0, "kJ"),
+        1000.0
+    ));
+    assert_eq!(
+        NutrientUnit::Kilocalorie.convert(100.0, "KCAL"),
+        Some(100.0)
+    );
+    assert_eq!(NutrientUnit::Gram.convert(1.0, "kcal"), None);
+    assert_eq!(NutrientUnit::Gram.convert(1.0, "IU"), None);
+}
+
+#[test]
+fn from_food_nutrients() {
+    let food = match cheddar() {
+        FDCMeta::Other(food) => food,
+        _ => unreachable!(),
+    };
+    let profile = NutrientProfile::from_food_nutrients(&food.food_nutrients);
+    assert_eq!(profile.len(), 4);
+    assert_eq!(profile.get(Nutrient::Protein), Some(24.9));
+    assert!(close(profile.get(Nutrient::Energy), 406.07));
+    assert_eq!(profile.get(Nutrient::Fiber), None);
+}
+
+#[test]
+fn energy_precedence() {
+    let energies = [
+        food_nutrient(1062, "Energy", "kJ", 1000.0),
+        food_nutrient(2048, "Energy (Atwater Specific Factors)", "KCAL", 230.0),
+        food_nutrient(2047, "Energy (Atwater General Factors)", "KCAL", 240.0),
+        food_nutrient(1008, "Energy", "KCAL", 250.0),
+    ];
+    let profile = |nutrients: &[serde_json::Value]| {
+        let nutrients = serde_json::from_value::<Vec<FoodNutrient>>(nutrients.into()).unwrap();
+        NutrientProfile::from_food_nutrients(&nutrients)
+    };
+    // 1008 wins, then the Atwater general and specific energies, then kJ
+    assert_eq!(profile(&energies).energy_kcal(), Some(250.0));
+    assert_eq!(profile(&energies[..3]).energy_kcal(), Some(240.0));
+    assert_eq!(profile(&energies[..2]).energy_kcal(), Some(230.0));
+    assert!(close(profile(&energies[..1]).energy_kcal(), 239.006));
+
+    // an energy in an unknown unit falls back to the next id
+    let calories = [
+        food_nutrient(1008, "Energy", "cal", 250_000.0),
+        energies[0].clone(),
+    ];
+    assert!(close(profile(&calories).energy_kj(), 1000.0));
+
+    // the accessors agree with each other, and with the label's Calories
+    let bar = NutrientProfile::per_serving(&granola_bar()).unwrap();
+    assert_eq!(bar.energy_kcal(), Some(190.0));
+    assert!(close(bar.energy_kj(), 190.0 * KJ_PER_KCAL));
+    assert_eq!(NutrientProfile::new().energy_kj(), None);
+}
+
+#[test]
+fn branded_per_serving() {
+    let bar = granola_bar();
+    let one = profile_for(&bar, &Quantity::Nominal(1.0, "serving".into()), None).unwrap();
+    assert_eq!(one.get(Nutrient::Energy), Some(190.0));
+    assert_eq!(one.get(Nutrient::Sodium), Some(90.0));
+
+    // the household serving text and the serving size resolve the same way
+    let bars = profile_for(&bar, &Quantity::Nominal(2.0, "bars".into()), None).unwrap();
+    let grams = profile_for(&bar, &Quantity::Mass(Mass::new::<gram>(84.0)), None).unwrap();
+    assert_eq!(bars.get(Nutrient::Protein), Some(8.0));
+    assert!(close(grams.get(Nutrient::Protein), 8.0));
+}
+
+#[test]
+fn foundation_per_100g() {
+    let cheese = cheddar();
+    let one_ounce = profile_for(&cheese, &Quantity::Mass(Mass::new::<ounce>(1.0)), None).unwrap();
+    assert!(close(one_ounce.get(Nutrient::Protein), 24.9 * 0.283_495));
+
+    let slices = profile_for(&cheese, &Quantity::Nominal(2.0, "Slices".into()), None).unwrap();
+    assert!(close(slices.get(Nutrient::Sodium), 654.0 * 0.56));
+
+    let cup = profile_for(&cheese, &Quantity::Nominal(0.5, "cup".into()), None).unwrap();
+    assert!(close(cup.get(Nutrient::TotalFat), 33.8 * 0.66));
+}
+
+#[test]
+fn unresolvable_quantities() {
+    let err = profile_for(&cheddar(), &Quantity::Nominal(1.0, "wedge".into()), None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cannot resolve 1 wedge of food 2002, no portion matches"
+    );
+    assert!(profile_for(&granola_bar(), &Quantity::Nominal(1.0, "box".into()), None).is_err());
+}
+
+#[test]
+fn custom_units() {
+    let units = CustomUnits::new().with_unit("wedge", 50.0);
+    let wedge = Quantity::Nominal(1.0, "wedge".into());
+    let profile = profile_for_with(&cheddar(), &wedge, &units, None).unwrap();
+    assert!(close(profile.get(Nutrient::Protein), 24.9 * 0.5));
+
+    // branded foods resolve the mass against their household serving of an ounce
+    let profile = profile_for_with(&branded_cheddar("g"), &wedge, &units, None).unwrap();
+    assert!(close(profile.get(Nutrient::Protein), 7.0 * 50.0 / 28.3495));
+}
+
+/// A branded cheddar whose label reports 7 g protein and 110 kcal per 28 g serving.
+fn branded_cheddar(serving_size_unit: &str) -> FDCMeta {
+    let mut label = [0.0; 13];
+    label[8] = 7.0;
+    label[12] = 110.0;
+    let names = [
+        "fat",
+        "saturatedFat",
+        "transFat",
+        "cholesterol",
+        "sodium",
+        "carbohydrates",
+        "fiber",
+        "sugars",
+        "protein",
+        "calcium",
+        "iron",
+        "potassium",
+        "calories",
+    ];
+    let label = names
+        .iter()
+        .zip(label.iter())
+        .map(|(name, value)| (name.to_string(), serde_json::json!({ "value": value })))
+        .collect::<serde_json::Map<_, _>>();
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Branded",
+        "fdcId": 2003,
+        "description": "SHARP CHEDDAR CHEESE",
+        "ingredients": "PASTEURIZED MILK, CHEESE CULTURE, SALT, ENZYMES.",
+        "servingSize": 28.0,
+        "servingSizeUnit": serving_size_unit,
+        "householdServingFullText": "1 oz",
+        "labelNutrients": label,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn per_100g() {
+    let branded = NutrientProfile::per_100g(&branded_cheddar("g")).unwrap();
+    let legacy = NutrientProfile::per_100g(&cheddar()).unwrap();
+    assert!(close(branded.get(Nutrient::Protein), 25.0));
+    assert_eq!(legacy.get(Nutrient::Protein), Some(24.9));
+    // the label rounds to whole grams, so the two only agree roughly
+    let ratio = branded.get(Nutrient::Protein).unwrap() / legacy.get(Nutrient::Protein).unwrap();
+    assert!((0.9..1.1).contains(&ratio));
+
+    let err = NutrientProfile::per_100g(&branded_cheddar("ml")).unwrap_err();
+    let err = match err {
+        crate::Error::Nutrition(NutritionError::NeedsDensity(e)) => e,
+        e => panic!("Should have needed a density: {}", e),
+    };
+    assert_eq!(err.fdc_id, 2003);
+}
+
+#[test]
+fn per_serving() {
+    let branded = NutrientProfile::per_serving(&branded_cheddar("g")).unwrap();
+    assert_eq!(branded.get(Nutrient::Energy), Some(110.0));
+    let slice = NutrientProfile::per_serving(&cheddar()).unwrap();
+    assert!(close(slice.get(Nutrient::Protein), 24.9 * 0.28));
+}
+
+#[test]
+fn sum_profiles() {
+    let bar = profile_for(&granola_bar(), &Quantity::Nominal(1.0, "bar".into()), None).unwrap();
+    let cheese = profile_for(&cheddar(), &Quantity::Mass(Mass::new::<gram>(100.0)), None).unwrap();
+    let total = sum(&[bar, cheese]);
+    assert!(close(total.get(Nutrient::Protein), 28.9));
+    assert!(close(total.get(Nutrient::Sodium), 744.0));
+    // only in the bar
+    assert_eq!(total.get(Nutrient::Fiber), Some(2.0));
+    assert!(sum(Vec::<NutrientProfile>::new()).is_empty());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn par_sum_profiles() {
+    fn shared<T: Send + Sync>() {}
+    shared::<NutrientProfile>();
+
+    let profile = |i: usize| {
+        let mut profile = NutrientProfile::new();
+        profile.insert(Nutrient::Protein, 0.1 + i as f32 * 0.37);
+        if i.is_multiple_of(3) {
+            profile.insert(Nutrient::Sodium, i as f32 * 1.1);
+        }
+        profile
+    };
+    let few = (0..PAR_CHUNK).map(profile).collect::<Vec<_>>();
+    assert_eq!(par_sum(&few), sum(&few));
+
+    let many = (0..10 * PAR_CHUNK + 7).map(profile).collect::<Vec<_>>();
+    let total = par_sum(&many);
+    let chunked = sum(many.chunks(PAR_CHUNK).map(sum).collect::<Vec<_>>());
+    assert_eq!(total, chunked);
+    for threads in [1, 3] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        assert_eq!(pool.install(|| par_sum(&many)), total);
+    }
+    assert!(par_sum::<NutrientProfile>(&[]).is_empty());
+}
+
+mod diff {
+    use super::*;
+
+    /// A foundation mozzarella reporting 22.2 g protein, 0.627 g sodium, 300 kcal, and 0.3 g
+    /// sugars per 100 g, but no fat.
+    fn mozzarella() -> FDCMeta {
+        serde_json::from_value(serde_json::json!({
+            "dataType": "Other",
+            "fdcId": 2003,
+            "description": "Cheese, mozzarella, whole milk",
+            "foodAttributes": [],
+            "foodPortions": [],
+            "foodNutrients": [
+                food_nutrient(1003, "Protein", "g", 22.2),
+                food_nutrient(1093, "Sodium, Na", "g", 0.627),
+                food_nutrient(1008, "Energy", "kcal", 300.0),
+                food_nutrient(2000, "Sugars, total", "g", 0.3),
+            ],
+        }))
+        .unwrap()
+    }
+
+    fn cheeses() -> NutrientDiff {
+        let a = NutrientProfile::per_100g(&cheddar()).unwrap();
+        let b = NutrientProfile::per_100g(&mozzarella()).unwrap();
+        diff(&a, &b)
+    }
+
+    #[test]
+    fn aligns_units() {
+        let diff = cheeses();
+        let sodium = diff.line(Nutrient::Sodium).unwrap();
+        assert_eq!(sodium.b, Some(627.0));
+        assert!(close(sodium.difference, 27.0));
+        assert!(close(diff.line(Nutrient::Protein).unwrap().difference, 2.7));
+        assert!(close(
+            diff.line(Nutrient::Energy).unwrap().difference,
+            106.07
+        ));
+    }
+
+    #[test]
+    fn one_sided() {
+        let diff = cheeses();
+        assert_eq!(
+            diff.lines
+                .iter()
+                .map(|line| line.nutrient)
+                .collect::<Vec<_>>(),
+            [
+                Nutrient::Energy,
+                Nutrient::Protein,
+                Nutrient::TotalFat,
+                Nutrient::Sugars,
+                Nutrient::Sodium
+            ]
+        );
+        let fat = diff.line(Nutrient::TotalFat).unwrap();
+        assert_eq!((fat.a, fat.b, fat.difference), (Some(33.8), None, None));
+        let sugars = diff.line(Nutrient::Sugars).unwrap();
+        assert_eq!(
+            (sugars.a, sugars.b, sugars.difference),
+            (None, Some(0.3), None)
+        );
+        assert!(diff.line(Nutrient::Fiber).is_none());
+    }
+
+    #[test]
+    fn better_in() {
+        let diff = cheeses();
+        assert_eq!(
+            diff.better_in(Nutrient::Protein, Better::More),
+            Some(Side::A)
+        );
+        assert_eq!(
+            diff.better_in(Nutrient::Sodium, Better::Less),
+            Some(Side::B)
+        );
+        assert_eq!(diff.better_in(Nutrient::TotalFat, Better::Less), None);
+        assert_eq!(diff.better_in(Nutrient::Fiber, Better::More), None);
+
+        let same = super::diff(&NutrientProfile::new(), &NutrientProfile::new());
+        assert!(same.lines.is_empty());
+        let profile = NutrientProfile::per_100g(&cheddar()).unwrap();
+        let same = super::diff(&profile, &profile);
+        assert_eq!(
+            same.better_in(Nutrient::Protein, Better::More),
+            Some(Side::Neither)
+        );
+    }
+
+    #[test]
+    fn table() {
+        let table = cheeses().table("Cheddar", "Mozzarella");
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "Nutrient    Cheddar  Mozzarella  Difference");
+        assert_eq!(lines[1], "Energy     406 kcal    300 kcal   +106 kcal");
+        assert_eq!(lines[2], "Protein      24.9 g      22.2 g      +2.7 g");
+        assert_eq!(lines[3], "Total fat    33.8 g           -           -");
+        assert_eq!(lines[5], "Sodium       654 mg      627 mg      +27 mg");
+        // every line is as wide as the header
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() == lines[0].len()));
+    }
+}
+
+mod format {
+    use super::*;
+
+    #[test]
+    fn format_amount() {
+        let cases = [
+            (Nutrient::Energy, 0.0, "0 kcal"),
+            (Nutrient::Energy, 52.4, "52 kcal"),
+            (Nutrient::Energy, 999.5, "1,000 kcal"),
+            (Nutrient::Energy, 2000.0, "2,000 kcal"),
+            (Nutrient::Energy, 1_234_567.0, "1,234,567 kcal"),
+            (Nutrient::Protein, 24.94, "24.9 g"),
+            (Nutrient::Protein, 0.05, "0.1 g"),
+            (Nutrient::TransFat, 0.04, "0.0 g"),
+            (Nutrient::Fiber, -0.04, "0.0 g"),
+            (Nutrient::Carbohydrate, 1500.0, "1500.0 g"),
+            (Nutrient::Sodium, 0.56, "1 mg"),
+            (Nutrient::Sodium, 654.4, "654 mg"),
+            (Nutrient::Sodium, 999.4, "999 mg"),
+            (Nutrient::Sodium, 999.5, "1.0 g"),
+            (Nutrient::Sodium, 999.95, "1.0 g"),
+            (Nutrient::Potassium, 2345.0, "2.3 g"),
+            (Nutrient::Cholesterol, -12.6, "-13 mg"),
+            (Nutrient::VitaminD, 2.55, "2.6 µg"),
+            (Nutrient::VitaminD, 999.96, "1 mg"),
+            (Nutrient::VitaminC, 0.0, "0 mg"),
+        ];
+        for (nutrient, amount, expected) in cases.iter() {
+            assert_eq!(
+                super::format_amount(*nutrient, *amount),
+                *expected,
+                "{} of {}",
+                amount,
+                nutrient
+            );
+        }
+    }
+}
+
+mod reconcile {
+    use super::*;
+
+    /// The granola bar with the given food nutrients per 100 g and serving size unit.
+    fn bar(nutrients: &[(i32, &str, f32)], serving_size_unit: &str) -> BrandedFoodItem {
+        let mut food = match granola_bar() {
+            FDCMeta::Branded(food) => food,
+            _ => unreachable!(),
+        };
+        food.serving_size_unit = serving_size_unit.into();
+        food.food_nutrients = nutrients
+            .iter()
+            .map(|(id, unit, amount)| {
+                serde_json::from_value(food_nutrient(*id, "", unit, *amount)).unwrap()
+            })
+            .collect();
+        food
+    }
+
+    #[test]
+    fn consistent() {
+        // 42 g of each gives 4 g protein, 90 mg sodium, 190 kcal, and 7 g fat
+        let food = bar(
+            &[
+                (1003, "g", 9.52),
+                (1093, "mg", 214.0),
+                (1008, "kcal", 452.0),
+                (1004, "g", 16.7),
+            ],
+            "g",
+        );
+        assert!(reconcile(&food).is_empty());
+    }
+
+    #[test]
+    fn inconsistent() {
+        // 42 g of each gives 8.4 g protein and 210 mg sodium, but only 0.04 g more fat
+        let food = bar(
+            &[(1003, "g", 20.0), (1093, "g", 0.5), (1004, "g", 16.8)],
+            "GRM",
+        );
+        let discrepancies = reconcile(&food);
+        assert_eq!(discrepancies.len(), 2);
+        match &discrepancies[0] {
+            Discrepancy::Differs {
+                nutrient,
+                label,
+                scaled,
+                relative,
+            } => {
+                assert_eq!(*nutrient, Nutrient::Protein);
+                assert_eq!(*label, 4.0);
+                assert!(close(Some(*scaled), 8.4));
+                assert!(close(Some(*relative), 1.1));
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+        assert!(matches!(
+            discrepancies[1],
+            Discrepancy::Differs {
+                nutrient: Nutrient::Sodium,
+                ..
+            }
+        ));
+
+        // a looser threshold tolerates both
+        assert_eq!(reconcile_with(&food, 1.5).len(), 0);
+    }
+
+    #[test]
+    fn volume_serving() {
+        let food = bar(&[(1003, "g", 20.0)], "ml");
+        match &reconcile(&food)[..] {
+            [Discrepancy::NotComparable { reason }] => assert!(reason.contains("density")),
+            other => panic!("Unexpected {:?}", other),
+        }
+    }
+}
+
+mod energy {
+    use super::*;
+
+    /// A foundation food with the nutrients given by FDC id and the calorie conversion factors.
+    fn foundation(nutrients: &[(i32, &str, f32)], factors: Option<(f32, f32, f32)>) -> FDCMeta {
+        let factors = factors
+            .map(|(protein, carbohydrate, fat)| {
+                vec![serde_json::json!({
+                    "type": ".CalorieConversionFactor",
+                    "proteinValue": protein,
+                    "carbohydrateValue": carbohydrate,
+                    "fatValue": fat,
+                })]
+            })
+            .unwrap_or_default();
+        serde_json::from_value(serde_json::json!({
+            "dataType": "Other",
+            "fdcId": 2004,
+            "foodAttributes": [],
+            "foodPortions": [],
+            "foodNutrients": nutrients
+                .iter()
+                .map(|(id, unit, amount)| food_nutrient(*id, "", unit, *amount))
+                .collect::<Vec<_>>(),
+            "nutrientConversionFactors": factors,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn clean() {
+        // 4 * 4 + 29 * 4 + 7 * 9 = 195 against 190
+        let check = validate_energy(&granola_bar());
+        assert_eq!(check.reported, Some(190.0));
+        assert_eq!(check.computed, Some(195.0));
+        assert_eq!(check.absolute, Some(5.0));
+        assert_eq!(check.status, EnergyStatus::Pass);
+
+        // taking the 2 g of fiber out brings it to 187
+        let thresholds = EnergyThresholds {
+            subtract_fiber: true,
+            ..EnergyThresholds::default()
+        };
+        let check = validate_energy_with(&granola_bar(), &thresholds);
+        assert_eq!(check.computed, Some(187.0));
+    }
+
+    #[test]
+    fn conversion_factors() {
+        let nutrients = [
+            (1008, "kcal", 389.0),
+            (1003, "g", 16.9),
+            (1005, "g", 66.3),
+            (1004, "g", 6.9),
+        ];
+        let atwater = validate_energy(&foundation(&nutrients, None));
+        let factored = validate_energy(&foundation(&nutrients, Some((3.46, 4.12, 8.37))));
+        assert!(close(atwater.computed, 394.9));
+        assert!(close(factored.computed, 389.383));
+        assert!(factored.relative < atwater.relative);
+    }
+
+    #[test]
+    fn discrepancy() {
+        // 130 kcal computed against 100 kcal reported
+        let food = foundation(
+            &[
+                (1008, "kcal", 100.0),
+                (1003, "g", 10.0),
+                (1005, "g", 10.0),
+                (1004, "g", 10.0 / 9.0 * 5.0),
+            ],
+            None,
+        );
+        let check = validate_energy(&food);
+        assert!(close(check.relative, 0.3));
+        assert_eq!(check.status, EnergyStatus::Fail);
+
+        let lenient = EnergyThresholds {
+            warn: 0.25,
+            fail: 0.5,
+            subtract_fiber: false,
+        };
+        assert_eq!(
+            validate_energy_with(&food, &lenient).status,
+            EnergyStatus::Warn
+        );
+    }
+
+    #[test]
+    fn insufficient_data() {
+        let food = foundation(&[(1008, "kcal", 100.0), (1003, "g", 10.0)], None);
+        let check = validate_energy(&food);
+        assert_eq!(check.reported, Some(100.0));
+        assert_eq!(check.computed, None);
+        assert_eq!(check.relative, None);
+        assert_eq!(check.status, EnergyStatus::InsufficientData);
+    }
+}
+
+mod targets {
+    use super::*;
+
+    fn day() -> NutrientProfile {
+        let mut profile = NutrientProfile::new();
+        profile.insert(Nutrient::Energy, 1650.0);
+        profile.insert(Nutrient::Protein, 130.0);
+        profile.insert(Nutrient::Sodium, 2500.0);
+        profile.insert(Nutrient::Fiber, 20.0);
+        profile
+    }
+
+    fn targets() -> NutrientTargets {
+        NutrientTargets::from_toml(
+            r#"
+            Energy = { at_most = 1800 }
+            Protein = { at_least = 120 }
+            Sodium = { range = [1500, 2300] }
+            Fiber = { at_least = 0 }
+            VitaminC = { at_least = 90 }
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn from_toml() {
+        let targets = targets();
+        assert_eq!(targets.0[&Nutrient::Energy], Target::AtMost(1800.0));
+        assert_eq!(targets.0[&Nutrient::Sodium], Target::Range(1500.0, 2300.0));
+        assert!(NutrientTargets::from_toml("Energy = { about = 3 }").is_err());
+        assert!(NutrientTargets::from_toml("Vibes = { at_least = 3 }").is_err());
+    }
+
+    #[test]
+    fn at_most() {
+        let report = day().against(&targets());
+        let energy = report.line(Nutrient::Energy).unwrap();
+        assert_eq!(energy.remaining, 150.0);
+        assert_eq!(energy.over, 0.0);
+        assert!(energy.met);
+        assert!(report.passed(Direction::AtMost));
+    }
+
+    #[test]
+    fn at_least() {
+        let report = day().against(&targets());
+        let protein = report.line(Nutrient::Protein).unwrap();
+        assert_eq!(protein.remaining, 0.0);
+        assert_eq!(protein.over, 10.0);
+        assert!(protein.met);
+        // vitamin C drags the direction down
+        assert!(!report.passed(Direction::AtLeast));
+    }
+
+    #[test]
+    fn range() {
+        let report = day().against(&targets());
+        let sodium = report.line(Nutrient::Sodium).unwrap();
+        assert_eq!(sodium.remaining, 0.0);
+        assert_eq!(sodium.over, 200.0);
+        assert!(!sodium.met);
+        assert!(!report.passed(Direction::Range));
+        assert!(!report.passed_all());
+
+        let mut low = NutrientProfile::new();
+        low.insert(Nutrient::Sodium, 1000.0);
+        let sodium = low
+            .against(&targets())
+            .line(Nutrient::Sodium)
+            .cloned()
+            .unwrap();
+        assert_eq!(sodium.remaining, 1300.0);
+        assert!(!sodium.met);
+    }
+
+    #[test]
+    fn zero_target() {
+        let report = day().against(&targets());
+        let fiber = report.line(Nutrient::Fiber).unwrap();
+        assert_eq!(fiber.percent, None);
+        assert!(fiber.met);
+        let energy = report.line(Nutrient::Energy).unwrap();
+        assert!((energy.percent.unwrap() - 91.666_664).abs() < 1e-3);
+    }
+
+    #[test]
+    fn missing_from_profile() {
+        let report = day().against(&targets());
+        let vitamin_c = report.line(Nutrient::VitaminC).unwrap();
+        assert!(vitamin_c.missing);
+        assert_eq!(vitamin_c.consumed, 0.0);
+        assert_eq!(vitamin_c.remaining, 90.0);
+        assert!(!vitamin_c.met);
+        assert!(!report.line(Nutrient::Energy).unwrap().missing);
+    }
+
+    mod profiles {
+        use super::*;
+        use crate::nutrition::targets::{self, profiles_from_toml};
+
+        const HOUSEHOLD: &str = r#"
+[defaults]
+Sodium = { at_most = 2300 }
+Fiber = { at_least = 25 }
+
+[profiles.alice]
+Energy = { at_most = 1800 }
+Fiber = { at_least = 30 }
+
+[profiles.bob]
+Protein = { at_least = 150, at_most = 220 }
+"#;
+
+        #[test]
+        fn overrides_defaults() {
+            let profiles = profiles_from_toml(HOUSEHOLD).unwrap();
+            assert_eq!(profiles.len(), 2);
+            let alice = &profiles["alice"].0;
+            assert_eq!(alice[&Nutrient::Energy], Target::AtMost(1800.0));
+            assert_eq!(alice[&Nutrient::Fiber], Target::AtLeast(30.0));
+            assert_eq!(alice[&Nutrient::Sodium], Target::AtMost(2300.0));
+            let bob = &profiles["bob"].0;
+            assert_eq!(bob[&Nutrient::Protein], Target::Range(150.0, 220.0));
+            assert_eq!(bob[&Nutrient::Fiber], Target::AtLeast(25.0));
+            assert!(!bob.contains_key(&Nutrient::Energy));
+        }
+
+        #[test]
+        fn unknown_nutrient() {
+            let input = "[defaults]\nSodium = { at_most = 2300 }\n\n[profiles.alice]\nVibes = { at_least = 3 }\n";
+            let e = profiles_from_toml(input).unwrap_err().to_string();
+            assert!(e.contains("Vibes"), "{}", e);
+            assert!(e.contains("line 5"), "{}", e);
+        }
+
+        #[test]
+        fn conflicting_bounds() {
+            let e =
+                profiles_from_toml("[profiles.bob]\nProtein = { at_least = 150, at_most = 100 }")
+                    .unwrap_err()
+                    .to_string();
+            assert!(e.contains("line 2"), "{}", e);
+            assert!(profiles_from_toml("[defaults]\nSodium = { range = [2300, 1500] }").is_err());
+            assert!(profiles_from_toml(
+                "[defaults]\nSodium = { range = [1500, 2300], at_most = 1 }"
+            )
+            .is_err());
+            assert!(profiles_from_toml("[defaults]\nSodium = {}").is_err());
+            assert!(profiles_from_toml("[defaults]\nSodium = { about = 3 }").is_err());
+        }
+
+        #[test]
+        fn load_and_select() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("targets.toml");
+            std::fs::write(&path, HOUSEHOLD).unwrap();
+            let profiles = targets::load(&path).unwrap();
+            let bob = targets::select(profiles.clone(), Some("bob")).unwrap();
+            assert_eq!(bob, profiles["bob"]);
+            assert!(targets::select(profiles.clone(), Some("carol")).is_err());
+
+            // no other test reads the variable, which is only read with the `env` feature
+            #[cfg(feature = "env")]
+            {
+                std::env::set_var("NUTRIENT_PROFILE", "alice");
+                let alice = targets::select(profiles.clone(), None).unwrap();
+                assert_eq!(alice, profiles["alice"]);
+                std::env::remove_var("NUTRIENT_PROFILE");
+            }
+            assert!(targets::select(profiles, None).is_err());
+            // a lone profile needs no name
+            let only = profiles_from_toml("[profiles.alice]\nEnergy = { at_most = 1800 }").unwrap();
+            let alice = targets::select(only, None).unwrap();
+            assert_eq!(alice.0[&Nutrient::Energy], Target::AtMost(1800.0));
+        }
+    }
+}
+
+mod screening {
+    use super::*;
+    use crate::nutrition::screening::{classify, classify_with, Threshold};
+
+    fn profile(amounts: &[(Nutrient, f32)]) -> NutrientProfile {
+        let mut profile = NutrientProfile::new();
+        for (nutrient, amount) in amounts {
+            profile.insert(*nutrient, *amount);
+        }
+        profile
+    }
+
+    fn level(levels: &[(Nutrient, Level)], nutrient: Nutrient) -> Level {
+        levels.iter().find(|(n, _)| *n == nutrient).unwrap().1
+    }
+
+    #[test]
+    fn each_side_of_each_threshold() {
+        for basis in [Basis::Food, Basis::Drink] {
+            for threshold in basis.thresholds() {
+                let nutrient = threshold.nutrient;
+                for (amount, expected) in [
+                    (0.0, Level::Low),
+                    (threshold.low, Level::Low),
+                    (threshold.low + 0.01, Level::Medium),
+                    (threshold.high, Level::Medium),
+                    (threshold.high + 0.01, Level::High),
+                ] {
+                    let levels = classify(&profile(&[(nutrient, amount)]), basis);
+                    assert_eq!(
+                        level(&levels, nutrient),
+                        expected,
+                        "{:?} {} {:?}",
+                        basis,
+                        amount,
+                        nutrient
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn absent_is_not_zero() {
+        // a salted cracker, with no sugars reported
+        let cracker = profile(&[
+            (Nutrient::TotalFat, 9.0),
+            (Nutrient::SaturatedFat, 0.0),
+            (Nutrient::Sodium, 700.0),
+        ]);
+        assert_eq!(
+            classify(&cracker, Basis::Food),
+            [
+                (Nutrient::TotalFat, Level::Medium),
+                (Nutrient::SaturatedFat, Level::Low),
+                (Nutrient::Sugars, Level::Unknown),
+                (Nutrient::Sodium, Level::High),
+            ]
+        );
+    }
+
+    #[test]
+    fn drinks_are_stricter() {
+        let soda = profile(&[(Nutrient::Sugars, 12.0), (Nutrient::Sodium, 400.0)]);
+        let food = classify(&soda, Basis::Food);
+        let drink = classify(&soda, Basis::Drink);
+        assert_eq!(level(&food, Nutrient::Sugars), Level::Medium);
+        assert_eq!(level(&drink, Nutrient::Sugars), Level::High);
+        assert_eq!(level(&food, Nutrient::Sodium), Level::Medium);
+        assert_eq!(level(&drink, Nutrient::Sodium), Level::High);
+    }
+
+    #[test]
+    fn custom_thresholds() {
+        let thresholds = [Threshold {
+            nutrient: Nutrient::Sodium,
+            low: 140.0,
+            high: 400.0,
+        }];
+        let soup = profile(&[(Nutrient::Sodium, 450.0), (Nutrient::Sugars, 30.0)]);
+        assert_eq!(
+            classify_with(&soup, &thresholds),
+            [(Nutrient::Sodium, Level::High)]
+        );
+    }
+}
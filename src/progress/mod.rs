@@ -0,0 +1,100 @@
+//! This module lets callers follow and abort long-running operations, like loading a bulk download
+//! or fetching thousands of foods. Operations report a [`ProgressEvent`] to a callback as they go,
+//! and check a [`CancellationToken`] before each step, failing with [`Cancelled`] once it is
+//! cancelled. Single requests instead race [`CancellationToken::cancelled`], to stop at once.
+
+use std::fmt;
+use std::future::poll_fn;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+/// How far an operation got.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProgressEvent {
+    /// The number of items done so far.
+    pub completed: usize,
+    /// The number of items to do, when known beforehand.
+    pub total: Option<usize>,
+    /// The FDC id of the last item done, if any.
+    pub current_item: Option<i32>,
+}
+
+/// A flag shared by an operation and whoever may abort it. Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<Flag>);
+
+/// The flag of a token, with the tasks waiting for it to be set.
+#[derive(Debug, Default)]
+struct Flag {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    /// generate a new CancellationToken which is not cancelled
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Ask the operations holding the token to stop before their next step, and wake those
+    /// waiting on [`CancellationToken::cancelled`].
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        for waker in self.0.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled, which may be never.
+    pub async fn cancelled(&self) {
+        poll_fn(|cx| {
+            let mut wakers = self.0.wakers.lock().unwrap();
+            // checked under the lock, so that a cancel cannot slip between the check and the
+            // waker being registered
+            if self.is_cancelled() {
+                return Poll::Ready(());
+            }
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Fail with [`Cancelled`] at the given progress if the token was cancelled.
+    pub fn check(&self, progress: ProgressEvent) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled { progress })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The failure of an operation whose [`CancellationToken`] was cancelled, with how far it got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled {
+    pub progress: ProgressEvent,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cancelled after {}", self.progress.completed)?;
+        if let Some(total) = self.progress.total {
+            write!(f, " of {}", total)?;
+        }
+        write!(f, " items")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,37 @@
+use super::{CancellationToken, Cancelled, ProgressEvent};
+
+#[test]
+fn clones_share_the_flag() {
+    let token = CancellationToken::new();
+    let held = token.clone();
+    let progress = ProgressEvent {
+        completed: 3,
+        total: Some(10),
+        current_item: Some(7),
+    };
+    assert!(held.check(progress).is_ok());
+    token.cancel();
+    assert!(held.is_cancelled());
+    let err = held.check(progress).unwrap_err();
+    assert_eq!(err, Cancelled { progress });
+    assert_eq!(err.to_string(), "Cancelled after 3 of 10 items");
+}
+
+#[tokio::test]
+async fn cancelled_wakes() {
+    let token = CancellationToken::new();
+    let waiting = tokio::spawn({
+        let token = token.clone();
+        async move { token.cancelled().await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert!(!waiting.is_finished());
+    token.cancel();
+    tokio::time::timeout(std::time::Duration::from_secs(5), waiting)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // once cancelled, it resolves right away
+    token.cancelled().await;
+}
@@ -3,15 +3,508 @@
 
 pub mod parse;
 
+use crate::error::Result;
+
+use num_rational::Ratio;
+use num_traits::CheckedMul;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter::Sum;
 use uom::si::f32::{Mass, Volume};
+use uom::si::mass::{gram, kilogram, milligram, ounce, pound};
+use uom::si::volume::{cubic_meter, cup, gallon, liter, milliliter, tablespoon, teaspoon};
+
+/// The largest mass, in grams, [`Quantity::is_reasonable_serving`] takes for a serving.
+pub const MAX_SERVING_GRAMS: f32 = 3000.0;
+
+/// The largest volume, in milliliters, [`Quantity::is_reasonable_serving`] takes for a serving.
+pub const MAX_SERVING_MILLILITERS: f32 = 3000.0;
+
+/// The largest count of nominal units, like pieces, [`Quantity::is_reasonable_serving`] takes for
+/// a serving.
+pub const MAX_SERVING_COUNT: f32 = 100.0;
+
+/// The failure to read quantities or to combine them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseQuantityError {
+    /// The text is not a quantity, like `"a handful"`.
+    Invalid(String),
+    /// Two quantities do not add up, like a mass and a volume.
+    Incompatible(Quantity, Quantity),
+    /// No quantities were summed, so the kind of the sum is unknown.
+    Empty,
+}
+
+impl fmt::Display for ParseQuantityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseQuantityError::Invalid(text) => write!(f, "invalid quantity \"{}\"", text),
+            ParseQuantityError::Incompatible(a, b) => write!(f, "Cannot add {:?} and {:?}", a, b),
+            ParseQuantityError::Empty => write!(f, "Cannot sum no quantities"),
+        }
+    }
+}
+
+impl std::error::Error for ParseQuantityError {}
+
+/// The system of units quantities are written in by [`Quantity::display_in`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnitSystem {
+    /// Milligrams, grams, and kilograms, or milliliters and liters.
+    Metric,
+    /// Ounces and pounds, or teaspoons, tablespoons, cups, and gallons.
+    Us,
+}
 
 /// Serving quantities are either measured in volume/mass SI units or nominally.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(from = "QuantityEnvelope", into = "QuantityEnvelope")]
 pub enum Quantity {
     Volume(Volume),
     Mass(Mass),
     Nominal(f32, String),
 }
 
+impl Quantity {
+    /// Create the mass or volume quantity of an amount in a unit like `"g"`, `"fl oz"`, or
+    /// `"tablespoons"`, without going through the text parser. Returns [`None`] when the unit is
+    /// not a known mass or volume unit.
+    pub fn from_unit(amount: f32, unit: &str) -> Option<Quantity> {
+        parse::units::si_quantity(amount, unit.trim())
+    }
+
+    /// The mass of the quantity without a food to weigh it against: masses as they are, and
+    /// nominal quantities of the custom units. Volumes and other nominal quantities have none.
+    pub fn to_mass(&self, units: &CustomUnits) -> Option<Mass> {
+        match self {
+            Quantity::Mass(mass) => Some(*mass),
+            Quantity::Nominal(amount, name) => {
+                Some(Mass::new::<gram>(amount * units.grams_per(name)?))
+            }
+            Quantity::Volume(_) => None,
+        }
+    }
+
+    /// Whether the quantity could be a single serving: more than nothing, and at most
+    /// [`MAX_SERVING_GRAMS`], [`MAX_SERVING_MILLILITERS`], or [`MAX_SERVING_COUNT`] units, so that
+    /// misparses and bad labels like `"5000 gallons"` can be caught before they are stored.
+    pub fn is_reasonable_serving(&self) -> bool {
+        let (amount, max) = match self {
+            Quantity::Mass(m) => (m.get::<gram>(), MAX_SERVING_GRAMS),
+            Quantity::Volume(v) => (v.get::<milliliter>(), MAX_SERVING_MILLILITERS),
+            Quantity::Nominal(amount, _) => (*amount, MAX_SERVING_COUNT),
+        };
+        amount > 0.0 && amount <= max
+    }
+
+    /// Multiply the quantity by the factor, keeping its kind.
+    pub fn scale(&self, factor: f32) -> Quantity {
+        match self {
+            Quantity::Volume(v) => Quantity::Volume(*v * factor),
+            Quantity::Mass(m) => Quantity::Mass(*m * factor),
+            Quantity::Nominal(amount, name) => Quantity::Nominal(amount * factor, name.clone()),
+        }
+    }
+
+    /// Add two quantities of the same kind. Nominal quantities add up when their names are the
+    /// same, ignoring case and a plural "s", and keep the name of the first. Masses, volumes, and
+    /// differently named nominals do not add up.
+    pub fn try_add(&self, other: &Quantity) -> Result<Quantity> {
+        match (self, other) {
+            (Quantity::Volume(a), Quantity::Volume(b)) => Ok(Quantity::Volume(*a + *b)),
+            (Quantity::Mass(a), Quantity::Mass(b)) => Ok(Quantity::Mass(*a + *b)),
+            (Quantity::Nominal(a, name), Quantity::Nominal(b, other_name))
+                if same_name(name, other_name) =>
+            {
+                Ok(Quantity::Nominal(a + b, name.clone()))
+            }
+            _ => Err(ParseQuantityError::Incompatible(self.clone(), other.clone()).into()),
+        }
+    }
+
+    /// Write the quantity in the largest unit of the system it comes to at least one of, like
+    /// `"1.5 kg"` or `"12 oz"`, with two decimals at most. US volumes of simple fractions are
+    /// written as such, like `"1/3 cup"`. Nominal quantities are written as [`fmt::Display`] does.
+    pub fn display_in(&self, system: UnitSystem) -> String {
+        let (amount, unit) = match (self, system) {
+            (Quantity::Mass(m), UnitSystem::Metric) => {
+                let grams = m.get::<gram>().abs();
+                if reaches(grams, 1000.0) {
+                    (m.get::<kilogram>(), "kg")
+                } else if reaches(grams, 1.0) || grams == 0.0 {
+                    (m.get::<gram>(), "g")
+                } else {
+                    (m.get::<milligram>(), "mg")
+                }
+            }
+            (Quantity::Mass(m), UnitSystem::Us) => {
+                if reaches(m.get::<pound>(), 1.0) {
+                    (m.get::<pound>(), "lb")
+                } else {
+                    (m.get::<ounce>(), "oz")
+                }
+            }
+            (Quantity::Volume(v), UnitSystem::Metric) => {
+                if reaches(v.get::<liter>(), 1.0) {
+                    (v.get::<liter>(), "l")
+                } else {
+                    (v.get::<milliliter>(), "ml")
+                }
+            }
+            (Quantity::Volume(v), UnitSystem::Us) => {
+                let (amount, unit) = if reaches(v.get::<gallon>(), 1.0) {
+                    (v.get::<gallon>(), "gal")
+                } else if reaches(v.get::<cup>(), 0.25) {
+                    let cups = v.get::<cup>();
+                    let plural = cups > 1.0 && !close(cups, 1.0);
+                    (cups, if plural { "cups" } else { "cup" })
+                } else if reaches(v.get::<tablespoon>(), 1.0) {
+                    (v.get::<tablespoon>(), "tbsp")
+                } else {
+                    (v.get::<teaspoon>(), "tsp")
+                };
+                let amount = fraction(amount).unwrap_or_else(|| decimal(amount));
+                return format!("{} {}", amount, unit);
+            }
+            (Quantity::Nominal(..), _) => return self.to_string(),
+        };
+        format!("{} {}", decimal(amount), unit)
+    }
+}
+
+/// Writes the quantity the way labels do, like `"1/3 cup"`, `"2.5 oz"`, or `"3 large eggs"`.
+/// Masses are written in grams or milligrams when they are whole, else in ounces when those are a
+/// whole number of quarters. Volumes are written in milliliters when they are whole, else in cups,
+/// tablespoons, or teaspoons when they come to simple fractions of one, so that fluid ounces come
+/// out as tablespoons. Anything else is written in grams or milliliters with two decimals at most.
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Quantity::Mass(m) => {
+                let grams = m.get::<gram>();
+                if let Some(amount) = whole(grams) {
+                    write!(f, "{} g", amount)
+                } else if let Some(amount) = whole(m.get::<milligram>()).filter(|_| grams < 1.0) {
+                    write!(f, "{} mg", amount)
+                } else if let Some(amount) = quarters(m.get::<ounce>()) {
+                    write!(f, "{} oz", decimal(amount))
+                } else {
+                    write!(f, "{} g", decimal(grams))
+                }
+            }
+            Quantity::Volume(v) => {
+                if let Some(amount) = whole(v.get::<milliliter>()) {
+                    write!(f, "{} ml", amount)
+                } else if let Some(amount) = fraction(v.get::<cup>()) {
+                    let plural = v.get::<cup>() > 1.0;
+                    write!(f, "{} {}", amount, if plural { "cups" } else { "cup" })
+                } else if let Some(amount) = fraction(v.get::<tablespoon>()) {
+                    write!(f, "{} tbsp", amount)
+                } else if let Some(amount) = fraction(v.get::<teaspoon>()) {
+                    write!(f, "{} tsp", amount)
+                } else {
+                    write!(f, "{} ml", decimal(v.get::<milliliter>()))
+                }
+            }
+            Quantity::Nominal(amount, name) => {
+                let amount = fraction(*amount).unwrap_or_else(|| decimal(*amount));
+                if name.is_empty() {
+                    write!(f, "{}", amount)
+                } else {
+                    write!(f, "{} {}", amount, name)
+                }
+            }
+        }
+    }
+}
+
+/// Whether the amounts are equal but for float error.
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() <= 1e-4 * a.abs().max(1.0)
+}
+
+/// Whether the amount comes to at least the threshold, either way from zero, but for float error.
+fn reaches(amount: f32, threshold: f32) -> bool {
+    amount.abs() >= threshold || close(amount.abs(), threshold)
+}
+
+/// The amount as a whole number, if it is one.
+fn whole(amount: f32) -> Option<f32> {
+    Some(amount.round()).filter(|rounded| *rounded > 0.0 && close(amount, *rounded))
+}
+
+/// The amount rounded to a whole number of quarters, if it is one.
+fn quarters(amount: f32) -> Option<f32> {
+    Some((amount * 4.0).round() / 4.0).filter(|rounded| *rounded > 0.0 && close(amount, *rounded))
+}
+
+/// The amount written as a whole number and a fraction like `"1 1/2"` or `"2/3"`, if it is a whole
+/// number of quarters or thirds.
+fn fraction(amount: f32) -> Option<String> {
+    let (numer, denom) = [4, 3].iter().find_map(|denom| {
+        let numer = (amount * *denom as f32).round();
+        Some((numer as u32, *denom)).filter(|_| numer > 0.0 && close(amount, numer / *denom as f32))
+    })?;
+    let ratio = Ratio::new(numer, denom);
+    let (whole, rest) = (ratio.to_integer(), ratio.fract());
+    Some(match (whole, *rest.numer()) {
+        (_, 0) => whole.to_string(),
+        (0, _) => rest.to_string(),
+        _ => format!("{} {}", whole, rest),
+    })
+}
+
+/// The amount with two decimals at most.
+fn decimal(amount: f32) -> String {
+    ((amount * 100.0).round() / 100.0).to_string()
+}
+
+/// Write quantities back as a label, like `"1 cup (240 ml)"`. The quantities are in the order
+/// [`parse::quantities`] returns them, whose last quantity is the one written first, so that
+/// parsing the label again gives the same quantities. The other quantities follow it in
+/// parentheses.
+pub fn format_quantities(qs: &[Quantity]) -> String {
+    match qs.split_last() {
+        Some((first, rest)) => rest.iter().fold(first.to_string(), |label, quantity| {
+            format!("{} ({})", label, quantity)
+        }),
+        None => String::new(),
+    }
+}
+
+/// Sum quantities with [`Quantity::try_add`], failing on the first which does not add up or when
+/// there are none, as the kind of the sum would be unknown.
+impl Sum<Quantity> for Result<Quantity> {
+    fn sum<I: Iterator<Item = Quantity>>(mut iter: I) -> Result<Quantity> {
+        let first = iter.next().ok_or(ParseQuantityError::Empty)?;
+        iter.try_fold(first, |total, quantity| total.try_add(&quantity))
+    }
+}
+
+impl<'a> Sum<&'a Quantity> for Result<Quantity> {
+    fn sum<I: Iterator<Item = &'a Quantity>>(iter: I) -> Result<Quantity> {
+        iter.cloned().sum()
+    }
+}
+
+/// The quantities of a label as [`parse::quantities`] gives them, along with the quantity of one
+/// item when the label states it, like the 40 g of `"2 bars (40g each)"`, from [`parse::itemized`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Itemized {
+    pub quantities: Vec<Quantity>,
+    pub each: Option<Quantity>,
+}
+
+/// A quantity along with how far off it may be, as labels like `"500 g ± 10 g"` say.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toleranced {
+    pub quantity: Quantity,
+    pub tolerance: Option<Quantity>,
+}
+
+/// A quantity along with its unit as it was written, like `"Tbsp."` rather than tablespoons, from
+/// [`parse::spelled_quantity`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedQuantity {
+    pub quantity: Quantity,
+    pub raw_unit: String,
+}
+
+/// The serialized form of a [`Quantity`]. Masses and volumes are stored in their SI base units, so
+/// that they round-trip exactly.
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum QuantityEnvelope {
+    Volume { cubic_meters: f32 },
+    Mass { kilograms: f32 },
+    Nominal { amount: f32, name: String },
+}
+
+impl From<Quantity> for QuantityEnvelope {
+    fn from(quantity: Quantity) -> QuantityEnvelope {
+        match quantity {
+            Quantity::Volume(v) => QuantityEnvelope::Volume {
+                cubic_meters: v.get::<cubic_meter>(),
+            },
+            Quantity::Mass(m) => QuantityEnvelope::Mass {
+                kilograms: m.get::<kilogram>(),
+            },
+            Quantity::Nominal(amount, name) => QuantityEnvelope::Nominal { amount, name },
+        }
+    }
+}
+
+impl From<QuantityEnvelope> for Quantity {
+    fn from(envelope: QuantityEnvelope) -> Quantity {
+        match envelope {
+            QuantityEnvelope::Volume { cubic_meters } => {
+                Quantity::Volume(Volume::new::<cubic_meter>(cubic_meters))
+            }
+            QuantityEnvelope::Mass { kilograms } => {
+                Quantity::Mass(Mass::new::<kilogram>(kilograms))
+            }
+            QuantityEnvelope::Nominal { amount, name } => Quantity::Nominal(amount, name),
+        }
+    }
+}
+
+/// A nominal count whose amount is kept as an exact ratio, so that scaling a recipe up or down
+/// does not accumulate float error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExactNominal {
+    pub amount: Ratio<u32>,
+    pub name: String,
+}
+
+impl ExactNominal {
+    /// Scale the amount by an exact factor, or [`None`] when the numerator or denominator of the
+    /// scaled amount would overflow.
+    pub fn scale(&self, factor: Ratio<u32>) -> Option<ExactNominal> {
+        Some(ExactNominal {
+            amount: self.amount.checked_mul(&factor)?,
+            name: self.name.clone(),
+        })
+    }
+
+    /// Convert to the lossy [`Quantity::Nominal`] variant.
+    pub fn to_quantity(&self) -> Quantity {
+        let amount = *self.amount.numer() as f32 / *self.amount.denom() as f32;
+        Quantity::Nominal(amount, self.name.clone())
+    }
+}
+
+/// Grams of nominal units defined by the user rather than by foods, like a scoop of a protein
+/// powder. Names match ignoring case and a plural "s", and deserialize from a table of names to
+/// grams.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct CustomUnits(BTreeMap<String, f32>);
+
+impl CustomUnits {
+    /// generate a new CustomUnits without any unit
+    pub fn new() -> CustomUnits {
+        CustomUnits::default()
+    }
+
+    /// Define one of the unit as weighing the grams, in place of any unit of the same name.
+    pub fn with_unit<S: Into<String>>(mut self, name: S, grams: f32) -> CustomUnits {
+        let name = name.into();
+        self.0.retain(|known, _| !same_name(known, &name));
+        self.0.insert(name, grams);
+        self
+    }
+
+    /// The grams of one of the unit, if it is defined.
+    pub fn grams_per(&self, name: &str) -> Option<f32> {
+        self.0
+            .iter()
+            .find(|(known, _)| same_name(known, name))
+            .map(|(_, grams)| *grams)
+    }
+
+    /// The quantity as a mass when it is a nominal quantity of a custom unit, or else as it is.
+    pub fn resolve(&self, quantity: &Quantity) -> Quantity {
+        match quantity {
+            Quantity::Nominal(..) => quantity
+                .to_mass(self)
+                .map_or_else(|| quantity.clone(), Quantity::Mass),
+            _ => quantity.clone(),
+        }
+    }
+}
+
+/// Options of [`ParseOptions::parse`], the parser of quantities typed by users, like aliases of
+/// units it does not know. Aliases deserialize from a table of aliases to units.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ParseOptions {
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+    /// Read masses and volumes written with their unit first, like `"g 100"`, when the text is not
+    /// a quantity otherwise.
+    #[serde(default)]
+    pub reversed_units: bool,
+}
+
+impl ParseOptions {
+    /// generate a new ParseOptions without any alias
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Read the unit `alias` as `unit`, like `"heaping cup"` as `"cup"`, in place of any alias of
+    /// the same name. Aliases match ignoring case and a plural "s".
+    pub fn with_alias<A: Into<String>, U: Into<String>>(
+        mut self,
+        alias: A,
+        unit: U,
+    ) -> ParseOptions {
+        let alias = alias.into();
+        self.aliases.retain(|known, _| !same_name(known, &alias));
+        self.aliases.insert(alias, unit.into());
+        self
+    }
+
+    /// Read masses and volumes written with their unit first, like `"g 100"`, with
+    /// [`parse::reversed_quantity`].
+    pub fn with_reversed_units(self) -> ParseOptions {
+        ParseOptions {
+            reversed_units: true,
+            ..self
+        }
+    }
+
+    /// The unit the alias stands for, if it is one.
+    pub fn unit_of(&self, alias: &str) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|(known, _)| same_name(known, alias))
+            .map(|(_, unit)| &unit[..])
+    }
+
+    /// Parse the quantity written first in the text with [`parse::quantities`], like `"1 cup"` of
+    /// `"1 cup (240 ml)"`, reading nominal quantities of an alias in its unit, and with the unit
+    /// first when [`ParseOptions::reversed_units`] is set.
+    pub fn parse(&self, text: &str) -> Result<Quantity> {
+        let quantity = parse::quantities(text)
+            .ok()
+            .and_then(|(_, quants)| quants.into_iter().last())
+            .or_else(|| self.parse_reversed(text))
+            .ok_or_else(|| ParseQuantityError::Invalid(text.into()))?;
+        Ok(match &quantity {
+            Quantity::Nominal(amount, name) => match self.unit_of(name) {
+                Some(unit) => Quantity::from_unit(*amount, unit)
+                    .unwrap_or_else(|| Quantity::Nominal(*amount, unit.to_string())),
+                None => quantity,
+            },
+            _ => quantity,
+        })
+    }
+
+    /// The whole text as a quantity with its unit first, if those are read.
+    fn parse_reversed(&self, text: &str) -> Option<Quantity> {
+        if !self.reversed_units {
+            return None;
+        }
+        match parse::reversed_quantity(text.trim()) {
+            Ok((rest, quantity)) if rest.trim().is_empty() => Some(quantity),
+            _ => None,
+        }
+    }
+}
+
+/// Whether two unit names are the same, ignoring case and a plural "s".
+pub(crate) fn same_name(a: &str, b: &str) -> bool {
+    singular(a) == singular(b)
+}
+
+/// Lowercase a name and strip a plural "s".
+pub(crate) fn singular(s: &str) -> String {
+    let s = s.trim().to_lowercase();
+    match s.strip_suffix('s') {
+        Some(stripped) if !stripped.is_empty() => stripped.to_string(),
+        _ => s,
+    }
+}
+
 #[cfg(test)]
 mod test;
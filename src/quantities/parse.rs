@@ -1,16 +1,28 @@
 //! This module provides the parsing functionality for serving quantities.
 
-use super::Quantity;
+use super::{ExactNominal, Itemized, ParsedQuantity, Quantity, Toleranced};
 
 use nom::branch::alt;
-use nom::bytes::complete::{tag, tag_no_case};
-use nom::character::complete::{char, digit1, multispace0, multispace1};
+use nom::bytes::complete::{tag, tag_no_case, take_while, take_while1};
+use nom::character::complete::{alpha1, char, digit1};
 use nom::character::is_alphabetic;
-use nom::combinator::{eof, iterator, map_opt, opt};
+use nom::combinator::{eof, iterator, map, map_opt, opt};
 use nom::error::{Error, ErrorKind};
 use nom::number::complete::float;
 use nom::sequence::{delimited, preceded, terminated, tuple};
 use nom::{Err, IResult, Parser};
+use num_rational::Ratio;
+
+/// Parse any whitespace, including Unicode spaces like the non-breaking and thin spaces of text
+/// scraped from the web, which nom's `multispace0` does not accept.
+fn multispace0(input: &str) -> IResult<&str, &str> {
+    take_while(char::is_whitespace)(input)
+}
+
+/// Parse at least one whitespace character, as [`multispace0`] does.
+fn multispace1(input: &str) -> IResult<&str, &str> {
+    take_while1(char::is_whitespace)(input)
+}
 
 /// Parse a fraction string like `"1/2"` to the corresponding float.
 fn fraction(input: &str) -> IResult<&str, f32> {
@@ -34,24 +46,113 @@ fn compound_fraction(input: &str) -> IResult<&str, f32> {
     )(input)
 }
 
-/// Parse any numeric string like `"3/2"`, `"1 1/2"`, or `"1.5"` to the corresponding float.
+/// Parse a spelled-out number from `"one"` through `"twenty"`, or `"dozen"`, to the corresponding
+/// float. The whole word must match, so `"tender"` is not ten.
+pub fn word_number(input: &str) -> IResult<&str, f32> {
+    map_opt(alpha1, |word: &str| {
+        let n = match &word.to_lowercase()[..] {
+            "one" => 1.0,
+            "two" => 2.0,
+            "three" => 3.0,
+            "four" => 4.0,
+            "five" => 5.0,
+            "six" => 6.0,
+            "seven" => 7.0,
+            "eight" => 8.0,
+            "nine" => 9.0,
+            "ten" => 10.0,
+            "eleven" => 11.0,
+            "twelve" | "dozen" => 12.0,
+            "thirteen" => 13.0,
+            "fourteen" => 14.0,
+            "fifteen" => 15.0,
+            "sixteen" => 16.0,
+            "seventeen" => 17.0,
+            "eighteen" => 18.0,
+            "nineteen" => 19.0,
+            "twenty" => 20.0,
+            _ => return None,
+        };
+        Some(n)
+    })(input)
+}
+
+/// Parse any numeric string like `"3/2"`, `"1 1/2"`, `"1.5"`, or `"two"` to the corresponding
+/// float.
 pub fn number(input: &str) -> IResult<&str, f32> {
-    alt((compound_fraction, fraction, float))(input)
+    alt((compound_fraction, fraction, float, word_number))(input)
+}
+
+/// Parse a fraction string like `"1/3"` to its numerator and denominator, without going through
+/// a float. Fails on a zero denominator or on digits overflowing a `u32`.
+pub fn fraction_exact(input: &str) -> IResult<&str, (u32, u32)> {
+    let digit_pair = tuple((
+        digit1,
+        delimited(multispace0, char('/'), multispace0),
+        terminated(digit1, multispace0),
+    ));
+    map_opt(digit_pair, |(str0, _, str1): (&str, char, &str)| {
+        let num0 = str0.parse::<u32>().ok()?;
+        let num1 = str1.parse::<u32>().ok()?;
+        if num1 == 0 {
+            None
+        } else {
+            Some((num0, num1))
+        }
+    })(input)
+}
+
+/// Parse any numeric string like `"3/2"`, `"1 1/2"`, or `"2"` to an exact, reduced ratio. Decimal
+/// strings are not accepted as they are seldom exact to begin with.
+pub fn number_exact(input: &str) -> IResult<&str, Ratio<u32>> {
+    let compound = map_opt(
+        tuple((digit1, multispace1, fraction_exact)),
+        |(whole, _, (numer, denom)): (&str, &str, (u32, u32))| {
+            let whole = whole.parse::<u32>().ok()?;
+            let numer = whole.checked_mul(denom)?.checked_add(numer)?;
+            Some(Ratio::new(numer, denom))
+        },
+    );
+    let fraction = map_opt(fraction_exact, |(numer, denom)| {
+        Some(Ratio::new(numer, denom))
+    });
+    let whole = map_opt(digit1, |s: &str| s.parse::<u32>().ok().map(Ratio::from));
+    alt((compound, fraction, whole))(input)
+}
+
+/// Parser for a nominal count like `"1/3 cup of oats"`, keeping the amount as an exact ratio and
+/// the following words as the name. Unlike [`quantity`], no attempt is made at matching the words
+/// to an SI unit.
+pub fn nominal_exact(input: &str) -> IResult<&str, ExactNominal> {
+    let (input, amount) = terminated(number_exact, multispace0)(input)?;
+    let (input, first) = unit_word(input)?;
+    let mut name = first.to_lowercase();
+    let mut iter = iterator(input, preceded(multispace1, unit_word));
+    iter.for_each(|word| {
+        name.push(' ');
+        name.push_str(&word.to_lowercase());
+    });
+    let (input, _) = iter.finish()?;
+    Ok((input, ExactNominal { amount, name }))
 }
 
 /// This is a simple parser that allows for words to have inter-hyphens and terminating
-/// periods, as is usually the case with unit names.
-pub fn unit_word<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
+/// periods, as is usually the case with unit names. It takes the longest run it can, so `"gal"`
+/// is a single word and never `"g"` followed by `"al"`.
+pub fn unit_word(input: &str) -> IResult<&str, &str> {
     let opt_split_index = input.as_bytes().iter().enumerate().find_map(|(i, b)| {
         let c = char::from(*b);
-        if is_alphabetic(*b) || ((c == '.' || c == '-') && i != 0) {
+        // a leading period is kept only before a letter, as in the ".oz" of "fl .oz"
+        let leading_period =
+            c == '.' && i == 0 && input.as_bytes().get(1).is_some_and(|b| is_alphabetic(*b));
+        if is_alphabetic(*b) || ((c == '.' || c == '-') && i != 0) || leading_period {
             None
         } else {
             Some(i)
         }
     });
     match opt_split_index {
-        Some(i) if i == 0 => Err(Err::Error(Error::new(input, ErrorKind::Alpha))),
+        Some(0) => Err(Err::Error(Error::new(input, ErrorKind::Alpha))),
         Some(i) => Ok((&input[i..], &input[..i])),
         None => Ok(("", input)),
     }
@@ -60,7 +161,10 @@ pub fn unit_word<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
 /// Parser for a food quantity. It is achieved by first matching on a numeric value and
 /// iteratively grabbing words until the resulting string matches an SI unit or it can grab no
 /// more. In the latter case, it returns the [`Quantity::Nominal`] variant.
-pub fn quantity<'a>(input: &'a str) -> IResult<&'a str, Quantity> {
+///
+/// Units only match whole words, so `"1g"` is a gram, `"1gal"` a gallon, and `"1ga"`, which is
+/// neither, the nominal quantity "ga" rather than a gram with some trailing text.
+pub fn quantity(input: &str) -> IResult<&str, Quantity> {
     // any quantity must be a number and at least one word
     let number_space = terminated(number, multispace0);
     let mut required = tuple((number_space, unit_word));
@@ -68,9 +172,9 @@ pub fn quantity<'a>(input: &'a str) -> IResult<&'a str, Quantity> {
         // if we cannot match "number word", then we consider the parser failed
         Err(e) => Err(e),
         // otherwise, we check if "word" is associated to some si unit
-        Ok((input, (val, word))) => match units::si_quantity(val.clone(), word) {
-            // if so, return the quantity
-            Some(quantity) => Ok((input, quantity)),
+        Ok((input, (val, word))) => match units::si_quantity(val, word) {
+            // if so, return the quantity, unless a reversed fluid modifier makes it a volume
+            Some(quantity) => Ok(reversed_fluid(input, val, word).unwrap_or((input, quantity))),
             // if not, continue grabbing words
             None => {
                 // a string buffer will hold the words we see
@@ -83,9 +187,9 @@ pub fn quantity<'a>(input: &'a str) -> IResult<&'a str, Quantity> {
                 let mut iter = iterator(input, preceded(multispace1, unit_word));
                 let quantity = iter
                     .scan(&mut words, |words, word| {
-                        words.push_str(" ");
+                        words.push(' ');
                         words.push_str(&word.to_lowercase());
-                        Some(units::si_quantity(val.clone(), words))
+                        Some(units::si_quantity(val, words))
                     })
                     .find_map(|opt_quant| opt_quant)
                     .unwrap_or(Quantity::Nominal(val, words));
@@ -96,6 +200,107 @@ pub fn quantity<'a>(input: &'a str) -> IResult<&'a str, Quantity> {
     }
 }
 
+/// Parse the "fl" or "fluid" some datasets write after ounces rather than before, as in `"3 oz fl"`
+/// or `"3 oz, fluid"`, to the volume of the amount of fluid ounces. The input follows the unit
+/// word, which must be ounces for the modifier to apply.
+fn reversed_fluid<'a>(input: &'a str, val: f32, word: &str) -> Option<(&'a str, Quantity)> {
+    let separator = alt((preceded(char(','), multispace0), multispace1));
+    let (rest, modifier) = preceded(separator, unit_word)(input).ok()?;
+    match units::si_quantity(val, &format!("{} {}", word.to_lowercase(), modifier)) {
+        Some(quantity @ Quantity::Volume(_)) => Some((rest, quantity)),
+        _ => None,
+    }
+}
+
+/// Parser for a mass or volume written with its unit first, like `"g 100"` or `"fl oz 8"`, as some
+/// mangled sources do. Only units of mass and volume are read this way, as a word followed by a
+/// number is too often something else, like the `"slice 2"` of a list.
+pub fn reversed_quantity(input: &str) -> IResult<&str, Quantity> {
+    let (mut rest, word) = unit_word(input)?;
+    let mut words = word.to_lowercase();
+    loop {
+        if let Ok((after, val)) = preceded(multispace0, number)(rest) {
+            return match units::si_quantity(val, &words) {
+                Some(quantity) => Ok((after, quantity)),
+                None => Err(Err::Error(Error::new(input, ErrorKind::Verify))),
+            };
+        }
+        let (after, word) = preceded(multispace1, unit_word)(rest)?;
+        words.push(' ');
+        words.push_str(&word.to_lowercase());
+        rest = after;
+    }
+}
+
+/// Parser for a run of masses written one after the other, like `"1 lb 4 oz"` or `"2 kg 300 g"`,
+/// added up into a single [`Quantity::Mass`], so that `"1 lb 4 oz"` is about 567 g. Unlike
+/// [`quantities`], which keeps each of them, the run stops at the first quantity which is not a
+/// mass, and the input must start with one.
+pub fn combined_mass(input: &str) -> IResult<&str, Quantity> {
+    let mass = |input| match quantity(input)? {
+        (rest, Quantity::Mass(mass)) => Ok((rest, mass)),
+        _ => Err(Err::Error(Error::new(input, ErrorKind::Verify))),
+    };
+    let (input, first) = mass(input)?;
+    let mut iter = iterator(input, preceded(multispace1, mass));
+    let total = iter.fold(first, |total, mass| total + mass);
+    let (input, _) = iter.finish()?;
+    Ok((input, Quantity::Mass(total)))
+}
+
+/// Parser for a quantity like [`quantity`], which also keeps the unit as it was written, with its
+/// case and spacing.
+pub fn spelled_quantity(input: &str) -> IResult<&str, ParsedQuantity> {
+    let (unit, _) = terminated(number, multispace0)(input)?;
+    let (rest, quantity) = quantity(input)?;
+    let raw_unit = unit[..unit.len() - rest.len()].trim_end().to_string();
+    Ok((rest, ParsedQuantity { quantity, raw_unit }))
+}
+
+/// Parser for a tolerance like `"± 10 g"` or `"+/- 10 g"`. A tolerance without a unit, like in
+/// `"500 g ± 10"`, is in the unit of the quantity `of`, which has the given amount, and so needs an
+/// amount other than zero. A tolerance of another kind than `of`, like `"500 g ± 10 ml"`, fails.
+fn tolerance<'a>(input: &'a str, amount: f32, of: &Quantity) -> IResult<&'a str, Quantity> {
+    let start = input;
+    let (input, _) = delimited(
+        multispace0,
+        alt((tag("±"), tag("+/-"), tag("+-"))),
+        multispace0,
+    )(input)?;
+    match quantity(input) {
+        // a bare number parses as a nameless nominal quantity
+        Ok((_, Quantity::Nominal(_, name))) if name.is_empty() => {}
+        Ok((_, tolerance)) if of.try_add(&tolerance).is_err() => {
+            return Err(Err::Error(Error::new(start, ErrorKind::Verify)));
+        }
+        Ok(o) => return Ok(o),
+        Err(_) => {}
+    }
+    if amount == 0.0 {
+        return Err(Err::Error(Error::new(start, ErrorKind::Verify)));
+    }
+    let (input, val) = number(input)?;
+    Ok((input, of.scale(val / amount)))
+}
+
+/// Parser for a quantity optionally followed by a tolerance, like `"500 g ± 10 g"`.
+pub fn toleranced(input: &str) -> IResult<&str, Toleranced> {
+    let (_, amount) = number(input)?;
+    let (input, quantity) = quantity(input)?;
+    let (input, tolerance) = match tolerance(input, amount, &quantity) {
+        Ok((input, tolerance)) => (input, Some(tolerance)),
+        Err(Err::Error(_)) => (input, None),
+        Err(e) => return Err(e),
+    };
+    Ok((
+        input,
+        Toleranced {
+            quantity,
+            tolerance,
+        },
+    ))
+}
+
 pub fn noise<'a>(input: &'a str) -> IResult<&'a str, ()> {
     let mut iter = iterator::<&'a str, &'a str, Error<&'a str>, _>(
         input,
@@ -104,6 +309,8 @@ pub fn noise<'a>(input: &'a str) -> IResult<&'a str, ()> {
             tag_no_case("approx."),
             tag_no_case("approximately"),
             tag_no_case("makes"),
+            tag("~"),
+            tag("≈"),
             tag("\""),
             tag("|"),
             multispace1,
@@ -116,24 +323,59 @@ pub fn noise<'a>(input: &'a str) -> IResult<&'a str, ()> {
     }
 }
 
+/// Parser for the "each" which follows the weight of one item of a count, as in `"40g each"`, or
+/// its abbreviation "ea".
+fn each(input: &str) -> IResult<&str, ()> {
+    map_opt(unit_word, |word| {
+        matches!(&word.to_lowercase()[..], "each" | "ea" | "ea.").then_some(())
+    })(input)
+}
+
 /// Parser for the food quantities on a label. Implemented by stripping artifacts and repeatedly
-/// applying the [`quantity`] parser.
-pub fn quantities<'a>(input: &'a str) -> IResult<&'a str, Vec<Quantity>> {
-    // first run a parse on a single quantity
-    let res = delimited(noise, quantity, multispace0)(input);
+/// applying the [`toleranced`] parser, leaving tolerances out.
+///
+/// A quantity followed by "each" is of one item of a leading count, so that `"2 bars (40g each)"`
+/// gives the 80 g of both bars along with the 2 bars, as `"2 bars (80g)"` would. The 40 g of one
+/// bar is kept by [`itemized`].
+pub fn quantities(input: &str) -> IResult<&str, Vec<Quantity>> {
+    map(itemized, |itemized| itemized.quantities)(input)
+}
+
+/// Parser for the food quantities on a label like [`quantities`], which also keeps the quantity
+/// of one item of a leading count, like the 40 g of `"2 bars (40g each)"`.
+pub fn itemized(input: &str) -> IResult<&str, Itemized> {
+    // first run a parse on a single quantity, dropping any tolerance
+    let mut quantity = map(toleranced, |t| t.quantity);
+    let res = delimited(noise, &mut quantity, multispace0)(input);
     match res {
         Err(e) => Err(e),
         Ok((input, q)) => {
+            // a quantity of each item is scaled by the count, when the first quantity is one
+            let count = match q {
+                Quantity::Nominal(count, _) => Some(count),
+                _ => None,
+            };
+            let mut per_item = None;
+            let item = map(
+                tuple((
+                    delimited(noise, quantity, noise),
+                    opt(terminated(each, noise)),
+                )),
+                |(quantity, each)| match (each, count) {
+                    (Some(()), Some(count)) => {
+                        let scaled = quantity.scale(count);
+                        per_item.get_or_insert(quantity);
+                        scaled
+                    }
+                    _ => quantity,
+                },
+            );
             // if we saw a quantity, continue to parse until eof, allowing parentheses
             let mut iter = iterator(
                 input,
                 delimited(
                     multispace0,
-                    delimited(
-                        opt(tag("(")),
-                        delimited(noise, quantity, noise),
-                        opt(tag(")")),
-                    ),
+                    delimited(opt(tag("(")), item, opt(tag(")"))),
                     multispace0,
                 ),
             );
@@ -141,13 +383,119 @@ pub fn quantities<'a>(input: &'a str) -> IResult<&'a str, Vec<Quantity>> {
             quants.push(q);
             let (input, _) = iter.finish()?;
             let _ = preceded(noise, eof)(input)?;
-            Ok((input, quants))
+            Ok((
+                input,
+                Itemized {
+                    quantities: quants,
+                    each: per_item,
+                },
+            ))
+        }
+    }
+}
+
+/// Parse a blob of several foods' servings, one per line or semicolon separated segment, with
+/// [`quantities`]. Blank segments are skipped, and segments which fail to parse give an empty
+/// vector rather than failing the others.
+pub fn quantities_multi(input: &str) -> Vec<Vec<Quantity>> {
+    input
+        .split(['\n', ';'])
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            quantities(segment)
+                .map(|(_, quants)| quants)
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Recover every quantity of noisy text, like the OCR of a label, in reading order. Each line is
+/// read on its own, so that a nominal name does not run into the next line. Characters which
+/// cannot be part of a quantity or tolerance are blanked out first, then [`toleranced`] is tried at
+/// each token, skipping the tokens where it fails instead of failing. Percentages are skipped, and
+/// tolerances are left out.
+///
+/// To leave out quantities made of junk, only quantities with a positive amount are kept, and
+/// nominal quantities only when their name has one or two words of at least two letters each,
+/// like "large eggs" but not a bare number or the "x" of a stray "2 x".
+pub fn quantities_lossy(input: &str) -> Vec<Quantity> {
+    input.lines().flat_map(line_lossy).collect()
+}
+
+/// Recover the quantities of a single line for [`quantities_lossy`].
+fn line_lossy(line: &str) -> Vec<Quantity> {
+    let cleaned = line
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c.is_whitespace() || ".-/+±~≈%".contains(c) {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>();
+    let mut quants = Vec::new();
+    let mut rest = cleaned.trim_start();
+    while !rest.is_empty() {
+        rest = match toleranced(rest) {
+            Ok((after, toleranced)) if after.len() < rest.len() => {
+                if is_plausible(&toleranced.quantity) {
+                    quants.push(toleranced.quantity);
+                }
+                after
+            }
+            _ => skip_token(rest),
+        }
+        .trim_start();
+    }
+    quants
+}
+
+/// Whether a quantity recovered by [`quantities_lossy`] looks like one rather than junk.
+fn is_plausible(quantity: &Quantity) -> bool {
+    match quantity {
+        Quantity::Mass(m) => m.value > 0.0 && m.value.is_finite(),
+        Quantity::Volume(v) => v.value > 0.0 && v.value.is_finite(),
+        Quantity::Nominal(amount, name) => {
+            let words = name.split_whitespace().collect::<Vec<_>>();
+            *amount > 0.0
+                && amount.is_finite()
+                && (1..=2).contains(&words.len())
+                && words
+                    .iter()
+                    .all(|word| word.chars().filter(|c| c.is_alphabetic()).count() >= 2)
         }
     }
 }
 
+/// The input after its first token, which is a run of letters, of digits, or of any other
+/// characters which are not spaces.
+fn skip_token(input: &str) -> &str {
+    let class = |c: char| {
+        if c.is_alphabetic() {
+            0
+        } else if c.is_numeric() {
+            1
+        } else if c.is_whitespace() {
+            2
+        } else {
+            3
+        }
+    };
+    let mut chars = input.char_indices();
+    let first = match chars.next() {
+        Some((_, c)) => class(c),
+        None => return input,
+    };
+    match chars.find(|(_, c)| class(*c) != first) {
+        Some((i, _)) => &input[i..],
+        None => "",
+    }
+}
+
 /// This module simply holds static variables which are used for parsing units
-mod units {
+pub(super) mod units {
     use super::Quantity;
     use uom::si::{
         f32::{Mass, Volume},
@@ -159,7 +507,7 @@ mod units {
     };
 
     /// We store all the units in an enum to ensure each one is matched against
-    #[allow(non_camel_case_types)]
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     enum Units {
         CENTILITER,
         CUBIC_CENTIMETER,
@@ -184,11 +532,25 @@ mod units {
 
     /// Map various names associated to a unit to a normalized static candidate. A [`None`]
     /// variant corresponds to the input string slice not being associated to a unit.
-    fn normalize_unit<'a>(input: &'a str) -> Units {
-        match &input.to_lowercase()[..] {
+    fn normalize_unit(input: &str) -> Units {
+        let input = input.to_lowercase();
+        if let Some(rest) = fluid_modifier(&input) {
+            return match normalize_unit(rest) {
+                Units::OUNCE | Units::FLUID_OUNCE => Units::FLUID_OUNCE,
+                unit if unit.is_volume() => unit,
+                _ => Units::NONE,
+            };
+        }
+        if let Some(rest) = reversed_fluid_modifier(&input) {
+            return match normalize_unit(rest) {
+                Units::OUNCE => Units::FLUID_OUNCE,
+                _ => Units::NONE,
+            };
+        }
+        match &input[..] {
             // volumes
             "centiliter" | "centiliters" | "cl" => Units::CENTILITER,
-            "cubic centimeter" | "cubic centimeters" => Units::CUBIC_CENTIMETER,
+            "cubic centimeter" | "cubic centimeters" | "cc" | "ccs" => Units::CUBIC_CENTIMETER,
             "cubic inch" | "cubic inches" => Units::CUBIC_INCH,
             "cup" | "cups" => Units::CUP,
             "fl.oz." | "fl. oz." | "fl oz" | "fluid ounce" | "fluid oz" | "fluid ounces"
@@ -214,6 +576,37 @@ mod units {
         }
     }
 
+    /// Strip a leading "fl" or "fluid" volume modifier, along with the periods and spaces after
+    /// it, from a lowercase unit name. Without a period or space, as in "floz", the rest must be
+    /// ounces so that words like "flour" keep their meaning.
+    fn fluid_modifier(input: &str) -> Option<&str> {
+        let rest = ["fluid", "fl"]
+            .iter()
+            .find_map(|modifier| input.strip_prefix(modifier))?;
+        let unit = rest.trim_start_matches(|c: char| c == '.' || c.is_whitespace());
+        match normalize_unit(unit) {
+            _ if unit.is_empty() => None,
+            Units::OUNCE => Some(unit),
+            _ if unit.len() < rest.len() => Some(unit),
+            _ => None,
+        }
+    }
+
+    /// Strip a trailing "fl" or "fluid" volume modifier, along with the spaces and commas before
+    /// it, from a lowercase unit name, as in "oz fl" or "ounces, fluid". Unlike a leading modifier,
+    /// it must be apart from the unit, so that "oz" never ends up modified.
+    fn reversed_fluid_modifier(input: &str) -> Option<&str> {
+        let rest = ["fluid", "fl.", "fl"]
+            .iter()
+            .find_map(|modifier| input.strip_suffix(modifier))?;
+        let unit = rest.trim_end_matches(|c: char| c == ',' || c.is_whitespace());
+        if unit.is_empty() || unit.len() == rest.len() {
+            None
+        } else {
+            Some(unit)
+        }
+    }
+
     /// helper function which creates volume quantities
     fn v<U>(amount: f32) -> Quantity
     where
@@ -231,6 +624,25 @@ mod units {
     }
 
     impl Units {
+        /// Whether the unit measures volumes.
+        fn is_volume(&self) -> bool {
+            matches!(
+                self,
+                Units::CENTILITER
+                    | Units::CUBIC_CENTIMETER
+                    | Units::CUBIC_INCH
+                    | Units::CUP
+                    | Units::FLUID_OUNCE
+                    | Units::GALLON
+                    | Units::LITER
+                    | Units::MILLILITER
+                    | Units::PINT
+                    | Units::QUART
+                    | Units::TABLESPOON
+                    | Units::TEASPOON
+            )
+        }
+
         /// helper function which creates quantities
         fn si_quantity(&self, amount: f32) -> Option<Quantity> {
             match self {
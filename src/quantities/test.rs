@@ -8,6 +8,441 @@ fn number() {
     assert_eq!(parse::number("1 1/2."), Ok((".", 1.5)));
 }
 
+#[test]
+fn word_number() {
+    assert_eq!(parse::number("two tablespoons"), Ok((" tablespoons", 2.0)));
+    assert_eq!(parse::number("Seventeen"), Ok(("", 17.0)));
+    assert_eq!(parse::number("dozen eggs"), Ok((" eggs", 12.0)));
+    assert!(parse::word_number("tender").is_err());
+    assert!(parse::word_number("twentyone").is_err());
+}
+
+#[test]
+fn fraction_exact() {
+    assert_eq!(parse::fraction_exact("1/3 cup"), Ok(("cup", (1, 3))));
+    assert_eq!(parse::fraction_exact("2 / 4"), Ok(("", (2, 4))));
+    assert!(parse::fraction_exact("1/0").is_err());
+    assert!(parse::fraction_exact("0.5").is_err());
+}
+
+#[test]
+fn number_exact() {
+    use num_rational::Ratio;
+    assert_eq!(
+        parse::number_exact("1/3 cup"),
+        Ok(("cup", Ratio::new(1, 3)))
+    );
+    assert_eq!(
+        parse::number_exact("1 2/6 cup"),
+        Ok(("cup", Ratio::new(4, 3)))
+    );
+    assert_eq!(parse::number_exact("3 cups"), Ok((" cups", Ratio::from(3))));
+}
+
+#[test]
+fn nominal_exact() {
+    use num_rational::Ratio;
+    let (rest, nominal) = parse::nominal_exact("1/3 Heaping Scoop, sifted").unwrap();
+    assert_eq!(rest, ", sifted");
+    assert_eq!(nominal.amount, Ratio::new(1, 3));
+    assert_eq!(nominal.name, "heaping scoop");
+
+    // tripling a third is exactly one, unlike in floats
+    let tripled = nominal.scale(Ratio::from(3)).unwrap();
+    assert_eq!(tripled.amount, Ratio::from(1));
+    assert_eq!(
+        tripled.to_quantity(),
+        Quantity::Nominal(1.0, "heaping scoop".into())
+    );
+
+    // an amount too large to be exact has none, rather than wrapping around
+    assert!(nominal.scale(Ratio::new(u32::MAX, 1)).is_some());
+    let many = tripled.scale(Ratio::from(u32::MAX)).unwrap();
+    assert!(many.scale(Ratio::from(2)).is_none());
+    assert!(nominal.scale(Ratio::new(1, u32::MAX)).is_none());
+}
+
+#[test]
+fn from_unit() {
+    use uom::si::{f32::Mass, f32::Volume, mass::gram, volume::fluid_ounce};
+    assert_eq!(
+        Quantity::from_unit(30.0, " Grams "),
+        Some(Quantity::Mass(Mass::new::<gram>(30.0)))
+    );
+    assert_eq!(
+        Quantity::from_unit(30.0, "GRM"),
+        Quantity::from_unit(30.0, "g")
+    );
+    assert_eq!(
+        Quantity::from_unit(8.0, "fl oz"),
+        Some(Quantity::Volume(Volume::new::<fluid_ounce>(8.0)))
+    );
+    assert_eq!(Quantity::from_unit(2.0, "slices"), None);
+}
+
+#[test]
+fn serde_round_trip() {
+    let (_, quants) = parse::quantities("1/3 cup (79 ml) | 2.5 oz | 3 large eggs").unwrap();
+    let json = serde_json::to_string(&quants).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Vec<Quantity>>(&json).unwrap(),
+        quants
+    );
+    assert_eq!(
+        serde_json::to_value(Quantity::Nominal(3.0, "large eggs".into())).unwrap(),
+        serde_json::json!({ "kind": "nominal", "amount": 3.0, "name": "large eggs" })
+    );
+}
+
+#[test]
+fn format_quantities() {
+    use uom::si::{f32::Mass, mass::gram};
+    for label in [
+        "1 cup (240 ml)",
+        "1/3 cup (79 ml) (2.5 oz) (3 large eggs)",
+        "2 1/2 cups",
+        "1 tbsp (15 ml)",
+        "1/2 tsp",
+        "30 g",
+        "250 mg",
+        "1 slice",
+    ] {
+        let (_, quants) = parse::quantities(label).unwrap();
+        assert_eq!(super::format_quantities(&quants), label);
+    }
+    let (_, quants) = parse::quantities("1.5 fl oz").unwrap();
+    assert_eq!(super::format_quantities(&quants), "3 tbsp");
+    let grams = |g| Quantity::Mass(Mass::new::<gram>(g));
+    assert_eq!(grams(12.345).to_string(), "12.35 g");
+    assert_eq!(
+        Quantity::Nominal(0.7, "handful".into()).to_string(),
+        "0.7 handful"
+    );
+    assert_eq!(super::format_quantities(&[]), "");
+}
+
+#[test]
+fn display_in() {
+    use uom::si::{
+        f32::{Mass, Volume},
+        mass::{gram, ounce},
+        volume::{fluid_ounce, milliliter},
+    };
+
+    let grams = |g| Quantity::Mass(Mass::new::<gram>(g));
+    let ml = |ml| Quantity::Volume(Volume::new::<milliliter>(ml));
+    let cases = [
+        (grams(28.349524), "28.35 g", "1 oz"),
+        (grams(0.25), "250 mg", "0.01 oz"),
+        (grams(1500.0), "1.5 kg", "3.31 lb"),
+        (grams(0.0), "0 g", "0 oz"),
+        (
+            Quantity::Mass(Mass::new::<ounce>(12.0)),
+            "340.19 g",
+            "12 oz",
+        ),
+        (Quantity::Mass(Mass::new::<ounce>(16.0)), "453.59 g", "1 lb"),
+        (ml(240.0), "240 ml", "1.01 cups"),
+        (ml(2000.0), "2 l", "8.45 cups"),
+        (ml(5000.0), "5 l", "1.32 gal"),
+        (
+            Quantity::Volume(Volume::new::<fluid_ounce>(8.0)),
+            "236.59 ml",
+            "1 cup",
+        ),
+        (
+            Quantity::Volume(Volume::new::<fluid_ounce>(0.5)),
+            "14.79 ml",
+            "1 tbsp",
+        ),
+        (
+            Quantity::from_unit(0.5, "tsp").unwrap(),
+            "2.46 ml",
+            "1/2 tsp",
+        ),
+        (
+            Quantity::from_unit(1.0 / 3.0, "cup").unwrap(),
+            "78.86 ml",
+            "1/3 cup",
+        ),
+        (
+            Quantity::from_unit(1.5, "cups").unwrap(),
+            "354.88 ml",
+            "1 1/2 cups",
+        ),
+        (
+            Quantity::Nominal(2.0, "slices".into()),
+            "2 slices",
+            "2 slices",
+        ),
+    ];
+    for (quantity, metric, us) in cases.iter() {
+        assert_eq!(
+            quantity.display_in(UnitSystem::Metric),
+            *metric,
+            "{:?}",
+            quantity
+        );
+        assert_eq!(quantity.display_in(UnitSystem::Us), *us, "{:?}", quantity);
+    }
+}
+
+#[test]
+fn is_reasonable_serving() {
+    let reasonable = |input| parse::quantity(input).unwrap().1.is_reasonable_serving();
+    for input in [
+        "1 cup",
+        "30 g",
+        "2 lb",
+        "12 fl oz",
+        "2 l",
+        "2.5 kg",
+        "1/2 slice",
+        "24 chips",
+    ] {
+        assert!(reasonable(input), "{}", input);
+    }
+    for input in [
+        "5000 gallons",
+        "4 kg",
+        "3.5 l",
+        "0 g",
+        "500 pieces",
+        "10000000 mg",
+    ] {
+        assert!(!reasonable(input), "{}", input);
+    }
+    assert!(!Quantity::Nominal(f32::NAN, "slices".into()).is_reasonable_serving());
+    assert!(!Quantity::Nominal(-1.0, "slices".into()).is_reasonable_serving());
+}
+
+#[test]
+fn parse_options() {
+    use uom::si::{f32::Volume, volume::cup};
+    let cups = |amount| Quantity::Volume(Volume::new::<cup>(amount));
+
+    let plain = ParseOptions::new();
+    assert_eq!(
+        plain.parse("1 heaping cup").unwrap(),
+        Quantity::Nominal(1.0, "heaping cup".into())
+    );
+    assert_eq!(plain.parse("1 cup (240 ml)").unwrap(), cups(1.0));
+    assert!(plain.parse("a handful").is_err());
+
+    let options = plain
+        .with_alias("Heaping Cups", "cup")
+        .with_alias("knob", "pat")
+        .with_alias("heaping cup", "cups");
+    assert_eq!(options.unit_of("heaping cup"), Some("cups"));
+    assert_eq!(options.parse("2 heaping cups").unwrap(), cups(2.0));
+    assert_eq!(
+        options.parse("1 knob").unwrap(),
+        Quantity::Nominal(1.0, "pat".into())
+    );
+    assert_eq!(
+        options.parse("3 slices").unwrap(),
+        Quantity::Nominal(3.0, "slices".into())
+    );
+
+    let options: ParseOptions = toml::from_str("[aliases]\n\"heaping cup\" = \"cup\"\n").unwrap();
+    assert_eq!(options.parse("1 heaping cup").unwrap(), cups(1.0));
+}
+
+#[test]
+fn combined_mass() {
+    use uom::si::{
+        f32::Mass,
+        mass::{gram, ounce},
+    };
+    let grams = |text| match parse::combined_mass(text) {
+        Ok((rest, Quantity::Mass(mass))) => (rest, mass.get::<gram>()),
+        other => panic!("Should have been a mass: {:?}", other),
+    };
+    let (rest, pound_ounces) = grams("1 lb 4 oz");
+    assert_eq!(rest, "");
+    assert!((pound_ounces - 566.99).abs() < 0.01);
+    let (rest, kilos_grams) = grams("2 kg 300 g");
+    assert_eq!(rest, "");
+    assert!((kilos_grams - 2300.0).abs() < 0.01);
+    assert_eq!(grams("12 oz").1, Mass::new::<ounce>(12.0).get::<gram>());
+
+    // the run stops at the first quantity which is not a mass
+    let (rest, one_pound) = grams("1 lb 2 cups");
+    assert_eq!(rest, " 2 cups");
+    assert!((one_pound - 453.59).abs() < 0.01);
+    assert!(parse::combined_mass("2 cups 1 lb").is_err());
+
+    // the general parser keeps them apart
+    assert_eq!(parse::quantities("1 lb 4 oz").unwrap().1.len(), 2);
+}
+
+#[test]
+fn reversed_units() {
+    use uom::si::{f32::Mass, f32::Volume, mass::gram, volume::milliliter};
+    let grams = Quantity::Mass(Mass::new::<gram>(100.0));
+    assert_eq!(parse::reversed_quantity("g 100"), Ok(("", grams.clone())));
+    assert_eq!(
+        parse::reversed_quantity("ml 250 more").unwrap(),
+        (" more", Quantity::Volume(Volume::new::<milliliter>(250.0)))
+    );
+    assert_eq!(
+        parse::reversed_quantity("fl oz 8").unwrap().1,
+        parse::quantity("8 fl oz").unwrap().1
+    );
+    assert!(parse::reversed_quantity("slices 2").is_err());
+    assert!(parse::reversed_quantity("100 g").is_err());
+
+    // only when asked for, and only for the whole text
+    assert!(ParseOptions::new().parse("g 100").is_err());
+    let options = ParseOptions::new().with_reversed_units();
+    assert_eq!(options.parse(" g 100 ").unwrap(), grams);
+    assert_eq!(options.parse("100 g").unwrap(), grams);
+    assert!(options.parse("g 100 of sugar").is_err());
+    assert!(options.parse("slices 2").is_err());
+}
+
+#[test]
+fn toleranced() {
+    use uom::si::{f32::Mass, mass::gram};
+    let grams = |g| Quantity::Mass(Mass::new::<gram>(g));
+    let (rest, t) = parse::toleranced("500 g ± 10 g net").unwrap();
+    assert_eq!(rest, " net");
+    assert_eq!(t.quantity, grams(500.0));
+    assert_eq!(t.tolerance, Some(grams(10.0)));
+
+    // without a unit, the tolerance is in the unit of the quantity
+    let (_, t) = parse::toleranced("2 cups +/- 1/4").unwrap();
+    assert_eq!(t.tolerance, Some(parse::quantity("0.25 cups").unwrap().1));
+    let (_, t) = parse::toleranced("500g+-10g").unwrap();
+    assert_eq!(t.tolerance, Some(grams(10.0)));
+
+    let (rest, t) = parse::toleranced("500 g (1.1 lb)").unwrap();
+    assert_eq!(rest, " (1.1 lb)");
+    assert_eq!(t.tolerance, None);
+
+    // a tolerance of another kind than the quantity is not read
+    let (rest, t) = parse::toleranced("500 g ± 10 ml").unwrap();
+    assert_eq!(rest, " ± 10 ml");
+    assert_eq!(t.tolerance, None);
+    let (_, t) = parse::toleranced("2 bars ± 1 g").unwrap();
+    assert_eq!(t.tolerance, None);
+
+    // nor is one without a unit of a quantity of nothing, which it cannot be a share of
+    let (rest, t) = parse::toleranced("0 g ± 5").unwrap();
+    assert_eq!(rest, " ± 5");
+    assert_eq!(t.tolerance, None);
+    let (_, t) = parse::toleranced("0 g ± 5 g").unwrap();
+    assert_eq!(t.tolerance, Some(grams(5.0)));
+}
+
+#[test]
+fn quantities_with_tolerance() {
+    use uom::si::{f32::Mass, mass::gram};
+    let (_, quants) = parse::quantities("500 g ± 10 g").unwrap();
+    assert_eq!(quants, vec![Quantity::Mass(Mass::new::<gram>(500.0))]);
+    let (_, quants) = parse::quantities("1 bag (500 g +/- 10 g)").unwrap();
+    assert_eq!(quants.len(), 2);
+}
+
+#[test]
+fn quantities_each() {
+    use uom::si::{f32::Mass, mass::gram};
+    let grams = |g| Quantity::Mass(Mass::new::<gram>(g));
+    let (_, quants) = parse::quantities("2 bars (40g each)").unwrap();
+    assert_eq!(
+        quants,
+        vec![grams(80.0), Quantity::Nominal(2.0, "bars".into())]
+    );
+    let (_, quants) = parse::quantities("3 cookies (10 g ea.)").unwrap();
+    assert_eq!(quants[0], grams(30.0));
+    let (_, quants) = parse::quantities("2 bars 40 g EACH").unwrap();
+    assert_eq!(quants[0], grams(80.0));
+
+    // without "each", the weight in parentheses is of all the items
+    let (_, quants) = parse::quantities("2 bars (40g)").unwrap();
+    assert_eq!(quants[0], grams(40.0));
+    // and without a count, there is nothing to scale by
+    let (_, quants) = parse::quantities("1 cup (240 ml each)").unwrap();
+    assert_eq!(quants[0], parse::quantity("240 ml").unwrap().1);
+}
+
+#[test]
+fn itemized() {
+    use uom::si::{f32::Mass, mass::gram};
+    let grams = |g| Quantity::Mass(Mass::new::<gram>(g));
+    let (_, itemized) = parse::itemized("2 bars (40g each)").unwrap();
+    assert_eq!(itemized.each, Some(grams(40.0)));
+    assert_eq!(
+        itemized.quantities,
+        parse::quantities("2 bars (40g each)").unwrap().1
+    );
+
+    // a weight of all the items, or without a count, is of no one item
+    assert_eq!(parse::itemized("2 bars (40g)").unwrap().1.each, None);
+    assert_eq!(parse::itemized("1 cup (240 ml each)").unwrap().1.each, None);
+}
+
+#[test]
+fn quantities_multi() {
+    let blob = "1 cup (240 ml)\r\n2 large eggs; 30 g\n\nsome amount of stuff;3 slices";
+    let segments = parse::quantities_multi(blob);
+    assert_eq!(segments.len(), 5);
+    assert_eq!(segments[0], parse::quantities("1 cup (240 ml)").unwrap().1);
+    assert_eq!(
+        segments[1],
+        vec![Quantity::Nominal(2.0, "large eggs".into())]
+    );
+    assert_eq!(segments[2].len(), 1);
+    assert!(segments[3].is_empty());
+    assert_eq!(segments[4], vec![Quantity::Nominal(3.0, "slices".into())]);
+    assert!(parse::quantities_multi(" ;\n").is_empty());
+}
+
+#[test]
+fn quantities_lossy() {
+    use uom::si::{
+        f32::Mass, f32::Volume, mass::gram, mass::milligram, volume::cup, volume::milliliter,
+    };
+    let ocr = "Serving Size: l cup [240 mL}* | Servings ~about 4 **\n\
+               Calories 120 %DV\n\
+               Total Fat 8g 10% Sodium 160mg x 2 , 3 large eggs";
+    let quants = parse::quantities_lossy(ocr);
+    assert_eq!(
+        quants,
+        vec![
+            Quantity::Volume(Volume::new::<milliliter>(240.0)),
+            Quantity::Mass(Mass::new::<gram>(8.0)),
+            Quantity::Mass(Mass::new::<milligram>(160.0)),
+            Quantity::Nominal(3.0, "large eggs".into()),
+        ]
+    );
+    assert_eq!(
+        parse::quantities_lossy("1 cup (240 ml)"),
+        vec![
+            Quantity::Volume(Volume::new::<cup>(1.0)),
+            Quantity::Volume(Volume::new::<milliliter>(240.0)),
+        ]
+    );
+    assert!(parse::quantities_lossy("").is_empty());
+    assert!(parse::quantities_lossy("%%% ** 0 g").is_empty());
+}
+
+#[test]
+fn unicode_spaces() {
+    use uom::si::{f32::Mass, mass::gram};
+    let five_grams = Quantity::Mass(Mass::new::<gram>(5.0));
+    for space in ['\u{a0}', '\u{2009}', '\u{202f}', '\u{2007}'] {
+        let input = format!("5{}g", space);
+        assert_eq!(parse::quantity(&input), Ok(("", five_grams.clone())));
+        let input = format!("1{}1/2{}cups{}(355{}ml)", space, space, space, space);
+        assert_eq!(parse::quantities(&input).unwrap().1.len(), 2);
+    }
+    assert_eq!(
+        parse::quantity("2\u{a0}large\u{a0}eggs"),
+        Ok(("", Quantity::Nominal(2.0, "large eggs".into())))
+    );
+}
+
 #[test]
 fn noise_nonexistent() {
     assert_eq!(parse::noise("hello"), Ok(("hello", ())));
@@ -18,11 +453,22 @@ fn noise_existent() {
     assert_eq!(parse::noise(" | ABOUT  "), Ok(("", ())));
 }
 
+#[test]
+fn noise_approximately() {
+    use uom::si::{f32::Mass, mass::gram};
+    assert_eq!(parse::noise("~2 cups"), Ok(("2 cups", ())));
+    assert_eq!(parse::noise("≈ 50 g"), Ok(("50 g", ())));
+    let (_, quants) = parse::quantities("~2 cups").unwrap();
+    assert_eq!(quants, parse::quantities("2 cups").unwrap().1);
+    let (_, quants) = parse::quantities("≈ 50 g").unwrap();
+    assert_eq!(quants, vec![Quantity::Mass(Mass::new::<gram>(50.0))]);
+}
+
 mod quantity {
     use super::*;
     use uom::si::{
         f32::Volume,
-        volume::{cubic_inch, fluid_ounce, gallon},
+        volume::{cubic_centimeter, cubic_inch, cup, fluid_ounce, gallon, milliliter},
     };
 
     #[test]
@@ -39,6 +485,23 @@ mod quantity {
         );
     }
 
+    #[test]
+    fn ambiguous_prefixes() {
+        use uom::si::{f32::Mass, mass::gram};
+        let grams = Quantity::Mass(Mass::new::<gram>(1.0));
+        assert_eq!(parse::quantity("1g"), Ok(("", grams.clone())));
+        assert_eq!(parse::quantity("1gram"), Ok(("", grams)));
+        assert_eq!(
+            parse::quantity("1gal"),
+            Ok(("", Quantity::Volume(Volume::new::<gallon>(1.0))))
+        );
+        // not a gram followed by "a", nor the start of "gallon"
+        assert_eq!(
+            parse::quantity("1ga"),
+            Ok(("", Quantity::Nominal(1.0, "ga".into())))
+        );
+    }
+
     #[test]
     fn one_word_si_space() {
         assert_eq!(
@@ -86,6 +549,99 @@ mod quantity {
         );
     }
 
+    #[test]
+    fn fluid_modifier() {
+        let fl_oz = Quantity::Volume(Volume::new::<fluid_ounce>(8.0));
+        for input in [
+            "8 fl .oz",
+            "8 FLUID OZ",
+            "8 floz",
+            "8 fl. ounces",
+            "8 fluid. oz.",
+        ] {
+            assert_eq!(parse::quantity(input), Ok(("", fl_oz.clone())), "{}", input);
+        }
+        assert_eq!(
+            parse::quantity("1 fl cup"),
+            Ok(("", Quantity::Volume(Volume::new::<cup>(1.0))))
+        );
+        // masses take no modifier, and words merely starting like one are not modified
+        assert_eq!(
+            parse::quantity("1 fl gram"),
+            Ok(("", Quantity::Nominal(1.0, "fl gram".into())))
+        );
+        assert_eq!(
+            parse::quantity("2 flour tortillas"),
+            Ok(("", Quantity::Nominal(2.0, "flour tortillas".into())))
+        );
+    }
+
+    #[test]
+    fn reversed_fluid_modifier() {
+        use uom::si::{f32::Mass, mass::ounce};
+
+        let fl_oz = Quantity::Volume(Volume::new::<fluid_ounce>(3.0));
+        for input in [
+            "3 oz fl",
+            "3 OZ FL.",
+            "3 oz, fluid",
+            "3 ounces fluid",
+            "3 oz. fl",
+        ] {
+            assert_eq!(parse::quantity(input), Ok(("", fl_oz.clone())), "{}", input);
+        }
+        for unit in ["oz fl", "oz, fluid", "ounce fluid"] {
+            assert_eq!(
+                Quantity::from_unit(3.0, unit),
+                Some(fl_oz.clone()),
+                "{}",
+                unit
+            );
+        }
+        // a bare ounce is still a mass, and only ounces take the modifier
+        let oz = Quantity::Mass(Mass::new::<ounce>(3.0));
+        assert_eq!(parse::quantity("3 oz"), Ok(("", oz.clone())));
+        assert_eq!(parse::quantity("3 oz flour"), Ok((" flour", oz.clone())));
+        assert_eq!(parse::quantity("3 oz, 85 g"), Ok((", 85 g", oz)));
+        assert_eq!(Quantity::from_unit(3.0, "g fl"), None);
+        assert_eq!(Quantity::from_unit(3.0, "ozfl"), None);
+        assert_ne!(
+            parse::quantities("3 oz fl").unwrap().1,
+            parse::quantities("3 oz").unwrap().1
+        );
+    }
+
+    #[test]
+    fn cubic_centimeter_shorthand() {
+        assert_eq!(
+            parse::quantity("5 cc of saline"),
+            Ok((
+                " of saline",
+                Quantity::Volume(Volume::new::<cubic_centimeter>(5.0))
+            ))
+        );
+        assert_eq!(
+            parse::quantity("10 CCs"),
+            Ok(("", Quantity::Volume(Volume::new::<cubic_centimeter>(10.0))))
+        );
+        assert_eq!(
+            parse::quantity("250 mL"),
+            Ok(("", Quantity::Volume(Volume::new::<milliliter>(250.0))))
+        );
+    }
+
+    #[test]
+    fn spelled_out_number() {
+        assert_eq!(
+            parse::quantity("one cup of milk"),
+            Ok((" of milk", Quantity::Volume(Volume::new::<cup>(1.0))))
+        );
+        assert_eq!(
+            parse::quantity("dozen eggs"),
+            Ok(("", Quantity::Nominal(12.0, "eggs".to_string())))
+        );
+    }
+
     #[test]
     fn one_word_nominal() {
         assert_eq!(
@@ -115,4 +671,185 @@ mod quantity {
             Ok(("", Quantity::Nominal(4.12, "k-cups".to_string()))),
         );
     }
+
+    #[test]
+    fn spelled() {
+        let spelled = |input| {
+            let (rest, parsed) = parse::spelled_quantity(input).unwrap();
+            (rest, parsed.raw_unit, parsed.quantity)
+        };
+        let tbsp = Quantity::from_unit(2.0, "tbsp").unwrap();
+        assert_eq!(spelled("2 Tbsp"), ("", "Tbsp".into(), tbsp.clone()));
+        assert_eq!(
+            spelled("2 tablespoons of oil"),
+            (" of oil", "tablespoons".into(), tbsp)
+        );
+        assert_eq!(
+            spelled("5.26 fl.  Oz. (35g)"),
+            (
+                " (35g)",
+                "fl.  Oz.".into(),
+                Quantity::from_unit(5.26, "fl oz").unwrap()
+            )
+        );
+        assert_eq!(
+            spelled("1 Large Bag"),
+            (
+                "",
+                "Large Bag".into(),
+                Quantity::Nominal(1.0, "large bag".into())
+            )
+        );
+        assert!(parse::spelled_quantity("tbsp").is_err());
+    }
+}
+
+mod sum {
+    use super::*;
+    use uom::si::{
+        f32::{Mass, Volume},
+        mass::{gram, ounce},
+        volume::{cup, milliliter},
+    };
+
+    #[test]
+    fn same_dimension() {
+        let masses = vec![
+            Quantity::Mass(Mass::new::<gram>(100.0)),
+            Quantity::Mass(Mass::new::<ounce>(1.0)),
+        ];
+        match masses.into_iter().sum::<crate::Result<Quantity>>().unwrap() {
+            Quantity::Mass(m) => assert!((m.get::<gram>() - 128.349_5).abs() < 1e-3),
+            other => panic!("Unexpected {:?}", other),
+        }
+
+        let volumes = [
+            Quantity::Volume(Volume::new::<cup>(1.0)),
+            Quantity::Volume(Volume::new::<milliliter>(10.0)),
+        ];
+        let total: crate::Result<Quantity> = volumes.iter().sum();
+        match total.unwrap() {
+            Quantity::Volume(v) => assert!((v.get::<milliliter>() - 246.588).abs() < 1e-2),
+            other => panic!("Unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nominals() {
+        let eggs = vec![
+            Quantity::Nominal(2.0, "eggs".into()),
+            Quantity::Nominal(1.0, "Egg".into()),
+        ];
+        let total: crate::Result<Quantity> = eggs.into_iter().sum();
+        assert_eq!(total.unwrap(), Quantity::Nominal(3.0, "eggs".into()));
+
+        let mixed = vec![
+            Quantity::Nominal(2.0, "eggs".into()),
+            Quantity::Nominal(1.0, "slice".into()),
+        ];
+        assert!(mixed.into_iter().sum::<crate::Result<Quantity>>().is_err());
+    }
+
+    #[test]
+    fn mixed_or_empty() {
+        let mixed = vec![
+            Quantity::Mass(Mass::new::<gram>(100.0)),
+            Quantity::Volume(Volume::new::<cup>(1.0)),
+        ];
+        let err = mixed
+            .into_iter()
+            .sum::<crate::Result<Quantity>>()
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Cannot add"));
+        assert!(Vec::<Quantity>::new()
+            .into_iter()
+            .sum::<crate::Result<Quantity>>()
+            .is_err());
+    }
+}
+
+#[test]
+fn custom_units() {
+    let units = CustomUnits::new()
+        .with_unit("scoop", 30.0)
+        .with_unit("Packets", 8.0)
+        .with_unit("scoops", 32.0);
+    assert_eq!(units.grams_per("SCOOP"), Some(32.0));
+    assert_eq!(units.grams_per("packet"), Some(8.0));
+    assert_eq!(units.grams_per("cup"), None);
+
+    let grams = |q: Quantity| q.to_mass(&units).map(|m| m.get::<gram>());
+    assert_eq!(grams(Quantity::Nominal(1.5, "scoops".into())), Some(48.0));
+    assert_eq!(grams(Quantity::Mass(Mass::new::<gram>(5.0))), Some(5.0));
+    assert_eq!(grams(Quantity::Nominal(1.0, "slice".into())), None);
+    assert_eq!(grams(Quantity::Volume(Volume::new::<cup>(1.0))), None);
+
+    // unknown nominals are left for the food to resolve
+    let slice = Quantity::Nominal(1.0, "slice".into());
+    assert_eq!(units.resolve(&slice), slice);
+    assert_eq!(
+        units.resolve(&Quantity::Nominal(2.0, "packet".into())),
+        Quantity::Mass(Mass::new::<gram>(16.0))
+    );
+
+    // a table of names to grams, as in a config file
+    let units: CustomUnits = serde_json::from_str(r#"{ "scoop": 30.0 }"#).unwrap();
+    assert_eq!(units.grams_per("scoops"), Some(30.0));
+}
+
+/// The serving texts of `tests/fixtures/servings.txt`, without its comments. Until the fixture is
+/// regenerated from a bulk bundle, they are synthetic rather than sampled from FDC.
+fn serving_corpus() -> Vec<&'static str> {
+    include_str!("../../tests/fixtures/servings.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/// Why the parser could not read a serving text.
+fn failure_category(text: &str) -> &'static str {
+    if text.chars().all(|c| c.is_ascii_digit()) {
+        "portion code"
+    } else if parse::number(text.trim_start()).is_err() {
+        "no leading amount"
+    } else if parse::toleranced(text).is_ok() {
+        "text after a quantity"
+    } else {
+        "other"
+    }
+}
+
+#[test]
+fn serving_corpus_success_rate() {
+    // the share of the corpus the parser must read, to be raised as the parser improves. The corpus
+    // is synthetic for now, so this guards against regressions rather than measuring real servings.
+    // The parser reads 426 of its 469 texts, about 0.908
+    const MIN_SUCCESS: f32 = 0.9;
+
+    let corpus = serving_corpus();
+    assert!(corpus.len() >= 450, "{} serving texts", corpus.len());
+    let failures = corpus
+        .iter()
+        .filter(|text| parse::quantities(text).is_err())
+        .collect::<Vec<_>>();
+    let success = 1.0 - failures.len() as f32 / corpus.len() as f32;
+    if success < MIN_SUCCESS {
+        let mut report = BTreeMap::<&str, Vec<&str>>::new();
+        for text in failures.iter() {
+            report.entry(failure_category(text)).or_default().push(text);
+        }
+        for (category, texts) in report.iter() {
+            println!("{} ({}):", category, texts.len());
+            for text in texts {
+                println!("    {}", text);
+            }
+        }
+    }
+    assert!(
+        success >= MIN_SUCCESS,
+        "parsed {:.1}% of the synthetic serving corpus, below {:.1}%",
+        100.0 * success,
+        100.0 * MIN_SUCCESS
+    );
 }
@@ -0,0 +1,127 @@
+//! This module re-ranks FDC search results against the user's query through [`rerank`], as the
+//! API ranks flavored and branded products above the plain foods people usually mean, and picks
+//! the result a query most likely means through [`best_match`].
+
+use crate::fdc::{AbridgedFoodItem, DataType};
+use crate::storage::search::tokens;
+
+use std::cmp::Ordering;
+
+/// Words which neither match nor count against a description.
+const STOP_WORDS: &[&str] = &[
+    "a", "added", "and", "in", "of", "or", "the", "with", "without",
+];
+
+/// The data types of the reference foods FDC measures itself.
+const REFERENCE_DATA_TYPES: &[DataType] = &[DataType::Foundation, DataType::SrLegacy];
+
+/// How much each part of the score weighs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RankWeights {
+    /// Added for the share of query tokens found in the description, from 0 to 1.
+    pub overlap: f32,
+    /// Added when the description has the query tokens next to each other and in order.
+    pub phrase: f32,
+    /// Taken off for each description token not in the query, like a flavor.
+    pub extra_token: f32,
+    /// Added for Foundation and SR Legacy foods.
+    pub reference_data: f32,
+}
+
+impl Default for RankWeights {
+    fn default() -> RankWeights {
+        RankWeights {
+            overlap: 1.0,
+            phrase: 0.5,
+            extra_token: 0.15,
+            reference_data: 0.4,
+        }
+    }
+}
+
+/// Re-rank the results with the default [`RankWeights`].
+pub fn rerank(query: &str, results: &mut [AbridgedFoodItem]) {
+    rerank_with(query, results, &RankWeights::default())
+}
+
+/// Score each result against the query, replacing the score FDC gave it, and stably sort the
+/// results by score, highest first.
+pub fn rerank_with(query: &str, results: &mut [AbridgedFoodItem], weights: &RankWeights) {
+    let query = meaningful(query);
+    for result in results.iter_mut() {
+        result.score = Some(score_tokens(&query, result, weights));
+    }
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+}
+
+/// Pick the best match with the default [`RankWeights`].
+pub fn best_match<'a>(
+    query: &str,
+    results: &'a [AbridgedFoodItem],
+    min_score: f32,
+) -> Option<&'a AbridgedFoodItem> {
+    best_match_with(query, results, min_score, &RankWeights::default())
+}
+
+/// The result scoring highest against the query, the first of them on a tie, or `None` when even
+/// it scores below `min_score`, so that a weak match like "peanut butter cups" for "butter" can be
+/// rejected rather than used.
+pub fn best_match_with<'a>(
+    query: &str,
+    results: &'a [AbridgedFoodItem],
+    min_score: f32,
+    weights: &RankWeights,
+) -> Option<&'a AbridgedFoodItem> {
+    let query = meaningful(query);
+    let mut best: Option<(f32, &AbridgedFoodItem)> = None;
+    for result in results {
+        let score = score_tokens(&query, result, weights);
+        if score >= min_score && best.is_none_or(|(top, _)| score > top) {
+            best = Some((score, result));
+        }
+    }
+    best.map(|(_, result)| result)
+}
+
+/// The score of a result against the query, which [`rerank_with`] sorts by and [`best_match_with`]
+/// compares to its minimum. A result with every query token, in order, and no other token scores
+/// `overlap + phrase`, plus `reference_data` for reference foods.
+pub fn score(query: &str, result: &AbridgedFoodItem, weights: &RankWeights) -> f32 {
+    score_tokens(&meaningful(query), result, weights)
+}
+
+/// The score of a result against the tokens of the query.
+fn score_tokens(query: &[String], result: &AbridgedFoodItem, weights: &RankWeights) -> f32 {
+    let description = meaningful(&result.description);
+    let mut score = 0.0;
+    if !query.is_empty() {
+        let found = query.iter().filter(|t| description.contains(t)).count();
+        score += weights.overlap * found as f32 / query.len() as f32;
+        if description
+            .windows(query.len())
+            .any(|window| window == query)
+        {
+            score += weights.phrase;
+        }
+    }
+    let extra = description.iter().filter(|t| !query.contains(t)).count();
+    score -= weights.extra_token * extra as f32;
+    if REFERENCE_DATA_TYPES
+        .iter()
+        .any(|data_type| data_type.as_api_str() == result.data_type)
+    {
+        score += weights.reference_data;
+    }
+    score
+}
+
+/// The tokens of the text, leaving out stop words and numbers.
+fn meaningful(text: &str) -> Vec<String> {
+    tokens(text)
+        .into_iter()
+        .filter(|t| !STOP_WORDS.contains(&&t[..]) && !t.chars().all(|c| c.is_ascii_digit()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test;
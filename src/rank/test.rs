@@ -0,0 +1,187 @@
+use super::*;
+
+/// Search results as FDC returns them, as (data type, description).
+const RESULTS: &[(&str, &str)] = &[
+    ("Branded", "GREEK YOGURT, STRAWBERRY"),
+    ("Branded", "CHOBANI, GREEK YOGURT, PLAIN, WHOLE MILK"),
+    ("SR Legacy", "Yogurt, Greek, plain, nonfat"),
+    ("Branded", "GREEK YOGURT, HONEY VANILLA"),
+    ("Branded", "CHEDDAR CHEESE FLAVORED CRACKERS"),
+    ("SR Legacy", "Cheese, cheddar"),
+    ("Branded", "SHARP CHEDDAR CHEESE"),
+    ("Branded", "WHOLE MILK"),
+    (
+        "SR Legacy",
+        "Milk, whole, 3.25% milkfat, with added vitamin D",
+    ),
+    ("Branded", "CHOCOLATE WHOLE MILK"),
+    ("Foundation", "Eggs, Grade A, Large, egg whole"),
+    ("Branded", "LARGE BROWN EGGS"),
+    ("Branded", "EGG WHITES, LIQUID"),
+    ("SR Legacy", "Bananas, raw"),
+    ("Branded", "BANANA CHIPS"),
+    ("Branded", "BANANA NUT MUFFINS"),
+    ("SR Legacy", "Oats"),
+    ("Branded", "OATMEAL, MAPLE BROWN SUGAR"),
+    ("Branded", "ROLLED OATS"),
+    ("SR Legacy", "Peanut butter, smooth style, without salt"),
+    ("Branded", "PEANUT BUTTER CUPS"),
+    ("Branded", "CREAMY PEANUT BUTTER"),
+    ("Foundation", "Chicken, breast, boneless, skinless, raw"),
+    ("Branded", "BREADED CHICKEN BREAST NUGGETS"),
+    ("SR Legacy", "Rice, white, long-grain, regular, cooked"),
+    ("Branded", "RICE PUDDING"),
+    ("Branded", "WHITE RICE"),
+    ("Foundation", "Apples, fuji, with skin, raw"),
+    ("Branded", "APPLE JUICE"),
+    ("Branded", "APPLE PIE"),
+    ("SR Legacy", "Spinach, raw"),
+    ("Branded", "SPINACH & ARTICHOKE DIP"),
+    ("SR Legacy", "Salmon, Atlantic, farmed, raw"),
+    ("Branded", "SMOKED SALMON"),
+    ("SR Legacy", "Broccoli, raw"),
+    ("Branded", "BROCCOLI CHEDDAR SOUP"),
+];
+
+fn results() -> Vec<AbridgedFoodItem> {
+    RESULTS
+        .iter()
+        .enumerate()
+        .map(|(i, (data_type, description))| AbridgedFoodItem {
+            fdc_id: i as i32,
+            data_type: data_type.to_string(),
+            description: description.to_string(),
+            food_nutrients: Vec::new(),
+            score: Some(100.0 - i as f32),
+            publication_date: None,
+        })
+        .collect()
+}
+
+/// The description ranked first for the query, among the results sharing a token with it.
+fn top(query: &str) -> String {
+    let query_tokens = tokens(query);
+    let mut results = results()
+        .into_iter()
+        .filter(|r| {
+            tokens(&r.description)
+                .iter()
+                .any(|t| query_tokens.contains(t))
+        })
+        .collect::<Vec<_>>();
+    rerank(query, &mut results);
+    results[0].description.clone()
+}
+
+#[test]
+fn golden() {
+    let expected = [
+        ("greek yogurt plain", "Yogurt, Greek, plain, nonfat"),
+        ("cheddar cheese", "Cheese, cheddar"),
+        ("whole milk", "WHOLE MILK"),
+        ("egg", "Eggs, Grade A, Large, egg whole"),
+        ("large eggs", "Eggs, Grade A, Large, egg whole"),
+        ("brown eggs", "LARGE BROWN EGGS"),
+        ("banana", "Bananas, raw"),
+        ("oats", "Oats"),
+        ("peanut butter", "Peanut butter, smooth style, without salt"),
+        ("chicken breast", "Chicken, breast, boneless, skinless, raw"),
+        ("white rice", "WHITE RICE"),
+        ("apple", "Apples, fuji, with skin, raw"),
+        ("spinach", "Spinach, raw"),
+        ("salmon", "Salmon, Atlantic, farmed, raw"),
+        ("smoked salmon", "SMOKED SALMON"),
+        ("broccoli", "Broccoli, raw"),
+    ];
+    for (query, description) in expected.iter() {
+        assert_eq!(&top(query), description, "top result of {:?}", query);
+    }
+}
+
+#[test]
+fn stable_and_scored() {
+    // with every weight at zero, results keep their order
+    let mut unweighted = results();
+    let weights = RankWeights {
+        overlap: 0.0,
+        phrase: 0.0,
+        extra_token: 0.0,
+        reference_data: 0.0,
+    };
+    rerank_with("anything", &mut unweighted, &weights);
+    let ids = unweighted.iter().map(|r| r.fdc_id).collect::<Vec<_>>();
+    assert_eq!(ids, (0..RESULTS.len() as i32).collect::<Vec<_>>());
+    assert!(unweighted.iter().all(|r| r.score == Some(0.0)));
+
+    // favoring reference data brings it first
+    let weights = RankWeights {
+        reference_data: 10.0,
+        ..RankWeights::default()
+    };
+    let mut results = results();
+    rerank_with("greek yogurt", &mut results, &weights);
+    assert_eq!(results[0].data_type, "SR Legacy");
+}
+
+#[test]
+fn best_match_min_score() {
+    let results = results();
+    let description =
+        |query, min_score| best_match(query, &results, min_score).map(|r| r.description.as_str());
+    assert_eq!(description("spinach", 1.4), Some("Spinach, raw"));
+    assert_eq!(
+        description("peanut butter", 1.4),
+        Some("Peanut butter, smooth style, without salt")
+    );
+    // butter only comes as part of other foods, which a low minimum lets through
+    assert_eq!(
+        description("butter", 0.0),
+        Some("Peanut butter, smooth style, without salt")
+    );
+    assert_eq!(description("butter", 1.4), None);
+    assert_eq!(description("spinach", f32::INFINITY), None);
+    assert!(best_match("spinach", &[], f32::NEG_INFINITY).is_none());
+
+    // the exposed score is the one compared
+    let cups = &results[20];
+    assert_eq!(cups.description, "PEANUT BUTTER CUPS");
+    let weights = RankWeights::default();
+    let expected = weights.overlap + weights.phrase - 2.0 * weights.extra_token;
+    assert!((score("butter", cups, &weights) - expected).abs() < 1e-6);
+
+    // on a tie the earlier result wins
+    let unweighted = RankWeights {
+        overlap: 0.0,
+        phrase: 0.0,
+        extra_token: 0.0,
+        reference_data: 0.0,
+    };
+    let first = best_match_with("anything", &results, 0.0, &unweighted).unwrap();
+    assert_eq!(first.fdc_id, 0);
+}
+
+#[test]
+fn diacritics() {
+    let mut results = vec![
+        AbridgedFoodItem {
+            fdc_id: 1,
+            data_type: "Branded".into(),
+            description: "JALAPENO CHIPS".into(),
+            food_nutrients: Vec::new(),
+            score: None,
+            publication_date: None,
+        },
+        AbridgedFoodItem {
+            fdc_id: 2,
+            data_type: "SR Legacy".into(),
+            description: "Peppers, jalapeño, raw".into(),
+            food_nutrients: Vec::new(),
+            score: None,
+            publication_date: None,
+        },
+    ];
+    rerank("Jalapeño", &mut results);
+    assert_eq!(results[0].fdc_id, 2);
+    let best = best_match("jalapeno", &results, 1.4).unwrap();
+    assert_eq!(best.fdc_id, 2);
+}
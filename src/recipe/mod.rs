@@ -0,0 +1,117 @@
+//! This module provides the [`Recipe`] type, which composes foods in given quantities and derives
+//! the nutrition of a single serving.
+//!
+//! Recipes can be kept in TOML or JSON files. Quantities may be written as text, which goes
+//! through the quantities parser, like
+//!
+//! ```toml
+//! name = "Overnight oats"
+//! servings = 2
+//!
+//! [[ingredients]]
+//! fdc_id = 173904
+//! quantity = "80 g"
+//!
+//! [[ingredients]]
+//! fdc_id = 746782
+//! quantity = "1 cup"
+//! note = "any milk works"
+//! ```
+
+use crate::error::Result;
+use crate::nutrition::{self, NutrientProfile, NutritionError};
+use crate::quantities::{parse, ParseQuantityError, Quantity};
+use crate::source::FoodSource;
+
+use serde::{Deserialize, Deserializer};
+
+/// A quantity of a food going into a recipe.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Ingredient {
+    pub fdc_id: i32,
+    #[serde(deserialize_with = "quantity_or_text")]
+    pub quantity: Quantity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Foods combined into a number of servings.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Recipe {
+    pub name: String,
+    pub ingredients: Vec<Ingredient>,
+    pub servings: f32,
+}
+
+impl Recipe {
+    /// Read a recipe written in TOML.
+    pub fn from_toml(input: &str) -> Result<Recipe> {
+        Ok(toml::from_str(input)?)
+    }
+
+    /// Write the recipe as TOML.
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Read a recipe written in JSON.
+    pub fn from_json(input: &str) -> Result<Recipe> {
+        Ok(serde_json::from_str(input)?)
+    }
+
+    /// Write the recipe as JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// The nutrients of the whole recipe, summing each ingredient as resolved by the source.
+    pub async fn total_nutrition<S: FoodSource>(&self, resolver: &S) -> Result<NutrientProfile> {
+        let mut profiles = Vec::with_capacity(self.ingredients.len());
+        for ingredient in &self.ingredients {
+            let profile = resolver
+                .profile(ingredient.fdc_id, &ingredient.quantity)
+                .await
+                .map_err(|source| NutritionError::Recipe {
+                    name: self.name.clone(),
+                    source: Box::new(source),
+                })?;
+            profiles.push(profile);
+        }
+        Ok(nutrition::sum(profiles))
+    }
+
+    /// The nutrients of a single serving of the recipe.
+    pub async fn nutrition<S: FoodSource>(&self, resolver: &S) -> Result<NutrientProfile> {
+        if self.servings.is_nan() || self.servings <= 0.0 {
+            return Err(NutritionError::Invalid(format!(
+                "Recipe {} must make a positive number of servings, not {}",
+                self.name, self.servings
+            ))
+            .into());
+        }
+        Ok(self
+            .total_nutrition(resolver)
+            .await?
+            .scale(1.0 / self.servings))
+    }
+}
+
+/// Deserialize a quantity from either its serialized form or a text like `"1 cup"`.
+fn quantity_or_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Quantity, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum QuantityOrText {
+        Text(String),
+        Quantity(Quantity),
+    }
+    match QuantityOrText::deserialize(deserializer)? {
+        QuantityOrText::Quantity(quantity) => Ok(quantity),
+        QuantityOrText::Text(text) => match parse::quantity(text.trim()) {
+            Ok(("", quantity)) => Ok(quantity),
+            _ => Err(serde::de::Error::custom(ParseQuantityError::Invalid(text))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test;
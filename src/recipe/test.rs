@@ -0,0 +1,153 @@
+use super::*;
+use crate::fdc::FDCMeta;
+use crate::nutrition::Nutrient;
+use std::collections::HashMap;
+use uom::si::{f32::Mass, mass::gram};
+
+/// A branded food whose label only reports calories and protein.
+fn branded(
+    fdc_id: i32,
+    size: f32,
+    unit: &str,
+    household: &str,
+    kcal: f32,
+    protein: f32,
+) -> FDCMeta {
+    let label = [
+        "fat",
+        "saturatedFat",
+        "transFat",
+        "cholesterol",
+        "sodium",
+        "carbohydrates",
+        "fiber",
+        "sugars",
+        "calcium",
+        "iron",
+        "potassium",
+    ]
+    .iter()
+    .map(|name| (name.to_string(), serde_json::json!({ "value": 0.0 })))
+    .chain(vec![
+        (
+            "protein".to_string(),
+            serde_json::json!({ "value": protein }),
+        ),
+        ("calories".to_string(), serde_json::json!({ "value": kcal })),
+    ])
+    .collect::<serde_json::Map<_, _>>();
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Branded",
+        "fdcId": fdc_id,
+        "ingredients": "",
+        "servingSize": size,
+        "servingSizeUnit": unit,
+        "householdServingFullText": household,
+        "labelNutrients": label,
+    }))
+    .unwrap()
+}
+
+/// Rolled oats reporting 379 kcal and 13.2 g protein per 100 g, a cup of milk with 120 kcal and
+/// 8 g protein, and a tablespoon of honey with 60 kcal.
+fn pantry() -> HashMap<i32, FDCMeta> {
+    let oats: FDCMeta = serde_json::from_value(serde_json::json!({
+        "dataType": "Other",
+        "fdcId": 3001,
+        "description": "Oats, rolled",
+        "foodAttributes": [],
+        "foodPortions": [],
+        "foodNutrients": [
+            { "id": 1, "nutrient": { "id": 1008, "name": "Energy", "unitName": "kcal" }, "amount": 379.0 },
+            { "id": 2, "nutrient": { "id": 1003, "name": "Protein", "unitName": "g" }, "amount": 13.2 },
+        ],
+    }))
+    .unwrap();
+    let milk = branded(3002, 240.0, "ml", "1 cup", 120.0, 8.0);
+    let honey = branded(3003, 21.0, "g", "1 Tbsp", 60.0, 0.0);
+    vec![oats, milk, honey]
+        .into_iter()
+        .map(|food| (food.fdc_id(), food))
+        .collect()
+}
+
+const OVERNIGHT_OATS: &str = r#"
+name = "Overnight oats"
+servings = 2
+
+[[ingredients]]
+fdc_id = 3001
+quantity = "80 g"
+
+[[ingredients]]
+fdc_id = 3002
+quantity = "1 cup"
+note = "any milk works"
+
+[[ingredients]]
+fdc_id = 3003
+quantity = "1 tbsp"
+"#;
+
+fn close(a: Option<f32>, b: f32) -> bool {
+    a.is_some_and(|a| (a - b).abs() < 1e-3 * b.abs().max(1.0))
+}
+
+#[test]
+fn from_toml() {
+    let recipe = Recipe::from_toml(OVERNIGHT_OATS).unwrap();
+    assert_eq!(recipe.servings, 2.0);
+    assert_eq!(recipe.ingredients.len(), 3);
+    assert_eq!(
+        recipe.ingredients[0].quantity,
+        Quantity::Mass(Mass::new::<gram>(80.0))
+    );
+    assert_eq!(
+        recipe.ingredients[1].note.as_deref(),
+        Some("any milk works")
+    );
+    assert!(Recipe::from_toml(&OVERNIGHT_OATS.replace("80 g", "a handful")).is_err());
+}
+
+#[test]
+fn round_trip() {
+    let recipe = Recipe::from_toml(OVERNIGHT_OATS).unwrap();
+    assert_eq!(
+        Recipe::from_toml(&recipe.to_toml().unwrap()).unwrap(),
+        recipe
+    );
+    assert_eq!(
+        Recipe::from_json(&recipe.to_json().unwrap()).unwrap(),
+        recipe
+    );
+}
+
+#[tokio::test]
+async fn per_serving() {
+    let recipe = Recipe::from_toml(OVERNIGHT_OATS).unwrap();
+    let pantry = pantry();
+
+    let total = recipe.total_nutrition(&pantry).await.unwrap();
+    assert!(close(total.get(Nutrient::Energy), 483.2));
+    assert!(close(total.get(Nutrient::Protein), 18.56));
+
+    let serving = recipe.nutrition(&pantry).await.unwrap();
+    assert!(close(serving.get(Nutrient::Energy), 241.6));
+    assert!(close(serving.get(Nutrient::Protein), 9.28));
+}
+
+#[tokio::test]
+async fn unresolvable() {
+    let pantry = pantry();
+    let mut recipe = Recipe::from_toml(OVERNIGHT_OATS).unwrap();
+    recipe.servings = 0.0;
+    assert!(recipe.nutrition(&pantry).await.is_err());
+
+    // a missing food and a volume of a food without density both fail
+    recipe.servings = 1.0;
+    recipe.ingredients[0].fdc_id = 4000;
+    assert!(recipe.nutrition(&pantry).await.is_err());
+    recipe.ingredients[0].fdc_id = 3001;
+    recipe.ingredients[0].quantity = recipe.ingredients[1].quantity.clone();
+    assert!(recipe.nutrition(&pantry).await.is_err());
+}
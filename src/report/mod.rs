@@ -0,0 +1,265 @@
+//! This module summarizes the [`FoodLog`] over several days through [`summarize`], like the
+//! average sodium of the last 7 days or the protein of each week.
+//!
+//! A [`Window`] covers whole calendar days of a time zone. Each day is totalled into a
+//! [`NutrientProfile`], and the days are then averaged. Days without entries count as zero days
+//! unless the window excludes them.
+
+use crate::error::Result;
+use crate::log::FoodLog;
+use crate::nutrition::{self, NutrientProfile, NutritionError};
+use crate::source::FoodSource;
+
+use chrono::{Datelike, Duration, NaiveDate, TimeZone};
+use std::collections::BTreeSet;
+
+/// The calendar days of a time zone to summarize, from `start` to `end` inclusive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Window<Tz> {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub tz: Tz,
+    /// Whether days without entries count towards the mean, the minimum, and the maximum.
+    pub include_empty: bool,
+}
+
+impl<Tz: TimeZone> Window<Tz> {
+    /// generate a new Window of the days from `start` to `end` inclusive
+    pub fn range(start: NaiveDate, end: NaiveDate, tz: Tz) -> Window<Tz> {
+        Window {
+            start,
+            end,
+            tz,
+            include_empty: true,
+        }
+    }
+
+    /// generate a new Window of the `days` days ending with `end`
+    pub fn trailing(days: u32, end: NaiveDate, tz: Tz) -> Window<Tz> {
+        let start = end - Duration::days(i64::from(days.max(1)) - 1);
+        Window::range(start, end, tz)
+    }
+
+    /// generate a new Window of `weeks` calendar weeks, from Monday to Sunday, starting with the
+    /// week of `day`
+    pub fn weeks(weeks: u32, day: NaiveDate, tz: Tz) -> Window<Tz> {
+        let start = week_of(day);
+        let end = start + Duration::weeks(i64::from(weeks.max(1))) - Duration::days(1);
+        Window::range(start, end, tz)
+    }
+
+    /// Leave days without entries out of the mean, the minimum, and the maximum.
+    pub fn excluding_empty(self) -> Window<Tz> {
+        Window {
+            include_empty: false,
+            ..self
+        }
+    }
+}
+
+/// The Monday starting the calendar week of the day.
+fn week_of(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(i64::from(day.weekday().num_days_from_monday()))
+}
+
+/// The total of a single day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DaySummary {
+    pub date: NaiveDate,
+    pub profile: NutrientProfile,
+    /// The number of entries logged on the day.
+    pub entries: usize,
+}
+
+/// The totals of every day of a window, along with statistics over the days.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub days: Vec<DaySummary>,
+    /// The mean of each nutrient over the counted days.
+    pub mean: NutrientProfile,
+    /// The smallest daily total of each nutrient over the counted days.
+    pub min: NutrientProfile,
+    /// The largest daily total of each nutrient over the counted days.
+    pub max: NutrientProfile,
+    /// The number of entries logged over every day.
+    pub entries: usize,
+    /// The number of days the statistics are over.
+    pub counted_days: usize,
+    /// Whether days without entries are counted.
+    pub include_empty: bool,
+}
+
+impl Summary {
+    /// Compute the statistics of the days. A nutrient is in the statistics as soon as one counted
+    /// day has it, and counts as zero on the counted days which do not.
+    pub fn from_days(days: Vec<DaySummary>, include_empty: bool) -> Summary {
+        let counted = days
+            .iter()
+            .filter(|day| include_empty || day.entries > 0)
+            .collect::<Vec<_>>();
+        let nutrients = counted
+            .iter()
+            .flat_map(|day| day.profile.iter().map(|(nutrient, _)| nutrient))
+            .collect::<BTreeSet<_>>();
+
+        let mut mean = NutrientProfile::new();
+        let mut min = NutrientProfile::new();
+        let mut max = NutrientProfile::new();
+        for nutrient in nutrients {
+            let amounts = counted
+                .iter()
+                .map(|day| day.profile.get(nutrient).unwrap_or(0.0))
+                .collect::<Vec<_>>();
+            mean.insert(nutrient, amounts.iter().sum::<f32>() / amounts.len() as f32);
+            min.insert(
+                nutrient,
+                amounts.iter().copied().fold(f32::INFINITY, f32::min),
+            );
+            max.insert(
+                nutrient,
+                amounts.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            );
+        }
+
+        Summary {
+            entries: days.iter().map(|day| day.entries).sum(),
+            counted_days: counted.len(),
+            days,
+            mean,
+            min,
+            max,
+            include_empty,
+        }
+    }
+
+    /// Split the summary by calendar week, from Monday to Sunday, in order.
+    pub fn by_week(&self) -> Vec<(NaiveDate, Summary)> {
+        let mut weeks: Vec<(NaiveDate, Vec<DaySummary>)> = Vec::new();
+        for day in &self.days {
+            let week = week_of(day.date);
+            match weeks.last_mut() {
+                Some((start, days)) if *start == week => days.push(day.clone()),
+                _ => weeks.push((week, vec![day.clone()])),
+            }
+        }
+        weeks
+            .into_iter()
+            .map(|(start, days)| (start, Summary::from_days(days, self.include_empty)))
+            .collect()
+    }
+}
+
+/// Total every day of the window from the entries of the log, looking their foods up in the
+/// source, and compute the statistics over the days.
+pub async fn summarize<L, S, Tz>(log: &L, foods: &S, window: Window<Tz>) -> Result<Summary>
+where
+    L: FoodLog + ?Sized,
+    S: FoodSource + ?Sized,
+    Tz: TimeZone + Send + Sync,
+    Tz::Offset: Send + Sync,
+{
+    check(&window)?;
+
+    let mut days = Vec::new();
+    let mut date = window.start;
+    while date <= window.end {
+        let entries = log.entries_for_day(date, &window.tz).await?;
+        let mut profiles = Vec::with_capacity(entries.len());
+        for entry in &entries {
+            profiles.push(foods.profile(entry.fdc_id, &entry.quantity).await?);
+        }
+        days.push(DaySummary {
+            date,
+            profile: nutrition::sum(profiles),
+            entries: entries.len(),
+        });
+        date += Duration::days(1);
+    }
+    Ok(Summary::from_days(days, window.include_empty))
+}
+
+/// Summarize like [`summarize`], totalling the days across threads. The foods are looked up at
+/// once, and each day is totalled in the order of its entries, so every day matches
+/// [`summarize`] exactly. Foods the source can profile but not get, like composite foods, are
+/// profiled by the source before the days are totalled.
+#[cfg(feature = "parallel")]
+pub async fn par_summarize<L, S, Tz>(log: &L, foods: &S, window: Window<Tz>) -> Result<Summary>
+where
+    L: FoodLog + ?Sized,
+    S: FoodSource + ?Sized,
+    Tz: TimeZone + Send + Sync,
+    Tz::Offset: Send + Sync,
+{
+    use crate::fdc::FDCMeta;
+    use rayon::prelude::*;
+    use std::collections::HashMap;
+
+    check(&window)?;
+
+    let mut logged = Vec::new();
+    let mut date = window.start;
+    while date <= window.end {
+        logged.push((date, log.entries_for_day(date, &window.tz).await?));
+        date += Duration::days(1);
+    }
+
+    let fdc_ids = logged
+        .iter()
+        .flat_map(|(_, entries)| entries.iter().map(|entry| entry.fdc_id))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let found = fdc_ids
+        .iter()
+        .zip(foods.get_many(&fdc_ids).await?)
+        .filter_map(|(fdc_id, food)| Some((*fdc_id, food?)))
+        .collect::<HashMap<i32, FDCMeta>>();
+    let mut profiled = Vec::with_capacity(logged.len());
+    for (_, entries) in &logged {
+        let mut profiles = Vec::new();
+        for entry in entries {
+            profiles.push(if found.contains_key(&entry.fdc_id) {
+                None
+            } else {
+                Some(foods.profile(entry.fdc_id, &entry.quantity).await?)
+            });
+        }
+        profiled.push(profiles);
+    }
+
+    let days = logged
+        .into_par_iter()
+        .zip(profiled)
+        .map(|((date, entries), profiled)| {
+            let profiles = entries
+                .iter()
+                .zip(profiled)
+                .map(|(entry, profile)| match profile {
+                    Some(profile) => Ok(profile),
+                    None => nutrition::profile_for(&found[&entry.fdc_id], &entry.quantity, None),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(DaySummary {
+                date,
+                profile: nutrition::sum(profiles),
+                entries: entries.len(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Summary::from_days(days, window.include_empty))
+}
+
+/// Fail on windows which end before they start.
+fn check<Tz>(window: &Window<Tz>) -> Result<()> {
+    if window.end < window.start {
+        return Err(NutritionError::Invalid(format!(
+            "Cannot summarize from {} to the earlier {}",
+            window.start, window.end
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,171 @@
+use super::*;
+use crate::fdc::FDCMeta;
+use crate::log::{LogEntry, MemoryLog};
+use crate::nutrition::Nutrient;
+use crate::quantities::Quantity;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use std::collections::HashMap;
+use uom::si::{f32::Mass, mass::gram};
+
+/// A food with 10 g protein and 1000 mg sodium per 100 g.
+fn foods() -> HashMap<i32, FDCMeta> {
+    let nutrient = |id: i32, unit: &str, amount: f32| {
+        serde_json::json!({
+            "id": id * 10,
+            "nutrient": { "id": id, "name": "", "unitName": unit },
+            "amount": amount,
+        })
+    };
+    let food = serde_json::from_value(serde_json::json!({
+        "dataType": "Other",
+        "fdcId": 1,
+        "description": "Salted peanuts",
+        "foodAttributes": [],
+        "foodPortions": [],
+        "foodNutrients": [nutrient(1003, "g", 10.0), nutrient(1093, "mg", 1000.0)],
+    }))
+    .unwrap();
+    HashMap::from([(1, food)])
+}
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+}
+
+fn grams(amount: f32, at: &str) -> LogEntry {
+    let eaten_at = DateTime::parse_from_rfc3339(at)
+        .unwrap()
+        .with_timezone(&Utc);
+    LogEntry::new(1, Quantity::Mass(Mass::new::<gram>(amount)), eaten_at)
+}
+
+/// Two weeks from Monday the 1st of January 2024: 100 g a day over the first week, split in two
+/// entries, and 200 g a day from Monday the 8th to Thursday the 11th.
+async fn log() -> MemoryLog {
+    let log = MemoryLog::new();
+    for day in 1..=7 {
+        log.add(&grams(60.0, &format!("2024-01-{:02}T08:00:00Z", day)))
+            .await
+            .unwrap();
+        log.add(&grams(40.0, &format!("2024-01-{:02}T19:00:00Z", day)))
+            .await
+            .unwrap();
+    }
+    for day in 8..=11 {
+        log.add(&grams(200.0, &format!("2024-01-{:02}T12:00:00Z", day)))
+            .await
+            .unwrap();
+    }
+    log
+}
+
+fn close(a: Option<f32>, b: f32) -> bool {
+    a.is_some_and(|a| (a - b).abs() < 1e-3 * b.abs().max(1.0))
+}
+
+#[tokio::test]
+async fn date_range() {
+    let window = Window::range(date(1), date(14), Utc);
+    let summary = summarize(&log().await, &foods(), window).await.unwrap();
+    assert_eq!(summary.days.len(), 14);
+    assert_eq!(summary.entries, 18);
+    assert_eq!(summary.counted_days, 14);
+    assert!(close(summary.mean.get(Nutrient::Sodium), 15000.0 / 14.0));
+    assert_eq!(summary.min.get(Nutrient::Sodium), Some(0.0));
+    assert!(close(summary.max.get(Nutrient::Protein), 20.0));
+    assert_eq!(summary.days[0].entries, 2);
+    assert!(close(summary.days[0].profile.get(Nutrient::Protein), 10.0));
+    assert!(summary.days[13].profile.is_empty());
+}
+
+#[tokio::test]
+async fn trailing_days() {
+    let log = log().await;
+    let window = Window::trailing(7, date(14), Utc);
+    assert_eq!((window.start, window.end), (date(8), date(14)));
+    let summary = summarize(&log, &foods(), window.clone()).await.unwrap();
+    assert!(close(summary.mean.get(Nutrient::Sodium), 8000.0 / 7.0));
+
+    // leaving the empty days out averages the 4 days with entries
+    let summary = summarize(&log, &foods(), window.excluding_empty())
+        .await
+        .unwrap();
+    assert_eq!(summary.days.len(), 7);
+    assert_eq!(summary.counted_days, 4);
+    assert!(close(summary.mean.get(Nutrient::Sodium), 2000.0));
+    assert!(close(summary.min.get(Nutrient::Sodium), 2000.0));
+
+    // a window of only empty days has no statistics
+    let window = Window::trailing(3, date(20), Utc).excluding_empty();
+    let summary = summarize(&log, &foods(), window).await.unwrap();
+    assert_eq!(summary.counted_days, 0);
+    assert!(summary.mean.is_empty());
+}
+
+#[tokio::test]
+async fn calendar_weeks() {
+    // starting on a Wednesday snaps to the Monday of its week
+    let window = Window::weeks(2, date(3), Utc);
+    assert_eq!((window.start, window.end), (date(1), date(14)));
+    let summary = summarize(&log().await, &foods(), window).await.unwrap();
+    let weeks = summary.by_week();
+    assert_eq!(weeks.len(), 2);
+    assert_eq!(weeks[0].0, date(1));
+    assert!(close(weeks[0].1.mean.get(Nutrient::Protein), 10.0));
+    assert_eq!(weeks[1].0, date(8));
+    assert!(close(weeks[1].1.mean.get(Nutrient::Protein), 80.0 / 7.0));
+    assert_eq!(weeks[1].1.entries, 4);
+}
+
+#[tokio::test]
+async fn time_zone() {
+    // ten hours behind, the morning entry of the 1st moves to the 31st of December and the
+    // morning entry of the 2nd moves to the 1st
+    let behind = FixedOffset::west_opt(10 * 3600).unwrap();
+    let new_year = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+    let window = Window::range(new_year, date(1), behind);
+    let summary = summarize(&log().await, &foods(), window).await.unwrap();
+    assert_eq!(
+        summary
+            .days
+            .iter()
+            .map(|day| day.entries)
+            .collect::<Vec<_>>(),
+        [1, 2]
+    );
+    assert!(close(summary.days[0].profile.get(Nutrient::Protein), 6.0));
+    assert!(close(summary.days[1].profile.get(Nutrient::Protein), 10.0));
+}
+
+#[tokio::test]
+async fn rejects_reversed_window() {
+    let window = Window::range(date(2), date(1), Utc);
+    assert!(summarize(&log().await, &foods(), window).await.is_err());
+}
+
+#[cfg(feature = "parallel")]
+#[tokio::test]
+async fn par_summarize_matches() {
+    // uneven amounts, so that summing in another order would round differently
+    let log = MemoryLog::new();
+    for i in 0..500 {
+        let at = format!("2024-01-{:02}T{:02}:{:02}:00Z", i % 28 + 1, i % 24, i % 60);
+        log.add(&grams(0.1 + i as f32 * 1.37, &at)).await.unwrap();
+    }
+    let window = Window::range(date(1), date(31), Utc).excluding_empty();
+    let serial = summarize(&log, &foods(), window.clone()).await.unwrap();
+    let parallel = par_summarize(&log, &foods(), window.clone()).await.unwrap();
+    assert_eq!(parallel, serial);
+    assert_eq!(parallel.entries, 500);
+
+    log.add(&LogEntry::new(
+        2,
+        Quantity::Mass(Mass::new::<gram>(1.0)),
+        Utc::now(),
+    ))
+    .await
+    .unwrap();
+    let window = Window::trailing(1, Utc::now().date_naive(), Utc);
+    assert!(par_summarize(&log, &foods(), window).await.is_err());
+}
@@ -0,0 +1,266 @@
+//! This module resolves parsed [`Quantity`]s of a food to grams through [`to_grams`], using the
+//! serving of branded foods and the portions of other foods.
+
+use crate::error::Result;
+use crate::fdc::{APFoodItem, BrandedFoodItem, FDCMeta, FoodPortion};
+use crate::nutrition::NutritionError;
+use crate::quantities::{parse, same_name, singular, CustomUnits, Quantity};
+use crate::warnings::{warn, Warning, Warnings};
+
+use uom::si::{f32::Mass, mass::gram, volume::milliliter};
+
+/// The mass of the quantity of the food. Masses resolve directly. Volumes and nominal quantities
+/// resolve against the household serving and serving size of branded foods, and against the
+/// portions of other foods. Fails when nothing matches, or when several portions match equally well
+/// but weigh differently. Nominal quantities resolved by convention, like `"1 serving"`, or by a
+/// portion only sharing words with them, are pushed to the warnings.
+pub fn to_grams(
+    quantity: &Quantity,
+    food: &FDCMeta,
+    mut warnings: Option<&mut Warnings>,
+) -> Result<Mass> {
+    if let Quantity::Mass(mass) = quantity {
+        return Ok(*mass);
+    }
+    let grams = match food {
+        FDCMeta::Branded(food) => branded_grams(quantity, food, &mut warnings)?,
+        FDCMeta::Other(food) => portion_grams(quantity, food, &mut warnings)?,
+    };
+    Ok(Mass::new::<gram>(grams))
+}
+
+/// The mass of the quantity of the food like [`to_grams`], except that nominal quantities of the
+/// custom units resolve to their grams before the food is consulted.
+pub fn to_grams_with(
+    quantity: &Quantity,
+    food: &FDCMeta,
+    units: &CustomUnits,
+    warnings: Option<&mut Warnings>,
+) -> Result<Mass> {
+    to_grams(&units.resolve(quantity), food, warnings)
+}
+
+/// Resolve a quantity against the serving of a branded food, which must be given by mass.
+fn branded_grams(
+    quantity: &Quantity,
+    food: &BrandedFoodItem,
+    warnings: &mut Option<&mut Warnings>,
+) -> Result<f32> {
+    let serving = match food.serving_size_quantity()? {
+        Quantity::Mass(mass) => mass.get::<gram>(),
+        _ => {
+            return Err(NutritionError::Unresolved(format!(
+                "Cannot resolve {:?} of food {} to grams, its serving size is {} {}",
+                quantity, food.fdc_id, food.serving_size, food.serving_size_unit
+            ))
+            .into())
+        }
+    };
+    Ok(servings_in(quantity, food, warnings)? * serving)
+}
+
+/// The number of servings of a branded food in the quantity. Nominal servings, like `"2
+/// servings"`, count as such, and other quantities are compared to each quantity of the household
+/// serving text, then to the serving size.
+pub(crate) fn servings_in(
+    quantity: &Quantity,
+    food: &BrandedFoodItem,
+    warnings: &mut Option<&mut Warnings>,
+) -> Result<f32> {
+    if let Quantity::Nominal(amount, name) = quantity {
+        if same_name(name, "serving") {
+            warn(warnings, serving_convention(quantity, food));
+            return Ok(*amount);
+        }
+    }
+    let serving = food.serving_size_quantity()?;
+    food.household_serving_full_text
+        .as_deref()
+        .and_then(|text| parse::quantities(text).ok())
+        .map(|(_, quants)| quants)
+        .unwrap_or_default()
+        .iter()
+        .chain(std::iter::once(&serving))
+        .find_map(|reference| ratio(quantity, reference))
+        .ok_or_else(|| {
+            NutritionError::Unresolved(format!(
+                "Cannot resolve {:?} against the serving of food {} ({} {}{})",
+                quantity,
+                food.fdc_id,
+                food.serving_size,
+                food.serving_size_unit,
+                food.household_serving_full_text
+                    .as_deref()
+                    .map(|text| format!(", {}", text))
+                    .unwrap_or_default()
+            ))
+            .into()
+        })
+}
+
+/// Resolve a volume or nominal quantity against the portions of a non-branded food. Volumes match
+/// portions whose name is a volume unit, preferring the portions closest in size. Nominal
+/// quantities match portions by name, preferring exact names over names sharing words.
+fn portion_grams(
+    quantity: &Quantity,
+    food: &APFoodItem,
+    warnings: &mut Option<&mut Warnings>,
+) -> Result<f32> {
+    let portions = food.sanitized_portions();
+
+    // every candidate with its score and the grams for the quantity
+    let candidates: Vec<(&FoodPortion, usize, f32)> = match quantity {
+        Quantity::Volume(volume) => portions
+            .filter_map(|portion| {
+                let amount = portion.amount.unwrap_or(1.0);
+                portion_names(portion).find_map(|name| {
+                    match Quantity::from_unit(amount, name) {
+                        Some(Quantity::Volume(reference)) => {
+                            let ratio = volume.get::<milliliter>() / reference.get::<milliliter>();
+                            // the closer in size, the better, to the thousandth of a factor e
+                            let distance = (ratio.ln().abs() * 1000.0) as usize;
+                            Some((portion, usize::MAX - distance, portion.gram_weight * ratio))
+                        }
+                        _ => None,
+                    }
+                })
+            })
+            .collect(),
+        Quantity::Nominal(amount, name) => portions
+            .filter_map(|portion| {
+                let score = portion_names(portion)
+                    .map(|portion_name| match_score(name, portion_name))
+                    .max()
+                    .unwrap_or(0);
+                let grams = portion.gram_weight * amount / portion.amount.unwrap_or(1.0);
+                Some((portion, score, grams)).filter(|_| score > 0)
+            })
+            .collect(),
+        Quantity::Mass(mass) => return Ok(mass.get::<gram>()),
+    };
+
+    let best = candidates.iter().map(|(_, score, _)| *score).max();
+    let mut best: Vec<_> = candidates
+        .into_iter()
+        .filter(|(_, score, grams)| Some(*score) == best && grams.is_finite())
+        .collect();
+    best.sort_by(|a, b| a.2.total_cmp(&b.2));
+    best.dedup_by(|a, b| (a.2 - b.2).abs() < 1e-3);
+    match &best[..] {
+        [] => Err(NutritionError::Unresolved(format!(
+            "Cannot resolve {} of food {}, no portion matches",
+            describe(quantity),
+            food.fdc_id
+        ))
+        .into()),
+        [(portion, score, grams)] => {
+            if matches!(quantity, Quantity::Nominal(..)) && *score < usize::MAX {
+                warn(
+                    warnings,
+                    Warning::NominalByConvention {
+                        fdc_id: food.fdc_id,
+                        quantity: quantity.clone(),
+                        resolved_as: format!("the portion {}", portion_label(portion)),
+                    },
+                );
+            }
+            Ok(*grams)
+        }
+        _ => Err(NutritionError::Unresolved(format!(
+            "Cannot resolve {} of food {}, it matches the portions {}",
+            describe(quantity),
+            food.fdc_id,
+            best.iter()
+                .map(|(portion, _, _)| portion_label(portion))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+        .into()),
+    }
+}
+
+/// The warning of a nominal quantity of servings of a branded food, read as its serving size.
+pub(crate) fn serving_convention(quantity: &Quantity, food: &BrandedFoodItem) -> Warning {
+    Warning::NominalByConvention {
+        fdc_id: food.fdc_id,
+        quantity: quantity.clone(),
+        resolved_as: format!(
+            "servings of {} {}",
+            food.serving_size, food.serving_size_unit
+        ),
+    }
+}
+
+/// A readable description of a quantity, for error messages.
+fn describe(quantity: &Quantity) -> String {
+    match quantity {
+        Quantity::Mass(mass) => format!("{} g", mass.get::<gram>()),
+        Quantity::Volume(volume) => format!("{} ml", volume.get::<milliliter>()),
+        Quantity::Nominal(amount, name) => format!("{} {}", amount, name),
+    }
+}
+
+/// The names a portion goes by, from its modifier and its description.
+pub(crate) fn portion_names(portion: &FoodPortion) -> impl Iterator<Item = &str> {
+    portion
+        .modifier
+        .iter()
+        .chain(portion.portion_description.iter())
+        .map(|text| portion_name(text))
+        .filter(|name| !name.is_empty())
+}
+
+/// A readable label of a portion, for error messages.
+fn portion_label(portion: &FoodPortion) -> String {
+    let name = portion
+        .portion_description
+        .as_deref()
+        .filter(|text| !text.is_empty())
+        .or(portion.modifier.as_deref())
+        .unwrap_or("portion");
+    format!("\"{}\" ({} g)", name, portion.gram_weight)
+}
+
+/// The name a portion text refers to, like `"slice"` for `"1 slice"` or `"cup"` for
+/// `"cup, diced"`. Numeric codes, like the modifiers of survey foods, have no name.
+fn portion_name(text: &str) -> &str {
+    let text = match parse::number(text.trim()) {
+        Ok((rest, _)) => rest,
+        Err(_) => text,
+    };
+    text.split([',', '(']).next().unwrap_or("").trim()
+}
+
+/// How well a quantity name matches a portion name: the most for the same name, then the number
+/// of words the shorter shares with the longer when all of its words are in the longer, else zero.
+fn match_score(name: &str, portion_name: &str) -> usize {
+    if same_name(name, portion_name) {
+        return usize::MAX;
+    }
+    let words = |s: &str| -> Vec<String> { s.split_whitespace().map(singular).collect() };
+    let (name, portion_name) = (words(name), words(portion_name));
+    let (short, long) = if name.len() <= portion_name.len() {
+        (name, portion_name)
+    } else {
+        (portion_name, name)
+    };
+    if !short.is_empty() && short.iter().all(|word| long.contains(word)) {
+        short.len()
+    } else {
+        0
+    }
+}
+
+/// How many of `reference` fit in `quantity`, when both are of the same kind.
+pub(crate) fn ratio(quantity: &Quantity, reference: &Quantity) -> Option<f32> {
+    let ratio = match (quantity, reference) {
+        (Quantity::Mass(m), Quantity::Mass(r)) => m.get::<gram>() / r.get::<gram>(),
+        (Quantity::Volume(v), Quantity::Volume(r)) => v.get::<milliliter>() / r.get::<milliliter>(),
+        (Quantity::Nominal(a, n), Quantity::Nominal(r, rn)) if same_name(n, rn) => a / r,
+        _ => return None,
+    };
+    Some(ratio).filter(|r| r.is_finite())
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,159 @@
+use super::*;
+use uom::si::mass::{ounce, pound};
+
+/// A non-branded food with portions of (amount, gram weight, modifier, description), as the FDC
+/// reports them.
+fn food(portions: &[(f32, f32, &str, &str)]) -> FDCMeta {
+    let portions = portions
+        .iter()
+        .enumerate()
+        .map(|(i, (amount, grams, modifier, description))| {
+            serde_json::json!({
+                "id": i,
+                "amount": amount,
+                "gramWeight": grams,
+                "modifier": modifier,
+                "portionDescription": description,
+                "sequenceNumber": i + 1,
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Other",
+        "fdcId": 4001,
+        "foodAttributes": [],
+        "foodPortions": portions,
+    }))
+    .unwrap()
+}
+
+/// The SR Legacy portions of cheddar cheese.
+fn cheddar() -> FDCMeta {
+    food(&[
+        (1.0, 132.0, "cup, diced", ""),
+        (1.0, 244.0, "cup, melted", ""),
+        (1.0, 113.0, "cup, shredded", ""),
+        (1.0, 28.35, "oz", ""),
+        (1.0, 17.3, "cubic inch", ""),
+        (1.0, 28.0, "slice (1 oz)", ""),
+    ])
+}
+
+/// The SR Legacy portions of whole eggs.
+fn egg() -> FDCMeta {
+    food(&[
+        (1.0, 243.0, "cup (4.86 large eggs)", ""),
+        (1.0, 63.0, "jumbo", ""),
+        (1.0, 56.0, "extra large", ""),
+        (1.0, 50.0, "large", ""),
+        (1.0, 44.0, "medium", ""),
+        (1.0, 38.0, "small", ""),
+    ])
+}
+
+/// The survey portions of white bread, whose modifiers are numeric codes.
+fn bread() -> FDCMeta {
+    food(&[
+        (1.0, 28.0, "10205", "1 slice"),
+        (1.0, 14.0, "90000", "1 small or thin slice"),
+        (1.0, 30.0, "90000", "Quantity not specified"),
+    ])
+}
+
+/// A branded bar of 42 g served as "1 bar", and a branded milk of 240 ml served as "1 cup".
+fn branded(size: f32, unit: &str, household: &str) -> FDCMeta {
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Branded",
+        "fdcId": 4002,
+        "ingredients": "",
+        "servingSize": size,
+        "servingSizeUnit": unit,
+        "householdServingFullText": household,
+    }))
+    .unwrap()
+}
+
+fn grams(quantity: &str, food: &FDCMeta) -> Result<f32> {
+    let (_, quantity) = parse::quantity(quantity).unwrap();
+    Ok(to_grams(&quantity, food, None)?.get::<gram>())
+}
+
+fn close(a: Result<f32>, b: f32) -> bool {
+    a.is_ok_and(|a| (a - b).abs() < 1e-3 * b.abs().max(1.0))
+}
+
+#[test]
+fn mass() {
+    assert!(close(
+        grams("2 oz", &cheddar()),
+        Mass::new::<ounce>(2.0).get::<gram>()
+    ));
+    assert!(close(
+        grams("1 lb", &egg()),
+        Mass::new::<pound>(1.0).get::<gram>()
+    ));
+    assert!(close(grams("30 g", &branded(240.0, "ml", "1 cup")), 30.0));
+}
+
+#[test]
+fn nominal() {
+    assert!(close(grams("2 slices", &cheddar()), 56.0));
+    assert!(close(grams("3 large", &egg()), 150.0));
+    assert!(close(grams("2 Large Eggs", &egg()), 100.0));
+    assert!(close(grams("1 extra large egg", &egg()), 56.0));
+    assert!(close(grams("1 jumbo", &egg()), 63.0));
+    assert!(close(grams("2 slices", &bread()), 56.0));
+    assert!(close(grams("1 thin slice", &bread()), 14.0));
+}
+
+#[test]
+fn volume() {
+    assert!(close(grams("2 cubic inches", &cheddar()), 34.6));
+    // a cup of eggs is the only volume portion
+    assert!(close(grams("1/2 cup", &egg()), 121.5));
+    assert!(close(grams("2 tbsp", &egg()), 243.0 / 8.0));
+}
+
+#[test]
+fn ambiguous() {
+    let err = grams("1 cup", &cheddar()).unwrap_err();
+    assert!(err.to_string().ends_with(
+        "it matches the portions \"cup, shredded\" (113 g), \"cup, diced\" (132 g), \
+         \"cup, melted\" (244 g)"
+    ));
+}
+
+#[test]
+fn unmatched() {
+    assert!(grams("1 wedge", &cheddar()).is_err());
+    assert!(grams("1 egg", &egg()).is_err());
+    assert!(grams("1 cup", &bread()).is_err());
+}
+
+#[test]
+fn branded_serving() {
+    let bar = branded(42.0, "g", "1 bar");
+    assert!(close(grams("2 bars", &bar), 84.0));
+    assert!(close(grams("1.5 servings", &bar), 63.0));
+    assert!(grams("1 box", &bar).is_err());
+
+    let milk = branded(240.0, "MLT", "1 cup");
+    assert!(grams("1 cup", &milk).is_err());
+    let granola = branded(55.0, "GRM", "2/3 cup");
+    assert!(close(grams("1 cup", &granola), 82.5));
+}
+
+#[test]
+fn custom_units() {
+    let units = CustomUnits::new().with_unit("scoop", 30.0);
+    let scoops = Quantity::Nominal(2.0, "scoops".into());
+    let powder = food(&[(1.0, 240.0, "cup", "")]);
+    assert!(to_grams(&scoops, &powder, None).is_err());
+    let grams = to_grams_with(&scoops, &powder, &units, None).unwrap();
+    assert_eq!(grams.get::<gram>(), 60.0);
+
+    // other quantities still resolve against the food
+    let cup = Quantity::Nominal(1.0, "cup".into());
+    let grams = to_grams_with(&cup, &powder, &units, None).unwrap();
+    assert_eq!(grams.get::<gram>(), 240.0);
+}
@@ -0,0 +1,128 @@
+//! This module suggests a default serving of a food through [`suggest`], like "1 cup" for a
+//! cereal or "1 slice" for a bread, for interfaces to pre-fill, and lists the servings to pick
+//! from through [`options`].
+
+use crate::fdc::{FDCMeta, FoodPortion};
+use crate::quantities::{parse, Quantity};
+use crate::resolve::{self, portion_names};
+
+use uom::si::{f32::Mass, mass::gram};
+
+/// The most natural serving of the food: the household serving of branded foods, else the
+/// first portion of other foods by sequence number, else 100 g.
+pub fn suggest(food: &FDCMeta) -> Quantity {
+    let suggestion = match food {
+        FDCMeta::Branded(food) => food
+            .household_serving_full_text
+            .as_deref()
+            .and_then(|text| parse::quantities(text).ok())
+            // the quantity written first is the last one parsed
+            .and_then(|(_, quants)| quants.into_iter().last()),
+        FDCMeta::Other(food) => food.default_portion().map(portion_quantity),
+    };
+    suggestion.unwrap_or_else(|| Quantity::Mass(Mass::new::<gram>(100.0)))
+}
+
+/// How far apart in weight, relative to the heavier, two servings may be and still be the same.
+pub const SAME_WEIGHT: f32 = 0.02;
+
+/// A serving to pick from when logging a food.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServingOption {
+    /// The quantity followed by its weight, like `"1 cup (240 g)"`.
+    pub label: String,
+    pub quantity: Quantity,
+    /// The grams of the quantity, unless it is a volume of a food served by volume.
+    pub grams: Option<f32>,
+}
+
+impl ServingOption {
+    /// generate a new ServingOption labelled by the quantity and its grams
+    pub fn new(quantity: Quantity, grams: Option<f32>) -> ServingOption {
+        let label = match (&quantity, grams) {
+            (Quantity::Mass(_), _) | (_, None) => quantity.to_string(),
+            (_, Some(grams)) => {
+                format!(
+                    "{} ({})",
+                    quantity,
+                    Quantity::Mass(Mass::new::<gram>(grams))
+                )
+            }
+        };
+        ServingOption {
+            label,
+            quantity,
+            grams,
+        }
+    }
+
+    /// Whether the options weigh the same, or are the same quantity when either weight is unknown.
+    fn same_as(&self, other: &ServingOption) -> bool {
+        match (self.grams, other.grams) {
+            (Some(a), Some(b)) => (a - b).abs() <= SAME_WEIGHT * a.max(b),
+            _ => self.quantity == other.quantity,
+        }
+    }
+}
+
+/// The servings of the food to pick from, most typical first: the portions of other foods by
+/// sequence number then weight, or the household serving and then the serving size of branded
+/// foods, and 100 g last. Servings which weigh the same as one before them within [`SAME_WEIGHT`]
+/// are left out.
+pub fn options(food: &FDCMeta) -> Vec<ServingOption> {
+    let mut candidates = Vec::new();
+    match food {
+        FDCMeta::Branded(item) => {
+            let household = item
+                .household_serving_full_text
+                .as_deref()
+                .and_then(|text| parse::quantities(text).ok())
+                .and_then(|(_, quants)| quants.into_iter().last());
+            let serving = item.serving_size_quantity().ok();
+            for quantity in household.into_iter().chain(serving) {
+                let grams = resolve::to_grams(&quantity, food, None).ok();
+                candidates.push(ServingOption::new(quantity, grams.map(|m| m.get::<gram>())));
+            }
+        }
+        FDCMeta::Other(item) => {
+            let mut portions = item.sanitized_portions().collect::<Vec<_>>();
+            portions.sort_by(|a, b| {
+                let sequence = |p: &FoodPortion| p.sequence_number.unwrap_or(i32::MAX);
+                sequence(a)
+                    .cmp(&sequence(b))
+                    .then(a.gram_weight.total_cmp(&b.gram_weight))
+            });
+            for portion in portions {
+                candidates.push(ServingOption::new(
+                    portion_quantity(portion),
+                    Some(portion.gram_weight),
+                ));
+            }
+        }
+    }
+    candidates.push(ServingOption::new(
+        Quantity::Mass(Mass::new::<gram>(100.0)),
+        Some(100.0),
+    ));
+
+    let mut options: Vec<ServingOption> = Vec::new();
+    for candidate in candidates {
+        if !options.iter().any(|option| option.same_as(&candidate)) {
+            options.push(candidate);
+        }
+    }
+    options
+}
+
+/// The quantity a portion stands for, by its name when it has one and by its weight otherwise.
+fn portion_quantity(portion: &FoodPortion) -> Quantity {
+    let amount = portion.amount.unwrap_or(1.0);
+    match portion_names(portion).next() {
+        Some(name) => Quantity::from_unit(amount, name)
+            .unwrap_or_else(|| Quantity::Nominal(amount, name.to_lowercase())),
+        None => Quantity::Mass(Mass::new::<gram>(portion.gram_weight)),
+    }
+}
+
+#[cfg(test)]
+mod test;
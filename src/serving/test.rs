@@ -0,0 +1,165 @@
+use super::{options, suggest};
+use crate::fdc::FDCMeta;
+use crate::quantities::Quantity;
+use uom::si::{f32::Mass, f32::Volume, mass::gram, volume::cup};
+
+/// A non-branded food with portions of (sequence number, amount, gram weight, modifier,
+/// description), as the FDC reports them.
+fn food(portions: &[(Option<i32>, f32, f32, &str, &str)]) -> FDCMeta {
+    let portions = portions
+        .iter()
+        .enumerate()
+        .map(|(i, (sequence, amount, grams, modifier, description))| {
+            serde_json::json!({
+                "id": i,
+                "amount": amount,
+                "gramWeight": grams,
+                "modifier": modifier,
+                "portionDescription": description,
+                "sequenceNumber": sequence,
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Other",
+        "fdcId": 5001,
+        "foodAttributes": [],
+        "foodPortions": portions,
+    }))
+    .unwrap()
+}
+
+fn branded(household: Option<&str>) -> FDCMeta {
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Branded",
+        "fdcId": 5002,
+        "ingredients": "",
+        "servingSize": 30.0,
+        "servingSizeUnit": "g",
+        "householdServingFullText": household,
+    }))
+    .unwrap()
+}
+
+fn hundred_grams() -> Quantity {
+    Quantity::Mass(Mass::new::<gram>(100.0))
+}
+
+#[test]
+fn household_serving() {
+    assert_eq!(
+        suggest(&branded(Some("1 cup (30g)"))),
+        Quantity::Volume(Volume::new::<cup>(1.0))
+    );
+    assert_eq!(
+        suggest(&branded(Some("2 pieces"))),
+        Quantity::Nominal(2.0, "pieces".into())
+    );
+}
+
+#[test]
+fn first_portion() {
+    // the portions are out of order, and the first one weighs nothing
+    let cereal = food(&[
+        (Some(3), 1.0, 45.0, "cup, crushed", ""),
+        (Some(1), 1.0, 0.0, "serving", ""),
+        (Some(2), 1.0, 28.0, "cup", ""),
+        (None, 1.0, 10.0, "tbsp", ""),
+    ]);
+    assert_eq!(suggest(&cereal), Quantity::Volume(Volume::new::<cup>(1.0)));
+
+    // survey modifiers are codes, so the description names the portion
+    let bread = food(&[
+        (Some(2), 1.0, 14.0, "90000", "1 small or thin slice"),
+        (Some(1), 1.0, 28.0, "10205", "1 slice"),
+    ]);
+    assert_eq!(suggest(&bread), Quantity::Nominal(1.0, "slice".into()));
+
+    // nameless portions are suggested by weight
+    let nameless = food(&[(Some(1), 1.0, 42.0, "", "")]);
+    assert_eq!(suggest(&nameless), Quantity::Mass(Mass::new::<gram>(42.0)));
+}
+
+#[test]
+fn hundred_grams_last() {
+    assert_eq!(suggest(&branded(None)), hundred_grams());
+    assert_eq!(suggest(&branded(Some(""))), hundred_grams());
+    assert_eq!(suggest(&branded(Some("a handful"))), hundred_grams());
+    assert_eq!(suggest(&food(&[])), hundred_grams());
+    assert_eq!(
+        suggest(&food(&[(Some(1), 0.0, 30.0, "cup", "")])),
+        hundred_grams()
+    );
+}
+
+/// The labels of the serving options of the food.
+fn labels(food: &FDCMeta) -> Vec<String> {
+    options(food)
+        .into_iter()
+        .map(|option| option.label)
+        .collect()
+}
+
+fn fixture(json: &str) -> FDCMeta {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn branded_options() {
+    // the oil is served by volume, so only 100 g has a weight
+    let oil = fixture(include_str!("../../tests/fixtures/branded.json"));
+    let oil_options = options(&oil);
+    assert_eq!(labels(&oil), ["1 tbsp", "14 ml", "100 g"]);
+    assert_eq!(oil_options[0].grams, None);
+    assert_eq!(oil_options[2].grams, Some(100.0));
+
+    // the household serving weighs as much as the serving size, which is left out
+    let cereal = branded(Some("1 cup (30g)"));
+    assert_eq!(labels(&cereal), ["1 cup (30 g)", "100 g"]);
+    assert_eq!(
+        options(&cereal)[0].quantity,
+        Quantity::Volume(Volume::new::<cup>(1.0))
+    );
+    assert_eq!(labels(&branded(Some("a handful"))), ["30 g", "100 g"]);
+}
+
+#[test]
+fn portion_options() {
+    let eggs = fixture(include_str!("../../tests/fixtures/sr_legacy.json"));
+    assert_eq!(labels(&eggs), ["1 cup (243 g)", "1 large (50 g)", "100 g"]);
+
+    let cereal = food(&[
+        (Some(3), 1.0, 45.0, "cup, crushed", ""),
+        (Some(1), 1.0, 0.0, "serving", ""),
+        (Some(2), 1.0, 28.0, "cup", ""),
+        (Some(2), 2.0, 14.0, "tbsp", ""),
+        (None, 1.0, 28.5, "oz", ""),
+        (None, 1.0, 101.0, "bowl", ""),
+        (Some(4), 1.0, 44.5, "cup, heaping", ""),
+    ]);
+    let cereal_options = options(&cereal);
+    // the ounce and the heaping cup weigh as much as portions before them, and the bowl as 100 g
+    assert_eq!(
+        labels(&cereal),
+        [
+            "2 tbsp (14 g)",
+            "1 cup (28 g)",
+            "1 cup (45 g)",
+            "1 bowl (101 g)"
+        ]
+    );
+    assert_eq!(cereal_options[0].grams, Some(14.0));
+    assert_eq!(
+        cereal_options[3].quantity,
+        Quantity::Nominal(1.0, "bowl".into())
+    );
+}
+
+#[test]
+fn no_portions() {
+    let options = options(&food(&[]));
+    assert_eq!(options.len(), 1);
+    assert_eq!(options[0].label, "100 g");
+    assert_eq!(options[0].quantity, hundred_grams());
+    assert_eq!(options[0].grams, Some(100.0));
+}
@@ -0,0 +1,207 @@
+//! This module declares the [`FoodSource`] trait, through which higher level features like recipes
+//! look up the foods they need without caring where they come from.
+//!
+//! Sources can be layered with [`Chain`], like a local store in front of the FDC API, so that foods
+//! found in later layers are written back to the earlier ones.
+
+use crate::error::Result;
+use crate::fdc::{AbridgedFoodItem, FDCMeta, FDCService, HttpTransport};
+use crate::nutrition::{self, NutrientProfile, NutritionError};
+use crate::progress::CancellationToken;
+use crate::quantities::Quantity;
+use crate::storage::{CachedFoods, FoodStore, SqliteStore};
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// `FoodSource` is implemented by everything that can produce foods by FDC id.
+#[async_trait]
+pub trait FoodSource: Send + Sync {
+    /// Get the food of the FDC id, if the source has it.
+    async fn get(&self, fdc_id: i32) -> Result<Option<FDCMeta>>;
+
+    /// Get the foods of the FDC ids in input order, with `None` for the ids the source does not
+    /// have. Sources which can look up several foods at once should override this.
+    async fn get_many(&self, fdc_ids: &[i32]) -> Result<Vec<Option<FDCMeta>>> {
+        let mut foods = Vec::with_capacity(fdc_ids.len());
+        for fdc_id in fdc_ids {
+            foods.push(self.get(*fdc_id).await?);
+        }
+        Ok(foods)
+    }
+
+    /// Search the foods of the source, most relevant first. Sources which cannot search find
+    /// nothing.
+    async fn search(&self, _query: &str) -> Result<Vec<AbridgedFoodItem>> {
+        Ok(Vec::new())
+    }
+
+    /// Keep a food found elsewhere, so that it is found here next time. Sources which cannot keep
+    /// foods ignore it.
+    async fn put(&self, _food: &FDCMeta) -> Result<()> {
+        Ok(())
+    }
+
+    /// The nutrients in a quantity of the food of the FDC id. Sources which know of composite
+    /// foods, like recipes, can override this to resolve them.
+    async fn profile(&self, fdc_id: i32, quantity: &Quantity) -> Result<NutrientProfile> {
+        let food = self
+            .get(fdc_id)
+            .await?
+            .ok_or(NutritionError::NoFood(fdc_id))?;
+        nutrition::profile_for(&food, quantity, None)
+    }
+}
+
+#[async_trait]
+impl FoodSource for HashMap<i32, FDCMeta> {
+    async fn get(&self, fdc_id: i32) -> Result<Option<FDCMeta>> {
+        Ok(HashMap::get(self, &fdc_id).cloned())
+    }
+}
+
+#[async_trait]
+impl FoodSource for SqliteStore {
+    async fn get(&self, fdc_id: i32) -> Result<Option<FDCMeta>> {
+        self.get_food(fdc_id).await
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<AbridgedFoodItem>> {
+        self.search_local(query).await
+    }
+
+    async fn put(&self, food: &FDCMeta) -> Result<()> {
+        self.upsert_food(food).await
+    }
+}
+
+/// The API makes a new default transport for each call, so prefer [`CachedFoods`] to share one.
+#[async_trait]
+impl<T: HttpTransport + Default> FoodSource for FDCService<T> {
+    async fn get(&self, fdc_id: i32) -> Result<Option<FDCMeta>> {
+        Ok(self.v1_foods(&T::default(), &[fdc_id]).await?.pop())
+    }
+
+    async fn get_many(&self, fdc_ids: &[i32]) -> Result<Vec<Option<FDCMeta>>> {
+        let foods = self.v1_foods_chunked(&T::default(), fdc_ids).await?;
+        Ok(in_order(fdc_ids, foods))
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<AbridgedFoodItem>> {
+        self.v1_foods_search(&T::default(), query).await
+    }
+}
+
+#[async_trait]
+impl<S: FoodStore, T: HttpTransport> FoodSource for CachedFoods<S, T> {
+    async fn get(&self, fdc_id: i32) -> Result<Option<FDCMeta>> {
+        Ok(self
+            .get_or_fetch_found(&[fdc_id], |_| {}, &CancellationToken::new())
+            .await?
+            .remove(&fdc_id))
+    }
+
+    async fn get_many(&self, fdc_ids: &[i32]) -> Result<Vec<Option<FDCMeta>>> {
+        let found = self
+            .get_or_fetch_found(fdc_ids, |_| {}, &CancellationToken::new())
+            .await?;
+        Ok(fdc_ids.iter().map(|id| found.get(id).cloned()).collect())
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<AbridgedFoodItem>> {
+        self.service.v1_foods_search(&self.client, query).await
+    }
+
+    async fn put(&self, food: &FDCMeta) -> Result<()> {
+        self.store.upsert_food(food).await
+    }
+}
+
+/// Put the foods in the order of the FDC ids, with `None` for the ids without a food.
+fn in_order(fdc_ids: &[i32], foods: Vec<FDCMeta>) -> Vec<Option<FDCMeta>> {
+    let foods = foods
+        .into_iter()
+        .map(|food| (food.fdc_id(), food))
+        .collect::<HashMap<_, _>>();
+    fdc_ids.iter().map(|id| foods.get(id).cloned()).collect()
+}
+
+/// `Chain` tries layers of sources in order, like a local store before the FDC API. A food found
+/// in a layer is put into every layer before it, and searches return the results of the first
+/// layer finding anything.
+#[derive(Default)]
+pub struct Chain {
+    pub layers: Vec<Box<dyn FoodSource>>,
+}
+
+impl Chain {
+    /// generate a new Chain without layers
+    pub fn new() -> Chain {
+        Chain::default()
+    }
+
+    /// Add a layer, tried after every layer already added.
+    pub fn with_layer<S: FoodSource + 'static>(mut self, source: S) -> Chain {
+        self.layers.push(Box::new(source));
+        self
+    }
+
+    /// Put the food into the layers before the one which found it.
+    async fn backfill(&self, found_in: usize, food: &FDCMeta) -> Result<()> {
+        for layer in &self.layers[..found_in] {
+            layer.put(food).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FoodSource for Chain {
+    async fn get(&self, fdc_id: i32) -> Result<Option<FDCMeta>> {
+        for (i, layer) in self.layers.iter().enumerate() {
+            if let Some(food) = layer.get(fdc_id).await? {
+                self.backfill(i, &food).await?;
+                return Ok(Some(food));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Each layer is asked at once for the foods the layers before it did not have.
+    async fn get_many(&self, fdc_ids: &[i32]) -> Result<Vec<Option<FDCMeta>>> {
+        let mut foods = vec![None; fdc_ids.len()];
+        for (i, layer) in self.layers.iter().enumerate() {
+            let missing = (0..fdc_ids.len())
+                .filter(|j| foods[*j].is_none())
+                .collect::<Vec<_>>();
+            if missing.is_empty() {
+                break;
+            }
+            let ids = missing.iter().map(|j| fdc_ids[*j]).collect::<Vec<_>>();
+            for (j, food) in missing.into_iter().zip(layer.get_many(&ids).await?) {
+                if let Some(food) = food {
+                    self.backfill(i, &food).await?;
+                    foods[j] = Some(food);
+                }
+            }
+        }
+        Ok(foods)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<AbridgedFoodItem>> {
+        for layer in &self.layers {
+            let results = layer.search(query).await?;
+            if !results.is_empty() {
+                return Ok(results);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    async fn put(&self, food: &FDCMeta) -> Result<()> {
+        self.backfill(self.layers.len(), food).await
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,156 @@
+use super::{Chain, FoodSource};
+use crate::fdc::{AbridgedFoodItem, FDCMeta};
+
+use crate::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+fn food(fdc_id: i32) -> FDCMeta {
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Other",
+        "fdcId": fdc_id,
+        "description": format!("FOOD {}", fdc_id),
+        "foodAttributes": [],
+        "foodPortions": [],
+    }))
+    .unwrap()
+}
+
+/// A source keeping foods in memory and recording the ids it is asked for, whose state is shared
+/// with its clones so that tests can inspect it once it is in a chain.
+#[derive(Clone, Default)]
+struct Mock {
+    foods: Arc<Mutex<HashMap<i32, FDCMeta>>>,
+    asked: Arc<Mutex<Vec<Vec<i32>>>>,
+    results: Vec<AbridgedFoodItem>,
+}
+
+impl Mock {
+    fn with(fdc_ids: &[i32]) -> Mock {
+        let mock = Mock::default();
+        for fdc_id in fdc_ids {
+            mock.foods.lock().unwrap().insert(*fdc_id, food(*fdc_id));
+        }
+        mock
+    }
+
+    fn has(&self, fdc_id: i32) -> bool {
+        self.foods.lock().unwrap().contains_key(&fdc_id)
+    }
+
+    fn asked(&self) -> Vec<Vec<i32>> {
+        self.asked.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl FoodSource for Mock {
+    async fn get(&self, fdc_id: i32) -> Result<Option<FDCMeta>> {
+        self.asked.lock().unwrap().push(vec![fdc_id]);
+        Ok(self.foods.lock().unwrap().get(&fdc_id).cloned())
+    }
+
+    async fn get_many(&self, fdc_ids: &[i32]) -> Result<Vec<Option<FDCMeta>>> {
+        self.asked.lock().unwrap().push(fdc_ids.to_vec());
+        let foods = self.foods.lock().unwrap();
+        Ok(fdc_ids.iter().map(|id| foods.get(id).cloned()).collect())
+    }
+
+    async fn search(&self, _query: &str) -> Result<Vec<AbridgedFoodItem>> {
+        Ok(self.results.clone())
+    }
+
+    async fn put(&self, food: &FDCMeta) -> Result<()> {
+        self.foods
+            .lock()
+            .unwrap()
+            .insert(food.fdc_id(), food.clone());
+        Ok(())
+    }
+}
+
+fn ids(foods: &[Option<FDCMeta>]) -> Vec<Option<i32>> {
+    foods
+        .iter()
+        .map(|f| f.as_ref().map(FDCMeta::fdc_id))
+        .collect()
+}
+
+#[tokio::test]
+async fn get_backfills_earlier_layers() {
+    let (local, cache, remote) = (Mock::with(&[1]), Mock::with(&[2]), Mock::with(&[2, 3]));
+    let chain = Chain::new()
+        .with_layer(local.clone())
+        .with_layer(cache.clone())
+        .with_layer(remote.clone());
+
+    assert_eq!(chain.get(1).await.unwrap().unwrap().fdc_id(), 1);
+    assert!(cache.asked().is_empty());
+
+    assert_eq!(chain.get(3).await.unwrap().unwrap().fdc_id(), 3);
+    assert!(local.has(3) && cache.has(3));
+
+    // found in the middle layer, so the last layer is neither asked nor written
+    assert_eq!(chain.get(2).await.unwrap().unwrap().fdc_id(), 2);
+    assert!(local.has(2));
+    assert_eq!(remote.asked(), [vec![3]]);
+
+    assert!(chain.get(4).await.unwrap().is_none());
+    assert_eq!(remote.asked(), [vec![3], vec![4]]);
+}
+
+#[tokio::test]
+async fn get_many_asks_each_layer_for_the_rest() {
+    let (local, remote) = (Mock::with(&[1, 3]), Mock::with(&[2, 3, 4]));
+    let chain = Chain::new()
+        .with_layer(local.clone())
+        .with_layer(remote.clone());
+
+    let foods = chain.get_many(&[4, 1, 5, 2, 3]).await.unwrap();
+    assert_eq!(ids(&foods), [Some(4), Some(1), None, Some(2), Some(3)]);
+    assert_eq!(local.asked(), [vec![4, 1, 5, 2, 3]]);
+    assert_eq!(remote.asked(), [vec![4, 5, 2]]);
+    assert!(local.has(4) && local.has(2));
+
+    // everything is local now, except what no layer has
+    let foods = chain.get_many(&[2, 4]).await.unwrap();
+    assert_eq!(ids(&foods), [Some(2), Some(4)]);
+    assert_eq!(remote.asked().len(), 1);
+}
+
+#[tokio::test]
+async fn search_first_layer_finding_anything() {
+    let result = |fdc_id| AbridgedFoodItem {
+        fdc_id,
+        data_type: "Foundation".into(),
+        description: format!("FOOD {}", fdc_id),
+        food_nutrients: Vec::new(),
+        score: None,
+        publication_date: None,
+    };
+    let remote = Mock {
+        results: vec![result(7)],
+        ..Mock::default()
+    };
+    let chain = Chain::new()
+        .with_layer(Mock::default())
+        .with_layer(remote)
+        .with_layer(Mock {
+            results: vec![result(8)],
+            ..Mock::default()
+        });
+    let results = chain.search("food").await.unwrap();
+    assert_eq!(results.iter().map(|r| r.fdc_id).collect::<Vec<_>>(), [7]);
+    assert!(Chain::new().search("food").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn profile_through_chain() {
+    let chain = Chain::new()
+        .with_layer(HashMap::<i32, FDCMeta>::new())
+        .with_layer(Mock::with(&[9]));
+    let (_, quantity) = crate::quantities::parse::quantity("100 g").unwrap();
+    assert!(chain.profile(9, &quantity).await.is_ok());
+    assert!(chain.profile(10, &quantity).await.is_err());
+}
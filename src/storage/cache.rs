@@ -0,0 +1,147 @@
+//! This module provides [`CachedFoods`], which serves foods from a [`FoodStore`] and only makes
+//! requests to the FDC API for the foods it is missing.
+
+use super::FoodStore;
+use crate::error::Result;
+use crate::fdc::{FDCError, FDCMeta, FDCService, HttpTransport, CHUNK_SIZE};
+use crate::progress::{CancellationToken, ProgressEvent};
+
+use reqwest::Client;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Foods fetched from the API are refetched after a week by default.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// `CachedFoods` wraps an [`FDCService`] with its transport and a [`FoodStore`], persisting every
+/// food fetched.
+pub struct CachedFoods<S, T = Client> {
+    pub service: FDCService<T>,
+    pub client: T,
+    pub store: S,
+    pub ttl: Duration,
+}
+
+impl<S: FoodStore, T: HttpTransport> CachedFoods<S, T> {
+    /// generate a new CachedFoods whose foods expire after [`DEFAULT_TTL`]
+    pub fn new(service: FDCService<T>, client: T, store: S) -> CachedFoods<S, T> {
+        CachedFoods {
+            service,
+            client,
+            store,
+            ttl: DEFAULT_TTL,
+        }
+    }
+
+    /// Set how long fetched foods stay fresh in the store.
+    pub fn with_ttl(self, ttl: Duration) -> CachedFoods<S, T> {
+        CachedFoods { ttl, ..self }
+    }
+
+    /// Get the foods of the FDC ids in input order. Foods which are missing from the store or have
+    /// expired are fetched in chunks of at most [`CHUNK_SIZE`] ids and persisted.
+    pub async fn get_or_fetch(&self, fdc_ids: &[i32]) -> Result<Vec<FDCMeta>> {
+        self.get_or_fetch_with(fdc_ids, |_| {}, &CancellationToken::new())
+            .await
+    }
+
+    /// Get the foods like [`CachedFoods::get_or_fetch`], reporting progress over the foods to fetch
+    /// after each chunk, and stopping with [`Cancelled`](crate::progress::Cancelled) before the
+    /// next chunk once the token is cancelled. The chunks fetched before that stay persisted.
+    pub async fn get_or_fetch_with<F>(
+        &self,
+        fdc_ids: &[i32],
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<FDCMeta>>
+    where
+        F: Fn(ProgressEvent),
+    {
+        let found = self.get_or_fetch_found(fdc_ids, progress, cancel).await?;
+
+        // put everything back in input order
+        fdc_ids
+            .iter()
+            .map(|fdc_id| {
+                found
+                    .get(fdc_id)
+                    .cloned()
+                    .ok_or_else(|| FDCError::NotFound(*fdc_id).into())
+            })
+            .collect()
+    }
+
+    /// Get the foods of the FDC ids like [`CachedFoods::get_or_fetch`], keyed by FDC id and
+    /// leaving out the foods FDC did not return.
+    pub(crate) async fn get_or_fetch_found<F>(
+        &self,
+        fdc_ids: &[i32],
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<HashMap<i32, FDCMeta>>
+    where
+        F: Fn(ProgressEvent),
+    {
+        // look up every distinct id locally, remembering the ones we need to fetch
+        let now = SystemTime::now();
+        let mut found = HashMap::with_capacity(fdc_ids.len());
+        let mut missing = Vec::new();
+        for &fdc_id in fdc_ids {
+            if found.contains_key(&fdc_id) || missing.contains(&fdc_id) {
+                continue;
+            }
+            match self.store.get_stored_food(fdc_id).await? {
+                Some(stored) if stored.is_fresh(now) => {
+                    found.insert(fdc_id, stored.food);
+                }
+                _ => missing.push(fdc_id),
+            }
+        }
+
+        // fetch and persist the rest
+        for food in self.fetch(&missing, progress, cancel).await? {
+            found.insert(food.fdc_id(), food);
+        }
+        Ok(found)
+    }
+
+    /// Fetch the food from the API and persist it, whether or not it is stored and fresh.
+    pub async fn refresh(&self, fdc_id: i32) -> Result<FDCMeta> {
+        self.fetch(&[fdc_id], |_| {}, &CancellationToken::new())
+            .await?
+            .pop()
+            .ok_or_else(|| FDCError::NotFound(fdc_id).into())
+    }
+
+    /// Fetch the foods from the API chunk by chunk, persisting each chunk with a fresh expiry.
+    async fn fetch<F>(
+        &self,
+        fdc_ids: &[i32],
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<FDCMeta>>
+    where
+        F: Fn(ProgressEvent),
+    {
+        let mut foods = Vec::with_capacity(fdc_ids.len());
+        let mut current = ProgressEvent {
+            total: Some(fdc_ids.len()),
+            ..ProgressEvent::default()
+        };
+        for chunk in fdc_ids.chunks(CHUNK_SIZE) {
+            cancel.check(current)?;
+            let fetched = self.service.v1_foods_chunked(&self.client, chunk).await?;
+            let expires_at = SystemTime::now() + self.ttl;
+            for food in &fetched {
+                self.store
+                    .upsert_food_expiring(food, Some(expires_at))
+                    .await?;
+            }
+            foods.extend(fetched);
+            current.completed += chunk.len();
+            current.current_item = chunk.last().copied();
+            progress(current);
+        }
+        Ok(foods)
+    }
+}
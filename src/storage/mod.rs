@@ -0,0 +1,76 @@
+//! This module persists foods fetched from FDC through the [`FoodStore`] trait, with a backend
+//! chosen from the scheme of the `DATABASE_URL`.
+
+pub mod cache;
+pub mod search;
+pub mod sqlite;
+
+pub use crate::error::StorageError;
+pub use cache::CachedFoods;
+pub use sqlite::SqliteStore;
+
+use crate::fdc::{AbridgedFoodItem, FDCMeta};
+
+use crate::error::Result;
+
+use async_trait::async_trait;
+use std::time::SystemTime;
+
+/// A stored food along with the time after which it should be fetched again, if any.
+#[derive(Clone, Debug)]
+pub struct StoredFood {
+    pub food: FDCMeta,
+    pub expires_at: Option<SystemTime>,
+}
+
+impl StoredFood {
+    /// Whether the food is still fresh at the given time.
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+/// `FoodStore` is implemented by every storage backend.
+#[async_trait]
+pub trait FoodStore: Send + Sync {
+    /// Insert the food, replacing any food already stored under the same FDC id. The food never
+    /// expires.
+    async fn upsert_food(&self, food: &FDCMeta) -> Result<()> {
+        self.upsert_food_expiring(food, None).await
+    }
+
+    /// Insert the food like [`FoodStore::upsert_food`], recording when it expires.
+    async fn upsert_food_expiring(
+        &self,
+        food: &FDCMeta,
+        expires_at: Option<SystemTime>,
+    ) -> Result<()>;
+
+    /// Get the food stored under the FDC id, if any, regardless of whether it expired.
+    async fn get_food(&self, fdc_id: i32) -> Result<Option<FDCMeta>> {
+        Ok(self
+            .get_stored_food(fdc_id)
+            .await?
+            .map(|stored| stored.food))
+    }
+
+    /// Get the food stored under the FDC id along with its expiry, if any.
+    async fn get_stored_food(&self, fdc_id: i32) -> Result<Option<StoredFood>>;
+
+    /// Search the stored foods by the words of their description and brand, most relevant first.
+    async fn search_local(&self, text: &str) -> Result<Vec<AbridgedFoodItem>>;
+
+    /// Delete the food stored under the FDC id, returning whether there was one.
+    async fn delete_food(&self, fdc_id: i32) -> Result<bool>;
+}
+
+/// Open the store matching the scheme of the database url.
+pub async fn open(database_url: &str) -> Result<Box<dyn FoodStore>> {
+    match database_url.split(':').next() {
+        Some("sqlite") => Ok(Box::new(SqliteStore::connect(database_url).await?)),
+        _ => Err(StorageError::UnsupportedUrl(database_url.into()).into()),
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,87 @@
+//! Tokenized matching of stored foods, used by backends which have no full-text index of their
+//! own.
+//!
+//! Descriptions and brands are [normalized](crate::text::normalize) and split into alphanumeric
+//! tokens whose plural endings are stripped. A food matches when it has every token of the query.
+//! When no food does, foods having any token of the query are ranked instead.
+
+use crate::fdc::AbridgedFoodItem;
+use crate::text::normalize;
+
+use std::cmp::Ordering;
+
+/// Split text into normalized, singular tokens.
+pub fn tokens(text: &str) -> Vec<String> {
+    normalize(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(stem)
+        .collect()
+}
+
+/// Strip the plural ending of a lowercase word, like `"berries"` to `"berry"` or `"peaches"` to
+/// `"peach"`. Short words and words ending in "ss" are kept as they are.
+fn stem(word: &str) -> String {
+    if word.len() <= 3 || word.ends_with("ss") {
+        return word.to_string();
+    }
+    if let Some(stem) = word.strip_suffix("ies") {
+        return format!("{}y", stem);
+    }
+    for ending in ["ches", "shes", "xes", "sses"] {
+        if word.ends_with(ending) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+    word.strip_suffix('s').unwrap_or(word).to_string()
+}
+
+/// Rank foods, given along with their brand, against the query. Foods having every token of the
+/// query come first, or foods having any token when none has them all. Among those, foods whose
+/// description and brand are shorter rank higher, as fewer of their words are unrelated. The
+/// returned foods carry their score.
+pub fn rank<I>(query: &str, foods: I) -> Vec<AbridgedFoodItem>
+where
+    I: IntoIterator<Item = (AbridgedFoodItem, Option<String>)>,
+{
+    let mut query = tokens(query);
+    query.sort();
+    query.dedup();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let scored = foods
+        .into_iter()
+        .filter_map(|(food, brand)| {
+            let mut words = tokens(&food.description);
+            words.extend(brand.as_deref().map(tokens).unwrap_or_default());
+            let matched = query.iter().filter(|token| words.contains(token)).count();
+            if matched == 0 {
+                return None;
+            }
+            let score = matched as f32 / (words.len() as f32).sqrt();
+            Some((
+                matched == query.len(),
+                AbridgedFoodItem {
+                    score: Some(score),
+                    ..food
+                },
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    let all = scored.iter().any(|(all, _)| *all);
+    let mut ranked = scored
+        .into_iter()
+        .filter(|(matches_all, _)| *matches_all || !all)
+        .map(|(_, food)| food)
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.description.cmp(&b.description))
+    });
+    ranked
+}
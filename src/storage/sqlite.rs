@@ -0,0 +1,352 @@
+//! The sqlite backend of [`FoodStore`] and [`FoodLog`]. Foods are stored as json next to their
+//! indexed FDC id and description.
+//!
+//! When sqlite is built with FTS5, descriptions and brands are also kept [normalized](normalize) in
+//! a full-text index which [`FoodStore::search_local`] ranks with bm25. Otherwise it falls back to
+//! [`search::rank`].
+
+use super::{search, FoodStore, StorageError, StoredFood};
+use crate::error::Result;
+use crate::fdc::{AbridgedFoodItem, FDCMeta};
+use crate::log::{FoodLog, LogEntry};
+use crate::text::normalize;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Statements run on every connection to create the schema if needed.
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS foods (
+        fdc_id INTEGER PRIMARY KEY,
+        description TEXT NOT NULL,
+        data_type TEXT NOT NULL,
+        data TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS foods_description ON foods (description)",
+    "CREATE TABLE IF NOT EXISTS log_entries (
+        id TEXT PRIMARY KEY,
+        fdc_id INTEGER NOT NULL,
+        quantity TEXT NOT NULL,
+        eaten_at INTEGER NOT NULL,
+        note TEXT
+    )",
+    "CREATE INDEX IF NOT EXISTS log_entries_eaten_at ON log_entries (eaten_at)",
+];
+
+/// Columns added after the initial schema, with their tables and definitions. They are added to
+/// databases created before they existed.
+const MIGRATIONS: &[(&str, &str, &str)] = &[
+    ("foods", "expires_at", "INTEGER"),
+    ("foods", "brand", "TEXT"),
+    ("log_entries", "source_text", "TEXT"),
+];
+
+/// The full-text index of descriptions and brands, keyed by FDC id as its rowid.
+const FTS_SCHEMA: &str = "CREATE VIRTUAL TABLE foods_fts USING fts5(
+    description, brand, tokenize = 'porter unicode61'
+)";
+
+/// `SqliteStore` keeps foods in a sqlite database.
+#[derive(Clone, Debug)]
+pub struct SqliteStore {
+    pool: SqlitePool,
+    fts: bool,
+}
+
+impl SqliteStore {
+    /// Connect to the database at the url, creating the file and schema if needed. An in-memory
+    /// url like `"sqlite::memory:"` is restricted to a single connection so that every query sees
+    /// the same database.
+    pub async fn connect(database_url: &str) -> Result<SqliteStore> {
+        let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+        let max_connections = if database_url.contains(":memory:") {
+            1
+        } else {
+            4
+        };
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .connect_with(options)
+            .await?;
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(&pool).await?;
+        }
+        for (table, column, definition) in MIGRATIONS {
+            let exists = sqlx::query("SELECT 1 FROM pragma_table_info(?) WHERE name = ?")
+                .bind(table)
+                .bind(column)
+                .fetch_optional(&pool)
+                .await?
+                .is_some();
+            if !exists {
+                sqlx::query(&format!(
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table, column, definition
+                ))
+                .execute(&pool)
+                .await?;
+            }
+        }
+        let fts = full_text_index(&pool).await?;
+        Ok(SqliteStore { pool, fts })
+    }
+
+    /// The underlying connection pool.
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Whether searches go through the FTS5 full-text index.
+    pub fn has_full_text_index(&self) -> bool {
+        self.fts
+    }
+
+    /// Search the full-text index with every token of the query, or with any token when no food
+    /// has them all.
+    async fn search_fts(&self, tokens: &[String]) -> Result<Vec<AbridgedFoodItem>> {
+        if tokens.is_empty() {
+            return Ok(Vec::new());
+        }
+        let quoted = tokens
+            .iter()
+            .map(|token| format!("\"{}\"", token))
+            .collect::<Vec<_>>();
+        for query in [quoted.join(" "), quoted.join(" OR ")] {
+            let rows = sqlx::query(
+                "SELECT foods.fdc_id, foods.description, foods.data_type,
+                    -bm25(foods_fts) AS score
+                 FROM foods_fts JOIN foods ON foods.fdc_id = foods_fts.rowid
+                 WHERE foods_fts MATCH ?
+                 ORDER BY bm25(foods_fts), foods.description",
+            )
+            .bind(&query)
+            .fetch_all(&self.pool)
+            .await?;
+            if !rows.is_empty() {
+                return rows
+                    .iter()
+                    .map(|row| {
+                        Ok(AbridgedFoodItem {
+                            fdc_id: row.try_get("fdc_id")?,
+                            data_type: row.try_get("data_type")?,
+                            description: row.try_get("description")?,
+                            food_nutrients: Vec::new(),
+                            score: Some(row.try_get::<f64, _>("score")? as f32),
+                            publication_date: None,
+                        })
+                    })
+                    .collect();
+            }
+        }
+        Ok(Vec::new())
+    }
+}
+
+/// Create the full-text index if sqlite supports FTS5 and it does not exist yet, filling it with
+/// the foods already stored. Returns whether the index is available.
+async fn full_text_index(pool: &SqlitePool) -> Result<bool> {
+    let exists = sqlx::query("SELECT 1 FROM sqlite_master WHERE name = 'foods_fts'")
+        .fetch_optional(pool)
+        .await?
+        .is_some();
+    if exists {
+        return Ok(true);
+    }
+    if sqlx::query(FTS_SCHEMA).execute(pool).await.is_err() {
+        return Ok(false);
+    }
+    let rows = sqlx::query("SELECT fdc_id, description, brand FROM foods")
+        .fetch_all(pool)
+        .await?;
+    for row in rows {
+        let brand = row.try_get::<Option<String>, _>("brand")?;
+        index_food(
+            pool,
+            row.try_get("fdc_id")?,
+            row.try_get("description")?,
+            brand.as_deref(),
+        )
+        .await?;
+    }
+    Ok(true)
+}
+
+/// Add a food to the full-text index, with its description and brand normalized once so that
+/// searches need not.
+async fn index_food(
+    pool: &SqlitePool,
+    fdc_id: i32,
+    description: &str,
+    brand: Option<&str>,
+) -> Result<()> {
+    sqlx::query("INSERT INTO foods_fts (rowid, description, brand) VALUES (?, ?, ?)")
+        .bind(fdc_id)
+        .bind(normalize(description))
+        .bind(normalize(brand.unwrap_or("")))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[async_trait]
+impl FoodStore for SqliteStore {
+    async fn upsert_food_expiring(
+        &self,
+        food: &FDCMeta,
+        expires_at: Option<SystemTime>,
+    ) -> Result<()> {
+        let expires_at = expires_at
+            .map(|t| t.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64))
+            .transpose()
+            .map_err(|e| StorageError::Invalid(format!("Invalid expiry: {}", e)))?;
+        sqlx::query(
+            "INSERT INTO foods (fdc_id, description, data_type, data, expires_at, brand)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT (fdc_id) DO UPDATE SET
+                description = excluded.description,
+                data_type = excluded.data_type,
+                data = excluded.data,
+                expires_at = excluded.expires_at,
+                brand = excluded.brand",
+        )
+        .bind(food.fdc_id())
+        .bind(food.description())
+        .bind(food.data_type())
+        .bind(serde_json::to_string(food)?)
+        .bind(expires_at)
+        .bind(food.brand())
+        .execute(&self.pool)
+        .await?;
+        if self.fts {
+            sqlx::query("DELETE FROM foods_fts WHERE rowid = ?")
+                .bind(food.fdc_id())
+                .execute(&self.pool)
+                .await?;
+            index_food(&self.pool, food.fdc_id(), food.description(), food.brand()).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_stored_food(&self, fdc_id: i32) -> Result<Option<StoredFood>> {
+        let row = sqlx::query("SELECT data, expires_at FROM foods WHERE fdc_id = ?")
+            .bind(fdc_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        match row {
+            Some(row) => Ok(Some(StoredFood {
+                food: serde_json::from_str(row.try_get("data")?)?,
+                expires_at: row
+                    .try_get::<Option<i64>, _>("expires_at")?
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)),
+            })),
+            None => Ok(None),
+        }
+    }
+
+    async fn search_local(&self, text: &str) -> Result<Vec<AbridgedFoodItem>> {
+        if self.fts {
+            return self.search_fts(&search::tokens(text)).await;
+        }
+        let rows = sqlx::query("SELECT fdc_id, description, data_type, brand FROM foods")
+            .fetch_all(&self.pool)
+            .await?;
+        let foods = rows
+            .iter()
+            .map(|row| {
+                let food = AbridgedFoodItem {
+                    fdc_id: row.try_get("fdc_id")?,
+                    data_type: row.try_get("data_type")?,
+                    description: row.try_get("description")?,
+                    food_nutrients: Vec::new(),
+                    score: None,
+                    publication_date: None,
+                };
+                Ok((food, row.try_get("brand")?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(search::rank(text, foods))
+    }
+
+    async fn delete_food(&self, fdc_id: i32) -> Result<bool> {
+        let res = sqlx::query("DELETE FROM foods WHERE fdc_id = ?")
+            .bind(fdc_id)
+            .execute(&self.pool)
+            .await?;
+        if self.fts {
+            sqlx::query("DELETE FROM foods_fts WHERE rowid = ?")
+                .bind(fdc_id)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(res.rows_affected() > 0)
+    }
+}
+
+/// Read a log entry back from a row of `log_entries`. Times are stored as microseconds since the
+/// epoch.
+fn log_entry(row: &SqliteRow) -> Result<LogEntry> {
+    let eaten_at = row.try_get::<i64, _>("eaten_at")?;
+    let id = row.try_get::<String, _>("id")?;
+    Ok(LogEntry {
+        id: id
+            .parse()
+            .map_err(|e| StorageError::Invalid(format!("Invalid log entry id {}: {}", id, e)))?,
+        fdc_id: row.try_get("fdc_id")?,
+        quantity: serde_json::from_str(row.try_get("quantity")?)?,
+        eaten_at: DateTime::from_timestamp_micros(eaten_at).ok_or_else(|| {
+            StorageError::Invalid(format!("Invalid eaten_at timestamp {}", eaten_at))
+        })?,
+        note: row.try_get("note")?,
+        source_text: row.try_get("source_text")?,
+    })
+}
+
+#[async_trait]
+impl FoodLog for SqliteStore {
+    async fn add(&self, entry: &LogEntry) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO log_entries
+                (id, fdc_id, quantity, eaten_at, note, source_text)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(entry.id.to_string())
+        .bind(entry.fdc_id)
+        .bind(serde_json::to_string(&entry.quantity)?)
+        .bind(entry.eaten_at.timestamp_micros())
+        .bind(&entry.note)
+        .bind(&entry.source_text)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, id: uuid::Uuid) -> Result<bool> {
+        let res = sqlx::query("DELETE FROM log_entries WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn entries_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<LogEntry>> {
+        sqlx::query(
+            "SELECT * FROM log_entries WHERE eaten_at >= ? AND eaten_at < ? ORDER BY eaten_at",
+        )
+        .bind(start.timestamp_micros())
+        .bind(end.timestamp_micros())
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(log_entry)
+        .collect()
+    }
+}
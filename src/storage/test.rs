@@ -0,0 +1,427 @@
+use super::*;
+use crate::fdc::{AbridgedFoodItem, FDCMeta};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+fn branded() -> FDCMeta {
+    serde_json::from_value(serde_json::json!({
+        "dataType": "Branded",
+        "fdcId": 1455408,
+        "description": "WESSON Canola Oil 24 FL OZ",
+        "brandOwner": "Conagra Brands",
+        "gtinUpc": "00027000690260",
+        "ingredients": "CANOLA OIL.",
+        "servingSize": 15.0,
+        "servingSizeUnit": "ml",
+    }))
+    .unwrap()
+}
+
+fn legacy() -> FDCMeta {
+    serde_json::from_value(serde_json::json!({
+        "dataType": "SR Legacy",
+        "fdcId": 173414,
+        "description": "Cheese, cheddar",
+        "foodAttributes": [],
+        "foodPortions": [{
+            "id": 92296,
+            "amount": 1.0,
+            "gramWeight": 132.0,
+            "modifier": "cup, diced",
+            "sequenceNumber": 1,
+        }],
+    }))
+    .unwrap()
+}
+
+async fn memory() -> SqliteStore {
+    SqliteStore::connect("sqlite::memory:").await.unwrap()
+}
+
+#[tokio::test]
+async fn open_unsupported_scheme() {
+    assert!(open("postgres://localhost/foods").await.is_err());
+    assert!(open("sqlite::memory:").await.is_ok());
+}
+
+#[tokio::test]
+async fn upsert_and_get() {
+    let store = memory().await;
+    assert!(store.get_food(1455408).await.unwrap().is_none());
+    store.upsert_food(&branded()).await.unwrap();
+    store.upsert_food(&legacy()).await.unwrap();
+    match store.get_food(1455408).await.unwrap() {
+        Some(FDCMeta::Branded(food)) => {
+            assert_eq!(food.description, "WESSON Canola Oil 24 FL OZ");
+            assert_eq!(food.gtin_upc.as_deref(), Some("00027000690260"));
+        }
+        other => panic!("Should have been a branded food, got {:?}", other),
+    }
+    match store.get_food(173414).await.unwrap() {
+        Some(FDCMeta::Other(food)) => assert_eq!(food.food_portions[0].gram_weight, 132.0),
+        other => panic!("Should have been a legacy food, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn upsert_replaces() {
+    let store = memory().await;
+    store.upsert_food(&legacy()).await.unwrap();
+    let mut food = legacy();
+    if let FDCMeta::Other(food) = &mut food {
+        food.description = "Cheese, cheddar, sharp".into();
+    }
+    store.upsert_food(&food).await.unwrap();
+    let found = store.search_local("cheddar").await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].description, "Cheese, cheddar, sharp");
+}
+
+#[tokio::test]
+async fn search_local() {
+    let store = memory().await;
+    store.upsert_food(&branded()).await.unwrap();
+    store.upsert_food(&legacy()).await.unwrap();
+    let found = store.search_local("CANOLA").await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].fdc_id, 1455408);
+    assert_eq!(found[0].data_type, "Branded");
+    assert!(store.search_local("butter").await.unwrap().is_empty());
+
+    // other foods keep the data type FDC gave them, in the index and in the stored food
+    let found = store.search_local("cheddar").await.unwrap();
+    assert_eq!(found[0].data_type, "SR Legacy");
+    let food = store.get_food(173414).await.unwrap().unwrap();
+    assert_eq!(food.data_type(), "SR Legacy");
+}
+
+/// A small corpus of (FDC id, description, brand) to rank searches over.
+const CORPUS: &[(i32, &str, Option<&str>)] = &[
+    (173414, "Cheese, cheddar", None),
+    (2001, "CHEESE FLAVORED CRACKERS", Some("Snack Factory")),
+    (
+        2002,
+        "CHEDDAR CHEESE FLAVORED CRACKERS, BAKED, FAMILY SIZE",
+        Some("Goldfish"),
+    ),
+    (2003, "Cheese, swiss", None),
+    (2004, "SHARP CHEDDAR", Some("Tillamook")),
+    (2005, "Blueberries, raw", None),
+    (2006, "Peppers, jalapeño, raw", None),
+    (2007, "CAFÉ MOCHA", Some("Nestlé")),
+];
+
+fn corpus() -> Vec<FDCMeta> {
+    CORPUS
+        .iter()
+        .map(|(fdc_id, description, brand)| {
+            let food = match brand {
+                Some(brand) => serde_json::json!({
+                    "dataType": "Branded",
+                    "fdcId": fdc_id,
+                    "description": description,
+                    "brandOwner": brand,
+                    "ingredients": "",
+                    "servingSize": 30.0,
+                    "servingSizeUnit": "g",
+                }),
+                None => serde_json::json!({
+                    "dataType": "Other",
+                    "fdcId": fdc_id,
+                    "description": description,
+                    "foodAttributes": [],
+                    "foodPortions": [],
+                }),
+            };
+            serde_json::from_value(food).unwrap()
+        })
+        .collect()
+}
+
+/// The queries [`check_ranking`] looks at.
+const QUERIES: &[&str] = &[
+    "cheddar cheese",
+    "blueberry",
+    "tillamook cheddar",
+    "Cracker",
+    "swiss butter",
+    "butter",
+    "  ,",
+    "jalapeno",
+    "JALAPEÑO PEPPERS",
+    "cafe nestle",
+];
+
+/// Check the ranking of the results of each of [`QUERIES`] over the corpus, whichever way the
+/// search was made.
+fn check_ranking(results: &HashMap<&str, Vec<AbridgedFoodItem>>) {
+    let ids = |query: &str| results[query].iter().map(|f| f.fdc_id).collect::<Vec<_>>();
+
+    // every token must match, and the plain cheddar is the most relevant
+    let found = ids("cheddar cheese");
+    assert_eq!(found[0], 173414);
+    assert!(found.contains(&2002));
+    assert!(!found.contains(&2001));
+    assert!(!found.contains(&2003));
+    assert!(results["cheddar cheese"].iter().all(|f| f.score.is_some()));
+
+    // plurals and brands match
+    assert_eq!(ids("blueberry"), vec![2005]);
+    assert_eq!(ids("tillamook cheddar"), vec![2004]);
+    let mut found = ids("Cracker");
+    found.sort();
+    assert_eq!(found, vec![2001, 2002]);
+
+    // without a food having every token, any token will do
+    assert_eq!(ids("swiss butter"), vec![2003]);
+    assert!(ids("butter").is_empty());
+    assert!(ids("  ,").is_empty());
+
+    // diacritics match either way
+    assert_eq!(ids("jalapeno"), vec![2006]);
+    assert_eq!(ids("JALAPEÑO PEPPERS"), vec![2006]);
+    assert_eq!(ids("cafe nestle"), vec![2007]);
+}
+
+#[test]
+fn rank_corpus() {
+    let foods = corpus()
+        .into_iter()
+        .map(|food| {
+            let brand = food.brand().map(String::from);
+            let item = AbridgedFoodItem {
+                fdc_id: food.fdc_id(),
+                data_type: food.data_type().into(),
+                description: food.description().into(),
+                food_nutrients: Vec::new(),
+                score: None,
+                publication_date: None,
+            };
+            (item, brand)
+        })
+        .collect::<Vec<_>>();
+    let results = QUERIES
+        .iter()
+        .map(|query| (*query, search::rank(query, foods.clone())))
+        .collect();
+    check_ranking(&results);
+    assert_eq!(
+        search::tokens("Peaches, sliced (in syrup)"),
+        vec!["peach", "sliced", "in", "syrup"]
+    );
+    assert_eq!(search::tokens("Crème brûlée"), vec!["creme", "brulee"]);
+}
+
+#[tokio::test]
+async fn search_full_text() {
+    let store = memory().await;
+    assert!(store.has_full_text_index());
+    for food in corpus() {
+        store.upsert_food(&food).await.unwrap();
+    }
+    let mut results = HashMap::new();
+    for query in QUERIES {
+        results.insert(*query, store.search_local(query).await.unwrap());
+    }
+    check_ranking(&results);
+}
+
+#[tokio::test]
+async fn delete() {
+    let store = memory().await;
+    store.upsert_food(&legacy()).await.unwrap();
+    assert!(store.delete_food(173414).await.unwrap());
+    assert!(!store.delete_food(173414).await.unwrap());
+    assert!(store.get_food(173414).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn on_disk_persists() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("sqlite://{}", dir.path().join("foods.db").display());
+    {
+        let store = open(&url).await.unwrap();
+        store.upsert_food(&branded()).await.unwrap();
+    }
+    let store = open(&url).await.unwrap();
+    assert_eq!(
+        store.get_food(1455408).await.unwrap().map(|f| f.fdc_id()),
+        Some(1455408)
+    );
+}
+
+#[tokio::test]
+async fn migrates_missing_columns() {
+    use crate::log::FoodLog;
+    use crate::quantities::Quantity;
+    use chrono::{DateTime, Utc};
+
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("sqlite://{}", dir.path().join("foods.db").display());
+    {
+        let options = sqlx::sqlite::SqliteConnectOptions::from_str(&url)
+            .unwrap()
+            .create_if_missing(true);
+        let pool = sqlx::SqlitePool::connect_with(options).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE foods (fdc_id INTEGER PRIMARY KEY, description TEXT NOT NULL,
+             data_type TEXT NOT NULL, data TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE log_entries (id TEXT PRIMARY KEY, fdc_id INTEGER NOT NULL,
+             quantity TEXT NOT NULL, eaten_at INTEGER NOT NULL, note TEXT)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO log_entries VALUES
+             ('67e55044-10b1-426f-9247-bb680e5fe0c8', 173414,
+              '{\"kind\":\"nominal\",\"amount\":1.0,\"name\":\"stick\"}', 0, NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+    }
+    let store = SqliteStore::connect(&url).await.unwrap();
+    store.upsert_food(&legacy()).await.unwrap();
+    let stored = store.get_stored_food(173414).await.unwrap().unwrap();
+    assert!(stored.expires_at.is_none());
+
+    // entries logged before source texts were kept have none
+    let entries = store
+        .entries_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].quantity, Quantity::Nominal(1.0, "stick".into()));
+    assert_eq!(entries[0].source_text, None);
+    let entry = entries[0].clone().with_source_text("1 stick");
+    store.add(&entry).await.unwrap();
+    let entries = store
+        .entries_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+        .await
+        .unwrap();
+    assert_eq!(entries[0].source_text.as_deref(), Some("1 stick"));
+}
+
+mod cache {
+    use super::*;
+    use crate::fdc::FDCService;
+    use crate::progress::CancellationToken;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    /// Respond to "v1/foods" with a minimal foundation food for each requested id.
+    fn echo_foods(req: &Request) -> ResponseTemplate {
+        let body: serde_json::Value = serde_json::from_slice(&req.body).unwrap();
+        let foods = body["fdcIds"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|id| {
+                serde_json::json!({
+                    "dataType": "Foundation",
+                    "fdcId": id,
+                    "description": format!("Food {}", id),
+                    "foodAttributes": [],
+                    "foodPortions": [],
+                })
+            })
+            .collect::<Vec<_>>();
+        ResponseTemplate::new(200).set_body_json(foods)
+    }
+
+    async fn cached(server: &MockServer) -> CachedFoods<SqliteStore> {
+        Mock::given(method("POST"))
+            .and(path("/v1/foods"))
+            .respond_with(echo_foods)
+            .mount(server)
+            .await;
+        let service = FDCService::new("DEMO_KEY").with_base_url(server.uri());
+        CachedFoods::new(service, reqwest::Client::new(), memory().await)
+    }
+
+    #[tokio::test]
+    async fn second_call_is_local() {
+        let server = MockServer::start().await;
+        let cached = cached(&server).await;
+        let ids = [3, 1, 2, 1];
+        let foods = cached.get_or_fetch(&ids).await.unwrap();
+        assert_eq!(foods.iter().map(|f| f.fdc_id()).collect::<Vec<_>>(), ids);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        let foods = cached.get_or_fetch(&ids).await.unwrap();
+        assert_eq!(foods.iter().map(|f| f.fdc_id()).collect::<Vec<_>>(), ids);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn fetches_in_chunks() {
+        let server = MockServer::start().await;
+        let cached = cached(&server).await;
+        let ids = (1..=45).collect::<Vec<_>>();
+        let foods = cached.get_or_fetch(&ids).await.unwrap();
+        assert_eq!(foods.len(), 45);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        // only the new ids are fetched
+        cached.get_or_fetch(&[44, 45, 46]).await.unwrap();
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 4);
+        let body: serde_json::Value = serde_json::from_slice(&requests[3].body).unwrap();
+        assert_eq!(body["fdcIds"], serde_json::json!([46]));
+    }
+
+    #[tokio::test]
+    async fn cancelled_fetch_keeps_chunks() {
+        let server = MockServer::start().await;
+        let cached = cached(&server).await;
+        let cancel = CancellationToken::new();
+        let ids = (1..=100).collect::<Vec<_>>();
+        let err = cached
+            .get_or_fetch_with(&ids, |_| cancel.cancel(), &cancel)
+            .await
+            .unwrap_err();
+        let cancelled = match err {
+            crate::Error::Cancelled(cancelled) => cancelled,
+            e => panic!("Should have been cancelled: {}", e),
+        };
+        assert_eq!(cancelled.progress.completed, 20);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        // the first chunk was persisted, so only the rest is fetched
+        let foods = cached.get_or_fetch(&ids).await.unwrap();
+        assert_eq!(foods.len(), 100);
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 5);
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(body["fdcIds"][0], 21);
+    }
+
+    #[tokio::test]
+    async fn expired_foods_are_refetched() {
+        let server = MockServer::start().await;
+        let cached = cached(&server).await.with_ttl(Duration::from_secs(0));
+        cached.get_or_fetch(&[1]).await.unwrap();
+        cached.get_or_fetch(&[1]).await.unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn refresh_forces_fetch() {
+        let server = MockServer::start().await;
+        let cached = cached(&server).await;
+        cached.get_or_fetch(&[7]).await.unwrap();
+        let food = cached.refresh(7).await.unwrap();
+        assert_eq!(food.description(), "Food 7");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+        let stored = cached.store.get_stored_food(7).await.unwrap().unwrap();
+        assert!(stored.is_fresh(std::time::SystemTime::now()));
+    }
+}
@@ -0,0 +1,67 @@
+//! This module normalizes text for matching through [`normalize`], so that "Jalapeño" matches
+//! "jalapeno" and "CAFÉ" matches "cafe" in local search, re-ranking, and deduplication. It also
+//! guesses the brand of a search query through [`split_brand`].
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Lowercase the text, strip the diacritics of Latin letters, and collapse runs of whitespace into
+/// single spaces. Characters are decomposed for compatibility (NFKD), so that ligatures like "ﬁ"
+/// and full-width letters become plain ones, but characters which do not decompose to ASCII, like
+/// those of non-Latin scripts, are kept as they are, along with their combining marks.
+pub fn normalize(s: &str) -> String {
+    let mut normalized = String::with_capacity(s.len());
+    // whether the last character became ASCII, so that the marks following it are stripped
+    let mut latin = true;
+    for c in s.to_lowercase().chars() {
+        if is_combining_mark(c) {
+            if !latin {
+                normalized.push(c);
+            }
+            continue;
+        }
+        let decomposed = std::iter::once(c)
+            .nfkd()
+            .filter(|c| !is_combining_mark(*c))
+            .collect::<String>();
+        latin = decomposed.is_ascii();
+        if latin {
+            normalized.extend(decomposed.chars().flat_map(char::to_lowercase));
+        } else {
+            normalized.push(c);
+        }
+    }
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Guess the brand of a query as the run of words in capitals it starts with, like "KIND" in
+/// "KIND dark chocolate bar", and split it from the product after it. Brands of several words may
+/// be joined by "&", like "BEN & JERRY'S". Returns `None` when the query does not start with such a
+/// brand or has no product after it.
+pub fn split_brand(query: &str) -> Option<(String, String)> {
+    let words = query.split_whitespace().collect::<Vec<_>>();
+    let mut brand = words.iter().take_while(|word| brand_word(word)).count();
+    // a brand neither ends with nor is only a connective
+    while brand > 0 && words[brand - 1] == "&" {
+        brand -= 1;
+    }
+    if brand == 0 || brand == words.len() {
+        return None;
+    }
+    Some((words[..brand].join(" "), words[brand..].join(" ")))
+}
+
+/// Whether the word may be part of a brand: "&", or a word in capitals of at least two letters.
+fn brand_word(word: &str) -> bool {
+    if word == "&" {
+        return true;
+    }
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    letters >= 2
+        && word
+            .chars()
+            .all(|c| c.is_uppercase() || c.is_numeric() || "&'-.!".contains(c))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,79 @@
+use super::*;
+use proptest::prelude::*;
+
+#[test]
+fn accents() {
+    let cases = [
+        ("jalapeño", "jalapeno"),
+        ("Jalapeño Peppers", "jalapeno peppers"),
+        ("CAFÉ", "cafe"),
+        ("café au lait", "cafe au lait"),
+        // already decomposed
+        ("cafe\u{301}", "cafe"),
+        ("Crème Brûlée", "creme brulee"),
+        ("Häagen-Dazs", "haagen-dazs"),
+        ("Piña colada", "pina colada"),
+        ("Ångström", "angstrom"),
+        ("naïve façade", "naive facade"),
+        ("ﬁlet", "filet"),
+        ("ＴＯＦＵ", "tofu"),
+        ("  whole \t milk\n", "whole milk"),
+        ("nbsp\u{a0}space", "nbsp space"),
+        ("", ""),
+    ];
+    for (text, expected) in cases.iter() {
+        assert_eq!(normalize(text), *expected, "{:?}", text);
+    }
+}
+
+#[test]
+fn other_scripts() {
+    // lowercased, but neither stripped nor decomposed
+    assert_eq!(normalize("Σούπα"), "σούπα");
+    assert_eq!(normalize("豆腐"), "豆腐");
+    assert_eq!(normalize("がんも"), "がんも");
+    assert_eq!(normalize("김치"), "김치");
+    assert_eq!(normalize("दही"), "दही");
+    assert_eq!(normalize("Ærøskøbing smør"), "ærøskøbing smør");
+}
+
+proptest! {
+    #[test]
+    fn idempotent(s in "\\PC*") {
+        let once = normalize(&s);
+        prop_assert_eq!(normalize(&once), once);
+    }
+
+    #[test]
+    fn keeps_ascii_words(s in "[a-zA-Z0-9 ]*") {
+        let words = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        prop_assert_eq!(normalize(&s), words.to_lowercase());
+    }
+}
+
+#[test]
+fn split_brand() {
+    let split = |query| super::split_brand(query);
+    let pair = |brand: &str, product: &str| Some((brand.to_string(), product.to_string()));
+    assert_eq!(
+        split("KIND dark chocolate bar"),
+        pair("KIND", "dark chocolate bar")
+    );
+    assert_eq!(
+        split("  BEN & JERRY'S  cookie dough"),
+        pair("BEN & JERRY'S", "cookie dough")
+    );
+    assert_eq!(split("M&M'S peanut"), pair("M&M'S", "peanut"));
+    assert_eq!(
+        split("NESTLÉ TOLL HOUSE morsels"),
+        pair("NESTLÉ TOLL HOUSE", "morsels")
+    );
+    assert_eq!(split("7UP lime"), pair("7UP", "lime"));
+    // no words in capitals first, or nothing but them
+    assert_eq!(split("dark chocolate KIND bar"), None);
+    assert_eq!(split("Kind dark chocolate"), None);
+    assert_eq!(split("A banana"), None);
+    assert_eq!(split("& cheese"), None);
+    assert_eq!(split("CHEDDAR CHEESE"), None);
+    assert_eq!(split(""), None);
+}
@@ -0,0 +1,118 @@
+//! This module collects the judgment calls of lossy operations through the [`Warnings`] type, like
+//! quantities resolved by convention or nutrients dropped for their unit, so that callers can
+//! surface them without every result carrying them.
+
+use crate::nutrition::Nutrient;
+use crate::quantities::Quantity;
+
+use std::fmt;
+
+/// A guess made, or data dropped, while working on a food.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// A nominal quantity was resolved by convention rather than by a portion or serving of its
+    /// name, like `"1 serving"` read as the serving size, or `"2 slices"` read as a portion which
+    /// only shares words with it.
+    NominalByConvention {
+        fdc_id: i32,
+        quantity: Quantity,
+        /// What the quantity was read as, like `"servings of 42 g"`.
+        resolved_as: String,
+    },
+    /// A nutrient of a food was left out, as its amount is in a unit it cannot be converted from.
+    UnknownNutrientUnit {
+        fdc_id: i32,
+        nutrient: Nutrient,
+        /// The FDC id of the nutrient as reported, one of [`Nutrient::fdc_ids`].
+        nutrient_id: i32,
+        unit: String,
+    },
+    /// A duplicate search result was folded into a more recent submission of the same product.
+    Folded { fdc_id: i32, into: i32 },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::NominalByConvention {
+                fdc_id,
+                quantity,
+                resolved_as,
+            } => write!(f, "Food {}: read {} as {}", fdc_id, quantity, resolved_as),
+            Warning::UnknownNutrientUnit {
+                fdc_id,
+                nutrient,
+                nutrient_id,
+                unit,
+            } => write!(
+                f,
+                "Food {}: left out {} (nutrient {}), whose unit \"{}\" is unknown",
+                fdc_id,
+                nutrient.name(),
+                nutrient_id,
+                unit
+            ),
+            Warning::Folded { fdc_id, into } => write!(
+                f,
+                "Food {}: folded into food {}, a more recent submission of the same product",
+                fdc_id, into
+            ),
+        }
+    }
+}
+
+/// The warnings of one or more operations, in the order they were made.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Warnings(Vec<Warning>);
+
+impl Warnings {
+    /// generate a new Warnings without any warning
+    pub fn new() -> Warnings {
+        Warnings::default()
+    }
+
+    /// Add the warning after the others.
+    pub fn push(&mut self, warning: Warning) {
+        self.0.push(warning);
+    }
+
+    /// The number of warnings.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there is no warning.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The warnings, in the order they were made.
+    pub fn iter(&self) -> impl Iterator<Item = &Warning> {
+        self.0.iter()
+    }
+
+    /// A line for each warning, like `"warning: Food 1: read 2 servings as servings of 42 g"`, for
+    /// the command line.
+    pub fn render(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|warning| format!("warning: {}", warning))
+            .collect()
+    }
+}
+
+impl From<Warnings> for Vec<Warning> {
+    fn from(warnings: Warnings) -> Vec<Warning> {
+        warnings.0
+    }
+}
+
+/// Push the warning when warnings are collected.
+pub(crate) fn warn(warnings: &mut Option<&mut Warnings>, warning: Warning) {
+    if let Some(warnings) = warnings {
+        warnings.push(warning);
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,115 @@
+use super::{Warning, Warnings};
+use crate::dedup;
+use crate::fdc::test_util::{BrandedFoodBuilder, FoodBuilder};
+use crate::fdc::{AbridgedFoodItem, FDCMeta};
+use crate::nutrition::{self, Nutrient};
+use crate::quantities::Quantity;
+use crate::resolve;
+
+#[test]
+fn nominal_by_convention() {
+    let bar = BrandedFoodBuilder::new(1)
+        .serving(42.0, "g")
+        .household_serving("1 bar")
+        .label_nutrient(Nutrient::Protein, 4.0)
+        .meta();
+    let servings = Quantity::Nominal(2.0, "servings".into());
+    let mut warnings = Warnings::new();
+    let profile = nutrition::profile_for(&bar, &servings, Some(&mut warnings)).unwrap();
+    assert_eq!(profile.get(Nutrient::Protein), Some(8.0));
+    let expected = Warning::NominalByConvention {
+        fdc_id: 1,
+        quantity: servings.clone(),
+        resolved_as: "servings of 42 g".into(),
+    };
+    let mut resolved = Warnings::new();
+    resolve::to_grams(&servings, &bar, Some(&mut resolved)).unwrap();
+    assert_eq!(resolved, warnings);
+    assert_eq!(
+        warnings.render(),
+        ["warning: Food 1: read 2 servings as servings of 42 g"]
+    );
+    assert_eq!(Vec::from(warnings), [expected]);
+
+    // a portion sharing a word is a guess, while a portion of the name is not
+    let bread = FoodBuilder::new(2)
+        .food_nutrient(Nutrient::Protein, 10.0)
+        .portion(25.0, "1 slice, large")
+        .meta();
+    let slices = Quantity::Nominal(2.0, "large slices".into());
+    let mut warnings = Warnings::new();
+    nutrition::profile_for(&bread, &slices, Some(&mut warnings)).unwrap();
+    assert_eq!(
+        warnings.render(),
+        ["warning: Food 2: read 2 large slices as the portion \"1 slice, large\" (25 g)"]
+    );
+    let mut warnings = Warnings::new();
+    let slice = Quantity::Nominal(1.0, "slice".into());
+    nutrition::profile_for(&bread, &slice, Some(&mut warnings)).unwrap();
+    assert!(warnings.is_empty());
+    let grams = Quantity::from_unit(30.0, "g").unwrap();
+    nutrition::profile_for(&bar, &grams, Some(&mut warnings)).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn unknown_nutrient_unit() {
+    let mut food = FoodBuilder::new(3)
+        .food_nutrient(Nutrient::Protein, 10.0)
+        .food_nutrient(Nutrient::VitaminD, 40.0)
+        .build();
+    food.food_nutrients[1].nutrient.unit_name = "IU".into();
+    let food = FDCMeta::Other(food);
+    let grams = Quantity::from_unit(50.0, "g").unwrap();
+    let mut warnings = Warnings::new();
+    let profile = nutrition::profile_for(&food, &grams, Some(&mut warnings)).unwrap();
+    assert_eq!(profile.get(Nutrient::Protein), Some(5.0));
+    assert_eq!(profile.get(Nutrient::VitaminD), None);
+    assert_eq!(
+        Vec::from(warnings.clone()),
+        [Warning::UnknownNutrientUnit {
+            fdc_id: 3,
+            nutrient: Nutrient::VitaminD,
+            nutrient_id: Nutrient::VitaminD.fdc_ids()[0],
+            unit: "IU".into(),
+        }]
+    );
+    assert!(warnings.render()[0].contains("whose unit \"IU\" is unknown"));
+
+    // without a collector, the profile is the same
+    assert_eq!(
+        nutrition::profile_for(&food, &grams, None).unwrap(),
+        profile
+    );
+}
+
+#[test]
+fn folded_duplicates() {
+    let details = [1, 2]
+        .iter()
+        .map(|&fdc_id| {
+            BrandedFoodBuilder::new(fdc_id)
+                .gtin("027000612323")
+                .published(format!("2021-0{}-01", fdc_id))
+                .build()
+        })
+        .collect::<Vec<_>>();
+    let results = [1, 2]
+        .iter()
+        .map(|&fdc_id| AbridgedFoodItem {
+            fdc_id,
+            data_type: "Branded".into(),
+            description: "WESSON OIL".into(),
+            food_nutrients: Vec::new(),
+            score: None,
+            publication_date: None,
+        })
+        .collect();
+    let mut warnings = Warnings::new();
+    let folded = dedup::branded(results, &details, Some(&mut warnings));
+    assert_eq!(folded.len(), 1);
+    assert_eq!(
+        Vec::from(warnings),
+        [Warning::Folded { fdc_id: 1, into: 2 }]
+    );
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "cli")]
+
+use assert_cmd::Command;
+
+fn nutrack() -> Command {
+    let mut cmd = Command::cargo_bin("nutrack").unwrap();
+    // parsing needs no configuration, so make sure none leaks in
+    cmd.env_remove("FDC_KEY").env_remove("DATABASE_URL");
+    cmd
+}
+
+#[test]
+fn parse_prints_quantities() {
+    let output = nutrack()
+        .args(["parse", "1 cup (240 ml)"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.starts_with("Volume(")));
+}
+
+#[test]
+fn parse_reports_errors_without_backtrace() {
+    let assert = nutrack()
+        .args(["parse", "some amount of stuff"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(stderr.starts_with("Error: Cannot parse \"some amount of stuff\""));
+    assert!(!stderr.contains("Stack backtrace"));
+}
@@ -0,0 +1,92 @@
+//! Measures the peak memory of reading a large "v1/foods" response, to check that foods are
+//! deserialized straight from the body rather than through a `serde_json::Value` of all of it.
+
+#![cfg(all(feature = "fdc", feature = "env", feature = "storage"))]
+
+use nutrition::fdc::{FDCMeta, TransportResponse};
+
+use serde_json::{json, Value};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An allocator which counts the bytes in use and the most that were at once.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(current, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// The most bytes allocated at once while running the function, beyond those in use before it.
+fn peak_during<T>(f: impl FnOnce() -> T) -> usize {
+    let before = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    drop(f());
+    PEAK.load(Ordering::SeqCst) - before
+}
+
+/// A response of 20 branded foods in the "full" format with a thousand nutrients each, like the
+/// largest chunks FDC answers with.
+fn large_response() -> Vec<u8> {
+    let nutrient = |id: i32| {
+        json!({
+            "id": id,
+            "nutrient": { "id": id, "number": id.to_string(), "name": "Nutrient", "unitName": "mg" },
+            "amount": 1.5,
+            "dataPoints": 3,
+            "foodNutrientDerivation": {
+                "id": 70,
+                "code": "LCCS",
+                "description": "Calculated from value per serving size measure",
+                "foodNutrientSource": { "id": 9, "code": "12", "description": "Manufacturer's analytical" },
+            },
+        })
+    };
+    let foods = (1..=20)
+        .map(|fdc_id| {
+            json!({
+                "dataType": "Branded",
+                "fdcId": fdc_id,
+                "description": "CHEDDAR CHEESE",
+                "ingredients": "PASTEURIZED MILK, CHEESE CULTURE, SALT, ENZYMES",
+                "servingSize": 28.0,
+                "servingSizeUnit": "g",
+                "foodNutrients": (1000..2000).map(nutrient).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_vec(&foods).unwrap()
+}
+
+#[test]
+fn v1_foods_peak_memory() {
+    let body = large_response();
+    let res = TransportResponse::new(200, body.clone());
+
+    // the way foods used to be read, through a value of the whole body
+    let buffered = peak_during(|| {
+        let value: Value = serde_json::from_slice(&body).unwrap();
+        serde_json::from_value::<Vec<FDCMeta>>(value).unwrap()
+    });
+    let streamed = peak_during(|| res.json::<Vec<FDCMeta>>().unwrap());
+    assert!(
+        streamed * 2 < buffered,
+        "streamed peak of {} bytes, buffered peak of {} bytes",
+        streamed,
+        buffered
+    );
+}
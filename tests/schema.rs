@@ -0,0 +1,263 @@
+//! Deserializes the FDC responses checked into `tests/fixtures` through the public types, so that
+//! changes to the types which would break on real responses are caught. The ignored `canary` test
+//! fetches the same foods live and reports how their keys drifted from the fixtures.
+
+#![cfg(all(feature = "fdc", feature = "env", feature = "storage"))]
+
+use nutrition::fdc::{
+    APFoodItem, AbridgedFoodItem, AttributeKind, BrandedFoodItem, FDCMeta, FDCService,
+};
+
+use nutrition::nutrition::{Nutrient, NutrientProfile};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// The fixtures of single foods, with their FDC ids.
+const FOODS: &[(&str, i32)] = &[
+    ("branded", 1105904),
+    ("foundation", 748967),
+    ("sr_legacy", 171287),
+    ("survey", 1100618),
+];
+
+/// The text of the fixture, as FDC sent it.
+fn fixture_text(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .with_extension("json");
+    std::fs::read_to_string(path).unwrap()
+}
+
+fn fixture(name: &str) -> Value {
+    serde_json::from_str(&fixture_text(name)).unwrap()
+}
+
+/// The top-level keys of `new` which `old` does not have, and those of `old` which `new` does not
+/// have.
+fn key_diff(old: &Value, new: &Value) -> (Vec<String>, Vec<String>) {
+    let keys = |value: &Value| -> BTreeSet<String> {
+        value
+            .as_object()
+            .map(|object| object.keys().cloned().collect())
+            .unwrap_or_default()
+    };
+    let (old, new) = (keys(old), keys(new));
+    (
+        new.difference(&old).cloned().collect(),
+        old.difference(&new).cloned().collect(),
+    )
+}
+
+/// Deserialize a food as it is written by FDC, whatever its `dataType`.
+fn food(value: Value) -> FDCMeta {
+    serde_json::from_value(value).unwrap()
+}
+
+/// Serialize a food back through the public types, tagged with its variant.
+fn reserialize(food: &FDCMeta) -> Value {
+    serde_json::to_value(food).unwrap()
+}
+
+#[test]
+fn search() {
+    let mut page = fixture("search");
+    let foods: Vec<AbridgedFoodItem> = serde_json::from_value(page["foods"].take()).unwrap();
+    assert_eq!(
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>(),
+        [328637, 2019736]
+    );
+    assert_eq!(foods[0].data_type, "Foundation");
+    assert_eq!(foods[0].score, Some(874.5));
+    assert_eq!(foods[0].macros().protein_g, Some(23.3));
+    assert_eq!(foods[1].macros().calories, Some(393.0));
+    assert_eq!(
+        foods[1].published(),
+        chrono::NaiveDate::from_ymd_opt(2021, 10, 28)
+    );
+}
+
+#[test]
+fn branded() {
+    let item: BrandedFoodItem = serde_json::from_value(fixture("branded")).unwrap();
+    assert_eq!(item.fdc_id, 1105904);
+    assert_eq!(item.gtin_upc.as_deref(), Some("027000612323"));
+    assert_eq!(item.serving_size, 14.0);
+    assert_eq!(item.serving_size_unit, "ml");
+    assert_eq!(item.household_serving_full_text.as_deref(), Some("1 Tbsp"));
+    assert_eq!(item.label_nutrients.as_ref().unwrap().calories.value, 120.0);
+    assert_eq!(item.food_nutrients.len(), 2);
+    assert_eq!(
+        item.food_nutrients[0]
+            .food_nutrient_derivation
+            .as_ref()
+            .and_then(|derivation| derivation.code.as_deref()),
+        Some("LCCS")
+    );
+    assert!(item.published().is_some());
+    assert!(matches!(food(fixture("branded")), FDCMeta::Branded(_)));
+}
+
+#[test]
+fn foundation() {
+    let item: APFoodItem = serde_json::from_value(fixture("foundation")).unwrap();
+    assert_eq!(item.fdc_id, 748967);
+    assert_eq!(item.food_portions[0].gram_weight, 50.3);
+    assert_eq!(item.food_nutrients[0].amount, Some(12.4));
+    assert_eq!(item.food_nutrients[0].data_points, Some(24));
+    let factors = &item.nutrient_conversion_factors;
+    assert_eq!(factors.len(), 2);
+    assert_eq!(factors[1].protein_value, Some(4.36));
+    assert!(matches!(food(fixture("foundation")), FDCMeta::Other(_)));
+}
+
+#[test]
+fn foundation_energy() {
+    // the egg reports its energy by both Atwater factors and in kJ, but not under 1008, so the
+    // general factors win
+    let item: APFoodItem = serde_json::from_value(fixture("foundation")).unwrap();
+    let profile = NutrientProfile::from_food_nutrients(&item.food_nutrients);
+    assert_eq!(profile.energy_kcal(), Some(148.0));
+    assert_eq!(profile.get(Nutrient::Energy), profile.energy_kcal());
+
+    // without the Atwater energies, the kJ are converted to kcal
+    let kj_only = item
+        .food_nutrients
+        .iter()
+        .filter(|fnut| fnut.nutrient.id == 1062)
+        .cloned()
+        .collect::<Vec<_>>();
+    let profile = NutrientProfile::from_food_nutrients(&kj_only);
+    assert!((profile.energy_kj().unwrap() - 599.0).abs() < 1e-2);
+}
+
+#[test]
+fn sr_legacy() {
+    let item: APFoodItem = serde_json::from_value(fixture("sr_legacy")).unwrap();
+    assert_eq!(item.fdc_id, 171287);
+    assert_eq!(
+        item.food_portions[1].modifier.as_deref(),
+        Some("cup (4.86 large eggs)")
+    );
+    assert_eq!(item.food_portions[1].sequence_number, Some(1));
+    assert_eq!(item.food_nutrients[0].nutrient.unit_name, "kcal");
+}
+
+#[test]
+fn survey() {
+    let item: APFoodItem = serde_json::from_value(fixture("survey")).unwrap();
+    assert_eq!(item.fdc_id, 1100618);
+    assert_eq!(
+        item.food_portions[0].portion_description.as_deref(),
+        Some("1 slice")
+    );
+    assert_eq!(item.attributes_by_type("WWEIA Category").len(), 1);
+    assert_eq!(
+        item.attributes_of(AttributeKind::WweiaCategory)[0].value,
+        "51101000"
+    );
+    assert_eq!(item.additional_descriptions(), ["white bread"]);
+    assert!(item.common_names().is_empty());
+}
+
+#[test]
+fn v1_foods_body() {
+    // the fixtures are the foods of "v1/foods" responses, which are read as they come
+    let fixtures = FOODS
+        .iter()
+        .map(|(name, _)| fixture_text(name))
+        .collect::<Vec<_>>();
+    let body = format!("[{}]", fixtures.join(","));
+    let foods: Vec<FDCMeta> = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        foods.iter().map(FDCMeta::fdc_id).collect::<Vec<_>>(),
+        FOODS.iter().map(|(_, fdc_id)| *fdc_id).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        foods.iter().map(FDCMeta::data_type).collect::<Vec<_>>(),
+        ["Branded", "Foundation", "SR Legacy", "Survey (FNDDS)"]
+    );
+}
+
+#[test]
+fn error_body() {
+    let error = fixture("error");
+    assert_eq!(error["error"]["code"], "API_KEY_INVALID");
+    // an error must never pass for foods
+    assert!(serde_json::from_value::<Vec<FDCMeta>>(error.clone()).is_err());
+    assert!(serde_json::from_value::<BrandedFoodItem>(error.clone()).is_err());
+    assert!(serde_json::from_value::<APFoodItem>(error).is_err());
+}
+
+#[test]
+fn round_trip() {
+    for (name, _) in FOODS {
+        let once = reserialize(&food(fixture(name)));
+        let twice = reserialize(&food(once.clone()));
+        assert_eq!(key_diff(&once, &twice), (vec![], vec![]), "{}", name);
+    }
+}
+
+/// Fetch the foods of the fixtures live with the `FDC_KEY` of the environment, printing the keys
+/// FDC added or dropped since the fixtures were taken. Fails when the live foods no longer
+/// deserialize, or lose keys the public types keep.
+#[tokio::test]
+#[ignore]
+async fn canary() {
+    let service = FDCService::from_env().unwrap();
+    let client = reqwest::Client::new();
+    let mut drifted = false;
+    let mut report = |name: &str, old: &Value, new: &Value| {
+        let (added, removed) = key_diff(old, new);
+        if !added.is_empty() || !removed.is_empty() {
+            drifted = true;
+            println!("{}: new keys {:?}, missing keys {:?}", name, added, removed);
+        }
+    };
+
+    for (name, fdc_id) in FOODS {
+        let mut live = client
+            .post(format!(
+                "{}/v1/foods?api_key={}",
+                service.base_url, service.fdc_key
+            ))
+            .json(&serde_json::json!({ "fdcIds": [fdc_id], "format": "full" }))
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .unwrap()
+            .json::<Value>()
+            .await
+            .unwrap();
+        let live = live[0].take();
+        let fixture = fixture(name);
+        report(name, &fixture, &live);
+
+        let (_, lost) = key_diff(&reserialize(&food(fixture)), &reserialize(&food(live)));
+        assert!(lost.is_empty(), "{} lost the keys {:?}", name, lost);
+    }
+
+    let mut live = client
+        .post(format!(
+            "{}/v1/foods/search?api_key={}",
+            service.base_url, service.fdc_key
+        ))
+        .json(&serde_json::json!({ "query": "cheddar cheese", "pageSize": 10 }))
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    let search = fixture("search");
+    report("search", &search, &live);
+    report("search result", &search["foods"][0], &live["foods"][0]);
+    let foods: Vec<AbridgedFoodItem> = serde_json::from_value(live["foods"].take()).unwrap();
+    assert!(!foods.is_empty());
+
+    if !drifted {
+        println!("No drift from the fixtures");
+    }
+}
@@ -0,0 +1,55 @@
+//! Checks that the crate builds for `wasm32-unknown-unknown` without its default features, so that
+//! the quantities parser stays usable in a browser, and that it builds with each of the `fdc`,
+//! `env`, and `storage` features on its own. The checks are ignored by default as they build the
+//! crate again, and the first needs the target installed, through
+//! `rustup target add wasm32-unknown-unknown`.
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+#[ignore]
+fn no_default_features() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // a target directory of its own keeps the check from waiting on the lock of the test build
+    let status = Command::new(env!("CARGO"))
+        .current_dir(root)
+        .args([
+            "check",
+            "--lib",
+            "--no-default-features",
+            "--target",
+            "wasm32-unknown-unknown",
+            "--target-dir",
+        ])
+        .arg(root.join("target").join("wasm"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+#[ignore]
+fn single_features() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    for feature in ["fdc", "env", "storage"].iter() {
+        let status = Command::new(env!("CARGO"))
+            .current_dir(root)
+            .args([
+                "check",
+                "--all-targets",
+                "--no-default-features",
+                "--features",
+                feature,
+                "--target-dir",
+            ])
+            .arg(root.join("target").join("features"))
+            .status()
+            .unwrap();
+        assert!(
+            status.success(),
+            "the crate does not build with only {}",
+            feature
+        );
+    }
+}